@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tokio::sync::oneshot;
+
+/// The user's answer to a tool approval request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Decision {
+    Allow,
+    /// Allow this call and every later call of the same tool
+    AlwaysAllow,
+    Deny,
+}
+
+struct Pending {
+    tool: String,
+    reply: oneshot::Sender<Decision>,
+}
+
+/// Tool calls waiting for the user to allow or deny them.
+/// The agent loop registers a request and awaits the receiver; the UI answers through
+/// `resolve_tool_approval`, which completes it.
+pub struct ApprovalGate {
+    next_id: AtomicU64,
+    pending: Mutex<HashMap<u64, Pending>>,
+    always_allowed: Mutex<HashSet<String>>,
+}
+
+impl ApprovalGate {
+    /// `always_allow` comes from `[tools]` in the config
+    pub fn new(always_allow: &[String]) -> Self {
+        Self {
+            next_id: AtomicU64::new(1),
+            pending: Mutex::new(HashMap::new()),
+            always_allowed: Mutex::new(always_allow.iter().cloned().collect()),
+        }
+    }
+
+    pub fn is_always_allowed(&self, tool: &str) -> bool {
+        self.always_allowed.lock().unwrap().contains(tool)
+    }
+
+    /// Register a request for `tool`. The receiver yields the user's decision,
+    /// or an error if the request was cancelled.
+    pub fn request(&self, tool: &str) -> (u64, oneshot::Receiver<Decision>) {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (reply, rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(
+            id,
+            Pending {
+                tool: tool.to_string(),
+                reply,
+            },
+        );
+        (id, rx)
+    }
+
+    /// Answer request `id`. Returns false if it is no longer waiting.
+    pub fn resolve(&self, id: u64, decision: Decision) -> bool {
+        let Some(pending) = self.pending.lock().unwrap().remove(&id) else {
+            return false;
+        };
+        if decision == Decision::AlwaysAllow {
+            self.always_allowed.lock().unwrap().insert(pending.tool);
+        }
+        pending.reply.send(decision).is_ok()
+    }
+
+    /// Drop request `id` without an answer (e.g. the reply was stopped)
+    pub fn cancel(&self, id: u64) {
+        self.pending.lock().unwrap().remove(&id);
+    }
+}
@@ -0,0 +1,139 @@
+/// Slash commands typed into the chat input.
+/// These are handled by the agent loop directly and never sent to the LLM.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Markdown,
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SlashCommand {
+    Clear,
+    Continue,
+    Regenerate,
+    Edit(String),
+    Help,
+    Tools,
+    Export(ExportFormat),
+    Persona(String),
+    Model(String),
+    Reload,
+    ClearCache,
+    NewConversation,
+    SwitchConversation(i64),
+    DeleteConversation(i64),
+    Conversations,
+    Region,
+    Intensity(u8),
+}
+
+/// (usage, description) for every supported command, shown by `/help`
+pub const COMMANDS: &[(&str, &str)] = &[
+    ("/clear", "Start a fresh conversation"),
+    ("/new", "Open a new saved conversation"),
+    ("/conversations", "List saved conversations"),
+    ("/switch <id>", "Resume a saved conversation"),
+    ("/delete <id>", "Delete a saved conversation"),
+    ("/continue", "Resume a reply cut off at the token limit"),
+    ("/regenerate", "Answer your last message again"),
+    (
+        "/edit <message>",
+        "Replace your last message and answer it again",
+    ),
+    (
+        "/export md|json",
+        "Export the conversation to the exports/ folder",
+    ),
+    ("/persona <name>", "Switch persona"),
+    ("/model <name>", "Switch the Ollama model"),
+    ("/reload", "Reconnect to Ollama and load the model again"),
+    (
+        "/intensity <0-100>",
+        "Set persona intensity (professional → playful)",
+    ),
+    ("/region", "Select a screen region to ask about"),
+    ("/tools", "List enabled tools"),
+    ("/cache clear", "Discard cached tool results"),
+    ("/help", "Show this list"),
+];
+
+pub fn help_text() -> String {
+    let mut text = String::from("Available commands:\n");
+    for (usage, description) in COMMANDS {
+        text.push_str(&format!("- `{}` — {}\n", usage, description));
+    }
+    text
+}
+
+impl SlashCommand {
+    /// Returns `None` if the input isn't a slash command,
+    /// or `Some(Err(message))` with a user-facing explanation if it is malformed.
+    pub fn parse(input: &str) -> Option<Result<Self, String>> {
+        let rest = input.strip_prefix('/')?;
+        let mut parts = rest.split_whitespace();
+        let name = parts.next().unwrap_or("").to_lowercase();
+        let arg = parts.collect::<Vec<_>>().join(" ");
+        // Keeps the line breaks that `arg` collapses, for commands that take a message
+        let text = rest
+            .trim_start()
+            .split_once(char::is_whitespace)
+            .map_or("", |(_, text)| text.trim());
+
+        let require_arg = |usage: &str| -> Result<String, String> {
+            if arg.is_empty() {
+                Err(format!("Usage: `{}`", usage))
+            } else {
+                Ok(arg.clone())
+            }
+        };
+
+        let parsed = match name.as_str() {
+            "clear" => Ok(SlashCommand::Clear),
+            "continue" => Ok(SlashCommand::Continue),
+            "regenerate" => Ok(SlashCommand::Regenerate),
+            "edit" => require_arg("/edit <message>").map(|_| SlashCommand::Edit(text.to_string())),
+            "help" => Ok(SlashCommand::Help),
+            "tools" => Ok(SlashCommand::Tools),
+            "region" => Ok(SlashCommand::Region),
+            "export" => match arg.to_lowercase().as_str() {
+                "" | "md" | "markdown" => Ok(SlashCommand::Export(ExportFormat::Markdown)),
+                "json" => Ok(SlashCommand::Export(ExportFormat::Json)),
+                other => Err(format!(
+                    "Unknown export format '{}'. Use `md` or `json`.",
+                    other
+                )),
+            },
+            "persona" => require_arg("/persona <name>").map(SlashCommand::Persona),
+            "model" => require_arg("/model <name>").map(SlashCommand::Model),
+            "reload" => Ok(SlashCommand::Reload),
+            "intensity" => require_arg("/intensity <0-100>").and_then(|value| {
+                value
+                    .trim_end_matches('%')
+                    .parse::<u8>()
+                    .ok()
+                    .filter(|v| *v <= 100)
+                    .map(SlashCommand::Intensity)
+                    .ok_or_else(|| format!("'{}' is not a value between 0 and 100", value))
+            }),
+            "new" => Ok(SlashCommand::NewConversation),
+            "conversations" => Ok(SlashCommand::Conversations),
+            "switch" => require_arg("/switch <id>").and_then(|id| {
+                id.parse()
+                    .map(SlashCommand::SwitchConversation)
+                    .map_err(|_| format!("'{}' is not a conversation id", id))
+            }),
+            "delete" => require_arg("/delete <id>").and_then(|id| {
+                id.parse()
+                    .map(SlashCommand::DeleteConversation)
+                    .map_err(|_| format!("'{}' is not a conversation id", id))
+            }),
+            "cache" => match arg.to_lowercase().as_str() {
+                "clear" => Ok(SlashCommand::ClearCache),
+                _ => Err("Usage: `/cache clear`".to_string()),
+            },
+            _ => Err(format!("Unknown command `/{}`.\n\n{}", name, help_text())),
+        };
+        Some(parsed)
+    }
+}
@@ -0,0 +1,94 @@
+use anyhow::Result;
+
+use crate::config::ContextSettings;
+use crate::llm::ollama::OllamaClient;
+use crate::llm::Message;
+
+/// Share of the context window kept free for the model's reply
+const REPLY_RESERVE_RATIO: f32 = 0.125;
+
+/// Once trimming is needed, the prompt is cut down to this share of the budget. Ollama reuses
+/// its KV cache only for an unchanged prompt prefix, so trimming a little every turn would
+/// force a full re-decode each time; cutting deeper keeps the prefix stable for several turns.
+const TRIM_TARGET_RATIO: f32 = 0.75;
+
+/// Marks the system message carrying the rolling summary of trimmed messages
+pub const SUMMARY_PREFIX: &str = "[Earlier conversation summary]";
+
+/// When `history` no longer fits the window minus the reply reserve, remove the oldest
+/// messages before `keep_from` until it is under the trim target and return them.
+/// Leading system messages (prompt, summary) and the current turn are never removed;
+/// the database keeps everything.
+pub fn trim(
+    client: &OllamaClient,
+    history: &mut Vec<Message>,
+    keep_from: usize,
+    settings: &ContextSettings,
+) -> Vec<Message> {
+    let budget = (settings.window_tokens as f32 * (1.0 - REPLY_RESERVE_RATIO)) as usize;
+    if client.count_tokens(history) <= budget {
+        return Vec::new();
+    }
+    let target = (budget as f32 * TRIM_TARGET_RATIO) as usize;
+    let first = leading_system_messages(history);
+    let mut removed = Vec::new();
+    while client.count_tokens(history) > target && first + removed.len() < keep_from {
+        removed.push(history.remove(first));
+    }
+    removed
+}
+
+/// Fold `dropped` messages into the running summary using the model itself
+pub async fn summarize(
+    client: &OllamaClient,
+    previous: Option<&str>,
+    dropped: &[Message],
+) -> Result<String> {
+    let mut transcript = String::new();
+    if let Some(previous) = previous {
+        transcript.push_str(&format!("Summary so far:\n{}\n\n", previous));
+    }
+    transcript.push_str("New messages:\n");
+    for message in dropped {
+        transcript.push_str(&format!("{}: {}\n", message.role, message.content));
+    }
+
+    let messages = vec![
+        Message {
+            role: "system".to_string(),
+            content: "Update the summary of an ongoing conversation so it can stand in for the messages it covers. Keep facts about the user, decisions, names, file paths and open tasks. Drop small talk. Reply with the summary only, under 200 words.".to_string(),
+            images: None,
+        },
+        Message {
+            role: "user".to_string(),
+            content: transcript,
+            images: None,
+        },
+    ];
+    Ok(client.chat(messages).await?.content.trim().to_string())
+}
+
+/// Put `summary` right after the system prompt, replacing an earlier one.
+/// Returns true if a message was inserted (shifting later indices by one).
+pub fn apply_summary(history: &mut Vec<Message>, summary: &str) -> bool {
+    let message = Message {
+        role: "system".to_string(),
+        content: format!("{}\n{}", SUMMARY_PREFIX, summary),
+        images: None,
+    };
+    if let Some(existing) = history
+        .iter_mut()
+        .take_while(|m| m.role == "system")
+        .find(|m| m.content.starts_with(SUMMARY_PREFIX))
+    {
+        *existing = message;
+        return false;
+    }
+    let at = usize::from(history.first().is_some_and(|m| m.role == "system"));
+    history.insert(at, message);
+    true
+}
+
+fn leading_system_messages(history: &[Message]) -> usize {
+    history.iter().take_while(|m| m.role == "system").count()
+}
@@ -0,0 +1,147 @@
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Emotion {
+    Neutral,
+    Happy,
+    Angry,
+    Surprised,
+    Sad,
+}
+
+impl Emotion {
+    /// Simple keyword-based emotion detection.
+    /// Mirrors `detectEmotion` in src/hooks/useChat.ts so voice and avatar agree.
+    pub fn detect(content: &str) -> Self {
+        let lower = content.to_lowercase();
+        let has = |s: &str| lower.contains(s);
+
+        if (has("!") && has("?"))
+            || (has("뭐") && has("!"))
+            || has("え")
+            || has("놀")
+            || has("대박")
+        {
+            return Emotion::Surprised;
+        }
+        if has("바보") || has("흥") || has("짜증") || has("하아") || has("변태") {
+            return Emotion::Angry;
+        }
+        if has("ㅎㅎ") || has("ㅋㅋ") || has("좋") || has("감사") || has("기뻐") || has("^^")
+        {
+            return Emotion::Happy;
+        }
+        if has("슬프") || has("아쉽") || has("미안") || has("걱정") {
+            return Emotion::Sad;
+        }
+        Emotion::Neutral
+    }
+
+    /// Emotion for a tag name like "happy"; None for names the avatar has no face for
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "neutral" => Some(Emotion::Neutral),
+            "happy" => Some(Emotion::Happy),
+            "angry" => Some(Emotion::Angry),
+            "surprised" => Some(Emotion::Surprised),
+            "sad" => Some(Emotion::Sad),
+            _ => None,
+        }
+    }
+}
+
+const TAG_OPEN: &str = "[emotion:";
+
+// A reply start still undecided after this many bytes is not a tag
+const MAX_TAG_LEN: usize = 32;
+
+/// Asks the model to open each reply with its mood, which drives the avatar's expression
+pub const TAG_INSTRUCTION: &str = "Begin every reply to the user with how you feel, as exactly one of [emotion:neutral], [emotion:happy], [emotion:angry], [emotion:surprised] or [emotion:sad]. The tag sets your avatar's expression and is never shown or spoken. Tool calls take no tag.";
+
+/// Split a leading `[emotion:name]` tag off a reply. The emotion is None when there is no
+/// tag; an unknown name still counts as a tag and comes back as Neutral.
+pub fn split_tag(reply: &str) -> (Option<Emotion>, &str) {
+    let trimmed = reply.trim_start();
+    let Some(tag) = trimmed
+        .get(..TAG_OPEN.len())
+        .filter(|open| open.eq_ignore_ascii_case(TAG_OPEN))
+        .map(|_| &trimmed[TAG_OPEN.len()..])
+    else {
+        return (None, reply);
+    };
+    match tag.split_once(']') {
+        Some((name, rest)) if name.len() <= MAX_TAG_LEN => (
+            Some(Emotion::from_name(name).unwrap_or(Emotion::Neutral)),
+            rest.trim_start(),
+        ),
+        _ => (None, reply),
+    }
+}
+
+/// Remove a leading emotion tag from `reply` in place, returning its emotion
+pub fn strip_tag(reply: &mut String) -> Option<Emotion> {
+    let (emotion, rest) = split_tag(reply);
+    if emotion.is_some() {
+        *reply = rest.to_string();
+    }
+    emotion
+}
+
+/// Holds back the start of a streamed reply until it is clear whether it opens with an
+/// emotion tag, so the tag never reaches the chat or the voice
+#[derive(Default)]
+pub struct TagFilter {
+    head: String,
+    decided: bool,
+    emotion: Option<Emotion>,
+    /// The tag came alone, so the space after it is still to be dropped
+    trim_next: bool,
+}
+
+impl TagFilter {
+    /// Text of `token` to pass on; empty while the start of the reply is undecided
+    pub fn push(&mut self, token: &str) -> String {
+        if self.trim_next {
+            let token = token.trim_start();
+            self.trim_next = token.is_empty();
+            return token.to_string();
+        }
+        if self.decided {
+            return token.to_string();
+        }
+        self.head.push_str(token);
+        let trimmed = self.head.trim_start();
+        // Could still become "[emotion:name]": a prefix of the opening, or the opening
+        // waiting for its closing bracket
+        let n = trimmed.len().min(TAG_OPEN.len());
+        let opens_like_tag = trimmed
+            .get(..n)
+            .is_some_and(|start| start.eq_ignore_ascii_case(&TAG_OPEN[..n]));
+        let undecided = opens_like_tag
+            && !trimmed.contains(']')
+            && trimmed.len() < TAG_OPEN.len() + MAX_TAG_LEN;
+        if undecided {
+            return String::new();
+        }
+        self.finish()
+    }
+
+    /// Whatever is still held back, once the reply has ended
+    pub fn finish(&mut self) -> String {
+        if self.decided {
+            return String::new();
+        }
+        self.decided = true;
+        let head = std::mem::take(&mut self.head);
+        let (emotion, rest) = split_tag(&head);
+        self.emotion = emotion;
+        self.trim_next = emotion.is_some() && rest.is_empty();
+        rest.to_string()
+    }
+
+    /// Emotion from the reply's tag, once it has been read
+    pub fn emotion(&self) -> Option<Emotion> {
+        self.emotion
+    }
+}
@@ -0,0 +1,54 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::agent::emotion::Emotion;
+
+/// Tauri event carrying every `AgentEvent`
+pub const AGENT_EVENT: &str = "agent-event";
+
+/// Progress the agent loop reports to the UI apart from chat messages.
+/// Serialized with a `type` tag (`{"type": "tool_started", ...}`) so the frontend can switch on it.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AgentEvent {
+    /// Next piece of an assistant reply that is still being generated
+    AssistantDelta {
+        turn_id: Option<u64>,
+        content: String,
+    },
+    /// The streamed reply is complete
+    AssistantDone {
+        turn_id: Option<u64>,
+        /// From the reply's emotion tag; without one the UI guesses from the words
+        #[serde(skip_serializing_if = "Option::is_none")]
+        emotion: Option<Emotion>,
+    },
+    ToolStarted {
+        turn_id: u64,
+        tool: String,
+        args: Value,
+    },
+    ToolFinished {
+        turn_id: u64,
+        tool: String,
+        /// Served from the tool result cache instead of running again
+        cached: bool,
+        /// `data:` URL of an image the tool returned, shown inline instead of its base64
+        #[serde(skip_serializing_if = "Option::is_none")]
+        image: Option<String>,
+    },
+    ToolFailed {
+        turn_id: u64,
+        tool: String,
+        error: String,
+        /// Stopped for running past its time limit
+        timed_out: bool,
+    },
+    /// Short status line shown in the header
+    Status { status: String, is_thinking: bool },
+    /// The turn could not be completed (e.g. Ollama is unreachable)
+    Error {
+        turn_id: Option<u64>,
+        message: String,
+    },
+}
@@ -0,0 +1,35 @@
+use anyhow::Result;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::agent::commands::ExportFormat;
+use crate::llm::Message;
+
+const EXPORT_DIR: &str = "exports";
+
+/// Write the conversation (without the system prompt) to `exports/` and return the file path
+pub fn export_history(history: &[Message], format: ExportFormat) -> Result<PathBuf> {
+    let messages: Vec<&Message> = history.iter().filter(|m| m.role != "system").collect();
+
+    let (extension, body) = match format {
+        ExportFormat::Markdown => {
+            let mut md = String::from("# Amadeus Conversation\n\n");
+            for msg in &messages {
+                let speaker = match msg.role.as_str() {
+                    "user" => "Guest",
+                    "assistant" => "Amadeus",
+                    other => other,
+                };
+                md.push_str(&format!("## {}\n\n{}\n\n", speaker, msg.content));
+            }
+            ("md", md)
+        }
+        ExportFormat::Json => ("json", serde_json::to_string_pretty(&messages)?),
+    };
+
+    std::fs::create_dir_all(EXPORT_DIR)?;
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let path = PathBuf::from(EXPORT_DIR).join(format!("amadeus-{}.{}", timestamp, extension));
+    std::fs::write(&path, body)?;
+    Ok(path)
+}
@@ -0,0 +1,175 @@
+use anyhow::Result;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::agent::jsonrpc::RpcClient;
+use crate::agent::tools::{Tool, ToolResult};
+use crate::config::ToolProcess;
+
+/// Starting a program and getting its description; a slower one is skipped
+const DESCRIBE_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// One tool as a program describes it
+#[derive(Debug, Clone, Deserialize)]
+struct Manifest {
+    name: String,
+    description: String,
+    #[serde(default = "empty_schema")]
+    parameters: Value,
+}
+
+fn empty_schema() -> Value {
+    json!({ "type": "object", "properties": {} })
+}
+
+/// A program from `[[tools.external]]`, kept running between calls. It reads JSON-RPC
+/// requests from stdin and answers on stdout, one message per line:
+/// - `describe`: a tool `{ "name", "description", "parameters" }`, or `{ "tools": [...] }`
+/// - `execute` with `{ "name", "arguments" }`: the output as a string, or `{ "output" }`
+///
+/// Failures are JSON-RPC errors, whose message is shown to the model.
+struct ToolProgram {
+    config: ToolProcess,
+    label: String,
+    client: Mutex<Arc<RpcClient>>,
+}
+
+impl ToolProgram {
+    async fn spawn(config: &ToolProcess, label: &str) -> Result<RpcClient> {
+        RpcClient::spawn(label, &config.command, &config.args, &config.env).await
+    }
+
+    /// The running process, started again if it has exited since the last call
+    async fn client(&self) -> Result<Arc<RpcClient>> {
+        let mut client = self.client.lock().await;
+        if client.is_closed() {
+            warn!("[Tools] {} exited; restarting it", self.label);
+            *client = Arc::new(Self::spawn(&self.config, &self.label).await?);
+        }
+        Ok(Arc::clone(&client))
+    }
+}
+
+/// The script or program name, for logs and errors: `python3 -u tools/weather.py`
+/// gives "weather.py"
+fn label(config: &ToolProcess) -> String {
+    let target = config
+        .args
+        .iter()
+        .find(|arg| !arg.starts_with('-'))
+        .unwrap_or(&config.command);
+    Path::new(target)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| target.clone())
+}
+
+/// Start every configured program at once and collect the tools they describe. One that
+/// fails to start or answer is reported and left out.
+pub async fn start_all(programs: &[ToolProcess]) -> Vec<Box<dyn Tool>> {
+    let starts = programs.iter().map(|config| async move {
+        let label = label(config);
+        match tokio::time::timeout(DESCRIBE_TIMEOUT, start(config, &label)).await {
+            Ok(Ok(tools)) => {
+                info!("[Tools] {}: {} tool(s)", label, tools.len());
+                tools
+            }
+            Ok(Err(e)) => {
+                warn!("[Tools] {}: {}", label, e);
+                Vec::new()
+            }
+            Err(_) => {
+                warn!(
+                    "[Tools] {}: no answer to describe within {:?}",
+                    label, DESCRIBE_TIMEOUT
+                );
+                Vec::new()
+            }
+        }
+    });
+    futures_util::future::join_all(starts)
+        .await
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+async fn start(config: &ToolProcess, label: &str) -> Result<Vec<Box<dyn Tool>>> {
+    let client = ToolProgram::spawn(config, label).await?;
+    let described = client.request("describe", json!({})).await?;
+    let manifests: Vec<Manifest> = match described.get("tools") {
+        Some(list) => serde_json::from_value(list.clone()),
+        None => serde_json::from_value(described).map(|manifest| vec![manifest]),
+    }
+    .map_err(|e| anyhow::anyhow!("describe returned an invalid tool: {}", e))?;
+    if manifests.iter().any(|m| m.name.trim().is_empty()) {
+        return Err(anyhow::anyhow!(
+            "describe returned a tool with an empty name"
+        ));
+    }
+
+    let program = Arc::new(ToolProgram {
+        config: config.clone(),
+        label: label.to_string(),
+        client: Mutex::new(Arc::new(client)),
+    });
+    Ok(manifests
+        .into_iter()
+        .map(|manifest| {
+            Box::new(ExternalTool {
+                manifest,
+                program: Arc::clone(&program),
+            }) as Box<dyn Tool>
+        })
+        .collect())
+}
+
+pub struct ExternalTool {
+    manifest: Manifest,
+    program: Arc<ToolProgram>,
+}
+
+impl Tool for ExternalTool {
+    fn name(&self) -> &str {
+        &self.manifest.name
+    }
+
+    fn description(&self) -> &str {
+        &self.manifest.description
+    }
+
+    fn parameters(&self) -> Value {
+        self.manifest.parameters.clone()
+    }
+
+    fn execute(&self, args: Value) -> Pin<Box<dyn Future<Output = ToolResult> + Send>> {
+        let program = Arc::clone(&self.program);
+        let name = self.manifest.name.clone();
+        Box::pin(async move {
+            let client = program.client().await?;
+            let result = client
+                .request("execute", json!({ "name": name, "arguments": args }))
+                .await?;
+            Ok(match result {
+                Value::String(output) => output,
+                Value::Object(ref fields) => match fields.get("output") {
+                    Some(Value::String(output)) => output.clone(),
+                    _ => result.to_string(),
+                },
+                other => other.to_string(),
+            })
+        })
+    }
+
+    /// The program can do anything the user can, so calls are approved unless it's trusted
+    fn requires_approval(&self, _args: &Value) -> bool {
+        !self.program.config.trusted
+    }
+}
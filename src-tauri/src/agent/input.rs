@@ -0,0 +1,119 @@
+use anyhow::Result;
+use std::path::Path;
+
+use crate::system::screenshot;
+
+// Text files longer than this are cut off before being inlined into the message
+const MAX_FILE_CHARS: usize = 20_000;
+// Bigger files are refused instead of read into memory
+const MAX_FILE_BYTES: u64 = 20 * 1024 * 1024;
+
+/// What the UI sends to the agent loop: typed or spoken text (or a slash command)
+/// plus any files the user attached
+pub struct UserInput {
+    pub text: String,
+    pub attachments: Vec<Attachment>,
+}
+
+/// A file dropped onto the chat window
+pub enum Attachment {
+    /// Base64 JPEG, scaled for the model and sent in `Message::images`
+    Image { name: String, base64: String },
+    /// Text inlined into the message
+    File {
+        name: String,
+        content: String,
+        /// Cut off at `MAX_FILE_CHARS`
+        truncated: bool,
+    },
+}
+
+impl From<String> for UserInput {
+    fn from(text: String) -> Self {
+        Self {
+            text,
+            attachments: Vec::new(),
+        }
+    }
+}
+
+impl From<&str> for UserInput {
+    fn from(text: &str) -> Self {
+        text.to_string().into()
+    }
+}
+
+impl UserInput {
+    /// The message text as the model sees it and the images to go with it. Each attachment
+    /// leaves an `[Attached file: name]` or `[Attached image: name]` marker after the text,
+    /// which the UI shows as a chip.
+    pub fn into_message_parts(self) -> (String, Option<Vec<String>>) {
+        let mut text = self.text;
+        let mut images = Vec::new();
+        for attachment in self.attachments {
+            match attachment {
+                Attachment::Image { name, base64 } => {
+                    text.push_str(&format!("\n\n[Attached image: {}]", name));
+                    images.push(base64);
+                }
+                Attachment::File {
+                    name,
+                    content,
+                    truncated,
+                } => {
+                    text.push_str(&format!(
+                        "\n\n[Attached file: {}]\n```\n{}\n```",
+                        name, content
+                    ));
+                    if truncated {
+                        text.push_str(&format!("\n(truncated to {} characters)", MAX_FILE_CHARS));
+                    }
+                }
+            }
+        }
+        (text, (!images.is_empty()).then_some(images))
+    }
+}
+
+impl Attachment {
+    /// Read a file as an image if it decodes as one, otherwise as UTF-8 text
+    pub fn load(path: &Path) -> Result<Self> {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+        let size = std::fs::metadata(path)
+            .map_err(|e| anyhow::anyhow!("Cannot read {}: {}", name, e))?
+            .len();
+        if size > MAX_FILE_BYTES {
+            return Err(anyhow::anyhow!(
+                "{} is too large to attach ({} MB, limit {} MB)",
+                name,
+                size / (1024 * 1024),
+                MAX_FILE_BYTES / (1024 * 1024)
+            ));
+        }
+        let bytes =
+            std::fs::read(path).map_err(|e| anyhow::anyhow!("Cannot read {}: {}", name, e))?;
+
+        if let Ok(image) = image::load_from_memory(&bytes) {
+            return Ok(Self::Image {
+                name,
+                base64: screenshot::encode_for_llm(&image)?,
+            });
+        }
+        let text = String::from_utf8(bytes)
+            .map_err(|_| anyhow::anyhow!("{} is neither an image nor a text file", name))?;
+        let truncated = text.chars().count() > MAX_FILE_CHARS;
+        let content = if truncated {
+            text.chars().take(MAX_FILE_CHARS).collect()
+        } else {
+            text
+        };
+        Ok(Self::File {
+            name,
+            content,
+            truncated,
+        })
+    }
+}
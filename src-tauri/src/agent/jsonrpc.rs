@@ -0,0 +1,309 @@
+use anyhow::Result;
+use futures_util::StreamExt;
+use reqwest::Url;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+use crate::system::secrets;
+
+/// A request the other side never answers. Tool calls are also bound by `[tools] timeout_secs`.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(300);
+
+type Pending = Arc<Mutex<HashMap<u64, oneshot::Sender<Result<Value>>>>>;
+
+/// A JSON-RPC 2.0 connection to another process over stdio, or to a server over SSE.
+/// A reader task matches responses to waiting requests by id and answers the other
+/// side's pings. Dropping the client ends its tasks and, for stdio, the process.
+pub struct RpcClient {
+    /// Who is on the other end, for error messages
+    name: String,
+    outgoing: mpsc::UnboundedSender<Value>,
+    pending: Pending,
+    next_id: AtomicU64,
+    tasks: Vec<JoinHandle<()>>,
+    /// Killed on drop
+    _child: Option<Child>,
+}
+
+impl Drop for RpcClient {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+impl RpcClient {
+    /// Launch `command` and talk to it over stdin/stdout, one JSON message per line
+    pub async fn spawn(
+        name: &str,
+        command: &str,
+        args: &[String],
+        env: &HashMap<String, String>,
+    ) -> Result<Self> {
+        let mut process = Command::new(command);
+        process
+            .args(args)
+            .envs(resolve_env(env)?)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            // Logging goes to stderr, which is ours
+            .stderr(Stdio::inherit())
+            .kill_on_drop(true);
+        #[cfg(windows)]
+        {
+            const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+            process.creation_flags(CREATE_NO_WINDOW);
+        }
+        let mut child = process
+            .spawn()
+            .map_err(|e| anyhow::anyhow!("Failed to start {}: {}", command, e))?;
+        let mut stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("No stdin"))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("No stdout"))?;
+
+        let (outgoing, mut outbox) = mpsc::unbounded_channel::<Value>();
+        let pending: Pending = Arc::default();
+
+        let writer = tokio::spawn(async move {
+            while let Some(message) = outbox.recv().await {
+                let line = format!("{}\n", message);
+                if stdin.write_all(line.as_bytes()).await.is_err() || stdin.flush().await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let reader = {
+            let pending = Arc::clone(&pending);
+            let replies = outgoing.clone();
+            let name = name.to_string();
+            tokio::spawn(async move {
+                let mut lines = BufReader::new(stdout).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    if let Ok(message) = serde_json::from_str(&line) {
+                        route(&pending, &replies, message);
+                    }
+                }
+                fail_pending(&pending, &format!("{} exited", name));
+            })
+        };
+
+        Ok(Self {
+            name: name.to_string(),
+            outgoing,
+            pending,
+            next_id: AtomicU64::new(1),
+            tasks: vec![writer, reader],
+            _child: Some(child),
+        })
+    }
+
+    /// HTTP+SSE transport: messages arrive as `message` events on a GET stream, and go out
+    /// as POSTs to the endpoint the server names in its first `endpoint` event
+    pub async fn connect_sse(name: &str, url: &str) -> Result<Self> {
+        let base = Url::parse(url).map_err(|e| anyhow::anyhow!("Invalid url: {}", e))?;
+        let client = reqwest::Client::new();
+        let response = client
+            .get(base.clone())
+            .header(reqwest::header::ACCEPT, "text/event-stream")
+            .send()
+            .await?
+            .error_for_status()?;
+        let mut stream = response.bytes_stream();
+        let mut events = SseParser::default();
+
+        let endpoint = 'found: loop {
+            let chunk = stream
+                .next()
+                .await
+                .ok_or_else(|| anyhow::anyhow!("Stream closed before the endpoint event"))??;
+            for (event, data) in events.push(&chunk) {
+                if event == "endpoint" {
+                    break 'found base.join(data.trim())?;
+                }
+            }
+        };
+
+        let (outgoing, mut outbox) = mpsc::unbounded_channel::<Value>();
+        let pending: Pending = Arc::default();
+
+        let writer = {
+            let name = name.to_string();
+            tokio::spawn(async move {
+                while let Some(message) = outbox.recv().await {
+                    let sent = client
+                        .post(endpoint.clone())
+                        .json(&message)
+                        .send()
+                        .await
+                        .and_then(|r| r.error_for_status());
+                    if let Err(e) = sent {
+                        warn!("[RPC] {}: failed to send: {}", name, e);
+                    }
+                }
+            })
+        };
+
+        let reader = {
+            let pending = Arc::clone(&pending);
+            let replies = outgoing.clone();
+            let name = name.to_string();
+            tokio::spawn(async move {
+                while let Some(Ok(chunk)) = stream.next().await {
+                    for (event, data) in events.push(&chunk) {
+                        if event != "message" {
+                            continue;
+                        }
+                        if let Ok(message) = serde_json::from_str(&data) {
+                            route(&pending, &replies, message);
+                        }
+                    }
+                }
+                fail_pending(&pending, &format!("{} closed the connection", name));
+            })
+        };
+
+        Ok(Self {
+            name: name.to_string(),
+            outgoing,
+            pending,
+            next_id: AtomicU64::new(1),
+            tasks: vec![writer, reader],
+            _child: None,
+        })
+    }
+
+    pub async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, tx);
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        if self.outgoing.send(message).is_err() {
+            self.pending.lock().unwrap().remove(&id);
+            return Err(anyhow::anyhow!("{} is disconnected", self.name));
+        }
+        match tokio::time::timeout(REQUEST_TIMEOUT, rx).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(anyhow::anyhow!("{} is disconnected", self.name)),
+            Err(_) => {
+                self.pending.lock().unwrap().remove(&id);
+                Err(anyhow::anyhow!("{} did not answer {}", self.name, method))
+            }
+        }
+    }
+
+    /// The reader has stopped: the process exited or the stream closed
+    pub fn is_closed(&self) -> bool {
+        self.tasks.iter().any(|task| task.is_finished())
+    }
+
+    pub fn notify(&self, method: &str) {
+        let _ = self
+            .outgoing
+            .send(json!({ "jsonrpc": "2.0", "method": method }));
+    }
+}
+
+/// Deliver a response to its waiting request, or answer a request from the other side
+fn route(pending: &Pending, replies: &mpsc::UnboundedSender<Value>, message: Value) {
+    let id = message.get("id").cloned();
+    if let Some(method) = message["method"].as_str() {
+        // Notifications (no id) need no answer; of requests, only ping is supported
+        let Some(id) = id else {
+            return;
+        };
+        let reply = if method == "ping" {
+            json!({ "jsonrpc": "2.0", "id": id, "result": {} })
+        } else {
+            json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": -32601, "message": format!("Method not found: {}", method) }
+            })
+        };
+        let _ = replies.send(reply);
+        return;
+    }
+
+    let Some(id) = id.and_then(|id| id.as_u64()) else {
+        return;
+    };
+    let Some(waiter) = pending.lock().unwrap().remove(&id) else {
+        return;
+    };
+    let result = match message.get("error") {
+        Some(error) => Err(anyhow::anyhow!(
+            "{}",
+            error["message"].as_str().unwrap_or("Unknown error")
+        )),
+        None => Ok(message["result"].clone()),
+    };
+    let _ = waiter.send(result);
+}
+
+fn fail_pending(pending: &Pending, reason: &str) {
+    for (_, waiter) in pending.lock().unwrap().drain() {
+        let _ = waiter.send(Err(anyhow::anyhow!("{}", reason)));
+    }
+}
+
+/// Environment for a spawned process; `secret:<name>` values come from the keychain, so
+/// tokens don't have to sit in amadeus.toml
+fn resolve_env(env: &HashMap<String, String>) -> Result<HashMap<String, String>> {
+    env.iter()
+        .map(|(key, value)| {
+            let value = match value.strip_prefix("secret:") {
+                Some(name) => secrets::get(name)?
+                    .ok_or_else(|| anyhow::anyhow!("No secret named '{}' for {}", name, key))?,
+                None => value.clone(),
+            };
+            Ok((key.clone(), value))
+        })
+        .collect()
+}
+
+/// Splits a `text/event-stream` into (event, data) pairs. Bytes are buffered until an
+/// event is complete, so a multi-byte character split across chunks survives.
+#[derive(Default)]
+struct SseParser {
+    buffer: Vec<u8>,
+}
+
+impl SseParser {
+    fn push(&mut self, chunk: &[u8]) -> Vec<(String, String)> {
+        self.buffer.extend(chunk.iter().filter(|b| **b != b'\r'));
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.windows(2).position(|w| w == b"\n\n") {
+            let block: Vec<u8> = self.buffer.drain(..end + 2).collect();
+            let block = String::from_utf8_lossy(&block);
+            let mut event = "message".to_string();
+            let mut data = Vec::new();
+            for line in block.lines() {
+                if let Some(value) = line.strip_prefix("event:") {
+                    event = value.trim().to_string();
+                } else if let Some(value) = line.strip_prefix("data:") {
+                    data.push(value.strip_prefix(' ').unwrap_or(value));
+                }
+            }
+            if !data.is_empty() {
+                events.push((event, data.join("\n")));
+            }
+        }
+        events
+    }
+}
@@ -0,0 +1,45 @@
+use serde_json::Value;
+
+use crate::config::ToolSettings;
+
+/// Stops a turn whose model keeps calling tools: too many calls in total,
+/// or the same call over and over
+pub struct LoopGuard {
+    max_iterations: usize,
+    max_repeats: usize,
+    calls: Vec<String>,
+}
+
+impl LoopGuard {
+    pub fn new(settings: &ToolSettings) -> Self {
+        Self {
+            max_iterations: settings.max_iterations,
+            max_repeats: settings.max_repeats,
+            calls: Vec::new(),
+        }
+    }
+
+    /// Record a call about to run. Returns why the turn should stop instead, if it should.
+    pub fn check(&mut self, tool: &str, args: &Value) -> Option<String> {
+        // serde_json keeps object keys sorted, so identical args print identically
+        let call = format!("{}:{}", tool, args);
+        let repeats = self.calls.iter().filter(|c| **c == call).count();
+        self.calls.push(call);
+
+        if repeats >= self.max_repeats {
+            Some(format!(
+                "{} was called with the same arguments {} times",
+                tool,
+                repeats + 1
+            ))
+        } else if self.calls.len() > self.max_iterations {
+            Some(format!(
+                "{} tool calls in one turn (limit {})",
+                self.calls.len(),
+                self.max_iterations
+            ))
+        } else {
+            None
+        }
+    }
+}
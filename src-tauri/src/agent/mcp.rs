@@ -0,0 +1,286 @@
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::agent::jsonrpc::RpcClient;
+use crate::agent::tools::{Tool, ToolResult};
+use crate::config::{McpServer, McpSettings};
+use crate::system::screenshot;
+
+/// MCP revision spoken to servers; they answer with the one they support
+const PROTOCOL_VERSION: &str = "2024-11-05";
+/// Starting a server and listing its tools; a server slower than this is skipped
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(20);
+/// Resources listed in the description of a server's resource tool
+const MAX_LISTED_RESOURCES: usize = 30;
+
+/// Handshake; returns the server's capabilities
+async fn initialize(client: &RpcClient) -> Result<Value> {
+    let result = client
+        .request(
+            "initialize",
+            json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": {},
+                "clientInfo": { "name": "Amadeus", "version": env!("CARGO_PKG_VERSION") }
+            }),
+        )
+        .await?;
+    client.notify("notifications/initialized");
+    Ok(result["capabilities"].clone())
+}
+
+/// Every item of a paginated list method (`tools/list`, `resources/list`)
+async fn list_all(client: &RpcClient, method: &str, key: &str) -> Result<Vec<Value>> {
+    let mut items = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let params = match &cursor {
+            Some(cursor) => json!({ "cursor": cursor }),
+            None => json!({}),
+        };
+        let page = client.request(method, params).await?;
+        if let Some(list) = page[key].as_array() {
+            items.extend(list.iter().cloned());
+        }
+        match page["nextCursor"].as_str() {
+            Some(next) if !next.is_empty() => cursor = Some(next.to_string()),
+            _ => return Ok(items),
+        }
+    }
+}
+
+/// Connect to every configured server at once and collect their tools. A server that
+/// fails to start or answer is reported and left out.
+pub async fn connect_all(settings: &McpSettings) -> Vec<Box<dyn Tool>> {
+    let connections = settings.servers.iter().map(|server| async move {
+        match tokio::time::timeout(CONNECT_TIMEOUT, connect(server)).await {
+            Ok(Ok(tools)) => {
+                info!("[MCP] {}: {} tool(s)", server.name, tools.len());
+                tools
+            }
+            Ok(Err(e)) => {
+                warn!("[MCP] {}: {}", server.name, e);
+                Vec::new()
+            }
+            Err(_) => {
+                warn!(
+                    "[MCP] {}: no answer within {:?}",
+                    server.name, CONNECT_TIMEOUT
+                );
+                Vec::new()
+            }
+        }
+    });
+    futures_util::future::join_all(connections)
+        .await
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+async fn connect(server: &McpServer) -> Result<Vec<Box<dyn Tool>>> {
+    let client = match (&server.command, &server.url) {
+        (Some(command), _) => {
+            RpcClient::spawn(&server.name, command, &server.args, &server.env).await?
+        }
+        (None, Some(url)) => RpcClient::connect_sse(&server.name, url).await?,
+        (None, None) => return Err(anyhow::anyhow!("Set either command or url")),
+    };
+    let capabilities = initialize(&client).await?;
+    let client = Arc::new(client);
+
+    let mut tools: Vec<Box<dyn Tool>> = Vec::new();
+    if capabilities.get("tools").is_some() {
+        for tool in list_all(&client, "tools/list", "tools").await? {
+            let Some(remote_name) = tool["name"].as_str() else {
+                continue;
+            };
+            tools.push(Box::new(McpTool {
+                client: Arc::clone(&client),
+                name: format!("{}.{}", server.name, remote_name),
+                remote_name: remote_name.to_string(),
+                description: format!(
+                    "{} (from the {} MCP server)",
+                    tool["description"].as_str().unwrap_or(remote_name),
+                    server.name
+                ),
+                parameters: tool
+                    .get("inputSchema")
+                    .cloned()
+                    .unwrap_or_else(|| json!({ "type": "object", "properties": {} })),
+                // readOnlyHint is the server's own claim, so it doesn't skip approval
+                trusted: server.trusted,
+            }));
+        }
+    }
+    if capabilities.get("resources").is_some() {
+        let resources = list_all(&client, "resources/list", "resources").await?;
+        if !resources.is_empty() {
+            tools.push(Box::new(McpResourceTool::new(
+                Arc::clone(&client),
+                &server.name,
+                &resources,
+            )));
+        }
+    }
+    Ok(tools)
+}
+
+/// Text parts joined, with the first image attached the way screenshot tools return one
+fn call_output(result: &Value) -> Result<String> {
+    let mut text = Vec::new();
+    let mut image = None;
+    for part in result["content"].as_array().into_iter().flatten() {
+        match part["type"].as_str() {
+            Some("text") => text.push(part["text"].as_str().unwrap_or_default().to_string()),
+            Some("image") if image.is_none() => image = part["data"].as_str(),
+            Some("resource") => {
+                if let Some(contents) = part["resource"]["text"].as_str() {
+                    text.push(contents.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+    let text = text.join("\n");
+    if result["isError"].as_bool() == Some(true) {
+        return Err(anyhow::anyhow!("{}", text));
+    }
+    Ok(match image {
+        Some(image) => screenshot::image_output(image, &text),
+        None => text,
+    })
+}
+
+/// A tool offered by an MCP server, named `<server>.<tool>`
+struct McpTool {
+    client: Arc<RpcClient>,
+    name: String,
+    remote_name: String,
+    description: String,
+    parameters: Value,
+    /// The server is trusted in config
+    trusted: bool,
+}
+
+impl Tool for McpTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn parameters(&self) -> Value {
+        self.parameters.clone()
+    }
+
+    fn execute(&self, args: Value) -> Pin<Box<dyn Future<Output = ToolResult> + Send>> {
+        let client = Arc::clone(&self.client);
+        let name = self.remote_name.clone();
+        Box::pin(async move {
+            let result = client
+                .request("tools/call", json!({ "name": name, "arguments": args }))
+                .await?;
+            call_output(&result)
+        })
+    }
+
+    /// Third-party code with unknown side effects asks first, unless the user trusts it
+    fn requires_approval(&self, _args: &Value) -> bool {
+        !self.trusted
+    }
+}
+
+/// Read access to a server's resources (files, records, ...), named `<server>.resources`
+struct McpResourceTool {
+    client: Arc<RpcClient>,
+    name: String,
+    description: String,
+}
+
+impl McpResourceTool {
+    fn new(client: Arc<RpcClient>, server: &str, resources: &[Value]) -> Self {
+        let mut listing: Vec<String> = resources
+            .iter()
+            .take(MAX_LISTED_RESOURCES)
+            .filter_map(|r| {
+                let uri = r["uri"].as_str()?;
+                Some(match r["name"].as_str() {
+                    Some(name) => format!("{} ({})", uri, name),
+                    None => uri.to_string(),
+                })
+            })
+            .collect();
+        if resources.len() > MAX_LISTED_RESOURCES {
+            listing.push(format!(
+                "... {} more",
+                resources.len() - MAX_LISTED_RESOURCES
+            ));
+        }
+        Self {
+            client,
+            name: format!("{}.resources", server),
+            description: format!(
+                "Read a resource from the {} MCP server by uri. Available: {}",
+                server,
+                listing.join(", ")
+            ),
+        }
+    }
+}
+
+impl Tool for McpResourceTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "uri": { "type": "string" }
+            },
+            "required": ["uri"]
+        })
+    }
+
+    fn execute(&self, args: Value) -> Pin<Box<dyn Future<Output = ToolResult> + Send>> {
+        let client = Arc::clone(&self.client);
+        Box::pin(async move {
+            let uri = args["uri"]
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("Missing uri"))?;
+            let result = client
+                .request("resources/read", json!({ "uri": uri }))
+                .await?;
+            let contents: Vec<String> = result["contents"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|part| match part["text"].as_str() {
+                    Some(text) => text.to_string(),
+                    None => format!(
+                        "[{} binary content not shown]",
+                        part["mimeType"].as_str().unwrap_or("unknown")
+                    ),
+                })
+                .collect();
+            Ok(contents.join("\n"))
+        })
+    }
+
+    fn is_cacheable(&self, _args: &Value) -> bool {
+        true
+    }
+}
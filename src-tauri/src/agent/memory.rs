@@ -1,14 +1,168 @@
+use crate::llm::ollama::{estimate_tokens, ReplyMetrics};
 use crate::llm::Message;
 use anyhow::Result;
+use futures_util::TryStreamExt;
+use serde::Serialize;
 use sqlx::{
     sqlite::{SqliteConnectOptions, SqlitePoolOptions},
     Pool, Row, Sqlite,
 };
 use std::str::FromStr;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 
 #[derive(Debug, Clone)]
 pub struct MemoryManager {
     pool: Pool<Sqlite>,
+    /// Conversation that message reads and writes are scoped to
+    active_conversation: Arc<AtomicI64>,
+}
+
+/// What a saved message is, beyond its role
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageKind {
+    Chat,
+    /// An assistant reply asking to run one or more tools
+    ToolCall,
+    /// Tool output fed back to the model (saved with the user role)
+    ToolResult,
+}
+
+impl MessageKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            MessageKind::Chat => "chat",
+            MessageKind::ToolCall => "tool_call",
+            MessageKind::ToolResult => "tool_result",
+        }
+    }
+
+    fn parse(kind: &str) -> Self {
+        match kind {
+            "tool_call" => MessageKind::ToolCall,
+            "tool_result" => MessageKind::ToolResult,
+            _ => MessageKind::Chat,
+        }
+    }
+}
+
+/// A message as saved, with its metadata
+#[derive(Debug, Clone, Serialize)]
+pub struct StoredMessage {
+    pub id: i64,
+    /// Random hex id that stays unique when messages are copied into a branch
+    pub uuid: String,
+    /// The conversation (session) it belongs to
+    pub conversation_id: Option<i64>,
+    pub role: String,
+    pub content: String,
+    pub kind: MessageKind,
+    /// Estimated tokens; missing for messages saved before they were counted
+    pub token_count: Option<i64>,
+    /// The message before it in its conversation, so a tool result points at its call
+    pub parent_id: Option<i64>,
+    pub pinned: bool,
+    pub timestamp: String,
+}
+
+impl StoredMessage {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Self {
+        Self {
+            id: row.get("id"),
+            uuid: row.get("uuid"),
+            conversation_id: row.get("conversation_id"),
+            role: row.get("role"),
+            content: row.get("content"),
+            kind: MessageKind::parse(row.get("kind")),
+            token_count: row.get("token_count"),
+            parent_id: row.get("parent_id"),
+            pinned: row.get("pinned"),
+            timestamp: row.get("timestamp"),
+        }
+    }
+
+    /// The message as sent to the model
+    pub fn into_message(self) -> Message {
+        Message {
+            role: self.role,
+            content: self.content,
+            images: None,
+        }
+    }
+}
+
+/// Columns `StoredMessage::from_row` reads
+const MESSAGE_COLUMNS: &str =
+    "id, uuid, conversation_id, role, content, kind, token_count, parent_id, pinned, timestamp";
+
+/// Columns `conversation_from_row` reads
+const CONVERSATION_COLUMNS: &str =
+    "id, persona_name, model, created_at, parent_id, branch_message_id,
+     (SELECT substr(content, 1, 80) FROM messages
+      WHERE conversation_id = conversations.id AND role = 'user'
+      ORDER BY id LIMIT 1) AS preview,
+     (SELECT substr(content, 1, 80) FROM messages
+      WHERE id = conversations.branch_message_id) AS branch_preview";
+
+/// A message matching a history search
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub message_id: i64,
+    pub conversation_id: Option<i64>,
+    pub role: String,
+    /// Excerpt around the match, with matched terms wrapped in `**`
+    pub snippet: String,
+    pub timestamp: String,
+}
+
+/// A conversation and the setup it was pinned to
+#[derive(Debug, Clone, Serialize)]
+pub struct Conversation {
+    pub id: i64,
+    pub persona_name: String,
+    pub model: String,
+    pub created_at: String,
+    /// Conversation this one was branched from, if any
+    pub parent_id: Option<i64>,
+    /// Message in the parent the branch was taken after
+    pub branch_message_id: Option<i64>,
+    /// Start of the first user message, to tell conversations apart in a list
+    pub preview: Option<String>,
+    /// Start of the message the branch was taken after, while it still exists
+    pub branch_preview: Option<String>,
+}
+
+/// A pending reminder; times are local ("2025-01-31 15:00:00")
+#[derive(Debug, Clone, Serialize)]
+pub struct Reminder {
+    pub id: i64,
+    pub message: String,
+    pub due_at: String,
+}
+
+/// One tool call as recorded in the audit log; `timestamp` is local time
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolAuditEntry {
+    pub id: i64,
+    pub timestamp: String,
+    pub tool: String,
+    /// Arguments as JSON
+    pub args: String,
+    /// "ok", "cached", "error", "timeout" or "denied"
+    pub status: String,
+    /// Start of the result, or the error message
+    pub output: String,
+    pub duration_ms: i64,
+}
+
+/// When a new reminder is due
+pub enum ReminderTime {
+    /// This many minutes from now
+    InMinutes(u32),
+    /// Local "HH:MM" (the next time it comes round) or "YYYY-MM-DD HH:MM"
+    Local(String),
 }
 
 impl MemoryManager {
@@ -20,8 +174,27 @@ impl MemoryManager {
             .max_connections(5)
             .connect_with(options)
             .await?;
+        Self::with_pool(pool).await
+    }
 
-        let manager = Self { pool };
+    /// A throwaway database that lives as long as the manager
+    #[cfg(test)]
+    pub async fn in_memory() -> Result<Self> {
+        // Every connection to :memory: opens its own database, so the pool keeps just one
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect_with(SqliteConnectOptions::from_str("sqlite::memory:")?)
+            .await?;
+        Self::with_pool(pool).await
+    }
+
+    async fn with_pool(pool: Pool<Sqlite>) -> Result<Self> {
+        let manager = Self {
+            pool,
+            active_conversation: Arc::new(AtomicI64::new(0)),
+        };
         manager.init_tables().await?;
 
         Ok(manager)
@@ -39,36 +212,558 @@ impl MemoryManager {
         .execute(&self.pool)
         .await?;
 
+        self.ensure_column("messages", "pinned", "INTEGER NOT NULL DEFAULT 0")
+            .await?;
+        self.ensure_column("messages", "conversation_id", "INTEGER")
+            .await?;
+        self.init_message_metadata().await?;
+        // Older versions saved the system prompt of the time as the first message
+        sqlx::query("DELETE FROM messages WHERE role = 'system'")
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS conversations (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                persona_name TEXT NOT NULL,
+                model TEXT NOT NULL,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        self.ensure_column("conversations", "parent_id", "INTEGER")
+            .await?;
+        self.ensure_column("conversations", "branch_message_id", "INTEGER")
+            .await?;
+        self.ensure_column("conversations", "summary", "TEXT")
+            .await?;
+
+        self.init_search_index().await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS memories (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                content TEXT NOT NULL,
+                embedding BLOB NOT NULL,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS reminders (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                message TEXT NOT NULL,
+                due_at DATETIME NOT NULL,
+                fired INTEGER NOT NULL DEFAULT 0,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS tool_audit (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                tool TEXT NOT NULL,
+                args TEXT NOT NULL,
+                status TEXT NOT NULL,
+                output TEXT NOT NULL,
+                duration_ms INTEGER NOT NULL DEFAULT 0,
+                timestamp DATETIME DEFAULT CURRENT_TIMESTAMP
+            )",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS metrics (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                conversation_id INTEGER,
+                model TEXT NOT NULL,
+                prompt_tokens INTEGER NOT NULL,
+                generated_tokens INTEGER NOT NULL,
+                load_ms INTEGER NOT NULL,
+                prompt_eval_ms INTEGER NOT NULL,
+                generation_ms INTEGER NOT NULL,
+                timestamp DATETIME DEFAULT CURRENT_TIMESTAMP
+            )",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS kv_memory (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL,
+                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Columns added to messages after the first release, filled in for existing rows
+    /// when they are created
+    async fn init_message_metadata(&self) -> Result<()> {
+        if self.ensure_column("messages", "uuid", "TEXT").await? {
+            sqlx::query("UPDATE messages SET uuid = lower(hex(randomblob(16)))")
+                .execute(&self.pool)
+                .await?;
+        }
+        sqlx::query("CREATE UNIQUE INDEX IF NOT EXISTS idx_messages_uuid ON messages (uuid)")
+            .execute(&self.pool)
+            .await?;
+
+        if self
+            .ensure_column("messages", "kind", "TEXT NOT NULL DEFAULT 'chat'")
+            .await?
+        {
+            sqlx::query(
+                "UPDATE messages SET kind = CASE
+                    WHEN role = 'assistant' AND ltrim(content) LIKE '{%'
+                         AND (content LIKE '%\"tool\"%' OR content LIKE '%\"tools\"%')
+                        THEN 'tool_call'
+                    WHEN role = 'user'
+                         AND (content LIKE 'Tool Output:%' OR content LIKE 'Tool Error:%')
+                        THEN 'tool_result'
+                    ELSE 'chat'
+                 END",
+            )
+            .execute(&self.pool)
+            .await?;
+        }
+
+        self.ensure_column("messages", "token_count", "INTEGER")
+            .await?;
+
+        if self
+            .ensure_column("messages", "parent_id", "INTEGER")
+            .await?
+        {
+            sqlx::query(
+                "UPDATE messages SET parent_id = (
+                    SELECT MAX(p.id) FROM messages p
+                    WHERE p.conversation_id IS messages.conversation_id AND p.id < messages.id
+                 )",
+            )
+            .execute(&self.pool)
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// FTS5 index over message content, kept in sync by triggers.
+    /// Built from existing messages the first time it is created.
+    async fn init_search_index(&self) -> Result<()> {
+        let exists: Option<String> = sqlx::query_scalar(
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name = 'messages_fts'",
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts
+             USING fts5(content, content='messages', content_rowid='id')",
+        )
+        .execute(&self.pool)
+        .await?;
+        for trigger in [
+            "CREATE TRIGGER IF NOT EXISTS messages_fts_insert AFTER INSERT ON messages BEGIN
+                INSERT INTO messages_fts(rowid, content) VALUES (new.id, new.content);
+             END",
+            "CREATE TRIGGER IF NOT EXISTS messages_fts_delete AFTER DELETE ON messages BEGIN
+                INSERT INTO messages_fts(messages_fts, rowid, content)
+                VALUES ('delete', old.id, old.content);
+             END",
+            "CREATE TRIGGER IF NOT EXISTS messages_fts_update AFTER UPDATE OF content ON messages BEGIN
+                INSERT INTO messages_fts(messages_fts, rowid, content)
+                VALUES ('delete', old.id, old.content);
+                INSERT INTO messages_fts(rowid, content) VALUES (new.id, new.content);
+             END",
+        ] {
+            sqlx::query(trigger).execute(&self.pool).await?;
+        }
+
+        if exists.is_none() {
+            sqlx::query("INSERT INTO messages_fts(messages_fts) VALUES ('rebuild')")
+                .execute(&self.pool)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Add a column to an existing table if it is missing (lightweight migration).
+    /// Returns whether it was added.
+    async fn ensure_column(&self, table: &str, column: &str, definition: &str) -> Result<bool> {
+        let rows = sqlx::query(&format!("PRAGMA table_info({})", table))
+            .fetch_all(&self.pool)
+            .await?;
+        let exists = rows
+            .iter()
+            .any(|row| row.get::<String, _>("name") == column);
+
+        if !exists {
+            sqlx::query(&format!(
+                "ALTER TABLE {} ADD COLUMN {} {}",
+                table, column, definition
+            ))
+            .execute(&self.pool)
+            .await?;
+        }
+        Ok(!exists)
+    }
+
+    pub fn active_conversation(&self) -> i64 {
+        self.active_conversation.load(Ordering::SeqCst)
+    }
+
+    /// Scope subsequent message reads and writes to conversation `id`
+    pub fn set_active_conversation(&self, id: i64) {
+        self.active_conversation.store(id, Ordering::SeqCst);
+    }
+
+    pub async fn create_conversation(&self, persona_name: &str, model: &str) -> Result<i64> {
+        let result = sqlx::query("INSERT INTO conversations (persona_name, model) VALUES (?, ?)")
+            .bind(persona_name)
+            .bind(model)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.last_insert_rowid())
+    }
+
+    pub async fn get_conversation(&self, id: i64) -> Result<Option<Conversation>> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM conversations WHERE id = ?",
+            CONVERSATION_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(|row| Self::conversation_from_row(&row)))
+    }
+
+    pub async fn list_conversations(&self) -> Result<Vec<Conversation>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM conversations ORDER BY id DESC",
+            CONVERSATION_COLUMNS
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.iter().map(Self::conversation_from_row).collect())
+    }
+
+    /// Most recent conversation, creating one with the given setup if none exist.
+    /// Messages saved before conversations existed are adopted by it.
+    pub async fn resume_latest_conversation(
+        &self,
+        persona_name: &str,
+        model: &str,
+    ) -> Result<Conversation> {
+        let latest = match self.list_conversations().await?.into_iter().next() {
+            Some(conversation) => conversation,
+            None => {
+                let id = self.create_conversation(persona_name, model).await?;
+                self.get_conversation(id)
+                    .await?
+                    .ok_or_else(|| anyhow::anyhow!("Conversation {} vanished", id))?
+            }
+        };
+
+        sqlx::query("UPDATE messages SET conversation_id = ? WHERE conversation_id IS NULL")
+            .bind(latest.id)
+            .execute(&self.pool)
+            .await?;
+        Ok(latest)
+    }
+
+    /// Pin the persona/model a conversation resumes with
+    pub async fn set_conversation_setup(
+        &self,
+        id: i64,
+        persona_name: &str,
+        model: &str,
+    ) -> Result<()> {
+        sqlx::query("UPDATE conversations SET persona_name = ?, model = ? WHERE id = ?")
+            .bind(persona_name)
+            .bind(model)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Delete a conversation and its messages. Branches taken from it are kept but
+    /// no longer point at it. Returns false if it didn't exist.
+    pub async fn delete_conversation(&self, id: i64) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM messages WHERE conversation_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "UPDATE conversations SET parent_id = NULL, branch_message_id = NULL
+             WHERE parent_id = ?",
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        let result = sqlx::query("DELETE FROM conversations WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Rolling summary of the messages trimmed out of this conversation's prompt
+    pub async fn conversation_summary(&self, id: i64) -> Result<Option<String>> {
+        let summary: Option<Option<String>> =
+            sqlx::query_scalar("SELECT summary FROM conversations WHERE id = ?")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(summary.flatten())
+    }
+
+    pub async fn set_conversation_summary(&self, id: i64, summary: Option<&str>) -> Result<()> {
+        sqlx::query("UPDATE conversations SET summary = ? WHERE id = ?")
+            .bind(summary)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
         Ok(())
     }
 
+    fn conversation_from_row(row: &sqlx::sqlite::SqliteRow) -> Conversation {
+        Conversation {
+            id: row.get("id"),
+            persona_name: row.get("persona_name"),
+            model: row.get("model"),
+            created_at: row.get("created_at"),
+            parent_id: row.get("parent_id"),
+            branch_message_id: row.get("branch_message_id"),
+            preview: row.get("preview"),
+            branch_preview: row.get("branch_preview"),
+        }
+    }
+
     pub async fn save_message(&self, message: &Message) -> Result<()> {
-        sqlx::query("INSERT INTO messages (role, content) VALUES (?, ?)")
+        self.save_turn(std::slice::from_ref(message)).await?;
+        Ok(())
+    }
+
+    /// Save every message of one turn (user, assistant, tool output) in a single transaction,
+    /// so a crash mid-turn leaves either the whole turn or none of it. System messages are
+    /// skipped: the prompt is rebuilt for every turn and never belongs in history.
+    /// Returns the row ids of the saved messages, in order.
+    #[tracing::instrument(level = "debug", skip_all, fields(messages = messages.len()))]
+    pub async fn save_turn(&self, messages: &[Message]) -> Result<Vec<i64>> {
+        let conversation_id = self.active_conversation();
+        let mut tx = self.pool.begin().await?;
+        let mut parent: Option<i64> =
+            sqlx::query_scalar("SELECT MAX(id) FROM messages WHERE conversation_id = ?")
+                .bind(conversation_id)
+                .fetch_one(&mut *tx)
+                .await?;
+        let mut previous = MessageKind::Chat;
+        let mut ids = Vec::with_capacity(messages.len());
+        for message in messages.iter().filter(|m| m.role != "system") {
+            // Within a turn, whatever the user role sends after a tool call is its output
+            let kind =
+                if message.role == "assistant" && crate::looks_like_tool_call(&message.content) {
+                    MessageKind::ToolCall
+                } else if message.role == "user" && previous == MessageKind::ToolCall {
+                    MessageKind::ToolResult
+                } else {
+                    MessageKind::Chat
+                };
+            let result = sqlx::query(
+                "INSERT INTO messages
+                    (uuid, role, content, kind, token_count, parent_id, conversation_id)
+                 VALUES (lower(hex(randomblob(16))), ?, ?, ?, ?, ?, ?)",
+            )
             .bind(&message.role)
             .bind(&message.content)
+            .bind(kind.as_str())
+            .bind(estimate_tokens(message) as i64)
+            .bind(parent)
+            .bind(conversation_id)
+            .execute(&mut *tx)
+            .await?;
+            let id = result.last_insert_rowid();
+            ids.push(id);
+            parent = Some(id);
+            previous = kind;
+        }
+        tx.commit().await?;
+        Ok(ids)
+    }
+
+    /// Extend the most recent message with `role`, e.g. when a cut-off reply is continued
+    pub async fn append_to_last_message(&self, role: &str, extra: &str) -> Result<()> {
+        let last: Option<(i64, String)> = sqlx::query_as(
+            "SELECT id, content FROM messages
+             WHERE id = (SELECT MAX(id) FROM messages WHERE role = ? AND conversation_id = ?)",
+        )
+        .bind(role)
+        .bind(self.active_conversation())
+        .fetch_optional(&self.pool)
+        .await?;
+        if let Some((id, content)) = last {
+            self.update_message(id, &(content + extra)).await?;
+        }
+        Ok(())
+    }
+
+    /// Row id of the last message the user typed in the active conversation, skipping tool
+    /// output and anything else the agent sent with the user role
+    pub async fn last_typed_message_id(&self) -> Result<Option<i64>> {
+        let mut rows = sqlx::query_as::<_, (i64, String)>(
+            "SELECT id, content FROM messages
+             WHERE role = 'user' AND kind = ? AND conversation_id = ?
+             ORDER BY id DESC",
+        )
+        .bind(MessageKind::Chat.as_str())
+        .bind(self.active_conversation())
+        .fetch(&self.pool);
+        while let Some((id, content)) = rows.try_next().await? {
+            if crate::typed_by_user(&content) {
+                return Ok(Some(id));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Replace a message's content and count its tokens again
+    pub async fn update_message(&self, id: i64, content: &str) -> Result<()> {
+        let tokens = estimate_tokens(&Message {
+            role: String::new(),
+            content: content.to_string(),
+            images: None,
+        });
+        sqlx::query("UPDATE messages SET content = ?, token_count = ? WHERE id = ?")
+            .bind(content)
+            .bind(tokens as i64)
+            .bind(id)
             .execute(&self.pool)
             .await?;
         Ok(())
     }
 
-    pub async fn get_recent_history(&self, limit: i64) -> Result<Vec<Message>> {
-        let rows = sqlx::query("SELECT role, content FROM messages ORDER BY id DESC LIMIT ?")
-            .bind(limit)
-            .fetch_all(&self.pool)
+    /// Delete one message. Its replies are re-parented onto its own parent so the chain
+    /// stays unbroken.
+    pub async fn delete_message(&self, id: i64) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "UPDATE messages SET parent_id = (SELECT parent_id FROM messages WHERE id = ?)
+             WHERE parent_id = ?",
+        )
+        .bind(id)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM messages WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
             .await?;
+        tx.commit().await?;
+        Ok(())
+    }
 
-        let mut messages = Vec::new();
-        for row in rows {
-            messages.push(Message {
-                role: row.get("role"),
-                content: row.get("content"),
-                images: None,
-            });
-        }
+    /// Delete every message saved after `id` in its conversation, e.g. the reply to a user
+    /// message that is answered again. Returns how many were removed.
+    pub async fn delete_messages_after(&self, id: i64) -> Result<u64> {
+        let result = sqlx::query(
+            "DELETE FROM messages WHERE id > ?
+             AND conversation_id = (SELECT conversation_id FROM messages WHERE id = ?)",
+        )
+        .bind(id)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// One page of the active conversation going back in time: up to `limit` messages
+    /// saved before `before_id`, or the latest ones without it. Oldest first.
+    pub async fn get_history(
+        &self,
+        before_id: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<StoredMessage>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM messages WHERE conversation_id = ? AND (? IS NULL OR id < ?)
+             ORDER BY id DESC LIMIT ?",
+            MESSAGE_COLUMNS
+        ))
+        .bind(self.active_conversation())
+        .bind(before_id)
+        .bind(before_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
 
         // Reverse to get chronological order
-        messages.reverse();
-        Ok(messages)
+        Ok(rows.iter().rev().map(StoredMessage::from_row).collect())
+    }
+
+    /// Fork the conversation containing `from_message_id` into a new conversation holding
+    /// copies of every message up to and including it. Returns the new conversation id.
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn branch_conversation(&self, from_message_id: i64) -> Result<i64> {
+        let mut tx = self.pool.begin().await?;
+
+        let source: Option<i64> =
+            sqlx::query_scalar("SELECT conversation_id FROM messages WHERE id = ?")
+                .bind(from_message_id)
+                .fetch_optional(&mut *tx)
+                .await?
+                .flatten();
+        let source =
+            source.ok_or_else(|| anyhow::anyhow!("Message {} not found", from_message_id))?;
+
+        let result = sqlx::query(
+            "INSERT INTO conversations (persona_name, model, parent_id, branch_message_id)
+             SELECT persona_name, model, id, ? FROM conversations WHERE id = ?",
+        )
+        .bind(from_message_id)
+        .bind(source)
+        .execute(&mut *tx)
+        .await?;
+        let branch_id = result.last_insert_rowid();
+
+        sqlx::query(
+            "INSERT INTO messages
+                (uuid, role, content, kind, token_count, timestamp, pinned, conversation_id)
+             SELECT lower(hex(randomblob(16))), role, content, kind, token_count, timestamp,
+                    pinned, ?
+             FROM messages WHERE conversation_id = ? AND id <= ? ORDER BY id",
+        )
+        .bind(branch_id)
+        .bind(source)
+        .bind(from_message_id)
+        .execute(&mut *tx)
+        .await?;
+        // Chain the copies to each other, not to the originals
+        sqlx::query(
+            "UPDATE messages SET parent_id = (
+                SELECT MAX(p.id) FROM messages p
+                WHERE p.conversation_id = messages.conversation_id AND p.id < messages.id
+             )
+             WHERE conversation_id = ?",
+        )
+        .bind(branch_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(branch_id)
     }
 
     #[allow(dead_code)]
@@ -78,4 +773,453 @@ impl MemoryManager {
             .await?;
         Ok(())
     }
+
+    /// Pinned messages survive `prune`
+    pub async fn set_pinned(&self, id: i64, pinned: bool) -> Result<()> {
+        sqlx::query("UPDATE messages SET pinned = ? WHERE id = ?")
+            .bind(pinned)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Current database size in bytes
+    pub async fn db_size(&self) -> Result<i64> {
+        let page_count: i64 = sqlx::query_scalar("PRAGMA page_count")
+            .fetch_one(&self.pool)
+            .await?;
+        let page_size: i64 = sqlx::query_scalar("PRAGMA page_size")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(page_count * page_size)
+    }
+
+    /// Rebuild the database file to reclaim space freed by deletes
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn vacuum(&self) -> Result<()> {
+        sqlx::query("VACUUM").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    /// Delete messages older than `older_than`, and tool audit entries and metrics along
+    /// with them.
+    /// Pinned messages are always kept.
+    /// Returns how many messages were removed.
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn prune(&self, older_than: Duration) -> Result<u64> {
+        let cutoff = format!("-{} seconds", older_than.as_secs());
+        let mut tx = self.pool.begin().await?;
+        let result =
+            sqlx::query("DELETE FROM messages WHERE pinned = 0 AND timestamp < datetime('now', ?)")
+                .bind(&cutoff)
+                .execute(&mut *tx)
+                .await?;
+        // Messages that followed a pruned one (pinned ones, or newer replies) now start
+        // their chain instead of pointing at a row that is gone
+        sqlx::query(
+            "UPDATE messages SET parent_id = NULL
+             WHERE parent_id IS NOT NULL AND parent_id NOT IN (SELECT id FROM messages)",
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM tool_audit WHERE timestamp < datetime('now', ?)")
+            .bind(&cutoff)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM metrics WHERE timestamp < datetime('now', ?)")
+            .bind(&cutoff)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Messages in any conversation containing every word of `query`, best match first.
+    /// Words are matched literally (no FTS5 operators), with prefix matching on the last one.
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn search_history(&self, query: &str, limit: i64) -> Result<Vec<SearchHit>> {
+        let words: Vec<String> = query
+            .split_whitespace()
+            .map(|w| format!("\"{}\"", w.replace('"', "\"\"")))
+            .collect();
+        let Some(last) = words.last() else {
+            return Ok(Vec::new());
+        };
+        let pattern = format!("{} {}*", words[..words.len() - 1].join(" "), last);
+
+        let rows = sqlx::query(
+            "SELECT m.id, m.conversation_id, m.role, m.timestamp,
+                    snippet(messages_fts, 0, '**', '**', '…', 12) AS snippet
+             FROM messages_fts
+             JOIN messages m ON m.id = messages_fts.rowid
+             WHERE messages_fts MATCH ? AND m.role != 'system'
+             ORDER BY bm25(messages_fts)
+             LIMIT ?",
+        )
+        .bind(pattern.trim_start())
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| SearchHit {
+                message_id: row.get("id"),
+                conversation_id: row.get("conversation_id"),
+                role: row.get("role"),
+                snippet: row.get("snippet"),
+                timestamp: row.get("timestamp"),
+            })
+            .collect())
+    }
+
+    // ===== Key/Value Scratchpad =====
+
+    pub async fn kv_set(&self, key: &str, value: &str) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO kv_memory (key, value) VALUES (?, ?)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = CURRENT_TIMESTAMP",
+        )
+        .bind(key)
+        .bind(value)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn kv_get(&self, key: &str) -> Result<Option<String>> {
+        let value = sqlx::query_scalar("SELECT value FROM kv_memory WHERE key = ?")
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(value)
+    }
+
+    pub async fn kv_list(&self) -> Result<Vec<(String, String)>> {
+        let rows = sqlx::query("SELECT key, value FROM kv_memory ORDER BY key")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.get("key"), row.get("value")))
+            .collect())
+    }
+
+    pub async fn kv_delete(&self, key: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM kv_memory WHERE key = ?")
+            .bind(key)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    // ===== Long-term Memories =====
+
+    /// Store a fact with its embedding (little-endian f32s)
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn add_memory(&self, content: &str, embedding: &[f32]) -> Result<i64> {
+        let bytes: Vec<u8> = embedding.iter().flat_map(|v| v.to_le_bytes()).collect();
+        let result = sqlx::query("INSERT INTO memories (content, embedding) VALUES (?, ?)")
+            .bind(content)
+            .bind(bytes)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.last_insert_rowid())
+    }
+
+    /// Every stored fact with its embedding, oldest first
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn memories(&self) -> Result<Vec<(i64, String, Vec<f32>)>> {
+        let rows = sqlx::query("SELECT id, content, embedding FROM memories ORDER BY id")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let bytes: Vec<u8> = row.get("embedding");
+                let embedding = bytes
+                    .chunks_exact(4)
+                    .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                    .collect();
+                (row.get("id"), row.get("content"), embedding)
+            })
+            .collect())
+    }
+
+    pub async fn delete_memory(&self, id: i64) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM memories WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    // ===== Reminders =====
+    // Stored in UTC like the other timestamps; SQLite converts to and from local time
+
+    pub async fn add_reminder(&self, message: &str, when: &ReminderTime) -> Result<Reminder> {
+        let row = match when {
+            ReminderTime::InMinutes(minutes) => {
+                sqlx::query(
+                    "INSERT INTO reminders (message, due_at) VALUES (?, datetime('now', ?))
+                     RETURNING id, message, datetime(due_at, 'localtime') AS due_at",
+                )
+                .bind(message)
+                .bind(format!("+{} minutes", minutes))
+                .fetch_one(&self.pool)
+                .await?
+            }
+            ReminderTime::Local(time) => {
+                let due_at: Option<String> = if time.trim().len() <= 5 {
+                    // A time of day: today if it is still ahead, otherwise tomorrow
+                    sqlx::query_scalar(
+                        "SELECT datetime(date('now', 'localtime') || ' ' || ?1,
+                             CASE WHEN datetime(date('now', 'localtime') || ' ' || ?1)
+                                       <= datetime('now', 'localtime')
+                                  THEN '+1 day' ELSE '+0 days' END,
+                             'utc')",
+                    )
+                    .bind(format!("{:0>5}", time.trim()))
+                    .fetch_one(&self.pool)
+                    .await?
+                } else {
+                    sqlx::query_scalar("SELECT datetime(?, 'utc')")
+                        .bind(time.trim())
+                        .fetch_one(&self.pool)
+                        .await?
+                };
+                let due_at = due_at.ok_or_else(|| {
+                    anyhow::anyhow!("Invalid time '{}' (use HH:MM or YYYY-MM-DD HH:MM)", time)
+                })?;
+                sqlx::query(
+                    "INSERT INTO reminders (message, due_at) VALUES (?, ?)
+                     RETURNING id, message, datetime(due_at, 'localtime') AS due_at",
+                )
+                .bind(message)
+                .bind(due_at)
+                .fetch_one(&self.pool)
+                .await?
+            }
+        };
+        Ok(Reminder {
+            id: row.get("id"),
+            message: row.get("message"),
+            due_at: row.get("due_at"),
+        })
+    }
+
+    /// Reminders that haven't fired yet, soonest first
+    pub async fn pending_reminders(&self) -> Result<Vec<Reminder>> {
+        self.query_reminders(
+            "SELECT id, message, datetime(due_at, 'localtime') AS due_at FROM reminders
+             WHERE fired = 0 ORDER BY due_at",
+        )
+        .await
+    }
+
+    /// Reminders whose time has come and that haven't fired yet
+    pub async fn due_reminders(&self) -> Result<Vec<Reminder>> {
+        self.query_reminders(
+            "SELECT id, message, datetime(due_at, 'localtime') AS due_at FROM reminders
+             WHERE fired = 0 AND due_at <= datetime('now') ORDER BY due_at",
+        )
+        .await
+    }
+
+    async fn query_reminders(&self, sql: &str) -> Result<Vec<Reminder>> {
+        let rows = sqlx::query(sql).fetch_all(&self.pool).await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| Reminder {
+                id: row.get("id"),
+                message: row.get("message"),
+                due_at: row.get("due_at"),
+            })
+            .collect())
+    }
+
+    pub async fn mark_reminder_fired(&self, id: i64) -> Result<()> {
+        sqlx::query("UPDATE reminders SET fired = 1 WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_reminder(&self, id: i64) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM reminders WHERE id = ? AND fired = 0")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn add_tool_audit(
+        &self,
+        tool: &str,
+        args: &str,
+        status: &str,
+        output: &str,
+        duration: Duration,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO tool_audit (tool, args, status, output, duration_ms)
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(tool)
+        .bind(args)
+        .bind(status)
+        .bind(output)
+        .bind(duration.as_millis() as i64)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Record the token counts and timings of a turn in the active conversation
+    pub async fn add_metrics(&self, model: &str, metrics: &ReplyMetrics) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO metrics (conversation_id, model, prompt_tokens, generated_tokens,
+                                  load_ms, prompt_eval_ms, generation_ms)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(self.active_conversation())
+        .bind(model)
+        .bind(metrics.prompt_tokens as i64)
+        .bind(metrics.generated_tokens as i64)
+        .bind(metrics.load_ms as i64)
+        .bind(metrics.prompt_eval_ms as i64)
+        .bind(metrics.generation_ms as i64)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Most recent tool calls first, optionally only those of `tool`
+    pub async fn tool_audit(&self, limit: i64, tool: Option<&str>) -> Result<Vec<ToolAuditEntry>> {
+        let rows = sqlx::query(
+            "SELECT id, datetime(timestamp, 'localtime') AS timestamp, tool, args, status,
+                    output, duration_ms
+             FROM tool_audit WHERE ?1 IS NULL OR tool = ?1
+             ORDER BY id DESC LIMIT ?2",
+        )
+        .bind(tool)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| ToolAuditEntry {
+                id: row.get("id"),
+                timestamp: row.get("timestamp"),
+                tool: row.get("tool"),
+                args: row.get("args"),
+                status: row.get("status"),
+                output: row.get("output"),
+                duration_ms: row.get("duration_ms"),
+            })
+            .collect())
+    }
+
+    /// Current local time, as reminders show it
+    pub async fn local_now(&self) -> Result<String> {
+        Ok(sqlx::query_scalar("SELECT datetime('now', 'localtime')")
+            .fetch_one(&self.pool)
+            .await?)
+    }
+
+    /// Seconds until the next local "HH:MM" (today if still ahead, otherwise tomorrow)
+    pub async fn seconds_until(&self, time: &str) -> Result<u64> {
+        let seconds: Option<i64> = sqlx::query_scalar(
+            "SELECT strftime('%s', datetime(date('now', 'localtime') || ' ' || ?1,
+                        CASE WHEN datetime(date('now', 'localtime') || ' ' || ?1)
+                                  <= datetime('now', 'localtime')
+                             THEN '+1 day' ELSE '+0 days' END,
+                        'utc')) - strftime('%s', 'now')",
+        )
+        .bind(format!("{:0>5}", time.trim()))
+        .fetch_one(&self.pool)
+        .await?;
+        seconds
+            .map(|s| s.max(0) as u64)
+            .ok_or_else(|| anyhow::anyhow!("Invalid time '{}' (use HH:MM)", time))
+    }
+}
+
+/// Empty in-memory database with one open conversation, for tests
+#[cfg(test)]
+pub async fn test_memory() -> MemoryManager {
+    let memory = MemoryManager::in_memory().await.unwrap();
+    let conversation = memory.create_conversation("Amadeus", "test").await.unwrap();
+    memory.set_active_conversation(conversation);
+    memory
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn saved_contents(memory: &MemoryManager) -> Vec<String> {
+        sqlx::query_scalar("SELECT content FROM messages ORDER BY id")
+            .fetch_all(&memory.pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn save_turn_keeps_nothing_when_a_write_fails() {
+        let memory = test_memory().await;
+        memory
+            .save_turn(&[
+                Message::new("user", "Hi"),
+                Message::new("assistant", "Hello."),
+            ])
+            .await
+            .unwrap();
+        // Fails the second insert of the next turn, after the first has gone through
+        sqlx::query(
+            "CREATE TRIGGER fail_reply BEFORE INSERT ON messages
+             WHEN NEW.content = 'Unsaveable reply.'
+             BEGIN SELECT RAISE(ABORT, 'forced failure'); END",
+        )
+        .execute(&memory.pool)
+        .await
+        .unwrap();
+
+        let turn = [
+            Message::new("user", "What time is it?"),
+            Message::new("assistant", "Unsaveable reply."),
+        ];
+        assert!(memory.save_turn(&turn).await.is_err());
+
+        assert_eq!(saved_contents(&memory).await, ["Hi", "Hello."]);
+    }
+
+    #[tokio::test]
+    async fn prune_keeps_pinned_messages_and_leaves_no_dangling_parents() {
+        let memory = test_memory().await;
+        let ids = memory
+            .save_turn(&[
+                Message::new("user", "Old question"),
+                Message::new("assistant", "Old answer worth keeping"),
+                Message::new("user", "Another old question"),
+                Message::new("assistant", "Recent answer"),
+            ])
+            .await
+            .unwrap();
+        sqlx::query("UPDATE messages SET timestamp = '2000-01-01 00:00:00' WHERE id < ?")
+            .bind(ids[3])
+            .execute(&memory.pool)
+            .await
+            .unwrap();
+        memory.set_pinned(ids[1], true).await.unwrap();
+
+        let pruned = memory.prune(Duration::from_secs(86_400)).await.unwrap();
+
+        assert_eq!(pruned, 2);
+        let kept = memory.get_history(None, 10).await.unwrap();
+        let kept: Vec<(i64, Option<i64>)> = kept.iter().map(|m| (m.id, m.parent_id)).collect();
+        assert_eq!(kept, [(ids[1], None), (ids[3], None)]);
+    }
 }
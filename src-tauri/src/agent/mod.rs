@@ -1,3 +1,23 @@
+pub mod approval;
+pub mod commands;
+pub mod context;
+pub mod emotion;
+pub mod events;
+pub mod export;
+pub mod external;
+pub mod input;
+pub mod jsonrpc;
+pub mod loop_guard;
+pub mod mcp;
 pub mod memory;
 pub mod persona;
+pub mod plugins;
+pub mod proactive;
+pub mod recall;
+pub mod reminders;
+pub mod replay;
+pub mod schema;
+pub mod scratchpad;
+pub mod timers;
+pub mod toolcall;
 pub mod tools;
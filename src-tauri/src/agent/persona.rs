@@ -1,9 +1,59 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::SystemTime;
+use tracing::warn;
+
+/// Directory scanned for user-defined personas (`*.toml`), next to `amadeus.toml`
+pub const PERSONA_DIR: &str = "personas";
+
+/// A character the assistant plays. Built-in personas live in code; more can be added as
+/// `personas/<name>.toml` files with the same fields (only `name` and `system_prompt` required).
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Persona {
     pub name: String,
     pub system_prompt: String,
+    /// macOS `say` voice (e.g. "Kyoko"); `None` keeps the system voice
+    #[serde(default)]
+    pub voice: Option<String>,
+    /// Language replies should be written in, added to the prompt when set
+    #[serde(default)]
+    pub language: Option<String>,
+    /// VRM model shown in the avatar view, as a URL path served by the frontend
+    #[serde(default)]
+    pub avatar_model: Option<String>,
+    /// Said when the persona is switched to
+    #[serde(default)]
+    pub greeting: Option<String>,
 }
 
 impl Persona {
+    /// Look up a persona by name (case-insensitive). Files in `personas/` take precedence
+    /// over a built-in persona with the same name.
+    pub fn by_name(name: &str) -> Option<Self> {
+        let wanted = name.to_lowercase();
+        if let Some(persona) = load_dir(Path::new(PERSONA_DIR))
+            .into_iter()
+            .find(|p| p.name.to_lowercase() == wanted)
+        {
+            return Some(persona);
+        }
+        match wanted.as_str() {
+            "amadeus" | "kurisu" => Some(Self::amadeus()),
+            _ => None,
+        }
+    }
+
+    /// Names of every available persona, built-in first
+    pub fn list() -> Vec<String> {
+        let mut names = vec![Self::amadeus().name];
+        for persona in load_dir(Path::new(PERSONA_DIR)) {
+            if !names.iter().any(|n| n.eq_ignore_ascii_case(&persona.name)) {
+                names.push(persona.name);
+            }
+        }
+        names
+    }
+
     pub fn amadeus() -> Self {
         Self {
             name: "Amadeus".to_string(),
@@ -20,6 +70,66 @@ CRITICAL RULES:
 
 You are running locally on the user's Mac and have access to system tools, but you should only use them when specifically requested.
 ".to_string(),
+            voice: None,
+            language: None,
+            avatar_model: None,
+            greeting: None,
         }
     }
 }
+
+/// Parse every `*.toml` in `dir`, sorted by file name. Invalid files are reported and skipped.
+fn load_dir(dir: &Path) -> Vec<Persona> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let text = std::fs::read_to_string(&path).ok()?;
+            match toml::from_str(&text) {
+                Ok(persona) => Some(persona),
+                Err(e) => {
+                    warn!("[Persona] Invalid {}: {}", path.display(), e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Latest modification time among the persona files, or `None` if there are none.
+/// Polled by the agent loop to pick up edits without a restart.
+pub fn dir_modified() -> Option<SystemTime> {
+    std::fs::read_dir(PERSONA_DIR)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "toml"))
+        .filter_map(|e| e.metadata().ok()?.modified().ok())
+        .max()
+}
+
+/// Sampling temperature for a persona intensity (0 = professional, 100 = playful)
+pub fn intensity_temperature(intensity: u8) -> f32 {
+    const MIN: f32 = 0.3;
+    const MAX: f32 = 1.1;
+    MIN + (MAX - MIN) * intensity.min(100) as f32 / 100.0
+}
+
+/// Tone instruction appended to the persona prompt for an intensity level.
+/// The middle band adds nothing so the persona prompt speaks for itself.
+pub fn intensity_modifier(intensity: u8) -> &'static str {
+    match intensity {
+        0..=19 => "Tone: strictly professional. Be concise and formal, skip teasing and emotional asides.",
+        20..=39 => "Tone: mostly professional. Keep personality light and answers to the point.",
+        40..=60 => "",
+        61..=80 => "Tone: let your personality show. Tease a little and react with more emotion.",
+        _ => "Tone: fully playful tsundere. Be teasing, expressive and dramatic, while still answering correctly.",
+    }
+}
@@ -0,0 +1,260 @@
+use anyhow::Result;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use tracing::{info, warn};
+use wasmtime::{Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, Trap};
+use wasmtime_wasi::preview1::{self, WasiP1Ctx};
+use wasmtime_wasi::{DirPerms, FilePerms, WasiCtxBuilder};
+
+use crate::agent::tools::{Tool, ToolResult};
+use crate::config::PluginSettings;
+use crate::system::sandbox::expand_home;
+
+/// Loads third-party tools from `.wasm` files in the plugins folder.
+///
+/// A plugin is a WebAssembly module exporting:
+/// - `memory`
+/// - `alloc(len: i32) -> i32`: room for `len` bytes of input
+/// - `describe() -> i64`: JSON `{ "name", "description", "parameters" }`
+/// - `execute(ptr: i32, len: i32) -> i64`: takes the args as JSON and returns
+///   `{ "output": "..." }` or `{ "error": "..." }`
+///
+/// Strings come back packed as `(ptr << 32) | len`, UTF-8 in the plugin's memory.
+/// WASI is available, but with no network, no environment and no files outside the
+/// folders granted in `[plugins.dirs]`.
+pub struct WasmToolHost {
+    engine: Engine,
+    settings: PluginSettings,
+}
+
+/// What `describe` returns
+#[derive(Debug, Clone, Deserialize)]
+struct Manifest {
+    name: String,
+    description: String,
+    #[serde(default = "empty_schema")]
+    parameters: Value,
+}
+
+fn empty_schema() -> Value {
+    json!({ "type": "object", "properties": {} })
+}
+
+/// What `execute` returns
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Outcome {
+    Output(String),
+    Error(String),
+}
+
+/// A compiled plugin. Every call gets a fresh instance, so nothing carries over between
+/// calls and a crash can't leave it in a bad state.
+struct Plugin {
+    engine: Engine,
+    module: Module,
+    max_memory_bytes: usize,
+    fuel: u64,
+    /// Host folders preopened for the plugin
+    dirs: Vec<PathBuf>,
+}
+
+struct PluginState {
+    wasi: WasiP1Ctx,
+    limits: StoreLimits,
+}
+
+pub struct WasmTool {
+    manifest: Manifest,
+    plugin: Arc<Plugin>,
+}
+
+impl WasmToolHost {
+    pub fn new(settings: PluginSettings) -> Result<Self> {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config)?;
+        Ok(Self { engine, settings })
+    }
+
+    /// Every plugin in the folder that loads; broken ones are reported and skipped.
+    /// Compiling is slow, so call this off the async runtime.
+    pub fn load_all(&self) -> Vec<WasmTool> {
+        let dir = expand_home(&self.settings.dir);
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            return Vec::new();
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "wasm"))
+            .collect();
+        paths.sort();
+
+        paths
+            .into_iter()
+            .filter_map(|path| match self.load(&path) {
+                Ok(tool) => {
+                    info!(
+                        "[Plugins] Loaded {} from {}",
+                        tool.manifest.name,
+                        path.display()
+                    );
+                    Some(tool)
+                }
+                Err(e) => {
+                    warn!("[Plugins] Skipping {}: {}", path.display(), e);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    fn load(&self, path: &Path) -> Result<WasmTool> {
+        let module = Module::from_file(&self.engine, path)?;
+        let mut plugin = Plugin {
+            engine: self.engine.clone(),
+            module,
+            max_memory_bytes: (self.settings.max_memory_mb * 1024 * 1024) as usize,
+            fuel: self.settings.fuel,
+            dirs: Vec::new(),
+        };
+        let manifest: Manifest = serde_json::from_str(&plugin.call("describe", None)?)
+            .map_err(|e| anyhow::anyhow!("describe() returned invalid JSON: {}", e))?;
+        if manifest.name.trim().is_empty() {
+            return Err(anyhow::anyhow!("describe() returned an empty name"));
+        }
+        // Granted by tool name, which is only known once the plugin has described itself
+        if let Some(dirs) = self.settings.dirs.get(&manifest.name) {
+            plugin.dirs = dirs.iter().map(|dir| expand_home(dir)).collect();
+        }
+        Ok(WasmTool {
+            manifest,
+            plugin: Arc::new(plugin),
+        })
+    }
+}
+
+impl Plugin {
+    /// Call `export`, passing `input` through `alloc` if given, and read back its string
+    fn call(&self, export: &str, input: Option<&str>) -> Result<String> {
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(self.max_memory_bytes)
+            .instances(1)
+            .build();
+        let mut store = Store::new(
+            &self.engine,
+            PluginState {
+                wasi: self.wasi()?,
+                limits,
+            },
+        );
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(self.fuel)?;
+
+        let mut linker: Linker<PluginState> = Linker::new(&self.engine);
+        preview1::add_to_linker_sync(&mut linker, |state| &mut state.wasi)?;
+        let instance = linker.instantiate(&mut store, &self.module)?;
+        // Modules built as WASI reactors set themselves up here
+        if let Ok(init) = instance.get_typed_func::<(), ()>(&mut store, "_initialize") {
+            init.call(&mut store, ()).map_err(trap_error)?;
+        }
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| anyhow::anyhow!("The plugin exports no memory"))?;
+
+        let packed = match input {
+            None => instance
+                .get_typed_func::<(), i64>(&mut store, export)?
+                .call(&mut store, ())
+                .map_err(trap_error)?,
+            Some(input) => {
+                let len = i32::try_from(input.len())?;
+                let ptr = instance
+                    .get_typed_func::<i32, i32>(&mut store, "alloc")?
+                    .call(&mut store, len)
+                    .map_err(trap_error)?;
+                memory.write(&mut store, ptr as u32 as usize, input.as_bytes())?;
+                instance
+                    .get_typed_func::<(i32, i32), i64>(&mut store, export)?
+                    .call(&mut store, (ptr, len))
+                    .map_err(trap_error)?
+            }
+        };
+
+        let packed = packed as u64;
+        let (ptr, len) = ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize);
+        // Sliced straight from the plugin's memory, so a bogus length can't make us allocate
+        let bytes = ptr
+            .checked_add(len)
+            .and_then(|end| memory.data(&store).get(ptr..end))
+            .ok_or_else(|| anyhow::anyhow!("{}() returned a string outside its memory", export))?;
+        std::str::from_utf8(bytes)
+            .map(str::to_string)
+            .map_err(|_| anyhow::anyhow!("{}() returned invalid UTF-8", export))
+    }
+
+    /// Only stderr (for the plugin's own logging) and the granted folders
+    fn wasi(&self) -> Result<WasiP1Ctx> {
+        let mut builder = WasiCtxBuilder::new();
+        builder.inherit_stderr();
+        for dir in &self.dirs {
+            let guest = dir
+                .file_name()
+                .map(|name| format!("/{}", name.to_string_lossy()))
+                .unwrap_or_else(|| "/".to_string());
+            builder
+                .preopened_dir(dir, &guest, DirPerms::all(), FilePerms::all())
+                .map_err(|e| anyhow::anyhow!("Can't open {}: {}", dir.display(), e))?;
+        }
+        Ok(builder.build_p1())
+    }
+}
+
+/// Spell out the traps a plugin author is likely to hit
+fn trap_error(error: anyhow::Error) -> anyhow::Error {
+    match error.downcast_ref::<Trap>() {
+        Some(Trap::OutOfFuel) => anyhow::anyhow!("The plugin ran out of its instruction budget"),
+        Some(Trap::UnreachableCodeReached) => anyhow::anyhow!("The plugin panicked"),
+        _ => error,
+    }
+}
+
+impl Tool for WasmTool {
+    fn name(&self) -> &str {
+        &self.manifest.name
+    }
+
+    fn description(&self) -> &str {
+        &self.manifest.description
+    }
+
+    fn parameters(&self) -> Value {
+        self.manifest.parameters.clone()
+    }
+
+    fn execute(&self, args: Value) -> Pin<Box<dyn Future<Output = ToolResult> + Send>> {
+        let plugin = Arc::clone(&self.plugin);
+        Box::pin(async move {
+            let reply = tokio::task::spawn_blocking(move || {
+                plugin.call("execute", Some(&args.to_string()))
+            })
+            .await??;
+            match serde_json::from_str::<Outcome>(&reply) {
+                Ok(Outcome::Output(output)) => Ok(output),
+                Ok(Outcome::Error(error)) => Err(anyhow::anyhow!("{}", error)),
+                // Plain text is taken as the output
+                Err(_) => Ok(reply),
+            }
+        })
+    }
+
+    /// A plugin with folder access can change files, so the user approves each call
+    fn requires_approval(&self, _args: &Value) -> bool {
+        !self.plugin.dirs.is_empty()
+    }
+}
@@ -0,0 +1,141 @@
+use std::time::{Duration, Instant};
+
+use crate::config::ProactiveSettings;
+
+/// Starts every prompt proactive mode sends, so they stay out of the visible transcript
+pub const PROMPT_PREFIX: &str = "[Proactive]";
+
+/// Why Amadeus is speaking up unprompted
+pub enum Trigger {
+    /// The user has been quiet for this many minutes
+    Idle(u32),
+    /// A `[[proactive.schedule]]` entry came up
+    Scheduled(String),
+    /// Reminders the user set came due
+    Reminder(Vec<String>),
+    /// screen_watch attached a new capture
+    ScreenChange,
+}
+
+impl Trigger {
+    /// The synthetic user message that starts the turn
+    pub fn prompt(&self) -> String {
+        let body = match self {
+            Trigger::Idle(minutes) => format!(
+                "The user has been quiet for {} minutes. If it feels natural, start a little small talk or check in on what they're doing, in one or two sentences.",
+                minutes
+            ),
+            Trigger::Scheduled(prompt) => prompt.clone(),
+            Trigger::Reminder(messages) => format!(
+                "These reminders the user set are due now: {}. Tell them in your own words.",
+                messages
+                    .iter()
+                    .map(|m| format!("\"{}\"", m))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Trigger::ScreenChange => "The screen changed (the latest capture is attached above). Point out anything worth the user's attention, briefly; if nothing stands out, make a short remark about what they're up to.".to_string(),
+        };
+        format!(
+            "{} {}\n(Sent by proactive mode, not typed by the user.)",
+            PROMPT_PREFIX, body
+        )
+    }
+}
+
+/// Decides when the idle, schedule and screen triggers fire. Reminder triggers bypass
+/// it: they come from the reminder poll and are never rate limited.
+pub struct Proactivity {
+    settings: ProactiveSettings,
+    last_activity: Instant,
+    /// The idle trigger fires once per quiet spell
+    idle_fired: bool,
+    last_fired: Option<Instant>,
+    /// Local "YYYY-MM-DD HH:MM" of the last poll, so each scheduled prompt runs once
+    last_minute: String,
+}
+
+impl Proactivity {
+    pub fn new(settings: ProactiveSettings) -> Self {
+        Self {
+            settings,
+            last_activity: Instant::now(),
+            idle_fired: false,
+            last_fired: None,
+            last_minute: String::new(),
+        }
+    }
+
+    /// Settings reloaded from the config file (e.g. on /clear)
+    pub fn set_settings(&mut self, settings: ProactiveSettings) {
+        self.settings = settings;
+    }
+
+    /// The user said or did something; restarts the idle clock
+    pub fn user_active(&mut self) {
+        self.last_activity = Instant::now();
+        self.idle_fired = false;
+    }
+
+    fn cooled_down(&self) -> bool {
+        let cooldown = Duration::from_secs(self.settings.cooldown_minutes as u64 * 60);
+        self.last_fired.map_or(true, |at| at.elapsed() >= cooldown)
+    }
+
+    fn fire(&mut self, trigger: Trigger) -> Trigger {
+        self.last_fired = Some(Instant::now());
+        trigger
+    }
+
+    /// Check the clock-driven triggers. `now` is local time as "YYYY-MM-DD HH:MM:SS".
+    pub fn poll(&mut self, now: &str) -> Option<Trigger> {
+        let minute = now.get(..16)?;
+        if minute != self.last_minute {
+            let first_poll = self.last_minute.is_empty();
+            self.last_minute = minute.to_string();
+            let time = parse_time(&minute[11..]);
+            // Scheduled prompts ignore the cooldown: the user asked for them at that time.
+            // Nothing runs on the first poll, so a restart at 09:00 doesn't repeat 09:00.
+            let scheduled = self
+                .settings
+                .schedule
+                .iter()
+                .find(|entry| !first_poll && parse_time(&entry.at) == time)
+                .map(|entry| entry.prompt.clone());
+            if let Some(prompt) = scheduled {
+                return Some(self.fire(Trigger::Scheduled(prompt)));
+            }
+        }
+
+        let idle = self.settings.idle_minutes;
+        if idle > 0
+            && !self.idle_fired
+            && self.cooled_down()
+            && self.last_activity.elapsed() >= Duration::from_secs(idle as u64 * 60)
+        {
+            self.idle_fired = true;
+            return Some(self.fire(Trigger::Idle(idle)));
+        }
+        None
+    }
+
+    /// A screen_watch capture arrived
+    pub fn screen_changed(&mut self) -> Option<Trigger> {
+        if !self.settings.on_screen_change || !self.cooled_down() {
+            return None;
+        }
+        Some(self.fire(Trigger::ScreenChange))
+    }
+}
+
+/// "9:05" and "09:05" alike
+fn parse_time(text: &str) -> Option<(u32, u32)> {
+    let (hour, minute) = text.trim().split_once(':')?;
+    let (hour, minute) = (hour.parse().ok()?, minute.parse().ok()?);
+    (hour < 24 && minute < 60).then_some((hour, minute))
+}
+
+/// Whether a stored user message is a prompt from proactive mode
+pub fn is_prompt(content: &str) -> bool {
+    content.starts_with(PROMPT_PREFIX)
+}
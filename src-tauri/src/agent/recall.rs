@@ -0,0 +1,128 @@
+use anyhow::Result;
+use serde_json::json;
+
+use crate::agent::memory::MemoryManager;
+use crate::config::MemorySettings;
+use crate::llm::ollama::OllamaClient;
+use crate::llm::Message;
+
+/// Marks the system message carrying recalled facts
+pub const RECALL_PREFIX: &str = "[Relevant memories]";
+
+/// A new fact this similar to a stored one is treated as a repeat and not stored again
+const DUPLICATE_SIMILARITY: f32 = 0.92;
+
+/// Stored facts most relevant to `query`, best match first
+pub async fn recall(
+    memory: &MemoryManager,
+    embedder: &OllamaClient,
+    query: &str,
+    settings: &MemorySettings,
+) -> Result<Vec<String>> {
+    let stored = memory.memories().await?;
+    if stored.is_empty() {
+        return Ok(Vec::new());
+    }
+    let query = embedder
+        .embed(&[query.to_string()])
+        .await?
+        .pop()
+        .unwrap_or_default();
+
+    let mut scored: Vec<(f32, String)> = stored
+        .into_iter()
+        .map(|(_, content, embedding)| (cosine(&query, &embedding), content))
+        .filter(|(score, _)| *score >= settings.min_similarity)
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    scored.truncate(settings.recall_limit);
+    Ok(scored.into_iter().map(|(_, content)| content).collect())
+}
+
+/// System message listing `facts` for the current turn
+pub fn prompt_message(facts: &[String]) -> Message {
+    let mut content = format!(
+        "{} Things you know about the user from earlier conversations:\n",
+        RECALL_PREFIX
+    );
+    for fact in facts {
+        content.push_str(&format!("- {}\n", fact));
+    }
+    Message {
+        role: "system".to_string(),
+        content,
+        images: None,
+    }
+}
+
+/// Ask the model for lasting facts worth remembering from one exchange
+/// (preferences, names, dates, ongoing projects). Usually returns nothing.
+pub async fn extract_facts(client: &OllamaClient, user: &str, reply: &str) -> Result<Vec<String>> {
+    let messages = vec![
+        Message {
+            role: "system".to_string(),
+            content: "Extract lasting facts about the user from this exchange: preferences, names, birthdays, relationships, projects, habits. Each fact is one short standalone sentence in English, e.g. \"The user prefers dark mode.\" Ignore small talk, questions and anything only relevant right now. Reply with a JSON array of strings; [] if there is nothing worth remembering.".to_string(),
+            images: None,
+        },
+        Message {
+            role: "user".to_string(),
+            content: format!("User: {}\nAssistant: {}", user, reply),
+            images: None,
+        },
+    ];
+    let schema = json!({ "type": "array", "items": { "type": "string" } });
+    let raw = client.chat_structured(messages, schema).await?;
+    let facts: Vec<String> = serde_json::from_str(&raw)?;
+    Ok(facts
+        .into_iter()
+        .map(|f| f.trim().to_string())
+        .filter(|f| !f.is_empty())
+        .collect())
+}
+
+/// Embed and store `facts`, skipping ones already known. Returns how many were stored.
+pub async fn store_facts(
+    memory: &MemoryManager,
+    embedder: &OllamaClient,
+    facts: &[String],
+) -> Result<usize> {
+    if facts.is_empty() {
+        return Ok(0);
+    }
+    let embeddings = embedder.embed(facts).await?;
+    let mut known: Vec<Vec<f32>> = memory
+        .memories()
+        .await?
+        .into_iter()
+        .map(|(_, _, embedding)| embedding)
+        .collect();
+
+    let mut stored = 0;
+    for (fact, embedding) in facts.iter().zip(embeddings) {
+        if known
+            .iter()
+            .any(|k| cosine(k, &embedding) >= DUPLICATE_SIMILARITY)
+        {
+            continue;
+        }
+        memory.add_memory(fact, &embedding).await?;
+        known.push(embedding);
+        stored += 1;
+    }
+    Ok(stored)
+}
+
+/// Cosine similarity; 0.0 for mismatched or empty vectors
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
@@ -0,0 +1,107 @@
+use serde_json::{json, Value};
+use std::future::Future;
+use std::pin::Pin;
+
+use crate::agent::memory::{MemoryManager, ReminderTime};
+use crate::agent::tools::{Tool, ToolResult};
+
+const MAX_MESSAGE_CHARS: usize = 200;
+
+/// Reminders stored in the database; the agent loop checks for due ones and speaks up
+/// on its own when one fires
+pub struct ReminderTool {
+    memory: MemoryManager,
+}
+
+impl ReminderTool {
+    pub fn new(memory: MemoryManager) -> Self {
+        Self { memory }
+    }
+}
+
+impl Tool for ReminderTool {
+    fn name(&self) -> &str {
+        "reminders"
+    }
+
+    fn description(&self) -> &str {
+        "Remind the user of something later; the reminder is shown (and spoken) to them when it is due. Actions: 'add' (message plus either 'at' local time 'HH:MM' / 'YYYY-MM-DD HH:MM', or 'in_minutes'), 'list' (pending reminders and the current local time), 'delete' (id)."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["add", "list", "delete"]
+                },
+                "message": { "type": "string", "description": "What to remind the user of (add)" },
+                "at": { "type": "string", "description": "Local time, 'HH:MM' for the next such time or 'YYYY-MM-DD HH:MM' (add)" },
+                "in_minutes": { "type": "integer", "description": "Minutes from now, instead of 'at' (add)" },
+                "id": { "type": "integer", "description": "Reminder to delete" }
+            },
+            "required": ["action"]
+        })
+    }
+
+    fn execute(&self, args: Value) -> Pin<Box<dyn Future<Output = ToolResult> + Send>> {
+        let memory = self.memory.clone();
+        Box::pin(async move {
+            let action = args["action"]
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("Missing action"))?;
+
+            match action {
+                "add" => {
+                    let message = args["message"]
+                        .as_str()
+                        .map(str::trim)
+                        .filter(|m| !m.is_empty())
+                        .ok_or_else(|| anyhow::anyhow!("Missing message"))?;
+                    if message.chars().count() > MAX_MESSAGE_CHARS {
+                        return Err(anyhow::anyhow!(
+                            "Message too long (max {} chars)",
+                            MAX_MESSAGE_CHARS
+                        ));
+                    }
+                    let when = match (args["at"].as_str(), args["in_minutes"].as_u64()) {
+                        (Some(at), _) => ReminderTime::Local(at.to_string()),
+                        (None, Some(minutes)) => ReminderTime::InMinutes(minutes as u32),
+                        (None, None) => {
+                            return Err(anyhow::anyhow!("Give either 'at' or 'in_minutes'"))
+                        }
+                    };
+                    let reminder = memory.add_reminder(message, &when).await?;
+                    Ok(format!(
+                        "Reminder #{} set for {}: {}",
+                        reminder.id, reminder.due_at, reminder.message
+                    ))
+                }
+                "list" => {
+                    let now = memory.local_now().await?;
+                    let reminders = memory.pending_reminders().await?;
+                    if reminders.is_empty() {
+                        return Ok(format!("Now: {}\nNo pending reminders", now));
+                    }
+                    let listing: Vec<String> = reminders
+                        .iter()
+                        .map(|r| format!("#{} {}: {}", r.id, r.due_at, r.message))
+                        .collect();
+                    Ok(format!("Now: {}\n{}", now, listing.join("\n")))
+                }
+                "delete" => {
+                    let id = args["id"]
+                        .as_i64()
+                        .ok_or_else(|| anyhow::anyhow!("Missing id"))?;
+                    if memory.delete_reminder(id).await? {
+                        Ok(format!("Deleted reminder #{}", id))
+                    } else {
+                        Ok(format!("No pending reminder #{}", id))
+                    }
+                }
+                _ => Err(anyhow::anyhow!("Unknown action: {}", action)),
+            }
+        })
+    }
+}
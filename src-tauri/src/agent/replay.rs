@@ -0,0 +1,68 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::llm::Message;
+
+/// Pause before each assistant reply, standing in for model latency
+const DEFAULT_DELAY_MS: u64 = 1200;
+
+/// Interval between simulated tokens when streaming
+pub const TOKEN_INTERVAL: Duration = Duration::from_millis(30);
+
+/// Prefixes the agent loop puts on tool results, which are stored as user messages
+const TOOL_PREFIXES: &[&str] = &["Tool Output:", "Tool Error:"];
+
+/// Command-line options for playing back an exported transcript instead of talking to Ollama:
+/// `--replay <file> [--replay-delay <ms>] [--replay-stream]`
+pub struct ReplayOptions {
+    pub path: PathBuf,
+    pub delay: Duration,
+    /// Reveal assistant replies word by word like a streaming model
+    pub stream: bool,
+}
+
+impl ReplayOptions {
+    /// `None` unless `--replay` was given
+    pub fn from_args() -> Option<Self> {
+        let args: Vec<String> = std::env::args().collect();
+        let value = |flag: &str| {
+            args.iter()
+                .position(|a| a == flag)
+                .and_then(|i| args.get(i + 1))
+                .cloned()
+        };
+
+        let path = PathBuf::from(value("--replay")?);
+        let delay = value("--replay-delay")
+            .and_then(|ms| ms.parse().ok())
+            .unwrap_or(DEFAULT_DELAY_MS);
+        Some(Self {
+            path,
+            delay: Duration::from_millis(delay),
+            stream: args.iter().any(|a| a == "--replay-stream"),
+        })
+    }
+}
+
+/// Read a transcript written by `/export json`
+pub fn load_transcript(path: &Path) -> Result<Vec<Message>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Cannot read transcript {}", path.display()))?;
+    serde_json::from_str(&text)
+        .with_context(|| format!("{} is not an exported JSON transcript", path.display()))
+}
+
+/// Tool results are saved with the user role but were shown as system messages
+pub fn display_role(message: &Message) -> &str {
+    if message.role == "user" && TOOL_PREFIXES.iter().any(|p| message.content.starts_with(p)) {
+        "system"
+    } else {
+        &message.role
+    }
+}
+
+/// Split a reply into word-sized chunks (whitespace kept) for simulated streaming
+pub fn tokens(content: &str) -> Vec<&str> {
+    content.split_inclusive(char::is_whitespace).collect()
+}
@@ -0,0 +1,74 @@
+use serde_json::Value;
+
+/// Check `value` against the subset of JSON Schema that Ollama's structured outputs honor:
+/// `type`, `properties`, `required`, `items` and `enum`.
+/// Returns a description of the first violation found.
+pub fn validate(value: &Value, schema: &Value) -> Result<(), String> {
+    validate_at(value, schema, "$")
+}
+
+fn validate_at(value: &Value, schema: &Value, path: &str) -> Result<(), String> {
+    if let Some(expected) = schema.get("type") {
+        let matches = match expected {
+            Value::String(t) => type_matches(value, t),
+            Value::Array(types) => types
+                .iter()
+                .filter_map(|t| t.as_str())
+                .any(|t| type_matches(value, t)),
+            _ => true,
+        };
+        if !matches {
+            return Err(format!(
+                "{}: expected type {}, got {}",
+                path, expected, value
+            ));
+        }
+    }
+
+    if let Some(options) = schema.get("enum").and_then(|e| e.as_array()) {
+        if !options.contains(value) {
+            return Err(format!(
+                "{}: {} is not one of {}",
+                path, value, schema["enum"]
+            ));
+        }
+    }
+
+    if let Value::Object(fields) = value {
+        if let Some(required) = schema.get("required").and_then(|r| r.as_array()) {
+            for key in required.iter().filter_map(|k| k.as_str()) {
+                if !fields.contains_key(key) {
+                    return Err(format!("{}: missing required field '{}'", path, key));
+                }
+            }
+        }
+        if let Some(properties) = schema.get("properties").and_then(|p| p.as_object()) {
+            for (key, field_schema) in properties {
+                if let Some(field) = fields.get(key) {
+                    validate_at(field, field_schema, &format!("{}.{}", path, key))?;
+                }
+            }
+        }
+    }
+
+    if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for (i, item) in items.iter().enumerate() {
+            validate_at(item, item_schema, &format!("{}[{}]", path, i))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn type_matches(value: &Value, expected: &str) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
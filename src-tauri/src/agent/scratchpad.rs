@@ -0,0 +1,134 @@
+use serde_json::{json, Value};
+use std::future::Future;
+use std::pin::Pin;
+use tracing::warn;
+
+use crate::agent::memory::MemoryManager;
+use crate::agent::tools::{Tool, ToolResult};
+
+const MAX_KEY_CHARS: usize = 64;
+const MAX_VALUE_CHARS: usize = 500;
+// Upper bound on how much of the store is injected into the system prompt
+const MAX_PROMPT_CHARS: usize = 2000;
+
+/// Persistent key/value scratchpad for facts the model wants to remember across sessions
+pub struct MemoryStoreTool {
+    memory: MemoryManager,
+}
+
+impl MemoryStoreTool {
+    pub fn new(memory: MemoryManager) -> Self {
+        Self { memory }
+    }
+}
+
+/// Render the stored facts as a system prompt section (empty if nothing is stored)
+pub async fn prompt_section(memory: &MemoryManager) -> String {
+    let entries = match memory.kv_list().await {
+        Ok(entries) => entries,
+        Err(e) => {
+            warn!("[Memory] Failed to load scratchpad: {}", e);
+            return String::new();
+        }
+    };
+    if entries.is_empty() {
+        return String::new();
+    }
+
+    let mut section = String::from("\n\nThings you have remembered (memory_store):\n");
+    for (key, value) in &entries {
+        let line = format!("- {}: {}\n", key, value);
+        if section.len() + line.len() > MAX_PROMPT_CHARS {
+            section.push_str("- ... (use memory_store 'list' to see the rest)\n");
+            break;
+        }
+        section.push_str(&line);
+    }
+    section
+}
+
+impl Tool for MemoryStoreTool {
+    fn name(&self) -> &str {
+        "memory_store"
+    }
+
+    fn description(&self) -> &str {
+        "Persistent key/value scratchpad for facts worth remembering across sessions (e.g. user preferences, project paths). Actions: 'set', 'get', 'list', 'delete'."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["set", "get", "list", "delete"]
+                },
+                "key": { "type": "string", "description": "Short identifier, e.g. 'preferred_units'" },
+                "value": { "type": "string", "description": "Value to store (for set, max 500 chars)" }
+            },
+            "required": ["action"]
+        })
+    }
+
+    fn execute(&self, args: Value) -> Pin<Box<dyn Future<Output = ToolResult> + Send>> {
+        let memory = self.memory.clone();
+        Box::pin(async move {
+            let action = args["action"]
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("Missing action"))?;
+
+            if action == "list" {
+                let entries = memory.kv_list().await?;
+                if entries.is_empty() {
+                    return Ok("Memory store is empty".to_string());
+                }
+                let listing: Vec<String> = entries
+                    .iter()
+                    .map(|(k, v)| format!("{}: {}", k, v))
+                    .collect();
+                return Ok(listing.join("\n"));
+            }
+
+            let key = args["key"]
+                .as_str()
+                .map(str::trim)
+                .filter(|k| !k.is_empty())
+                .ok_or_else(|| anyhow::anyhow!("Missing key"))?;
+            if key.chars().count() > MAX_KEY_CHARS {
+                return Err(anyhow::anyhow!(
+                    "Key too long (max {} chars)",
+                    MAX_KEY_CHARS
+                ));
+            }
+
+            match action {
+                "set" => {
+                    let value = args["value"]
+                        .as_str()
+                        .ok_or_else(|| anyhow::anyhow!("Missing value"))?;
+                    if value.chars().count() > MAX_VALUE_CHARS {
+                        return Err(anyhow::anyhow!(
+                            "Value too long (max {} chars). Store a shorter summary.",
+                            MAX_VALUE_CHARS
+                        ));
+                    }
+                    memory.kv_set(key, value).await?;
+                    Ok(format!("Remembered '{}'", key))
+                }
+                "get" => match memory.kv_get(key).await? {
+                    Some(value) => Ok(value),
+                    None => Ok(format!("No value stored for '{}'", key)),
+                },
+                "delete" => {
+                    if memory.kv_delete(key).await? {
+                        Ok(format!("Forgot '{}'", key))
+                    } else {
+                        Ok(format!("No value stored for '{}'", key))
+                    }
+                }
+                _ => Err(anyhow::anyhow!("Unknown action: {}", action)),
+            }
+        })
+    }
+}
@@ -0,0 +1,248 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::async_runtime::JoinHandle;
+use tokio::sync::mpsc;
+
+use crate::agent::memory::MemoryManager;
+use crate::agent::tools::{Tool, ToolResult};
+
+/// Longest countdown accepted, one day; later things belong in reminders
+const MAX_DURATION_SECS: u64 = 24 * 60 * 60;
+
+/// In-memory countdowns and alarms. Unlike reminders they don't survive a restart.
+/// Finished ones are sent to the agent loop, which announces them.
+pub struct Timers {
+    next_id: AtomicU64,
+    running: Mutex<Vec<Running>>,
+    done_tx: mpsc::UnboundedSender<TimerInfo>,
+}
+
+struct Running {
+    info: TimerInfo,
+    due: Instant,
+    task: JoinHandle<()>,
+}
+
+/// A timer as listed to the UI and the model
+#[derive(Debug, Clone, Serialize)]
+pub struct TimerInfo {
+    pub id: u64,
+    pub label: String,
+    /// Set for alarms: the local "HH:MM" they ring at
+    pub alarm_at: Option<String>,
+    /// Seconds left when listed
+    pub remaining_secs: u64,
+}
+
+impl TimerInfo {
+    /// "#3 Tea (4m 30s left)" / "#4 Wake up (alarm at 07:00)"
+    pub fn describe(&self) -> String {
+        match &self.alarm_at {
+            Some(at) => format!("#{} {} (alarm at {})", self.id, self.label, at),
+            None => format!(
+                "#{} {} ({} left)",
+                self.id,
+                self.label,
+                format_duration(self.remaining_secs)
+            ),
+        }
+    }
+}
+
+impl Timers {
+    pub fn new(done_tx: mpsc::UnboundedSender<TimerInfo>) -> Self {
+        Self {
+            next_id: AtomicU64::new(1),
+            running: Mutex::new(Vec::new()),
+            done_tx,
+        }
+    }
+
+    /// Start a countdown of `seconds`, or an alarm at the next local "HH:MM" when `at`
+    /// is given (`memory` supplies the local clock)
+    pub async fn set(
+        self: &Arc<Self>,
+        memory: &MemoryManager,
+        seconds: Option<u64>,
+        at: Option<&str>,
+        label: Option<&str>,
+    ) -> Result<TimerInfo, anyhow::Error> {
+        let seconds = match (at, seconds) {
+            (Some(at), _) => memory.seconds_until(at).await?,
+            (None, Some(0)) => return Err(anyhow::anyhow!("Duration must be positive")),
+            (None, Some(seconds)) if seconds > MAX_DURATION_SECS => {
+                return Err(anyhow::anyhow!(
+                    "Timers run for at most 24 hours; use a reminder for later"
+                ))
+            }
+            (None, Some(seconds)) => seconds,
+            (None, None) => return Err(anyhow::anyhow!("Give either 'seconds' or 'at'")),
+        };
+        let alarm_at = at.map(|at| format!("{:0>5}", at.trim()));
+        let label = label
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| if alarm_at.is_some() { "Alarm" } else { "Timer" }.to_string());
+        let info = TimerInfo {
+            id: self.next_id.fetch_add(1, Ordering::SeqCst),
+            label,
+            alarm_at,
+            remaining_secs: seconds,
+        };
+
+        let duration = Duration::from_secs(seconds);
+        // Held while spawning so the task can't finish before it is listed
+        let mut running = self.running.lock().unwrap();
+        let timers = Arc::clone(self);
+        let id = info.id;
+        let task = tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(duration).await;
+            timers.finish(id);
+        });
+        running.push(Running {
+            info: info.clone(),
+            due: Instant::now() + duration,
+            task,
+        });
+        Ok(info)
+    }
+
+    /// Running timers, soonest first
+    pub fn list(&self) -> Vec<TimerInfo> {
+        let now = Instant::now();
+        let mut timers: Vec<(Instant, TimerInfo)> = self
+            .running
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|r| {
+                let mut info = r.info.clone();
+                info.remaining_secs = r.due.saturating_duration_since(now).as_secs();
+                (r.due, info)
+            })
+            .collect();
+        timers.sort_by_key(|(due, _)| *due);
+        timers.into_iter().map(|(_, info)| info).collect()
+    }
+
+    /// Stop a timer before it rings; false if there is no such timer
+    pub fn cancel(&self, id: u64) -> bool {
+        let mut running = self.running.lock().unwrap();
+        match running.iter().position(|r| r.info.id == id) {
+            Some(index) => {
+                running.remove(index).task.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn finish(&self, id: u64) {
+        let mut running = self.running.lock().unwrap();
+        if let Some(index) = running.iter().position(|r| r.info.id == id) {
+            let mut info = running.remove(index).info;
+            info.remaining_secs = 0;
+            let _ = self.done_tx.send(info);
+        }
+    }
+}
+
+/// "1h 5m", "4m 30s", "45s"
+fn format_duration(seconds: u64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    match (hours, minutes, seconds) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, 0) => format!("{}m", m),
+        (0, m, s) => format!("{}m {}s", m, s),
+        (h, m, _) => format!("{}h {}m", h, m),
+    }
+}
+
+/// Countdown timers and alarms for the current session; the avatar reacts and
+/// Amadeus speaks up when one rings
+pub struct TimerTool {
+    timers: Arc<Timers>,
+    memory: MemoryManager,
+}
+
+impl TimerTool {
+    pub fn new(timers: Arc<Timers>, memory: MemoryManager) -> Self {
+        Self { timers, memory }
+    }
+}
+
+impl Tool for TimerTool {
+    fn name(&self) -> &str {
+        "timers"
+    }
+
+    fn description(&self) -> &str {
+        "Countdown timers and alarms that ring in this session (they don't survive a restart; use reminders for that). Actions: 'set' ('seconds' for a countdown or 'at' local 'HH:MM' for an alarm, optional 'label'), 'list', 'cancel' (id)."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["set", "list", "cancel"]
+                },
+                "seconds": { "type": "integer", "description": "Countdown length, e.g. 300 for five minutes (set)" },
+                "at": { "type": "string", "description": "Alarm time, local 'HH:MM' (set)" },
+                "label": { "type": "string", "description": "What the timer is for, e.g. 'Tea' (set)" },
+                "id": { "type": "integer", "description": "Timer to cancel" }
+            },
+            "required": ["action"]
+        })
+    }
+
+    fn execute(&self, args: Value) -> Pin<Box<dyn Future<Output = ToolResult> + Send>> {
+        let timers = self.timers.clone();
+        let memory = self.memory.clone();
+        Box::pin(async move {
+            let action = args["action"]
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("Missing action"))?;
+
+            match action {
+                "set" => {
+                    let timer = timers
+                        .set(
+                            &memory,
+                            args["seconds"].as_u64(),
+                            args["at"].as_str(),
+                            args["label"].as_str(),
+                        )
+                        .await?;
+                    Ok(format!("Started timer {}", timer.describe()))
+                }
+                "list" => {
+                    let running = timers.list();
+                    if running.is_empty() {
+                        return Ok("No timers running".to_string());
+                    }
+                    let listing: Vec<String> = running.iter().map(TimerInfo::describe).collect();
+                    Ok(listing.join("\n"))
+                }
+                "cancel" => {
+                    let id = args["id"]
+                        .as_u64()
+                        .ok_or_else(|| anyhow::anyhow!("Missing id"))?;
+                    if timers.cancel(id) {
+                        Ok(format!("Cancelled timer #{}", id))
+                    } else {
+                        Ok(format!("No running timer #{}", id))
+                    }
+                }
+                _ => Err(anyhow::anyhow!("Unknown action: {}", action)),
+            }
+        })
+    }
+}
@@ -0,0 +1,232 @@
+use serde_json::Value;
+
+use crate::agent::schema;
+use crate::agent::tools::ToolDispatcher;
+
+/// One tool and its arguments
+#[derive(Debug, Clone)]
+pub struct ToolCall {
+    pub name: String,
+    pub args: Value,
+}
+
+/// The tool calls found in a model reply: one `{ "tool": ..., "args": ... }`, or several
+/// at once as `{ "tools": [ ... ] }`
+#[derive(Debug, Clone)]
+pub struct ToolRequest {
+    pub calls: Vec<ToolCall>,
+    /// The reply with the call (and its code fence) removed, for display and speech
+    pub text: String,
+}
+
+/// Find a tool call in `reply`: the whole reply as JSON, a fenced ```json block, or a JSON
+/// object embedded in surrounding prose. Only calls naming a registered tool whose args
+/// satisfy that tool's schema are accepted, so example JSON in normal answers is left alone;
+/// a batch is accepted only if every call in it is.
+pub fn extract(reply: &str, dispatcher: &ToolDispatcher) -> Option<ToolRequest> {
+    candidates(reply).into_iter().find_map(|(start, end)| {
+        let value: Value = serde_json::from_str(&reply[start..end]).ok()?;
+        let calls = match value.get("tools") {
+            Some(batch) => batch
+                .as_array()
+                .filter(|calls| !calls.is_empty())?
+                .iter()
+                .map(|call| parse_call(call, dispatcher))
+                .collect::<Option<Vec<_>>>()?,
+            None => vec![parse_call(&value, dispatcher)?],
+        };
+
+        let (start, end) = widen_to_fence(reply, start, end);
+        let text = [reply[..start].trim(), reply[end..].trim()]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        Some(ToolRequest { calls, text })
+    })
+}
+
+/// `{ "tool": <name>, "args": { ... } }`, if it names a registered tool and the args fit
+fn parse_call(value: &Value, dispatcher: &ToolDispatcher) -> Option<ToolCall> {
+    let name = value.get("tool")?.as_str()?.to_string();
+    let args = value
+        .get("args")
+        .cloned()
+        .unwrap_or_else(|| Value::Object(Default::default()));
+    let parameters = dispatcher.parameters(&name)?;
+    schema::validate(&args, &parameters).ok()?;
+    Some(ToolCall { name, args })
+}
+
+/// Byte ranges of top-level `{ ... }` objects, in order of appearance.
+/// Braces inside JSON strings are skipped so `"a}b"` doesn't end an object early.
+fn candidates(reply: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (i, c) in reply.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' if depth > 0 => in_string = true,
+            '{' => {
+                if depth == 0 {
+                    start = i;
+                }
+                depth += 1;
+            }
+            '}' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    spans.push((start, i + 1));
+                }
+            }
+            _ => {}
+        }
+    }
+    spans
+}
+
+/// Extend a span to cover the surrounding ``` fence, if the object sits alone inside one
+fn widen_to_fence(reply: &str, start: usize, end: usize) -> (usize, usize) {
+    let before = reply[..start].trim_end();
+    let after = reply[end..].trim_start();
+    let Some(fence_start) = before.rfind("```") else {
+        return (start, end);
+    };
+    // Only the language tag (e.g. "json") may sit between the fence and the object
+    let tag = &before[fence_start + 3..];
+    if !tag.chars().all(|c| c.is_ascii_alphanumeric()) || !after.starts_with("```") {
+        return (start, end);
+    }
+    let fence_end = reply.len() - after.len() + 3;
+    (fence_start, fence_end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::tools::{Tool, ToolResult};
+    use crate::config::ToolSettings;
+    use serde_json::json;
+    use std::future::Future;
+    use std::pin::Pin;
+
+    struct Echo;
+
+    impl Tool for Echo {
+        fn name(&self) -> &str {
+            "echo"
+        }
+
+        fn description(&self) -> &str {
+            "Repeat the given text"
+        }
+
+        fn parameters(&self) -> Value {
+            json!({
+                "type": "object",
+                "properties": { "text": { "type": "string" } },
+                "required": ["text"]
+            })
+        }
+
+        fn execute(&self, args: Value) -> Pin<Box<dyn Future<Output = ToolResult> + Send>> {
+            Box::pin(async move { Ok(args["text"].as_str().unwrap_or_default().to_string()) })
+        }
+    }
+
+    fn dispatcher() -> ToolDispatcher {
+        let mut dispatcher = ToolDispatcher::new(&ToolSettings::default());
+        dispatcher.register(Box::new(Echo));
+        dispatcher
+    }
+
+    #[test]
+    fn whole_reply_as_json() {
+        let request =
+            extract(r#"{"tool": "echo", "args": {"text": "hi"}}"#, &dispatcher()).unwrap();
+        assert_eq!(request.calls[0].name, "echo");
+        assert_eq!(request.calls[0].args, json!({ "text": "hi" }));
+        assert_eq!(request.text, "");
+    }
+
+    #[test]
+    fn fenced_json_is_removed_with_its_fence() {
+        let reply = "Let me check.\n```json\n{\"tool\": \"echo\", \"args\": {\"text\": \"hi\"}}\n```\nOne moment.";
+        let request = extract(reply, &dispatcher()).unwrap();
+        assert_eq!(request.calls[0].name, "echo");
+        assert_eq!(request.text, "Let me check.\n\nOne moment.");
+    }
+
+    #[test]
+    fn bare_json_inside_prose() {
+        let reply = r#"Sure, calling {"tool": "echo", "args": {"text": "a}b"}} now."#;
+        let request = extract(reply, &dispatcher()).unwrap();
+        assert_eq!(request.calls[0].args, json!({ "text": "a}b" }));
+        assert_eq!(request.text, "Sure, calling\n\nnow.");
+    }
+
+    #[test]
+    fn first_valid_candidate_wins() {
+        let reply = r#"Example: {"name": "Okabe"}. Then {"tool": "echo", "args": {"text": "one"}} and {"tool": "echo", "args": {"text": "two"}}"#;
+        assert_eq!(candidates(reply).len(), 3);
+        let request = extract(reply, &dispatcher()).unwrap();
+        assert_eq!(request.calls[0].args, json!({ "text": "one" }));
+    }
+
+    #[test]
+    fn batch_of_calls() {
+        let reply = r#"{"tools": [{"tool": "echo", "args": {"text": "one"}}, {"tool": "echo", "args": {"text": "two"}}]}"#;
+        let request = extract(reply, &dispatcher()).unwrap();
+        let texts: Vec<&str> = request
+            .calls
+            .iter()
+            .map(|call| call.args["text"].as_str().unwrap())
+            .collect();
+        assert_eq!(texts, ["one", "two"]);
+    }
+
+    #[test]
+    fn batch_with_one_bad_call_is_ignored() {
+        let dispatcher = dispatcher();
+        let reply = r#"{"tools": [{"tool": "echo", "args": {"text": "one"}}, {"tool": "rm_rf", "args": {}}]}"#;
+        assert!(extract(reply, &dispatcher).is_none());
+        assert!(extract(r#"{"tools": []}"#, &dispatcher).is_none());
+    }
+
+    #[test]
+    fn non_tool_json_is_ignored() {
+        let dispatcher = dispatcher();
+        assert!(extract(r#"Here is your config: {"theme": "dark"}"#, &dispatcher).is_none());
+        assert!(extract(r#"{"tool": "rm_rf", "args": {}}"#, &dispatcher).is_none());
+        assert!(extract(r#"{"tool": "echo", "args": {"text": 42}}"#, &dispatcher).is_none());
+        assert!(extract("No JSON here {at all", &dispatcher).is_none());
+    }
+
+    #[test]
+    fn nested_objects_are_one_candidate() {
+        assert_eq!(
+            candidates(r#"{"a": {"b": {}}} {"c": "{"}"#),
+            vec![(0, 16), (17, 27)]
+        );
+    }
+
+    #[test]
+    fn fence_is_only_widened_when_the_object_is_alone_inside() {
+        let reply = "```json\n{}\n```";
+        assert_eq!(widen_to_fence(reply, 8, 10), (0, reply.len()));
+        let prose = "```json\nsee {}\n```";
+        assert_eq!(widen_to_fence(prose, 12, 14), (12, 14));
+    }
+}
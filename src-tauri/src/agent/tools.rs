@@ -3,27 +3,129 @@ use serde_json::Value;
 use std::collections::HashMap;
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::agent::memory::MemoryManager;
+use crate::config::ToolSettings;
+use crate::system::{screenshot, secrets};
 
 pub type ToolResult = Result<String>;
+pub type ToolPreview = Result<Option<String>>;
+
+/// How long a cached result stays valid unless a tool overrides `cache_ttl`
+const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// How much of each result the audit log keeps
+const AUDIT_OUTPUT_CHARS: usize = 1000;
 
 pub trait Tool: Send + Sync {
     fn name(&self) -> &str;
     fn description(&self) -> &str;
     fn parameters(&self) -> Value; // JSON Schema
     fn execute(&self, args: Value) -> Pin<Box<dyn Future<Output = ToolResult> + Send>>;
+
+    /// Human-readable preview of what `execute` would change (e.g. a diff), if any
+    fn preview(&self, _args: Value) -> Pin<Box<dyn Future<Output = ToolPreview> + Send>> {
+        Box::pin(async { Ok(None) })
+    }
+
+    /// Whether the result of this call can be reused for identical args.
+    /// Only read-only actions may return true; anything with side effects must not.
+    fn is_cacheable(&self, _args: &Value) -> bool {
+        false
+    }
+
+    fn cache_ttl(&self) -> Duration {
+        DEFAULT_CACHE_TTL
+    }
+
+    /// How long a call may run before it is abandoned, if this tool needs something other
+    /// than `[tools] timeout_secs`. An entry in `[tools.timeouts]` still wins.
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Whether the user must allow this call before it runs.
+    /// True for anything that acts on the machine: typing, clicking, writing files, browsing.
+    fn requires_approval(&self, _args: &Value) -> bool {
+        false
+    }
+
+    /// Whether "Always allow" may skip approval for later calls of this tool
+    fn can_always_allow(&self) -> bool {
+        true
+    }
+}
+
+pub struct ToolOutput {
+    pub content: String,
+    /// True if the result was served from the cache instead of running the tool
+    pub cached: bool,
+}
+
+/// A tool call that ran past its time limit. The call is dropped (blocking work already
+/// started may finish in the background) and the model is told so it can try another way.
+#[derive(Debug)]
+pub struct ToolTimeout {
+    pub tool: String,
+    pub after: Duration,
+}
+
+impl std::fmt::Display for ToolTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} timed out after {}s and was stopped. Try a smaller request (a narrower path, \
+             fewer results) or a different approach.",
+            self.tool,
+            self.after.as_secs()
+        )
+    }
+}
+
+impl std::error::Error for ToolTimeout {}
+
+struct CachedResult {
+    content: String,
+    expires_at: Instant,
 }
 
 pub struct ToolDispatcher {
     tools: HashMap<String, Box<dyn Tool>>,
+    cache: Mutex<HashMap<String, CachedResult>>,
+    default_timeout: Duration,
+    /// Per-tool overrides from `[tools.timeouts]`
+    timeouts: HashMap<String, Duration>,
+    /// Longest text output passed on; images are exempt
+    max_output_chars: usize,
+    /// Where every call is recorded, if anywhere
+    audit: Option<MemoryManager>,
 }
 
 impl ToolDispatcher {
-    pub fn new() -> Self {
+    pub fn new(settings: &ToolSettings) -> Self {
         Self {
             tools: HashMap::new(),
+            cache: Mutex::new(HashMap::new()),
+            default_timeout: Duration::from_secs(settings.timeout_secs),
+            timeouts: settings
+                .timeouts
+                .iter()
+                .map(|(name, secs)| (name.clone(), Duration::from_secs(*secs)))
+                .collect(),
+            max_output_chars: settings.max_output_chars,
+            audit: None,
         }
     }
 
+    /// Record every call (tool, args, outcome, duration) in the `tool_audit` table
+    pub fn with_audit(mut self, memory: MemoryManager) -> Self {
+        self.audit = Some(memory);
+        self
+    }
+
     pub fn register(&mut self, tool: Box<dyn Tool>) {
         self.tools.insert(tool.name().to_string(), tool);
     }
@@ -43,11 +145,311 @@ impl ToolDispatcher {
         serde_json::json!(schemas)
     }
 
-    pub async fn execute(&self, name: &str, args: Value) -> Result<String> {
-        if let Some(tool) = self.tools.get(name) {
-            tool.execute(args).await
-        } else {
-            Err(anyhow::anyhow!("Tool not found: {}", name))
+    /// JSON Schema matching one valid tool call, `{ "tool": <name>, "args": <its params> }`,
+    /// or a batch of them, `{ "tools": [ ... ] }`.
+    /// Passed to Ollama as `format`, it constrains sampling with a grammar built from the schema.
+    pub fn tool_call_schema(&self) -> Value {
+        let mut names: Vec<&String> = self.tools.keys().collect();
+        names.sort();
+        let variants: Vec<Value> = names
+            .into_iter()
+            .map(|name| {
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "tool": { "type": "string", "enum": [name] },
+                        "args": self.tools[name].parameters()
+                    },
+                    "required": ["tool", "args"]
+                })
+            })
+            .collect();
+        let batch = serde_json::json!({
+            "type": "object",
+            "properties": {
+                "tools": {
+                    "type": "array",
+                    "items": { "anyOf": variants.clone() },
+                    "minItems": 1
+                }
+            },
+            "required": ["tools"]
+        });
+        let mut variants = variants;
+        variants.push(batch);
+        serde_json::json!({ "anyOf": variants })
+    }
+
+    /// Whether this call needs the user's approval; unknown tools fail later in `execute`
+    pub fn requires_approval(&self, name: &str, args: &Value) -> bool {
+        self.tools
+            .get(name)
+            .is_some_and(|t| t.requires_approval(args))
+    }
+
+    /// Whether an "Always allow" for `name` may stand in for approving each call
+    pub fn can_always_allow(&self, name: &str) -> bool {
+        self.tools.get(name).is_some_and(|t| t.can_always_allow())
+    }
+
+    /// Parameter schema of a registered tool
+    pub fn parameters(&self, name: &str) -> Option<Value> {
+        self.tools.get(name).map(|t| t.parameters())
+    }
+
+    /// (name, description) of every registered tool, sorted by name
+    pub fn list_tools(&self) -> Vec<(String, String)> {
+        let mut tools: Vec<(String, String)> = self
+            .tools
+            .values()
+            .map(|t| (t.name().to_string(), t.description().to_string()))
+            .collect();
+        tools.sort();
+        tools
+    }
+
+    pub async fn preview(&self, name: &str, args: Value) -> ToolPreview {
+        match self.tools.get(name) {
+            Some(tool) => tool.preview(args).await,
+            None => Ok(None),
+        }
+    }
+
+    #[tracing::instrument(name = "tool", skip(self, args))]
+    pub async fn execute(&self, name: &str, args: Value) -> Result<ToolOutput> {
+        let started = Instant::now();
+        let args_text = args.to_string();
+        let result = self.dispatch(name, args).await;
+        let (status, output) = match &result {
+            Ok(output) if output.cached => ("cached", audit_excerpt(&output.content)),
+            Ok(output) => ("ok", audit_excerpt(&output.content)),
+            Err(e) if e.downcast_ref::<ToolTimeout>().is_some() => ("timeout", e.to_string()),
+            Err(e) => ("error", e.to_string()),
+        };
+        self.audit(name, &args_text, status, &output, started.elapsed())
+            .await;
+        result
+    }
+
+    /// A call the user refused, for the audit log
+    pub async fn record_denied(&self, name: &str, args: &Value) {
+        self.audit(
+            name,
+            &args.to_string(),
+            "denied",
+            "Denied by user",
+            Duration::ZERO,
+        )
+        .await;
+    }
+
+    /// Failing to write the log never fails the call itself
+    async fn audit(&self, name: &str, args: &str, status: &str, output: &str, duration: Duration) {
+        let Some(memory) = &self.audit else {
+            return;
+        };
+        let recorded = memory
+            .add_tool_audit(
+                name,
+                &secrets::redact(args),
+                status,
+                &secrets::redact(output),
+                duration,
+            )
+            .await;
+        if let Err(e) = recorded {
+            warn!("[Audit] Failed to record {}: {}", name, e);
+        }
+    }
+
+    async fn dispatch(&self, name: &str, args: Value) -> Result<ToolOutput> {
+        let tool = self
+            .tools
+            .get(name)
+            .ok_or_else(|| anyhow::anyhow!("Tool not found: {}", name))?;
+
+        if !tool.is_cacheable(&args) {
+            // A mutating call may change what any cached read would return, not just its
+            // own tool's: a file write changes the next directory listing
+            self.clear_cache();
+            let content = self.run(tool.as_ref(), args).await?;
+            return Ok(ToolOutput {
+                content,
+                cached: false,
+            });
+        }
+
+        // serde_json keeps object keys sorted, so this is a canonical form of the args
+        let key = format!("{}:{}", name, args);
+        if let Some(content) = self.cache_lookup(&key) {
+            return Ok(ToolOutput {
+                content,
+                cached: true,
+            });
+        }
+
+        let content = self.run(tool.as_ref(), args).await?;
+        let mut cache = self.cache.lock().unwrap();
+        let now = Instant::now();
+        cache.retain(|_, entry| entry.expires_at > now);
+        cache.insert(
+            key,
+            CachedResult {
+                content: content.clone(),
+                expires_at: now + tool.cache_ttl(),
+            },
+        );
+        Ok(ToolOutput {
+            content,
+            cached: false,
+        })
+    }
+
+    /// Run several calls concurrently. Results come back in the order of `calls`, each
+    /// with its own success or failure.
+    pub async fn execute_batch(&self, calls: Vec<(String, Value)>) -> Vec<Result<ToolOutput>> {
+        futures_util::future::join_all(
+            calls
+                .into_iter()
+                .map(|(name, args)| async move { self.execute(&name, args).await }),
+        )
+        .await
+    }
+
+    /// `tool.execute` under its time limit, with text output capped at `max_output_chars`
+    async fn run(&self, tool: &dyn Tool, args: Value) -> Result<String> {
+        let limit = self
+            .timeouts
+            .get(tool.name())
+            .copied()
+            .or_else(|| tool.timeout())
+            .unwrap_or(self.default_timeout);
+        let content = tokio::time::timeout(limit, tool.execute(args))
+            .await
+            .map_err(|_| ToolTimeout {
+                tool: tool.name().to_string(),
+                after: limit,
+            })??;
+        Ok(self.cap_output(content))
+    }
+
+    fn cap_output(&self, content: String) -> String {
+        if screenshot::parse_image_output(&content).is_some() {
+            return content;
         }
+        let total = content.chars().count();
+        if total <= self.max_output_chars {
+            return content;
+        }
+        let kept: String = content.chars().take(self.max_output_chars).collect();
+        format!(
+            "{}...\n\n[Output truncated: {} of {} chars shown]",
+            kept, self.max_output_chars, total
+        )
+    }
+
+    fn cache_lookup(&self, key: &str) -> Option<String> {
+        let cache = self.cache.lock().unwrap();
+        cache
+            .get(key)
+            .filter(|entry| entry.expires_at > Instant::now())
+            .map(|entry| entry.content.clone())
+    }
+
+    /// Drop every cached result. Returns how many entries were removed.
+    pub fn clear_cache(&self) -> usize {
+        let mut cache = self.cache.lock().unwrap();
+        let count = cache.len();
+        cache.clear();
+        count
+    }
+}
+
+/// What the audit log keeps of a result: images as a placeholder, text cut short
+fn audit_excerpt(content: &str) -> String {
+    if let Some((_, caption)) = screenshot::parse_image_output(content) {
+        return format!("[image] {}", caption).trim_end().to_string();
+    }
+    if content.chars().count() <= AUDIT_OUTPUT_CHARS {
+        return content.to_string();
+    }
+    let kept: String = content.chars().take(AUDIT_OUTPUT_CHARS).collect();
+    format!("{}...", kept)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// `read` reports the counter and is cacheable; `write` bumps it and is not
+    struct Counter {
+        name: &'static str,
+        value: Arc<AtomicUsize>,
+    }
+
+    impl Tool for Counter {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn description(&self) -> &str {
+            "Test counter"
+        }
+
+        fn parameters(&self) -> Value {
+            json!({ "type": "object", "properties": {} })
+        }
+
+        fn execute(&self, _args: Value) -> Pin<Box<dyn Future<Output = ToolResult> + Send>> {
+            let value = Arc::clone(&self.value);
+            let writes = self.name == "write";
+            Box::pin(async move {
+                if writes {
+                    value.fetch_add(1, Ordering::SeqCst);
+                }
+                Ok(value.load(Ordering::SeqCst).to_string())
+            })
+        }
+
+        fn is_cacheable(&self, _args: &Value) -> bool {
+            self.name == "read"
+        }
+    }
+
+    fn dispatcher() -> ToolDispatcher {
+        let value = Arc::new(AtomicUsize::new(0));
+        let mut dispatcher = ToolDispatcher::new(&ToolSettings::default());
+        for name in ["read", "write"] {
+            dispatcher.register(Box::new(Counter {
+                name,
+                value: Arc::clone(&value),
+            }));
+        }
+        dispatcher
+    }
+
+    #[tokio::test]
+    async fn repeated_reads_come_from_the_cache() {
+        let dispatcher = dispatcher();
+        let first = dispatcher.execute("read", json!({})).await.unwrap();
+        let second = dispatcher.execute("read", json!({})).await.unwrap();
+        assert!(!first.cached);
+        assert!(second.cached);
+        assert_eq!(second.content, "0");
+    }
+
+    #[tokio::test]
+    async fn a_write_invalidates_cached_reads_of_other_tools() {
+        let dispatcher = dispatcher();
+        dispatcher.execute("read", json!({})).await.unwrap();
+
+        dispatcher.execute("write", json!({})).await.unwrap();
+
+        let read = dispatcher.execute("read", json!({})).await.unwrap();
+        assert!(!read.cached);
+        assert_eq!(read.content, "1");
     }
 }
@@ -0,0 +1,934 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tracing::warn;
+
+use crate::llm::ollama::{HardwareConfig, SamplingConfig};
+use crate::llm::template::PromptTemplate;
+
+/// Config in the working directory, used instead of the per-user one when present
+const LOCAL_CONFIG_PATH: &str = "amadeus.toml";
+
+/// `amadeus.toml` in the working directory if there is one, otherwise
+/// `~/.config/amadeus/config.toml` (or the platform's equivalent)
+pub fn config_path() -> PathBuf {
+    let local = PathBuf::from(LOCAL_CONFIG_PATH);
+    if local.exists() {
+        return local;
+    }
+    dirs::config_dir()
+        .map(|dir| dir.join("amadeus").join("config.toml"))
+        .unwrap_or(local)
+}
+
+/// User-editable settings loaded from the config file (see `config_path`).
+/// Missing fields fall back to their defaults, so an empty file is valid.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    /// Text placed before the persona prompt (e.g. organization guardrails)
+    pub system_prompt_prefix: String,
+    /// Text placed after the persona prompt, before remembered facts and tools
+    pub system_prompt_suffix: String,
+    /// Ollama model for new conversations; existing ones keep the model they were pinned to
+    pub model: String,
+    /// Persona for new conversations
+    pub persona: String,
+    /// Set once the first-run walkthrough is finished or skipped
+    pub setup_complete: bool,
+    /// Path to the Whisper GGML model used for voice input
+    pub stt_model_path: String,
+    /// Chat format for the model; `auto` uses the template bundled with the model
+    pub prompt_template: PromptTemplate,
+    /// Ollama model used for requests carrying a fresh image (e.g. "llava"); `None` keeps the chat model
+    pub vision_model: Option<String>,
+    /// How long Ollama keeps the model loaded between messages ("30m"; "-1m" = forever)
+    pub keep_alive: String,
+    /// Persona intensity, 0 (strictly professional) - 100 (playfully tsundere).
+    /// Sets sampling temperature and a tone instruction in the system prompt.
+    pub persona_intensity: u8,
+    /// `[sampling]` section: sampler overrides; unset keys keep the model's defaults
+    pub sampling: SamplingConfig,
+    /// `[hardware]` section: GPU offload, batch size and threads Ollama loads the model with
+    pub hardware: HardwareConfig,
+    pub browser: BrowserSettings,
+    pub tts: TtsSettings,
+    pub avatar: AvatarSettings,
+    pub translation: TranslationSettings,
+    pub context: ContextSettings,
+    pub tools: ToolSettings,
+    pub memory: MemorySettings,
+    pub voice_input: VoiceInputSettings,
+    pub hotkeys: HotkeySettings,
+    pub notifications: NotificationSettings,
+    pub shell: ShellSettings,
+    pub files: FileSettings,
+    pub ocr: OcrSettings,
+    pub reminders: ReminderSettings,
+    pub proactive: ProactiveSettings,
+    pub http: HttpSettings,
+    pub email: EmailSettings,
+    pub plugins: PluginSettings,
+    pub mcp: McpSettings,
+    pub models: ModelSettings,
+    pub logging: LoggingSettings,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            system_prompt_prefix: String::new(),
+            system_prompt_suffix: String::new(),
+            model: "qwen2.5-coder:14b".to_string(),
+            persona: "Amadeus".to_string(),
+            setup_complete: false,
+            stt_model_path: crate::voice::stt::DEFAULT_MODEL_PATH.to_string(),
+            prompt_template: PromptTemplate::default(),
+            vision_model: None,
+            keep_alive: "30m".to_string(),
+            persona_intensity: 50,
+            sampling: SamplingConfig::default(),
+            hardware: HardwareConfig::default(),
+            browser: BrowserSettings::default(),
+            tts: TtsSettings::default(),
+            avatar: AvatarSettings::default(),
+            translation: TranslationSettings::default(),
+            context: ContextSettings::default(),
+            tools: ToolSettings::default(),
+            memory: MemorySettings::default(),
+            voice_input: VoiceInputSettings::default(),
+            hotkeys: HotkeySettings::default(),
+            notifications: NotificationSettings::default(),
+            shell: ShellSettings::default(),
+            files: FileSettings::default(),
+            ocr: OcrSettings::default(),
+            reminders: ReminderSettings::default(),
+            proactive: ProactiveSettings::default(),
+            http: HttpSettings::default(),
+            email: EmailSettings::default(),
+            plugins: PluginSettings::default(),
+            mcp: McpSettings::default(),
+            models: ModelSettings::default(),
+            logging: LoggingSettings::default(),
+        }
+    }
+}
+
+/// `[browser]` section: how the browser_automation tool launches Chromium
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BrowserSettings {
+    /// Headful by default so the user can watch what Amadeus is doing
+    pub headless: bool,
+    /// Persistent profile directory so cookies and logins survive restarts
+    pub user_data_dir: String,
+    pub window_width: u32,
+    pub window_height: u32,
+    pub user_agent: Option<String>,
+    /// Close the browser after this many seconds without use; 0 keeps it open
+    pub idle_timeout_secs: u64,
+}
+
+impl Default for BrowserSettings {
+    fn default() -> Self {
+        Self {
+            headless: false,
+            user_data_dir: "browser-profile".to_string(),
+            window_width: 1280,
+            window_height: 800,
+            user_agent: None,
+            idle_timeout_secs: 300,
+        }
+    }
+}
+
+impl AppConfig {
+    /// Load the config file, falling back to defaults if it is missing or invalid
+    pub fn load() -> Self {
+        let path = config_path();
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(_) => return Self::default(),
+        };
+        match toml::from_str(&text) {
+            Ok(config) => config,
+            Err(e) => {
+                warn!(
+                    "[Config] Invalid {}: {}. Using defaults.",
+                    path.display(),
+                    e
+                );
+                Self::default()
+            }
+        }
+    }
+
+    /// The part of the config the settings panel edits
+    pub fn settings(&self) -> Settings {
+        Settings {
+            model: self.model.clone(),
+            persona: self.persona.clone(),
+            persona_intensity: self.persona_intensity,
+            sampling: self.sampling.clone(),
+            context_window: self.context.window_tokens,
+            hardware: self.hardware.clone(),
+            tts_enabled: self.tts.enabled,
+            rate: self.tts.rate,
+            pitch: self.tts.pitch,
+            volume: self.tts.volume,
+            output_device: self.tts.output_device.clone(),
+            piper_model: self.tts.piper_model.clone(),
+            piper_speaker: self.tts.piper_speaker.clone(),
+            file_roots: self.files.roots.clone(),
+            proactive: self.proactive.enabled,
+        }
+    }
+
+    /// Write `settings` back to the config file, keeping the rest of it (comments
+    /// included) as the user wrote it
+    pub fn save_settings(settings: &Settings) -> anyhow::Result<()> {
+        let optional = |value: &Option<String>| value.as_deref().map(toml_edit::value);
+        let optional_f32 = |value: Option<f32>| value.map(float);
+        let mut roots = toml_edit::Array::new();
+        for root in &settings.file_roots {
+            roots.push(root.as_str());
+        }
+        let sampling = &settings.sampling;
+        let hardware = &settings.hardware;
+        let optional_u32 = |value: Option<u32>| value.map(|v| toml_edit::value(v as i64));
+
+        Self::save_values(&[
+            ("", "model", Some(toml_edit::value(settings.model.as_str()))),
+            (
+                "",
+                "persona",
+                Some(toml_edit::value(settings.persona.as_str())),
+            ),
+            (
+                "",
+                "persona_intensity",
+                Some(toml_edit::value(settings.persona_intensity as i64)),
+            ),
+            (
+                "sampling",
+                "temperature",
+                optional_f32(sampling.temperature),
+            ),
+            ("sampling", "top_p", optional_f32(sampling.top_p)),
+            (
+                "sampling",
+                "top_k",
+                sampling.top_k.map(|v| toml_edit::value(v as i64)),
+            ),
+            (
+                "sampling",
+                "repeat_penalty",
+                optional_f32(sampling.repeat_penalty),
+            ),
+            ("sampling", "seed", sampling.seed.map(toml_edit::value)),
+            (
+                "context",
+                "window_tokens",
+                Some(toml_edit::value(settings.context_window as i64)),
+            ),
+            (
+                "hardware",
+                "cpu_only",
+                Some(toml_edit::value(hardware.cpu_only)),
+            ),
+            ("hardware", "gpu_layers", optional_u32(hardware.gpu_layers)),
+            ("hardware", "batch_size", optional_u32(hardware.batch_size)),
+            ("hardware", "threads", optional_u32(hardware.threads)),
+            (
+                "tts",
+                "enabled",
+                Some(toml_edit::value(settings.tts_enabled)),
+            ),
+            ("tts", "rate", Some(toml_edit::value(settings.rate as i64))),
+            ("tts", "pitch", Some(float(settings.pitch))),
+            ("tts", "volume", Some(float(settings.volume))),
+            ("tts", "output_device", optional(&settings.output_device)),
+            ("tts", "piper_model", optional(&settings.piper_model)),
+            ("tts", "piper_speaker", optional(&settings.piper_speaker)),
+            ("files", "roots", Some(toml_edit::value(roots))),
+            (
+                "proactive",
+                "enabled",
+                Some(toml_edit::value(settings.proactive)),
+            ),
+        ])
+    }
+
+    /// Write `[files] roots` back to the config file
+    pub fn save_file_roots(roots: &[String]) -> anyhow::Result<()> {
+        let mut list = toml_edit::Array::new();
+        for root in roots {
+            list.push(root.as_str());
+        }
+        Self::save_values(&[("files", "roots", Some(toml_edit::value(list)))])
+    }
+
+    /// Write `[avatar] model` back to the config file
+    pub fn save_avatar_model(path: &str) -> anyhow::Result<()> {
+        Self::save_values(&[("avatar", "model", Some(toml_edit::value(path)))])
+    }
+
+    /// Remember that the first-run walkthrough is done
+    pub fn save_setup_complete() -> anyhow::Result<()> {
+        Self::save_values(&[("", "setup_complete", Some(toml_edit::value(true)))])
+    }
+
+    /// Write `[logging] level` back to the config file
+    pub fn save_log_level(level: &str) -> anyhow::Result<()> {
+        Self::save_values(&[("logging", "level", Some(toml_edit::value(level)))])
+    }
+
+    /// Write `[proactive] enabled` back to the config file
+    pub fn save_proactive_enabled(enabled: bool) -> anyhow::Result<()> {
+        Self::save_values(&[("proactive", "enabled", Some(toml_edit::value(enabled)))])
+    }
+
+    /// Set each `(table, key)`, or remove it for `None`. An empty table name is the top
+    /// level. The file and its folder are created if needed.
+    fn save_values(values: &[(&str, &str, Option<toml_edit::Item>)]) -> anyhow::Result<()> {
+        let path = config_path();
+        let text = std::fs::read_to_string(&path).unwrap_or_default();
+        let mut doc: toml_edit::DocumentMut = text
+            .parse()
+            .map_err(|e| anyhow::anyhow!("Invalid {}: {}", path.display(), e))?;
+        for (table, key, value) in values {
+            let target = if table.is_empty() {
+                doc.as_table_mut()
+            } else {
+                if !doc.contains_table(table) {
+                    doc[*table] = toml_edit::table();
+                }
+                doc[*table]
+                    .as_table_mut()
+                    .ok_or_else(|| anyhow::anyhow!("[{}] is not a table", table))?
+            };
+            match value {
+                Some(value) => {
+                    target[*key] = value.clone();
+                }
+                None => {
+                    target.remove(key);
+                }
+            }
+        }
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, doc.to_string())?;
+        Ok(())
+    }
+}
+
+/// An f32 as the decimal it was typed as: 0.7, not 0.699999988079071
+fn float(value: f32) -> toml_edit::Item {
+    toml_edit::value(value.to_string().parse::<f64>().unwrap_or(value as f64))
+}
+
+/// What the settings panel edits through `get_settings`/`set_settings`. Saved to the
+/// config file and applied without a restart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Settings {
+    pub model: String,
+    pub persona: String,
+    pub persona_intensity: u8,
+    pub sampling: SamplingConfig,
+    /// `[context] window_tokens`
+    pub context_window: u32,
+    pub hardware: HardwareConfig,
+    /// Speak replies aloud
+    pub tts_enabled: bool,
+    pub rate: u32,
+    pub pitch: f32,
+    pub volume: f32,
+    pub output_device: Option<String>,
+    pub piper_model: Option<String>,
+    pub piper_speaker: Option<String>,
+    pub file_roots: Vec<String>,
+    pub proactive: bool,
+}
+
+/// Speech engine behind `TtsManager`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TtsEngine {
+    /// macOS `say`
+    Say,
+    /// Windows SAPI via PowerShell
+    Sapi,
+    /// `espeak-ng`
+    Espeak,
+    /// Piper neural TTS with a local `.onnx` voice
+    Piper,
+}
+
+/// `[tts]` section: base voice and output routing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TtsSettings {
+    /// Speak replies aloud; off keeps Amadeus text-only until switched back on
+    pub enabled: bool,
+    /// `None` picks by platform: say on macOS, SAPI on Windows, otherwise piper when a
+    /// model is set and espeak-ng if not
+    pub backend: Option<TtsEngine>,
+    /// Voice for the piper backend: an id installed in `voices/` or a path to an `.onnx` model
+    pub piper_model: Option<String>,
+    /// Speaker name for multi-speaker piper models
+    pub piper_speaker: Option<String>,
+    /// Piper executable, looked up on PATH unless it is a path
+    pub piper_binary: String,
+    /// Base speaking rate in words per minute (175 is a natural pace)
+    pub rate: u32,
+    /// Pitch offset relative to the voice baseline
+    pub pitch: f32,
+    /// Output device name; `None` uses the system default
+    pub output_device: Option<String>,
+    /// Playback volume, 0.0 - 1.0
+    pub volume: f32,
+}
+
+impl Default for TtsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            backend: None,
+            piper_model: None,
+            piper_speaker: None,
+            piper_binary: "piper".to_string(),
+            rate: 175,
+            pitch: 0.0,
+            output_device: None,
+            volume: 1.0,
+        }
+    }
+}
+
+/// `[avatar]` section: rendering budget for the VRM view
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AvatarSettings {
+    /// Frame cap for the avatar canvas; lower saves battery on an always-on window
+    pub target_fps: u32,
+    /// A `.vrm` file shown instead of the bundled avatar, for personas that don't name
+    /// their own
+    pub model: Option<String>,
+    /// Ask the model to tag each reply with an emotion for the avatar's expression;
+    /// off, the expression is guessed from keywords
+    pub emotion_tags: bool,
+}
+
+impl Default for AvatarSettings {
+    fn default() -> Self {
+        Self {
+            target_fps: 30,
+            model: None,
+            emotion_tags: true,
+        }
+    }
+}
+
+/// `[translation]` section: the per-message translate button
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TranslationSettings {
+    /// Language replies are translated into, as a name the model understands
+    pub target_language: String,
+}
+
+impl Default for TranslationSettings {
+    fn default() -> Self {
+        Self {
+            target_language: "English".to_string(),
+        }
+    }
+}
+
+/// `[context]` section: the model's context window and when to warn about it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContextSettings {
+    /// Tokens requested from Ollama (`num_ctx`); older messages are dropped to stay inside it
+    pub window_tokens: u32,
+    /// Share of the window (0.0 - 1.0) at which the usage bar turns into a warning
+    pub warn_ratio: f32,
+    /// Replace trimmed messages with a model-written summary instead of just dropping them
+    pub summarize: bool,
+}
+
+impl Default for ContextSettings {
+    fn default() -> Self {
+        Self {
+            window_tokens: 8192,
+            warn_ratio: 0.8,
+            summarize: true,
+        }
+    }
+}
+
+/// `[tools]` section: which tools may act without asking first, and how long a turn may run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolSettings {
+    /// Tools that normally need approval (input_control, file_system writes,
+    /// browser_automation) but are allowed to run without asking
+    pub always_allow: Vec<String>,
+    /// Tool calls allowed in one turn before the agent gives up
+    pub max_iterations: usize,
+    /// Times the same call (tool and args) may run in one turn
+    pub max_repeats: usize,
+    /// Seconds a tool call may run before it is stopped and reported to the model as timed out
+    pub timeout_secs: u64,
+    /// Per-tool time limits in seconds, e.g. `browser_automation = 120`
+    pub timeouts: HashMap<String, u64>,
+    /// Longest tool output (in characters) put into the prompt; the rest is cut off
+    pub max_output_chars: usize,
+    /// `[[tools.external]]` programs that provide tools over JSON-RPC on stdin/stdout
+    pub external: Vec<ToolProcess>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolProcess {
+    /// Program to launch, e.g. "python3"
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// A value of `secret:<name>` is read from the keychain
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Run its tools without asking first
+    #[serde(default)]
+    pub trusted: bool,
+}
+
+impl Default for ToolSettings {
+    fn default() -> Self {
+        Self {
+            always_allow: Vec::new(),
+            max_iterations: 8,
+            max_repeats: 2,
+            timeout_secs: 60,
+            timeouts: HashMap::new(),
+            max_output_chars: 12000,
+            external: Vec::new(),
+        }
+    }
+}
+
+/// `[memory]` section: long-term facts recalled by meaning across conversations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MemorySettings {
+    /// Extract facts after each turn and recall relevant ones before each reply
+    pub enabled: bool,
+    /// Ollama embedding model (`ollama pull nomic-embed-text`)
+    pub embedding_model: String,
+    /// Most facts added to one prompt
+    pub recall_limit: usize,
+    /// Cosine similarity (0.0 - 1.0) a fact needs to count as relevant
+    pub min_similarity: f32,
+}
+
+impl Default for MemorySettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            embedding_model: "nomic-embed-text".to_string(),
+            recall_limit: 5,
+            min_similarity: 0.5,
+        }
+    }
+}
+
+/// `[voice_input]` section: hands-free listening with voice activity detection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VoiceInputSettings {
+    /// Listen continuously from startup; the 👂 button toggles it at runtime
+    pub continuous: bool,
+    /// Only speech containing this word is sent, e.g. "Amadeus, what time is it?".
+    /// Saying just the word sends the next utterance. `None` sends everything.
+    pub wake_word: Option<String>,
+    /// Silence that ends an utterance
+    pub end_silence_ms: u32,
+    /// Sounds shorter than this (clicks, coughs) don't start an utterance
+    pub min_speech_ms: u32,
+    /// Utterances are cut off after this long
+    pub max_utterance_secs: u32,
+    /// Quietest level (RMS, 0.0 - 1.0) counted as speech, however quiet the room is
+    pub min_level: f32,
+    /// Whisper language code ("en", "ko", ...) or "auto" to detect it per utterance
+    pub language: String,
+    /// Transcribe in the active persona's `language` when it has one
+    pub follow_persona_language: bool,
+}
+
+impl Default for VoiceInputSettings {
+    fn default() -> Self {
+        Self {
+            continuous: false,
+            wake_word: Some("Amadeus".to_string()),
+            end_silence_ms: 800,
+            min_speech_ms: 250,
+            max_utterance_secs: 15,
+            min_level: 0.01,
+            language: "auto".to_string(),
+            follow_persona_language: true,
+        }
+    }
+}
+
+/// `[hotkeys]` section: system-wide shortcuts that work while another app has focus,
+/// written like "CommandOrControl+Shift+A". An empty string turns a shortcut off.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HotkeySettings {
+    /// Open (or close) the quick-chat box over whatever is on screen
+    pub quick_chat: Option<String>,
+    /// Record while held and send what was said on release
+    pub push_to_talk: Option<String>,
+}
+
+impl Default for HotkeySettings {
+    fn default() -> Self {
+        Self {
+            quick_chat: Some("CommandOrControl+Shift+A".to_string()),
+            push_to_talk: Some("CommandOrControl+Shift+Space".to_string()),
+        }
+    }
+}
+
+/// `[notifications]` section: desktop notifications while the window is in the background
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationSettings {
+    pub enabled: bool,
+    /// A reply that took at least this long to produce is announced
+    pub long_task_secs: u64,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            long_task_secs: 20,
+        }
+    }
+}
+
+/// `[shell]` section: what the shell tool may run and for how long
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShellSettings {
+    /// Commands that run without asking. An entry matches commands starting with its
+    /// words: "git" allows any git command, "git status" only that one.
+    pub allow: Vec<String>,
+    /// Programs that never run, even with approval
+    pub deny: Vec<String>,
+    /// Commands still running after this long are killed
+    pub timeout_secs: u64,
+    /// stdout and stderr are each cut to their last this-many characters
+    pub max_output_chars: usize,
+}
+
+impl Default for ShellSettings {
+    fn default() -> Self {
+        let words = |list: &[&str]| list.iter().map(|s| s.to_string()).collect();
+        Self {
+            // Read-only commands only; builds and scripts run whatever the project says
+            allow: words(&["git status", "git diff", "git log", "ls", "pwd"]),
+            // Shells, interpreters and programs that run other programs would run a command
+            // the allowlist never sees
+            deny: words(&[
+                "sudo",
+                "su",
+                "doas",
+                "rm",
+                "rmdir",
+                "del",
+                "format",
+                "mkfs",
+                "dd",
+                "shutdown",
+                "reboot",
+                "sh",
+                "bash",
+                "zsh",
+                "fish",
+                "dash",
+                "ksh",
+                "csh",
+                "tcsh",
+                "cmd",
+                "powershell",
+                "pwsh",
+                "env",
+                "xargs",
+                "nohup",
+                "nice",
+                "timeout",
+                "busybox",
+                "find",
+                "python",
+                "py",
+                "node",
+                "deno",
+                "bun",
+                "perl",
+                "ruby",
+                "php",
+                "lua",
+                "osascript",
+                "wscript",
+                "cscript",
+            ]),
+            timeout_secs: 120,
+            max_output_chars: 4000,
+        }
+    }
+}
+
+/// `[files]` section: where the file_system and shell tools may work
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FileSettings {
+    /// Folders the tools can read and write; relative paths start in the first one.
+    /// `~` is the home folder. Edited from the settings panel.
+    pub roots: Vec<String>,
+}
+
+impl Default for FileSettings {
+    fn default() -> Self {
+        Self {
+            roots: vec!["~/AmadeusWorkspace".to_string()],
+        }
+    }
+}
+
+/// `[ocr]` section: the tesseract install behind the ocr tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OcrSettings {
+    pub tesseract_binary: String,
+    /// Tesseract language packs, joined with `+` (e.g. "eng+kor")
+    pub languages: String,
+}
+
+impl Default for OcrSettings {
+    fn default() -> Self {
+        Self {
+            tesseract_binary: "tesseract".to_string(),
+            languages: "eng".to_string(),
+        }
+    }
+}
+
+/// `[reminders]` section: how due reminders get the user's attention
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReminderSettings {
+    /// Read reminders aloud as well as posting them in the chat
+    pub speak: bool,
+}
+
+impl Default for ReminderSettings {
+    fn default() -> Self {
+        Self { speak: true }
+    }
+}
+
+/// `[proactive]` section: when Amadeus speaks up without being asked
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProactiveSettings {
+    /// Master switch, also toggled from the settings panel
+    pub enabled: bool,
+    /// Check in after the user has been quiet this long; 0 disables
+    pub idle_minutes: u32,
+    /// Least time between unprompted idle or screen remarks
+    pub cooldown_minutes: u32,
+    /// Have due reminders delivered in Amadeus's own words instead of a fixed notice
+    pub on_reminder: bool,
+    /// Comment on screen_watch captures as they arrive
+    pub on_screen_change: bool,
+    /// `[[proactive.schedule]]` entries: prompts run at a local time every day
+    pub schedule: Vec<ScheduledPrompt>,
+}
+
+impl Default for ProactiveSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            idle_minutes: 30,
+            cooldown_minutes: 10,
+            on_reminder: true,
+            on_screen_change: false,
+            schedule: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledPrompt {
+    /// Local time, "HH:MM"
+    pub at: String,
+    /// What Amadeus is asked to do then (e.g. "Give me a short morning briefing")
+    pub prompt: String,
+}
+
+/// `[http]` section: what the http_request tool may call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpSettings {
+    /// Hosts requests (and redirects) may go to; "example.com" also covers its subdomains
+    pub allowed_domains: Vec<String>,
+    /// Response bodies are cut off after this many bytes
+    pub max_response_bytes: usize,
+    pub timeout_secs: u64,
+}
+
+impl Default for HttpSettings {
+    fn default() -> Self {
+        Self {
+            allowed_domains: vec!["localhost".to_string(), "127.0.0.1".to_string()],
+            max_response_bytes: 20_000,
+            timeout_secs: 20,
+        }
+    }
+}
+
+/// `[email]` section: mail accounts for the email tool. Passwords live in the OS keychain
+/// (set from the settings panel), never in this file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmailSettings {
+    /// `[[email.accounts]]` entries; the first is used when the model doesn't pick one
+    pub accounts: Vec<EmailAccount>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailAccount {
+    /// Short name the model and the settings panel use, e.g. "work"
+    pub name: String,
+    /// Sender address
+    pub address: String,
+    /// Login name; the address when unset
+    #[serde(default)]
+    pub username: Option<String>,
+    pub imap_host: String,
+    /// IMAP over TLS
+    #[serde(default = "default_imap_port")]
+    pub imap_port: u16,
+    pub smtp_host: String,
+    /// 465 connects with TLS; any other port upgrades with STARTTLS
+    #[serde(default = "default_smtp_port")]
+    pub smtp_port: u16,
+}
+
+impl EmailAccount {
+    pub fn username(&self) -> &str {
+        self.username.as_deref().unwrap_or(&self.address)
+    }
+}
+
+fn default_imap_port() -> u16 {
+    993
+}
+
+fn default_smtp_port() -> u16 {
+    465
+}
+
+/// `[plugins]` section: third-party tools loaded from `.wasm` files. Plugins run in a
+/// sandbox with no network and no files beyond the folders granted to them here.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PluginSettings {
+    pub enabled: bool,
+    /// Folder scanned for `*.wasm` at startup
+    pub dir: String,
+    /// Linear memory one plugin instance may grow to
+    pub max_memory_mb: u64,
+    /// Instruction budget per call (wasmtime fuel); a plugin that runs out is stopped
+    pub fuel: u64,
+    /// Folders a plugin may read and write, by tool name, e.g. `notes = ["~/Notes"]`.
+    /// Each shows up inside the plugin under its own folder name.
+    pub dirs: HashMap<String, Vec<String>>,
+}
+
+/// `[mcp]` section: Model Context Protocol servers whose tools are offered alongside the
+/// built-in ones, as `<server>.<tool>`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct McpSettings {
+    /// `[[mcp.servers]]` entries, connected at startup
+    pub servers: Vec<McpServer>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpServer {
+    /// Prefix for the server's tools, e.g. "github" gives "github.create_issue"
+    pub name: String,
+    /// Program to launch for a stdio server
+    #[serde(default)]
+    pub command: Option<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Environment for `command`; a value of `secret:<name>` is read from the keychain
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// SSE endpoint of an already running server, instead of `command`
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Run every tool without asking. Otherwise only tools the server marks read-only do.
+    #[serde(default)]
+    pub trusted: bool,
+}
+
+impl Default for PluginSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            dir: "plugins".to_string(),
+            max_memory_mb: 64,
+            fuel: 10_000_000_000,
+            dirs: HashMap::new(),
+        }
+    }
+}
+
+/// `[models]` section: where the model picker looks for GGUF files to import into Ollama
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelSettings {
+    /// Folders searched for `*.gguf` (a few levels deep); `~` is the home folder
+    pub dirs: Vec<String>,
+}
+
+impl Default for ModelSettings {
+    fn default() -> Self {
+        Self {
+            dirs: vec!["models".to_string()],
+        }
+    }
+}
+
+/// `[logging]` section: how much goes to the console and the log files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoggingSettings {
+    /// "trace", "debug", "info", "warn" or "error", or a filter such as
+    /// "amadeus_lib=debug,sqlx=info"
+    pub level: String,
+}
+
+impl Default for LoggingSettings {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+        }
+    }
+}
@@ -1,32 +1,141 @@
 mod agent;
+mod config;
 mod llm;
+mod logging;
+mod notify;
+mod setup;
 mod system;
+mod tray;
 mod voice;
 
 use anyhow::Result;
+use futures_util::FutureExt;
 use serde::Serialize;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter, Manager};
-use tokio::sync::{mpsc, Mutex};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+use tokio::sync::{mpsc, watch, Mutex};
+use tracing::{error, info, warn};
 
-use crate::agent::memory::MemoryManager;
-use crate::agent::persona::Persona;
-use crate::agent::tools::ToolDispatcher;
-use crate::llm::ollama::{Message, OllamaClient};
+use crate::agent::approval::{ApprovalGate, Decision};
+use crate::agent::commands::{self, SlashCommand};
+use crate::agent::context;
+use crate::agent::emotion::{self, Emotion};
+use crate::agent::events::{AgentEvent, AGENT_EVENT};
+use crate::agent::export;
+use crate::agent::external;
+use crate::agent::input::{Attachment, UserInput};
+use crate::agent::loop_guard::LoopGuard;
+use crate::agent::mcp;
+use crate::agent::memory::{Conversation, MemoryManager, SearchHit, StoredMessage, ToolAuditEntry};
+use crate::agent::persona::{self, Persona};
+use crate::agent::plugins::WasmToolHost;
+use crate::agent::proactive::{self, Proactivity, Trigger};
+use crate::agent::recall;
+use crate::agent::reminders::ReminderTool;
+use crate::agent::replay::{self, ReplayOptions};
+use crate::agent::schema;
+use crate::agent::scratchpad::{self, MemoryStoreTool};
+use crate::agent::timers::{TimerInfo, TimerTool, Timers};
+use crate::agent::toolcall;
+use crate::agent::tools::{Tool, ToolDispatcher, ToolTimeout};
+use crate::config::{
+    AppConfig, AvatarSettings, ContextSettings, HotkeySettings, Settings, VoiceInputSettings,
+};
+use crate::llm::hardware::{self, LlmInfo};
+use crate::llm::models::{self, ModelEntry};
+use crate::llm::ollama::{Message, OllamaClient, ReplyMetrics, SamplingConfig};
+use crate::setup::{SetupStatus, SetupStep};
 
-use crate::system::browser::BrowserTool;
+use crate::system::apps::AppControlTool;
+use crate::system::browser::{BrowserManager, BrowserTool};
+use crate::system::clipboard::ClipboardTool;
+use crate::system::email::{self, EmailAccountStatus, EmailTool};
 use crate::system::files::FileSystemTool;
+use crate::system::http::HttpRequestTool;
 use crate::system::input::InputTool;
-use crate::system::screenshot::ScreenshotTool;
+use crate::system::media::MediaControlTool;
+use crate::system::ocr::{LatestImage, OcrTool};
+use crate::system::quick_chat;
+use crate::system::region_select::{self, Selection};
+use crate::system::sandbox::Sandbox;
+use crate::system::screen_watch::ScreenWatchTool;
+use crate::system::screenshot::{self, DisplayCapture, ScreenshotTool};
+use crate::system::secrets;
+use crate::system::shell::ShellTool;
+use crate::system::system_info::SystemInfoTool;
 
-use crate::voice::tts::TtsManager;
+use crate::voice::lipsync::{LipSyncEvent, LIP_SYNC_EVENT};
+use crate::voice::listener::{
+    self, ListenState, MicLevel, SttCaption, MIC_LEVEL_EVENT, STT_CAPTION_EVENT,
+    VOICE_LISTENING_EVENT,
+};
+use crate::voice::piper::{self, PiperVoice};
+use crate::voice::stt::{SttLanguage, SttManager};
+use crate::voice::tts::{self, TtsManager};
 
-const OLLAMA_MODEL: &str = "qwen2.5-coder:14b";
+// Attempts at producing schema-conforming output before send_structured gives up
+const STRUCTURED_ATTEMPTS: usize = 2;
+
+// Sent on the user's behalf along with a region picked in the selection overlay
+const REGION_PROMPT: &str = "I selected this region of my screen. What do you see?";
+
+// Marks in-memory history entries carrying a screen_watch capture
+const SCREEN_UPDATE_PREFIX: &str = "[Screen update]";
+
+// Most hits returned by a history search
+const SEARCH_RESULT_LIMIT: i64 = 50;
+
+// Messages loaded when a conversation opens, and per "load older" request
+const HISTORY_PAGE_SIZE: i64 = 50;
+
+// How often personas/*.toml is checked for edits
+const PERSONA_POLL_INTERVAL: Duration = Duration::from_secs(2);
+// How often the agent loop looks for reminders that have come due
+const REMINDER_POLL_INTERVAL: Duration = Duration::from_secs(15);
+// How often proactive mode checks the idle and schedule triggers
+const PROACTIVE_POLL_INTERVAL: Duration = Duration::from_secs(20);
+// How often the agent loop tries to reach Ollama again while it is offline
+const OLLAMA_RETRY_INTERVAL: Duration = Duration::from_secs(10);
+// Longest wait before a crashed agent loop is started again
+const AGENT_RESTART_MAX_DELAY: Duration = Duration::from_secs(30);
+// An agent loop that ran this long before crashing restarts without backoff
+const AGENT_HEALTHY_RUN: Duration = Duration::from_secs(60);
 
 // ===== Tauri State =====
 
 pub struct AppState {
-    pub tx: mpsc::UnboundedSender<String>,
+    pub tx: mpsc::UnboundedSender<UserInput>,
+    /// Screen regions picked with the selection overlay, as base64 JPEG
+    pub region_tx: mpsc::UnboundedSender<String>,
+    /// Frozen display captures shown by the open selection overlays
+    pub region_captures: Vec<DisplayCapture>,
+    /// Sampler settings from the settings panel, applied by the agent loop
+    pub sampling_tx: mpsc::UnboundedSender<SamplingConfig>,
+    pub sampling: SamplingConfig,
+    /// Stops the reply currently being generated
+    pub stop_tx: Arc<watch::Sender<bool>>,
+    /// Tool calls waiting for the user's Allow/Deny
+    pub approvals: Arc<ApprovalGate>,
+    pub memory: MemoryManager,
+    /// Chromium shared by browser_automation calls
+    pub browser: Arc<BrowserManager>,
+    /// Workspace folders of the file_system and shell tools
+    pub sandbox: Arc<Sandbox>,
+    /// Proactive mode switch from the settings panel, read by the agent loop
+    pub proactive: Arc<AtomicBool>,
+    /// Countdowns and alarms, set from the UI or the timers tool
+    pub timers: Arc<Timers>,
+    pub tts: Option<Arc<TtsManager>>,
+    pub stt: Option<Arc<SttManager>>,
+    pub stt_status: SttStatus,
+    /// Set to stop continuous listening; `None` while it is off
+    pub voice_listener: Option<Arc<AtomicBool>>,
+    /// Set to end the push-to-talk recording in progress; `None` when not recording
+    pub push_to_talk: Option<Arc<AtomicBool>>,
 }
 
 // ===== Events sent to frontend =====
@@ -35,137 +144,2201 @@ pub struct AppState {
 struct ChatEvent {
     role: String,
     content: String,
+    /// Groups the assistant, tool and system messages produced for one user message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    turn_id: Option<u64>,
+    /// Database row id, for messages loaded from history (used for branching)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message_id: Option<i64>,
+    /// Kept when old messages are pruned
+    pinned: bool,
+}
+
+/// Saved conversations were added, removed or switched; the picker reloads its list
+#[derive(Clone, Serialize)]
+struct ConversationsChangedEvent {
+    active: i64,
+}
+
+/// Saved conversations, newest first, and which one is open
+#[derive(Serialize)]
+struct ConversationList {
+    active: i64,
+    conversations: Vec<Conversation>,
+}
+
+/// A slash command offered by the chat input's autocomplete
+#[derive(Serialize)]
+struct SlashCommandInfo {
+    /// What the user types, e.g. `/switch`
+    command: &'static str,
+    /// With its arguments, e.g. `/switch <id>`
+    usage: &'static str,
+    description: &'static str,
+}
+
+/// One page of scroll-back, oldest first
+#[derive(Serialize)]
+struct HistoryPage {
+    messages: Vec<StoredMessage>,
+    /// Even older messages are saved
+    has_more: bool,
+}
+
+/// Row ids assigned once a turn is persisted, so the UI can branch from it
+#[derive(Clone, Serialize)]
+struct TurnSavedEvent {
+    turn_id: u64,
+    first_id: i64,
+    last_id: i64,
+}
+
+/// Continuation of a reply that was cut off at the token limit
+#[derive(Clone, Serialize)]
+struct ChatAppendEvent {
+    content: String,
+    truncated: bool,
+}
+
+/// Replaces the text of the last assistant message, e.g. with a tool call's JSON removed
+#[derive(Clone, Serialize)]
+struct ChatRewriteEvent {
+    content: String,
+}
+
+/// Asks the user to allow a tool call that acts on the machine
+#[derive(Clone, Serialize)]
+struct ToolApprovalRequestEvent {
+    id: u64,
+    tool: String,
+    args: serde_json::Value,
+    /// What the call would change, e.g. a write_file diff
+    preview: Option<String>,
+    turn_id: u64,
+    /// False when "Always allow" wouldn't skip later calls, so the UI can hide it
+    always_allowable: bool,
+}
+
+/// The approval request is answered or was cancelled; the prompt can be closed
+#[derive(Clone, Serialize)]
+struct ToolApprovalResolvedEvent {
+    id: u64,
+}
+
+/// The active persona changed; the avatar view loads its model
+#[derive(Clone, Serialize)]
+struct PersonaChangedEvent {
+    name: String,
+    avatar_model: Option<String>,
+}
+
+/// How much of the model's context window the prompt occupies
+#[derive(Clone, Serialize)]
+struct ContextUsageEvent {
+    used: usize,
+    limit: u32,
+    warn_at: u32,
+    /// Older messages dropped from the prompt this turn to make it fit
+    trimmed: usize,
+}
+
+/// Tokens and timings of a finished turn, summed over its model calls
+#[derive(Clone, Serialize)]
+struct ChatMetricsEvent {
+    turn_id: u64,
+    model: String,
+    #[serde(flatten)]
+    metrics: ReplyMetrics,
+    tokens_per_second: f32,
+}
+
+/// The agent loop failed and is about to be restarted
+#[derive(Clone, Serialize)]
+struct AgentCrashedEvent {
+    reason: String,
+    /// Crashes in a row, this one included
+    restarts: u32,
+    retry_in_secs: u64,
+}
+
+#[derive(Clone, Serialize)]
+struct SpeakingEvent {
+    speaking: bool,
+}
+
+/// Sent while `download_model` runs
+#[derive(Clone, Serialize)]
+struct ModelDownloadProgress {
+    url: String,
+    downloaded: u64,
+    /// `None` if the server didn't say how big the file is
+    total: Option<u64>,
+}
+
+/// Sets the avatar's expression outside of a reply, e.g. when a timer rings
+#[derive(Clone, Serialize)]
+struct EmotionEvent {
+    emotion: Emotion,
+}
+
+#[derive(Clone, Serialize)]
+struct DbStats {
+    size_bytes: i64,
+}
+
+#[derive(Clone, Serialize)]
+pub struct SttStatus {
+    available: bool,
+    reason: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+struct AudioOutputSettings {
+    devices: Vec<String>,
+    selected: Option<String>,
+    volume: f32,
+}
+
+#[derive(Clone, Serialize)]
+struct VoiceSettings {
+    /// TTS engine in use, e.g. "say" or "piper"
+    backend: &'static str,
+    rate: u32,
+    pitch: f32,
+    /// Installed piper voices
+    voices: Vec<PiperVoice>,
+    model: Option<String>,
+    speaker: Option<String>,
 }
 
 #[derive(Clone, Serialize)]
-struct StatusEvent {
-    status: String,
-    is_thinking: bool,
+struct MaintenanceReport {
+    size_before: i64,
+    size_after: i64,
+    pruned: u64,
 }
 
 // ===== Tauri Commands =====
 
+/// Send a message, with the files at `attachments` (paths dropped onto the window) loaded
+/// and attached to it
 #[tauri::command]
 async fn send_message(
     state: tauri::State<'_, Arc<Mutex<AppState>>>,
     message: String,
+    attachments: Option<Vec<String>>,
 ) -> Result<(), String> {
+    let paths = attachments.unwrap_or_default();
+    let attachments = tauri::async_runtime::spawn_blocking(move || {
+        paths
+            .iter()
+            .map(|path| Attachment::load(std::path::Path::new(path)))
+            .collect::<Result<Vec<_>>>()
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())?;
     let state = state.lock().await;
     state
         .tx
-        .send(message)
+        .send(UserInput {
+            text: message,
+            attachments,
+        })
         .map_err(|e| format!("Failed to send message: {}", e))
 }
 
+/// One-shot request whose whole reply must be JSON matching `schema`.
+/// Runs outside the chat history so scripts don't pollute the conversation.
+#[tauri::command]
+async fn send_structured(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    message: String,
+    schema: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    let mut config = AppConfig::load();
+    // An emotion tag in front would make the reply invalid JSON
+    config.avatar.emotion_tags = false;
+    let memory = state.lock().await.memory.clone();
+    // Answer as the persona of the open conversation, like the chat does
+    let conversation = memory
+        .get_conversation(memory.active_conversation())
+        .await
+        .map_err(|e| e.to_string())?;
+    let persona = conversation
+        .and_then(|conversation| Persona::by_name(&conversation.persona_name))
+        .or_else(|| Persona::by_name(&config.persona))
+        .unwrap_or_else(Persona::amadeus);
+    let system_prompt = build_system_prompt(&config, &persona, &memory, "").await;
+
+    let client = model_client(&config.model, &config);
+    let mut messages = vec![
+        Message {
+            role: "system".to_string(),
+            content: format!(
+                "{}\n\nReply ONLY with a JSON value matching this JSON Schema:\n{}",
+                system_prompt, schema
+            ),
+            images: None,
+        },
+        Message {
+            role: "user".to_string(),
+            content: message,
+            images: None,
+        },
+    ];
+
+    let mut last_error = String::new();
+    for _ in 0..STRUCTURED_ATTEMPTS {
+        let reply = client
+            .chat_structured(messages.clone(), schema.clone())
+            .await
+            .map_err(|e| format!("Ollama request failed: {}", e))?;
+
+        let result = serde_json::from_str::<serde_json::Value>(&reply)
+            .map_err(|e| format!("Reply is not valid JSON: {}", e))
+            .and_then(|value| schema::validate(&value, &schema).map(|_| value));
+        match result {
+            Ok(value) => return Ok(value),
+            Err(e) => {
+                // Feed the violation back so the model can correct itself
+                messages.push(Message {
+                    role: "assistant".to_string(),
+                    content: reply,
+                    images: None,
+                });
+                messages.push(Message {
+                    role: "user".to_string(),
+                    content: format!("That reply does not match the schema ({}). Try again.", e),
+                    images: None,
+                });
+                last_error = e;
+            }
+        }
+    }
+    Err(format!(
+        "Structured output failed validation: {}",
+        last_error
+    ))
+}
+
+/// Translate a message for display only; nothing is added to the conversation
+#[tauri::command]
+async fn translate_message(text: String) -> Result<String, String> {
+    let config = AppConfig::load();
+    let client = model_client(&config.model, &config);
+    let messages = vec![
+        Message {
+            role: "system".to_string(),
+            content: format!(
+                "Translate the user's text into {}. Keep code, markdown, names and technical terms intact. Reply with the translation only.",
+                config.translation.target_language
+            ),
+            images: None,
+        },
+        Message {
+            role: "user".to_string(),
+            content: text,
+            images: None,
+        },
+    ];
+    client
+        .chat(messages)
+        .await
+        .map(|reply| reply.content)
+        .map_err(|e| format!("Translation failed: {}", e))
+}
+
 #[tauri::command]
 async fn clear_chat(state: tauri::State<'_, Arc<Mutex<AppState>>>) -> Result<(), String> {
     let state = state.lock().await;
     state
         .tx
-        .send("__CLEAR__".to_string())
+        .send("/clear".into())
         .map_err(|e| format!("Failed to send clear: {}", e))
 }
 
-// ===== Agent Loop =====
+/// Drop the last reply and answer the last user message again
+#[tauri::command]
+async fn regenerate(state: tauri::State<'_, Arc<Mutex<AppState>>>) -> Result<(), String> {
+    let state = state.lock().await;
+    state
+        .tx
+        .send("/regenerate".into())
+        .map_err(|e| format!("Failed to regenerate: {}", e))
+}
+
+/// Replace the last user message with `content` and answer it again
+#[tauri::command]
+async fn edit_last_user_message(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    content: String,
+) -> Result<(), String> {
+    if content.trim().is_empty() {
+        return Err("Message is empty".to_string());
+    }
+    let state = state.lock().await;
+    state
+        .tx
+        .send(format!("/edit {}", content).into())
+        .map_err(|e| format!("Failed to edit message: {}", e))
+}
+
+/// Fork the conversation at `message_id` and switch the agent to the new branch
+#[tauri::command]
+async fn branch_conversation(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    message_id: i64,
+) -> Result<i64, String> {
+    let state = state.lock().await;
+    let branch_id = state
+        .memory
+        .branch_conversation(message_id)
+        .await
+        .map_err(|e| format!("Branching failed: {}", e))?;
+    state
+        .tx
+        .send(format!("/switch {}", branch_id).into())
+        .map_err(|e| format!("Failed to switch to branch: {}", e))?;
+    Ok(branch_id)
+}
+
+/// Delete one saved message, then reopen the conversation so the agent's history and the
+/// transcript drop it too
+#[tauri::command]
+async fn delete_message(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    message_id: i64,
+) -> Result<(), String> {
+    let state = state.lock().await;
+    state
+        .memory
+        .delete_message(message_id)
+        .await
+        .map_err(|e| format!("Failed to delete message: {}", e))?;
+    state
+        .tx
+        .send(format!("/switch {}", state.memory.active_conversation()).into())
+        .map_err(|e| format!("Failed to reload conversation: {}", e))
+}
+
+/// Pin or unpin a saved message; pruning never deletes pinned messages
+#[tauri::command]
+async fn set_message_pinned(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    message_id: i64,
+    pinned: bool,
+) -> Result<(), String> {
+    let memory = state.lock().await.memory.clone();
+    memory
+        .set_pinned(message_id, pinned)
+        .await
+        .map_err(|e| format!("Failed to pin message: {}", e))
+}
+
+#[tauri::command]
+async fn list_conversations(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+) -> Result<ConversationList, String> {
+    let memory = state.lock().await.memory.clone();
+    let conversations = memory
+        .list_conversations()
+        .await
+        .map_err(|e| format!("Failed to list conversations: {}", e))?;
+    Ok(ConversationList {
+        active: memory.active_conversation(),
+        conversations,
+    })
+}
+
+/// Full-text search over every conversation's messages
+#[tauri::command]
+async fn search_messages(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    query: String,
+) -> Result<Vec<SearchHit>, String> {
+    let memory = state.lock().await.memory.clone();
+    memory
+        .search_history(&query, SEARCH_RESULT_LIMIT)
+        .await
+        .map_err(|e| format!("Search failed: {}", e))
+}
+
+/// Messages of the open conversation saved before `before_id`, for scrolling back
+#[tauri::command]
+async fn get_history(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    before_id: Option<i64>,
+    limit: Option<i64>,
+) -> Result<HistoryPage, String> {
+    let memory = state.lock().await.memory.clone();
+    let limit = limit.unwrap_or(HISTORY_PAGE_SIZE);
+    let messages = memory
+        .get_history(before_id, limit)
+        .await
+        .map_err(|e| format!("Failed to load history: {}", e))?;
+    let has_more = messages.len() as i64 == limit;
+    Ok(HistoryPage {
+        // Proactive prompts were never shown
+        messages: messages
+            .into_iter()
+            .filter(|m| !proactive::is_prompt(&m.content))
+            .collect(),
+        has_more,
+    })
+}
+
+/// Conversation commands go through the agent loop like their slash command forms,
+/// since it owns the in-memory history
+#[tauri::command]
+async fn new_conversation(state: tauri::State<'_, Arc<Mutex<AppState>>>) -> Result<(), String> {
+    state
+        .lock()
+        .await
+        .tx
+        .send("/new".into())
+        .map_err(|e| format!("Failed to start a conversation: {}", e))
+}
+
+#[tauri::command]
+async fn switch_conversation(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+) -> Result<(), String> {
+    state
+        .lock()
+        .await
+        .tx
+        .send(format!("/switch {}", id).into())
+        .map_err(|e| format!("Failed to switch conversation: {}", e))
+}
+
+#[tauri::command]
+async fn delete_conversation(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    id: i64,
+) -> Result<(), String> {
+    state
+        .lock()
+        .await
+        .tx
+        .send(format!("/delete {}", id).into())
+        .map_err(|e| format!("Failed to delete conversation: {}", e))
+}
+
+#[tauri::command]
+async fn get_db_stats(state: tauri::State<'_, Arc<Mutex<AppState>>>) -> Result<DbStats, String> {
+    let memory = state.lock().await.memory.clone();
+    let size_bytes = memory
+        .db_size()
+        .await
+        .map_err(|e| format!("Failed to read database size: {}", e))?;
+    Ok(DbStats { size_bytes })
+}
+
+#[tauri::command]
+async fn run_maintenance(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    prune_days: Option<u64>,
+) -> Result<MaintenanceReport, String> {
+    // Clone the pool handle so the state lock isn't held during VACUUM
+    let memory = state.lock().await.memory.clone();
+    let run = async {
+        let size_before = memory.db_size().await?;
+        let pruned = match prune_days {
+            Some(days) => {
+                memory
+                    .prune(Duration::from_secs(days * 24 * 60 * 60))
+                    .await?
+            }
+            None => 0,
+        };
+        memory.vacuum().await?;
+        let size_after = memory.db_size().await?;
+        anyhow::Ok(MaintenanceReport {
+            size_before,
+            size_after,
+            pruned,
+        })
+    };
+    run.await
+        .map_err(|e| format!("Database maintenance failed: {}", e))
+}
+
+#[tauri::command]
+async fn get_audio_outputs(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+) -> Result<AudioOutputSettings, String> {
+    let state = state.lock().await;
+    let tts = state.tts.as_ref().ok_or("Voice output is unavailable")?;
+    let settings = tts.settings();
+    let devices =
+        tts::list_output_devices().map_err(|e| format!("Failed to list audio devices: {}", e))?;
+    Ok(AudioOutputSettings {
+        devices,
+        selected: settings.output_device,
+        volume: settings.volume,
+    })
+}
+
+#[tauri::command]
+async fn set_audio_output(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    device: Option<String>,
+    volume: f32,
+) -> Result<(), String> {
+    let state = state.lock().await;
+    let tts = state.tts.as_ref().ok_or("Voice output is unavailable")?;
+    tts.set_output(device.clone(), volume);
+    persist_settings(|settings| {
+        settings.output_device = device;
+        settings.volume = volume;
+    })
+}
+
+#[tauri::command]
+async fn get_voice_settings(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+) -> Result<VoiceSettings, String> {
+    let state = state.lock().await;
+    let tts = state.tts.as_ref().ok_or("Voice output is unavailable")?;
+    let settings = tts.settings();
+    Ok(VoiceSettings {
+        backend: tts.backend_name(),
+        rate: settings.rate,
+        pitch: settings.pitch,
+        voices: piper::installed(),
+        model: settings.piper_model,
+        speaker: settings.piper_speaker,
+    })
+}
+
+#[tauri::command]
+async fn set_speech_tuning(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    rate: u32,
+    pitch: f32,
+) -> Result<(), String> {
+    let state = state.lock().await;
+    let tts = state.tts.as_ref().ok_or("Voice output is unavailable")?;
+    tts.set_tuning(rate, pitch);
+    let applied = tts.settings();
+    persist_settings(|settings| {
+        settings.rate = applied.rate;
+        settings.pitch = applied.pitch;
+    })
+}
+
+#[tauri::command]
+async fn set_piper_voice(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    model: Option<String>,
+    speaker: Option<String>,
+) -> Result<(), String> {
+    let state = state.lock().await;
+    let tts = state.tts.as_ref().ok_or("Voice output is unavailable")?;
+    tts.set_piper_voice(model.clone(), speaker.clone())
+        .map_err(|e| format!("Failed to load voice: {}", e))?;
+    persist_settings(|settings| {
+        settings.piper_model = model;
+        settings.piper_speaker = speaker;
+    })
+}
+
+/// Fetch a piper voice (e.g. "en_US-amy-medium") into voices/
+#[tauri::command]
+async fn download_piper_voice(id: String) -> Result<Vec<PiperVoice>, String> {
+    piper::download(&id)
+        .await
+        .map_err(|e| format!("Failed to download {}: {}", id, e))?;
+    Ok(piper::installed())
+}
+
+#[tauri::command]
+async fn delete_piper_voice(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    id: String,
+) -> Result<Vec<PiperVoice>, String> {
+    let state = state.lock().await;
+    if let Some(tts) = &state.tts {
+        if tts.settings().piper_model.as_deref() == Some(id.as_str()) {
+            return Err("Can't delete the voice in use".to_string());
+        }
+    }
+    piper::delete(&id).map_err(|e| format!("Failed to delete {}: {}", id, e))?;
+    Ok(piper::installed())
+}
+
+#[tauri::command]
+fn list_personas() -> Vec<String> {
+    Persona::list()
+}
+
+/// The same table `/help` prints, so the UI never offers a command the loop doesn't know
+#[tauri::command]
+fn list_slash_commands() -> Vec<SlashCommandInfo> {
+    commands::COMMANDS
+        .iter()
+        .map(|&(usage, description)| SlashCommandInfo {
+            command: usage.split_whitespace().next().unwrap_or(usage),
+            usage,
+            description,
+        })
+        .collect()
+}
+
+/// Switch persona through the agent loop, like a typed `/persona`
+#[tauri::command]
+async fn set_persona(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    name: String,
+) -> Result<(), String> {
+    if Persona::by_name(&name).is_none() {
+        return Err(format!("Unknown persona: {}", name));
+    }
+    let state = state.lock().await;
+    state
+        .tx
+        .send(format!("/persona {}", name).into())
+        .map_err(|e| format!("Failed to switch persona: {}", e))?;
+    persist_settings(|settings| settings.persona = name)
+}
+
+/// Applied by the agent loop on the next turn, like a typed `/intensity`
+#[tauri::command]
+async fn set_persona_intensity(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    intensity: u8,
+) -> Result<(), String> {
+    let intensity = intensity.min(100);
+    let state = state.lock().await;
+    state
+        .tx
+        .send(format!("/intensity {}", intensity).into())
+        .map_err(|e| format!("Failed to set intensity: {}", e))?;
+    persist_settings(|settings| settings.persona_intensity = intensity)
+}
+
+/// Reconnect to Ollama and load the model again, like a typed `/reload`. Lets the app
+/// recover after Ollama is started or the model is pulled, without a restart.
+#[tauri::command]
+async fn reload_llm(state: tauri::State<'_, Arc<Mutex<AppState>>>) -> Result<(), String> {
+    let state = state.lock().await;
+    state
+        .tx
+        .send("/reload".into())
+        .map_err(|e| format!("Failed to reload: {}", e))
+}
+
+#[tauri::command]
+fn get_persona_intensity() -> u8 {
+    AppConfig::load().persona_intensity
+}
+
+/// Answer a `tool-approval-request`
+#[tauri::command]
+async fn resolve_tool_approval(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    id: u64,
+    decision: Decision,
+) -> Result<(), String> {
+    if state.lock().await.approvals.resolve(id, decision) {
+        Ok(())
+    } else {
+        Err("This approval request is no longer pending".to_string())
+    }
+}
+
+/// Shut the automation browser down now instead of waiting for the idle timeout.
+/// Returns whether it was running.
+#[tauri::command]
+async fn close_browser(state: tauri::State<'_, Arc<Mutex<AppState>>>) -> Result<bool, String> {
+    let browser = state.lock().await.browser.clone();
+    Ok(browser.close().await)
+}
+
+/// Folders the file_system and shell tools may use, first one first
+#[tauri::command]
+async fn get_file_roots(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+) -> Result<Vec<String>, String> {
+    let sandbox = state.lock().await.sandbox.clone();
+    Ok(root_names(&sandbox))
+}
+
+/// Allow the tools into `path` (picked with the folder dialog) and save it to the config
+#[tauri::command]
+async fn add_file_root(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    path: String,
+) -> Result<Vec<String>, String> {
+    let sandbox = state.lock().await.sandbox.clone();
+    let mut roots = root_names(&sandbox);
+    let added = std::path::Path::new(&path)
+        .canonicalize()
+        .map_err(|e| format!("Can't use {}: {}", path, e))?;
+    if !added.is_dir() {
+        return Err(format!("{} is not a folder", path));
+    }
+    let added = added.display().to_string();
+    if !roots.contains(&added) {
+        roots.push(added);
+    }
+    update_file_roots(&sandbox, roots)
+}
+
+#[tauri::command]
+async fn remove_file_root(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    path: String,
+) -> Result<Vec<String>, String> {
+    let sandbox = state.lock().await.sandbox.clone();
+    let mut roots = root_names(&sandbox);
+    roots.retain(|root| *root != path);
+    if roots.is_empty() {
+        return Err("Keep at least one workspace folder".to_string());
+    }
+    update_file_roots(&sandbox, roots)
+}
+
+fn root_names(sandbox: &Sandbox) -> Vec<String> {
+    sandbox
+        .roots()
+        .iter()
+        .map(|root| root.display().to_string())
+        .collect()
+}
+
+fn update_file_roots(sandbox: &Sandbox, roots: Vec<String>) -> Result<Vec<String>, String> {
+    AppConfig::save_file_roots(&roots).map_err(|e| format!("Failed to save config: {}", e))?;
+    sandbox.set_roots(&roots);
+    Ok(root_names(sandbox))
+}
+
+#[tauri::command]
+async fn get_proactive_mode(state: tauri::State<'_, Arc<Mutex<AppState>>>) -> Result<bool, String> {
+    Ok(state.lock().await.proactive.load(Ordering::SeqCst))
+}
+
+/// Let Amadeus speak up unprompted (idle check-ins, scheduled prompts, reminders,
+/// screen changes); saved to the config
+#[tauri::command]
+async fn set_proactive_mode(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    enabled: bool,
+) -> Result<(), String> {
+    AppConfig::save_proactive_enabled(enabled)
+        .map_err(|e| format!("Failed to save config: {}", e))?;
+    let state = state.lock().await;
+    state.proactive.store(enabled, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Ollama's models and GGUF files found in `[models] dirs`
+#[tauri::command]
+async fn list_models() -> Result<Vec<ModelEntry>, String> {
+    Ok(models::list(&AppConfig::load().models).await)
+}
+
+/// Switch the chat to `name` from `list_models`, importing its GGUF file into Ollama
+/// first if needed, and make it the default for new conversations. Returns the model name.
+#[tauri::command]
+async fn load_model(
+    app: AppHandle,
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    name: String,
+) -> Result<String, String> {
+    let entry = models::list(&AppConfig::load().models)
+        .await
+        .into_iter()
+        .find(|entry| entry.name == name)
+        .ok_or_else(|| format!("Unknown model: {}", name))?;
+    let model = models::prepare(&entry)
+        .await
+        .map_err(|e| format!("Failed to import {}: {}", name, e))?;
+    state
+        .lock()
+        .await
+        .tx
+        .send(format!("/model {}", model).into())
+        .map_err(|e| format!("Failed to switch model: {}", e))?;
+    persist_settings(|settings| settings.model = model.clone())?;
+    setup::refresh(&app).await;
+    Ok(model)
+}
+
+/// Fetch a GGUF file from Hugging Face into the models folder, reporting
+/// `model-download-progress`. Returns the updated model list, with the file ready to load.
+#[tauri::command]
+async fn download_model(
+    app: AppHandle,
+    url: String,
+    sha256: Option<String>,
+) -> Result<Vec<ModelEntry>, String> {
+    let config = AppConfig::load();
+    models::download(&config.models, &url, sha256, |downloaded, total| {
+        let _ = app.emit(
+            "model-download-progress",
+            ModelDownloadProgress {
+                url: url.clone(),
+                downloaded,
+                total,
+            },
+        );
+    })
+    .await
+    .map_err(|e| format!("Failed to download model: {}", e))?;
+    setup::refresh(&app).await;
+    Ok(models::list(&config.models).await)
+}
+
+/// What a new user still has to set up; also sent as `setup-status` when it changes
+#[tauri::command]
+async fn get_setup_status(app: AppHandle) -> SetupStatus {
+    setup::check(&app).await
+}
+
+/// Use the `.vrm` at `path` (picked with the file dialog) as the avatar
+#[tauri::command]
+async fn pick_avatar(app: AppHandle, path: String) -> Result<SetupStatus, String> {
+    let avatar = setup::import_avatar(&path).map_err(|e| format!("Can't use {}: {}", path, e))?;
+    let avatar = avatar.display().to_string();
+    AppConfig::save_avatar_model(&avatar).map_err(|e| format!("Failed to save config: {}", e))?;
+    setup::allow_avatar(&app, &AppConfig::load());
+    Ok(setup::refresh(&app).await)
+}
+
+/// Open the system settings page for the microphone or accessibility permission
+#[tauri::command]
+fn request_permissions(step: SetupStep) -> Result<(), String> {
+    setup::request_permission(step).map_err(|e| format!("Can't open settings: {}", e))
+}
+
+/// Finish or skip the walkthrough; it only comes back if chat stops working
+#[tauri::command]
+async fn finish_setup(app: AppHandle) -> Result<SetupStatus, String> {
+    AppConfig::save_setup_complete().map_err(|e| format!("Failed to save config: {}", e))?;
+    Ok(setup::refresh(&app).await)
+}
+
+#[tauri::command]
+fn get_settings() -> Settings {
+    AppConfig::load().settings()
+}
+
+/// Save the whole settings panel and apply what changed: voice, folders, proactive mode
+/// and sampling right away; model, persona and intensity through the agent loop like the
+/// matching slash commands
+#[tauri::command]
+async fn set_settings(
+    app: AppHandle,
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    settings: Settings,
+) -> Result<Settings, String> {
+    if Persona::by_name(&settings.persona).is_none() {
+        return Err(format!("Unknown persona: {}", settings.persona));
+    }
+    if settings.file_roots.is_empty() {
+        return Err("Keep at least one workspace folder".to_string());
+    }
+    if settings.context_window < 512 {
+        return Err("The context window needs at least 512 tokens".to_string());
+    }
+    let mut settings = settings;
+    settings.persona_intensity = settings.persona_intensity.min(100);
+    let previous = AppConfig::load().settings();
+
+    let mut state = state.lock().await;
+    if let Some(tts) = &state.tts {
+        if (&settings.piper_model, &settings.piper_speaker)
+            != (&previous.piper_model, &previous.piper_speaker)
+        {
+            tts.set_piper_voice(settings.piper_model.clone(), settings.piper_speaker.clone())
+                .map_err(|e| format!("Failed to load voice: {}", e))?;
+        }
+        tts.set_enabled(settings.tts_enabled);
+        tray::set_muted(&app, !settings.tts_enabled);
+        tts.set_output(settings.output_device.clone(), settings.volume);
+        tts.set_tuning(settings.rate, settings.pitch);
+    }
+    AppConfig::save_settings(&settings).map_err(|e| format!("Failed to save config: {}", e))?;
+
+    if settings.file_roots != previous.file_roots {
+        state.sandbox.set_roots(&settings.file_roots);
+    }
+    state.proactive.store(settings.proactive, Ordering::SeqCst);
+    if settings.sampling != state.sampling {
+        state
+            .sampling_tx
+            .send(settings.sampling.clone())
+            .map_err(|e| format!("Failed to apply sampling settings: {}", e))?;
+        state.sampling = settings.sampling.clone();
+    }
+    let commands = [
+        (settings.model != previous.model).then(|| format!("/model {}", settings.model)),
+        (settings.persona != previous.persona).then(|| format!("/persona {}", settings.persona)),
+        (settings.persona_intensity != previous.persona_intensity)
+            .then(|| format!("/intensity {}", settings.persona_intensity)),
+        (settings.hardware != previous.hardware
+            || settings.context_window != previous.context_window)
+            .then(|| "/reload".to_string()),
+    ];
+    for command in commands.into_iter().flatten() {
+        state
+            .tx
+            .send(command.into())
+            .map_err(|e| format!("Failed to apply settings: {}", e))?;
+    }
+    Ok(settings)
+}
+
+/// Save a change made from the UI so it survives a restart
+fn persist_settings(update: impl FnOnce(&mut Settings)) -> Result<(), String> {
+    let mut settings = AppConfig::load().settings();
+    update(&mut settings);
+    AppConfig::save_settings(&settings).map_err(|e| format!("Failed to save config: {}", e))
+}
+
+/// Change how much is logged, e.g. "debug" while chasing a problem, and keep it
+#[tauri::command]
+fn set_log_level(level: String) -> Result<(), String> {
+    logging::set_level(&level).map_err(|e| format!("Invalid log level: {}", e))?;
+    AppConfig::save_log_level(level.trim()).map_err(|e| format!("Failed to save config: {}", e))
+}
+
+/// GPU backend, runtime settings and what Ollama has loaded where
+#[tauri::command]
+async fn get_llm_info() -> LlmInfo {
+    hardware::info(&AppConfig::load()).await
+}
+
+/// Start a countdown of `seconds`, or an alarm at local "HH:MM" `at`
+#[tauri::command]
+async fn set_timer(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    seconds: Option<u64>,
+    at: Option<String>,
+    label: Option<String>,
+) -> Result<TimerInfo, String> {
+    let (timers, memory) = {
+        let state = state.lock().await;
+        (state.timers.clone(), state.memory.clone())
+    };
+    timers
+        .set(&memory, seconds, at.as_deref(), label.as_deref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn list_timers(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+) -> Result<Vec<TimerInfo>, String> {
+    Ok(state.lock().await.timers.list())
+}
+
+#[tauri::command]
+async fn cancel_timer(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    id: u64,
+) -> Result<bool, String> {
+    Ok(state.lock().await.timers.cancel(id))
+}
+
+/// Mail accounts from `[email]` and whether each has its password in the keychain
+#[tauri::command]
+async fn get_email_accounts() -> Result<Vec<EmailAccountStatus>, String> {
+    let settings = AppConfig::load().email;
+    tokio::task::spawn_blocking(move || email::account_statuses(&settings))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Save an account's password to the OS keychain; an empty password removes it
+#[tauri::command]
+async fn set_email_password(
+    account: String,
+    password: String,
+) -> Result<Vec<EmailAccountStatus>, String> {
+    let settings = AppConfig::load().email;
+    if !settings.accounts.iter().any(|a| a.name == account) {
+        return Err(format!("No email account named '{}'", account));
+    }
+    tokio::task::spawn_blocking(move || {
+        let key = email::password_key(&account);
+        let saved = if password.is_empty() {
+            secrets::delete(&key)
+        } else {
+            secrets::set(&key, &password)
+        };
+        saved.map_err(|e| e.to_string())?;
+        Ok(email::account_statuses(&settings))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Recent tool calls, newest first, optionally only those of one tool
+#[tauri::command]
+async fn get_tool_audit(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    limit: Option<i64>,
+    tool: Option<String>,
+) -> Result<Vec<ToolAuditEntry>, String> {
+    let memory = state.lock().await.memory.clone();
+    memory
+        .tool_audit(limit.unwrap_or(100), tool.as_deref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Names of the secrets in the keychain; values never leave the backend
+#[tauri::command]
+async fn list_secrets() -> Result<Vec<String>, String> {
+    tokio::task::spawn_blocking(secrets::list)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// Save an API key or other credential for tools to use; returns the updated names
+#[tauri::command]
+async fn set_secret(key: String, value: String) -> Result<Vec<String>, String> {
+    let key = key.trim().to_string();
+    if value.is_empty() {
+        return Err("The value is empty".to_string());
+    }
+    tokio::task::spawn_blocking(move || {
+        secrets::set(&key, &value)?;
+        secrets::list()
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn delete_secret(key: String) -> Result<Vec<String>, String> {
+    tokio::task::spawn_blocking(move || {
+        secrets::delete(&key)?;
+        secrets::list()
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
+
+/// Stop the reply being generated; the partial answer is kept
+#[tauri::command]
+async fn stop_generation(state: tauri::State<'_, Arc<Mutex<AppState>>>) -> Result<(), String> {
+    let state = state.lock().await;
+    state.stop_tx.send_replace(true);
+    if let Some(tts) = &state.tts {
+        tts.cancel();
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_sampling(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+) -> Result<SamplingConfig, String> {
+    Ok(state.lock().await.sampling.clone())
+}
+
+/// Takes effect from the next message; history is kept
+#[tauri::command]
+async fn set_sampling(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    sampling: SamplingConfig,
+) -> Result<(), String> {
+    let mut state = state.lock().await;
+    state
+        .sampling_tx
+        .send(sampling.clone())
+        .map_err(|e| format!("Failed to apply sampling settings: {}", e))?;
+    state.sampling = sampling.clone();
+    persist_settings(|settings| settings.sampling = sampling)
+}
+
+#[tauri::command]
+fn get_avatar_settings() -> AvatarSettings {
+    AppConfig::load().avatar
+}
+
+#[tauri::command]
+async fn get_stt_status(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+) -> Result<SttStatus, String> {
+    Ok(state.lock().await.stt_status.clone())
+}
+
+/// Push-to-talk: record until the user pauses or `release_push_to_talk` is called,
+/// showing live captions and the microphone level meanwhile
+#[tauri::command]
+async fn listen_once(app: AppHandle) -> Result<String, String> {
+    push_to_talk(&app).await
+}
+
+/// The push-to-talk button was let go: stop recording and send what was said
+#[tauri::command]
+async fn release_push_to_talk(app: AppHandle) -> Result<(), String> {
+    end_push_to_talk(&app).await;
+    Ok(())
+}
+
+/// Record one utterance and send its transcript, for the 🎤 button and the hotkey
+async fn push_to_talk(app: &AppHandle) -> Result<String, String> {
+    let state = app.state::<Arc<Mutex<AppState>>>();
+    let finish = Arc::new(AtomicBool::new(false));
+    let (stt, tx) = {
+        let mut state = state.lock().await;
+        if state.push_to_talk.is_some() {
+            return Err("Already listening".to_string());
+        }
+        let stt = state.stt.clone().ok_or_else(|| {
+            state
+                .stt_status
+                .reason
+                .clone()
+                .unwrap_or_else(|| "Voice input is unavailable".to_string())
+        })?;
+        state.push_to_talk = Some(Arc::clone(&finish));
+        (stt, state.tx.clone())
+    };
+
+    let level_app = app.clone();
+    let heard = stt
+        .listen_streaming(
+            &AppConfig::load().voice_input,
+            finish,
+            |partial| emit_caption(app, partial, false),
+            move |level| emit_mic_level(&level_app, level),
+        )
+        .await;
+    state.lock().await.push_to_talk = None;
+    let text = heard.map_err(|e| format!("Voice input failed: {}", e))?;
+    emit_caption(app, &text, true);
+
+    if !text.is_empty() {
+        tx.send(text.clone().into())
+            .map_err(|e| format!("Failed to send message: {}", e))?;
+    }
+    Ok(text)
+}
+
+async fn end_push_to_talk(app: &AppHandle) {
+    let state = app.state::<Arc<Mutex<AppState>>>();
+    if let Some(finish) = &state.lock().await.push_to_talk {
+        finish.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Register the `[hotkeys]` shortcuts. One that can't be registered (e.g. another app
+/// holds it) is logged and skipped.
+fn register_hotkeys(app: &AppHandle, settings: &HotkeySettings) {
+    let shortcuts = app.global_shortcut();
+    let enabled = |keys: &Option<String>| keys.clone().filter(|k| !k.trim().is_empty());
+    if let Some(keys) = enabled(&settings.quick_chat) {
+        let registered = shortcuts.on_shortcut(keys.as_str(), |app, _, event| {
+            if event.state == ShortcutState::Pressed {
+                if let Err(e) = quick_chat::toggle(app) {
+                    warn!("[Hotkeys] Failed to open quick chat: {}", e);
+                }
+            }
+        });
+        if let Err(e) = registered {
+            warn!(
+                "[Hotkeys] Could not register {} for quick chat: {}",
+                keys, e
+            );
+        }
+    }
+    if let Some(keys) = enabled(&settings.push_to_talk) {
+        let registered = shortcuts.on_shortcut(keys.as_str(), |app, _, event| {
+            let app = app.clone();
+            match event.state {
+                ShortcutState::Pressed => {
+                    tauri::async_runtime::spawn(async move {
+                        if let Err(e) = push_to_talk(&app).await {
+                            warn!("[Hotkeys] Push-to-talk: {}", e);
+                        }
+                    });
+                }
+                ShortcutState::Released => {
+                    tauri::async_runtime::spawn(async move { end_push_to_talk(&app).await });
+                }
+            }
+        });
+        if let Err(e) = registered {
+            warn!(
+                "[Hotkeys] Could not register {} for push-to-talk: {}",
+                keys, e
+            );
+        }
+    }
+}
+
+/// Send a message typed into the quick-chat box and close it
+#[tauri::command]
+async fn send_quick_chat(
+    app: AppHandle,
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    message: String,
+) -> Result<(), String> {
+    if !message.trim().is_empty() {
+        state
+            .lock()
+            .await
+            .tx
+            .send(message.into())
+            .map_err(|e| format!("Failed to send message: {}", e))?;
+    }
+    quick_chat::close(&app);
+    Ok(())
+}
+
+#[tauri::command]
+async fn close_quick_chat(app: AppHandle) -> Result<(), String> {
+    quick_chat::close(&app);
+    Ok(())
+}
+
+#[tauri::command]
+async fn is_continuous_listening(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+) -> Result<bool, String> {
+    Ok(state.lock().await.voice_listener.is_some())
+}
+
+/// Turn hands-free listening (voice activity detection + wake word) on or off
+#[tauri::command]
+async fn set_continuous_listening(
+    app: AppHandle,
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    enabled: bool,
+) -> Result<(), String> {
+    let mut state = state.lock().await;
+    if let Some(stop) = state.voice_listener.take() {
+        stop.store(true, Ordering::SeqCst);
+    }
+    if enabled {
+        let stop = start_voice_listener(&app, &state, AppConfig::load().voice_input)
+            .map_err(|e| format!("Voice input failed: {}", e))?;
+        state.voice_listener = Some(stop);
+    }
+    Ok(())
+}
+
+fn emit_listen_state(app: &AppHandle, state: ListenState) {
+    let _ = app.emit(VOICE_LISTENING_EVENT, state);
+}
+
+fn emit_mic_level(app: &AppHandle, level: f32) {
+    let _ = app.emit(MIC_LEVEL_EVENT, MicLevel { level });
+}
+
+fn emit_caption(app: &AppHandle, text: &str, is_final: bool) {
+    let _ = app.emit(
+        STT_CAPTION_EVENT,
+        SttCaption {
+            text: text.to_string(),
+            is_final,
+        },
+    );
+}
+
+/// Listen continuously, sending each endpointed utterance to the agent as a message.
+/// Returns the flag that stops it.
+fn start_voice_listener(
+    app: &AppHandle,
+    state: &AppState,
+    settings: VoiceInputSettings,
+) -> Result<Arc<AtomicBool>> {
+    let stt = state
+        .stt
+        .clone()
+        .ok_or_else(|| match &state.stt_status.reason {
+            Some(reason) => anyhow::anyhow!("{}", reason),
+            None => anyhow::anyhow!("Voice input is unavailable"),
+        })?;
+    let stop = Arc::new(AtomicBool::new(false));
+    let (utterance_tx, mut utterance_rx) = mpsc::unbounded_channel();
+    let state_app = app.clone();
+    let level_app = app.clone();
+    listener::spawn_capture(
+        settings.clone(),
+        stop.clone(),
+        Arc::default(),
+        utterance_tx,
+        move |s| emit_listen_state(&state_app, s),
+        move |level| emit_mic_level(&level_app, level),
+    )?;
+
+    let app = app.clone();
+    let tts = state.tts.clone();
+    let tx = state.tx.clone();
+    let task_stop = stop.clone();
+    tauri::async_runtime::spawn(async move {
+        // Set when the wake word was said on its own
+        let mut awake_until: Option<std::time::Instant> = None;
+        emit_listen_state(&app, ListenState::Listening);
+
+        while let Some(utterance) = utterance_rx.recv().await {
+            let awake = awake_until.is_some_and(|t| std::time::Instant::now() < t);
+            let idle = |awake: bool| {
+                if awake {
+                    ListenState::Awake
+                } else {
+                    ListenState::Listening
+                }
+            };
+            // The microphone hears Amadeus too
+            if tts.as_ref().is_some_and(|t| t.is_speaking()) {
+                if !utterance.partial {
+                    emit_listen_state(&app, idle(awake));
+                }
+                continue;
+            }
+            if utterance.partial {
+                // Skip stale snapshots when transcription falls behind
+                if utterance_rx.is_empty() {
+                    let stt = stt.clone();
+                    let partial = tauri::async_runtime::spawn_blocking(move || {
+                        stt.transcribe(&utterance.samples, utterance.sample_rate)
+                    })
+                    .await;
+                    if let Ok(Ok(text)) = partial {
+                        emit_caption(&app, &text, false);
+                    }
+                }
+                continue;
+            }
+
+            emit_listen_state(&app, ListenState::Transcribing);
+            let stt = stt.clone();
+            let transcribed = tauri::async_runtime::spawn_blocking(move || {
+                stt.transcribe(&utterance.samples, utterance.sample_rate)
+            })
+            .await;
+            let text = match transcribed {
+                Ok(Ok(text)) => text,
+                Ok(Err(e)) => {
+                    warn!("[STT] {}", e);
+                    String::new()
+                }
+                Err(e) => {
+                    warn!("[STT] Transcription crashed: {}", e);
+                    String::new()
+                }
+            };
+            emit_caption(&app, &text, true);
+            if task_stop.load(Ordering::SeqCst) {
+                break;
+            }
+
+            let message = match settings.wake_word.as_deref() {
+                Some(word) if !awake => match listener::after_wake_word(&text, word) {
+                    Some(rest) if rest.is_empty() => {
+                        awake_until = Some(std::time::Instant::now() + listener::WAKE_WINDOW);
+                        None
+                    }
+                    rest => rest,
+                },
+                _ => {
+                    awake_until = None;
+                    Some(text)
+                }
+            };
+            if let Some(message) = message.filter(|m| !m.is_empty()) {
+                info!("[STT] Heard: {}", message);
+                let _ = tx.send(message.into());
+            }
+            emit_listen_state(&app, idle(awake_until.is_some()));
+        }
+        emit_listen_state(&app, ListenState::Off);
+    });
+    Ok(stop)
+}
+
+/// Freeze every display and open the selection overlays on top of them
+async fn open_region_select(app: &AppHandle) -> Result<()> {
+    let captures = tauri::async_runtime::spawn_blocking(screenshot::capture_all_screens).await??;
+
+    // Store captures first so the overlays can fetch their backgrounds right away
+    let state = app.state::<Arc<Mutex<AppState>>>();
+    let mut state = state.lock().await;
+    region_select::close_overlays(app);
+    state.region_captures = captures;
+    region_select::open_overlays(app, &state.region_captures)
+}
+
+#[tauri::command]
+async fn start_region_select(app: AppHandle) -> Result<(), String> {
+    open_region_select(&app)
+        .await
+        .map_err(|e| format!("Region selection failed: {}", e))
+}
+
+#[tauri::command]
+async fn get_region_background(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    display_id: u32,
+) -> Result<String, String> {
+    let state = state.lock().await;
+    let capture = state
+        .region_captures
+        .iter()
+        .find(|c| c.id == display_id)
+        .ok_or_else(|| format!("No capture for display {}", display_id))?;
+    screenshot::encode_jpeg(&capture.image).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn finish_region_select(
+    app: AppHandle,
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    display_id: u32,
+    selection: Selection,
+) -> Result<(), String> {
+    let mut state = state.lock().await;
+    region_select::close_overlays(&app);
+    let captures = std::mem::take(&mut state.region_captures);
+
+    let capture = captures
+        .iter()
+        .find(|c| c.id == display_id)
+        .ok_or_else(|| format!("No capture for display {}", display_id))?;
+    let region = region_select::crop(&capture.image, selection).map_err(|e| e.to_string())?;
+    let encoded = screenshot::encode_for_llm(&region).map_err(|e| e.to_string())?;
+    state
+        .region_tx
+        .send(encoded)
+        .map_err(|e| format!("Failed to send region: {}", e))
+}
+
+#[tauri::command]
+async fn cancel_region_select(
+    app: AppHandle,
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+) -> Result<(), String> {
+    region_select::close_overlays(&app);
+    state.lock().await.region_captures.clear();
+    Ok(())
+}
+
+/// Load the Whisper model in the background and report whether voice input is usable
+fn init_stt(
+    app: AppHandle,
+    model_path: String,
+    language: Arc<SttLanguage>,
+    voice_input: VoiceInputSettings,
+) {
+    tauri::async_runtime::spawn(async move {
+        let loaded =
+            tauri::async_runtime::spawn_blocking(move || SttManager::new(&model_path, language))
+                .await;
+        let result = match loaded {
+            Ok(result) => result,
+            Err(e) => Err(anyhow::anyhow!("Speech model loader crashed: {}", e)),
+        };
+
+        let state = app.state::<Arc<Mutex<AppState>>>();
+        let mut state = state.lock().await;
+        match result {
+            Ok(stt) => {
+                info!("[System] Whisper model loaded.");
+                state.stt = Some(Arc::new(stt));
+                state.stt_status = SttStatus {
+                    available: true,
+                    reason: None,
+                };
+                if voice_input.continuous {
+                    match start_voice_listener(&app, &state, voice_input) {
+                        Ok(stop) => state.voice_listener = Some(stop),
+                        Err(e) => warn!("[STT] Continuous listening unavailable: {}", e),
+                    }
+                }
+            }
+            Err(e) => {
+                let err_msg = format!("[STT] Voice input unavailable: {}", e);
+                warn!("{}", err_msg);
+                let _ = app.emit(
+                    "chat-message",
+                    ChatEvent {
+                        role: "system".to_string(),
+                        content: err_msg,
+                        turn_id: None,
+                        message_id: None,
+                        pinned: false,
+                    },
+                );
+                state.stt_status = SttStatus {
+                    available: false,
+                    reason: Some(e.to_string()),
+                };
+            }
+        }
+        let _ = app.emit("stt-status", state.stt_status.clone());
+    });
+}
+
+// ===== Agent Loop =====
+
+/// Everything the agent loop restores when a conversation is opened
+struct OpenedConversation {
+    persona: Persona,
+    client: Arc<OllamaClient>,
+    history: Vec<Message>,
+    /// Fallbacks taken because the pinned persona/model no longer exists
+    notices: Vec<String>,
+}
+
+/// Apply the parts of a persona that live outside the prompt: TTS voice and avatar model
+fn apply_persona(
+    app: &AppHandle,
+    tts: Option<&TtsManager>,
+    stt_language: &SttLanguage,
+    persona: &Persona,
+) {
+    if let Some(tts) = tts {
+        tts.set_voice(persona.voice.clone());
+    }
+    stt_language.set_persona_language(persona.language.as_deref());
+    let _ = app.emit(
+        "persona-changed",
+        PersonaChangedEvent {
+            name: persona.name.clone(),
+            avatar_model: persona.avatar_model.clone(),
+        },
+    );
+}
+
+fn emit_event(app: &AppHandle, event: AgentEvent) {
+    if let AgentEvent::Status { status, .. } = &event {
+        tray::set_status(app, status);
+    }
+    if let AgentEvent::ToolFailed { tool, error, .. } = &event {
+        notify::send(app, &format!("{} failed", tool), error);
+    }
+    let _ = app.emit(AGENT_EVENT, event);
+}
+
+/// A user-role message the user wrote, as opposed to one the agent fed the model:
+/// proactive prompts, screen updates and tool output
+fn typed_by_user(content: &str) -> bool {
+    !proactive::is_prompt(content)
+        && ![SCREEN_UPDATE_PREFIX, "Tool Output:", "Tool Error:"]
+            .iter()
+            .any(|prefix| content.starts_with(prefix))
+}
+
+/// Index of the last message the user typed. Whatever follows a tool call is its output,
+/// the same rule `MemoryManager::save_turn` uses, which also catches batched results.
+fn last_typed_message(history: &[Message]) -> Option<usize> {
+    (0..history.len()).rev().find(|&i| {
+        let after_tool_call = i > 0
+            && history[i - 1].role == "assistant"
+            && looks_like_tool_call(&history[i - 1].content);
+        history[i].role == "user" && typed_by_user(&history[i].content) && !after_tool_call
+    })
+}
+
+/// Reply opens like a tool call (`{ "tool": ...` or `{ "tools": [...`) whether or not the
+/// JSON is complete
+fn looks_like_tool_call(reply: &str) -> bool {
+    let reply = reply.trim_start();
+    reply.starts_with('{') && (reply.contains("\"tool\"") || reply.contains("\"tools\""))
+}
+
+/// Client for `model` with the configured template, context window and keep-alive.
+/// Every client must agree on these, or Ollama reloads the model between requests.
+fn model_client(model: &str, config: &AppConfig) -> OllamaClient {
+    OllamaClient::new(model)
+        .with_template(config.prompt_template)
+        .with_context_window(config.context.window_tokens)
+        .with_keep_alive(&config.keep_alive)
+        .with_sampling(config.sampling.clone())
+        .with_hardware(config.hardware.clone())
+}
+
+/// Load the model in the background so the first message doesn't wait for it
+fn warm_up(client: &Arc<OllamaClient>) {
+    let client = Arc::clone(client);
+    tokio::spawn(async move {
+        match client.warm_up().await {
+            Ok(()) => info!("[System] Model {} loaded.", client.model()),
+            Err(e) => warn!("[LLM] Warm-up failed for {}: {}", client.model(), e),
+        }
+    });
+}
+
+/// Make `conversation` active and restore its pinned persona and model
+async fn open_conversation(
+    conversation: &Conversation,
+    config: &AppConfig,
+    memory: &MemoryManager,
+    tools_prompt: &str,
+) -> Result<OpenedConversation> {
+    memory.set_active_conversation(conversation.id);
+    let mut notices = Vec::new();
+
+    let persona = Persona::by_name(&conversation.persona_name).unwrap_or_else(|| {
+        notices.push(format!(
+            "Persona '{}' no longer exists, using Amadeus",
+            conversation.persona_name
+        ));
+        Persona::amadeus()
+    });
+
+    // If the model list can't be fetched, trust the stored name and let chat report errors
+    let probe = OllamaClient::new(&conversation.model);
+    let model = match probe.list_models().await {
+        Ok(models)
+            if !models.iter().any(|m| {
+                m == &conversation.model
+                    || m.strip_suffix(":latest") == Some(conversation.model.as_str())
+            }) =>
+        {
+            notices.push(format!(
+                "Model '{}' is not installed, using {}",
+                conversation.model, config.model
+            ));
+            config.model.clone()
+        }
+        _ => conversation.model.clone(),
+    };
+
+    let system_prompt = build_system_prompt(config, &persona, memory, tools_prompt).await;
+    let mut history: Vec<Message> = memory
+        .get_history(None, HISTORY_PAGE_SIZE)
+        .await?
+        .into_iter()
+        .map(StoredMessage::into_message)
+        .collect();
+    set_system_prompt(&mut history, system_prompt);
+
+    // Messages trimmed in an earlier session live on as their summary
+    if let Some(summary) = memory.conversation_summary(conversation.id).await? {
+        context::apply_summary(&mut history, &summary);
+    }
+
+    Ok(OpenedConversation {
+        persona,
+        client: Arc::new(
+            model_client(&model, config)
+                .with_temperature(persona::intensity_temperature(config.persona_intensity)),
+        ),
+        history,
+        notices,
+    })
+}
+
+fn emit_context_usage(app: &AppHandle, settings: &ContextSettings, used: usize, trimmed: usize) {
+    let _ = app.emit(
+        "context-usage",
+        ContextUsageEvent {
+            used,
+            limit: settings.window_tokens,
+            warn_at: (settings.window_tokens as f32 * settings.warn_ratio) as u32,
+            trimmed,
+        },
+    );
+}
+
+/// Put `prompt` in front of `history`, replacing the previous one. The prompt is built
+/// fresh for every turn and never saved, so stored history only holds the conversation.
+fn set_system_prompt(history: &mut Vec<Message>, prompt: String) {
+    let first = history
+        .first_mut()
+        .filter(|m| m.role == "system" && !m.content.starts_with(context::SUMMARY_PREFIX));
+    match first {
+        Some(existing) => existing.content = prompt,
+        None => history.insert(
+            0,
+            Message {
+                role: "system".to_string(),
+                content: prompt,
+                images: None,
+            },
+        ),
+    }
+}
+
+/// Configured prefix + persona prompt + reply language + tone + emotion tag instruction +
+/// configured suffix + remembered facts + tool instructions, a blank line apart
+async fn build_system_prompt(
+    config: &AppConfig,
+    persona: &Persona,
+    memory: &MemoryManager,
+    tools_prompt: &str,
+) -> String {
+    // Remembered facts are re-read each time so they're always current
+    let facts = scratchpad::prompt_section(memory).await;
+    let language = persona
+        .language
+        .as_ref()
+        .map(|language| format!("Respond in {}.", language))
+        .unwrap_or_default();
+    [
+        config.system_prompt_prefix.as_str(),
+        persona.system_prompt.as_str(),
+        language.as_str(),
+        persona::intensity_modifier(config.persona_intensity),
+        if config.avatar.emotion_tags {
+            emotion::TAG_INSTRUCTION
+        } else {
+            ""
+        },
+        config.system_prompt_suffix.as_str(),
+        facts.as_str(),
+        tools_prompt,
+    ]
+    .iter()
+    .map(|section| section.trim())
+    .filter(|section| !section.is_empty())
+    .collect::<Vec<_>>()
+    .join("\n\n")
+}
+
+/// Play back an exported transcript through the normal UI events, bypassing the LLM.
+/// Used for demos and for reproducing rendering bugs deterministically.
+async fn run_replay(
+    app: AppHandle,
+    mut agent_rx: mpsc::UnboundedReceiver<UserInput>,
+    options: ReplayOptions,
+) -> Result<()> {
+    let emit_status = |status: &str, is_thinking: bool| {
+        emit_event(
+            &app,
+            AgentEvent::Status {
+                status: status.to_string(),
+                is_thinking,
+            },
+        );
+    };
+    let emit_turn = |turn_id: u64, role: &str, content: &str| {
+        let _ = app.emit(
+            "chat-message",
+            ChatEvent {
+                role: role.to_string(),
+                content: content.to_string(),
+                turn_id: Some(turn_id),
+                message_id: None,
+                pinned: false,
+            },
+        );
+    };
+
+    let transcript = replay::load_transcript(&options.path)?;
+    info!(
+        "[Replay] Playing {} message(s) from {}",
+        transcript.len(),
+        options.path.display()
+    );
+    // Give the webview time to register its listeners
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let mut turn_id: u64 = 0;
+    for message in &transcript {
+        let role = replay::display_role(message);
+        match role {
+            "user" => {
+                turn_id += 1;
+                emit_turn(turn_id, role, &message.content);
+                emit_status("Thinking", true);
+            }
+            "assistant" => {
+                tokio::time::sleep(options.delay).await;
+                if options.stream {
+                    for token in replay::tokens(&message.content) {
+                        emit_event(
+                            &app,
+                            AgentEvent::AssistantDelta {
+                                turn_id: Some(turn_id),
+                                content: token.to_string(),
+                            },
+                        );
+                        tokio::time::sleep(replay::TOKEN_INTERVAL).await;
+                    }
+                    emit_event(
+                        &app,
+                        AgentEvent::AssistantDone {
+                            turn_id: Some(turn_id),
+                            emotion: None,
+                        },
+                    );
+                } else {
+                    emit_turn(turn_id, role, &message.content);
+                }
+                emit_status("Online", false);
+            }
+            _ => {
+                tokio::time::sleep(options.delay / 2).await;
+                emit_turn(turn_id, role, &message.content);
+            }
+        }
+    }
+    emit_status("Replay finished", false);
+
+    while agent_rx.recv().await.is_some() {
+        let _ = app.emit(
+            "chat-message",
+            ChatEvent {
+                role: "system".to_string(),
+                content:
+                    "Replay mode: input is not sent to a model. Restart without --replay to chat."
+                        .to_string(),
+                turn_id: None,
+                message_id: None,
+                pinned: false,
+            },
+        );
+    }
+    Ok(())
+}
+
+/// Everything the UI sends to the agent loop besides Tauri state
+struct AgentChannels {
+    input: mpsc::UnboundedReceiver<UserInput>,
+    region: mpsc::UnboundedReceiver<String>,
+    sampling: mpsc::UnboundedReceiver<SamplingConfig>,
+    /// Set to true by `stop_generation`; reset at the start of every turn
+    stop: Arc<watch::Sender<bool>>,
+    approvals: Arc<ApprovalGate>,
+    /// Proactive mode on/off, shared with `AppState`
+    proactive: Arc<AtomicBool>,
+    /// Timers that have rung
+    timers: mpsc::UnboundedReceiver<TimerInfo>,
+}
+
+/// What a restarted agent loop carries on with
+#[derive(Default)]
+struct AgentSession {
+    /// Chat history, including messages not saved yet
+    history: Vec<Message>,
+    /// Id of the last user turn shown in the UI
+    turn_id: u64,
+}
+
+/// Run the agent loop, starting it again after an error or a panic so messages don't
+/// go unanswered. The channels and chat history outlive each run, so nothing queued
+/// or said so far is lost. Returns once the loop ends normally (the app is closing).
+async fn supervise_agent_loop(
+    app: AppHandle,
+    mut channels: AgentChannels,
+    memory: MemoryManager,
+    config: AppConfig,
+    tts: Option<Arc<TtsManager>>,
+    stt_language: Arc<SttLanguage>,
+) {
+    let mut session = AgentSession::default();
+    // Settings may have changed by the time of a restart, so later runs load them again
+    let mut config = Some(config);
+    let mut restarts: u32 = 0;
+    loop {
+        let started = Instant::now();
+        let run = run_agent_loop(
+            app.clone(),
+            &mut channels,
+            &mut session,
+            memory.clone(),
+            config.take().unwrap_or_else(AppConfig::load),
+            tts.clone(),
+            Arc::clone(&stt_language),
+        );
+        let reason = match AssertUnwindSafe(run).catch_unwind().await {
+            Ok(Ok(())) => return,
+            Ok(Err(e)) => e.to_string(),
+            Err(panic) => panic
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "panic".to_string()),
+        };
+
+        if started.elapsed() >= AGENT_HEALTHY_RUN {
+            restarts = 0;
+        }
+        restarts += 1;
+        let delay = Duration::from_secs(1 << restarts.min(5)).min(AGENT_RESTART_MAX_DELAY);
+        error!(
+            "[Agent] Loop crashed: {}. Restarting in {}s",
+            reason,
+            delay.as_secs()
+        );
+        let _ = app.emit(
+            "agent-crashed",
+            AgentCrashedEvent {
+                reason,
+                restarts,
+                retry_in_secs: delay.as_secs(),
+            },
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
 
 async fn run_agent_loop(
     app: AppHandle,
-    mut agent_rx: mpsc::UnboundedReceiver<String>,
+    channels: &mut AgentChannels,
+    session: &mut AgentSession,
+    memory: MemoryManager,
+    mut config: AppConfig,
+    tts: Option<Arc<TtsManager>>,
+    stt_language: Arc<SttLanguage>,
 ) -> Result<()> {
-    println!("AMADEUS SYSTEM ONLINE.");
+    info!("AMADEUS SYSTEM ONLINE.");
+    let AgentSession {
+        history: chat_history,
+        turn_id: last_turn_id,
+    } = session;
+    let AgentChannels {
+        input: agent_rx,
+        region: region_rx,
+        sampling: sampling_rx,
+        stop,
+        approvals,
+        proactive: proactive_enabled,
+        timers: timer_rx,
+    } = channels;
 
     // Helper to emit chat messages to frontend
-    let emit_chat = |app: &AppHandle, role: &str, content: &str| {
+    let emit_turn = |app: &AppHandle, turn_id: Option<u64>, role: &str, content: &str| {
         let _ = app.emit(
             "chat-message",
             ChatEvent {
                 role: role.to_string(),
                 content: content.to_string(),
+                turn_id,
+                message_id: None,
+                pinned: false,
             },
         );
     };
+    let emit_chat =
+        |app: &AppHandle, role: &str, content: &str| emit_turn(app, None, role, content);
 
     let emit_status = |app: &AppHandle, status: &str, is_thinking: bool| {
-        let _ = app.emit(
-            "chat-status",
-            StatusEvent {
+        emit_event(
+            app,
+            AgentEvent::Status {
                 status: status.to_string(),
                 is_thinking,
             },
         );
     };
 
-    // Initialize Memory
-    let memory = MemoryManager::new("amadeus.db").await?;
+    // Speaks in the given tone; `speak` guesses the tone from the words
+    let speak_as = |text: &str, emotion: Emotion| {
+        if let Some(tts_manager) = &tts {
+            let speaking_app = app.clone();
+            let spoken = tts_manager.speak_with_emotion(text, emotion, move |speaking| {
+                let _ = speaking_app.emit("tts-speaking", SpeakingEvent { speaking });
+            });
+            if let Err(e) = spoken {
+                warn!("[TTS] Failed to speak: {}", e);
+            }
+        }
+    };
+    let speak = |text: &str| speak_as(text, Emotion::detect(text));
 
     // Initialize Ollama LLM
-    println!("[System] Connecting to Ollama (model: {})...", OLLAMA_MODEL);
+    info!("[System] Connecting to Ollama (model: {})...", config.model);
     emit_status(&app, "Connecting to Ollama...", true);
 
-    let client = Arc::new(OllamaClient::new(OLLAMA_MODEL));
+    let mut client = Arc::new(OllamaClient::new(&config.model));
+    // Embeds long-term facts for recall; None when `[memory] enabled = false`
+    let embedder = config.memory.enabled.then(|| {
+        Arc::new(
+            OllamaClient::new(&config.memory.embedding_model).with_keep_alive(&config.keep_alive),
+        )
+    });
 
     match client.health_check().await {
         Ok(true) => {
-            println!("[System] Ollama connected.");
+            info!("[System] Ollama connected.");
             emit_status(&app, "Online", false);
         }
         _ => {
             let err_msg = "[Error] Ollama not running. Start it with: ollama serve";
-            eprintln!("{}", err_msg);
+            warn!("{}", err_msg);
             emit_chat(&app, "assistant", err_msg);
             emit_status(&app, "Ollama Offline", false);
+            // The walkthrough explains how to get Ollama going
+            setup::refresh(&app).await;
 
-            while let Some(_) = agent_rx.recv().await {
-                emit_chat(
-                    &app,
-                    "assistant",
-                    "Ollama is not running. Please start it with `ollama serve` and pull a model with `ollama pull qwen2.5:7b`.",
-                );
+            // Try again every few seconds, on `/reload` and with every message. Messages
+            // sent while offline wait here and are answered, in order, once Ollama is back.
+            let mut retry = tokio::time::interval(OLLAMA_RETRY_INTERVAL);
+            retry.tick().await;
+            let mut queued: Vec<UserInput> = Vec::new();
+            loop {
+                let (held, reload) = tokio::select! {
+                    _ = retry.tick() => (None, false),
+                    input = agent_rx.recv() => match input {
+                        Some(input) => {
+                            let reload = matches!(
+                                SlashCommand::parse(&input.text),
+                                Some(Ok(SlashCommand::Reload))
+                            );
+                            ((!reload).then_some(input), reload)
+                        }
+                        None => return Ok(()),
+                    },
+                };
+                if reload {
+                    emit_status(&app, "Connecting to Ollama...", true);
+                }
+                if client.health_check().await.unwrap_or(false) {
+                    info!("[System] Ollama connected.");
+                    emit_status(&app, "Online", false);
+                    emit_chat(&app, "system", "Connected to Ollama.");
+                    setup::refresh(&app).await;
+                    let state = app.state::<Arc<Mutex<AppState>>>();
+                    let state = state.lock().await;
+                    for input in queued.into_iter().chain(held) {
+                        let _ = state.tx.send(input);
+                    }
+                    break;
+                }
+                if let Some(input) = held {
+                    queued.push(input);
+                    emit_status(
+                        &app,
+                        &format!("Ollama Offline - {} message(s) queued", queued.len()),
+                        false,
+                    );
+                    emit_chat(
+                        &app,
+                        "system",
+                        "Message queued. It will be sent once Ollama is running.",
+                    );
+                }
+                if reload {
+                    emit_status(&app, "Ollama Offline", false);
+                    emit_chat(
+                        &app,
+                        "assistant",
+                        &format!(
+                            "Ollama is not running. Please start it with `ollama serve` and pull a model with `ollama pull {}`.",
+                            config.model
+                        ),
+                    );
+                }
             }
-            return Ok(());
         }
     }
 
-    // Initialize Persona
-    let persona = Persona::amadeus();
+    // Frames from the background screen watcher
+    let (screen_tx, mut screen_rx) = mpsc::unbounded_channel::<String>();
 
     // Initialize Tools
-    let mut dispatcher = ToolDispatcher::new();
+    let mut dispatcher = ToolDispatcher::new(&config.tools).with_audit(memory.clone());
     dispatcher.register(Box::new(ScreenshotTool));
     dispatcher.register(Box::new(InputTool));
-    dispatcher.register(Box::new(FileSystemTool));
-    dispatcher.register(Box::new(BrowserTool));
-
-    // Voice
-    let tts = match TtsManager::new() {
-        Ok(t) => Some(t),
-        Err(e) => {
-            println!("Voice Output Unavailable: {}", e);
-            None
-        }
+    let (browser, sandbox, timers) = {
+        let state = app.state::<Arc<Mutex<AppState>>>();
+        let state = state.lock().await;
+        (
+            state.browser.clone(),
+            state.sandbox.clone(),
+            state.timers.clone(),
+        )
     };
+    dispatcher.register(Box::new(FileSystemTool::new(sandbox.clone())));
+    dispatcher.register(Box::new(BrowserTool::new(browser)));
+    dispatcher.register(Box::new(HttpRequestTool::new(config.http.clone())));
+    dispatcher.register(Box::new(EmailTool::new(config.email.clone())));
+    dispatcher.register(Box::new(MemoryStoreTool::new(memory.clone())));
+    dispatcher.register(Box::new(ReminderTool::new(memory.clone())));
+    dispatcher.register(Box::new(TimerTool::new(timers, memory.clone())));
+    dispatcher.register(Box::new(ScreenWatchTool::new(screen_tx)));
+    dispatcher.register(Box::new(MediaControlTool));
+    dispatcher.register(Box::new(ClipboardTool));
+    dispatcher.register(Box::new(SystemInfoTool));
+    dispatcher.register(Box::new(AppControlTool));
+    // Set whenever an image is attached to the conversation, for `ocr` to read
+    let latest_image = LatestImage::default();
+    dispatcher.register(Box::new(OcrTool::new(
+        config.ocr.clone(),
+        latest_image.clone(),
+    )));
+    dispatcher.register(Box::new(ShellTool::new(config.shell.clone(), sandbox)));
+
+    // Third-party tools from plugins/*.wasm; built-in tools keep their names
+    if config.plugins.enabled {
+        match WasmToolHost::new(config.plugins.clone()) {
+            Ok(host) => {
+                for tool in tokio::task::spawn_blocking(move || host.load_all()).await? {
+                    if dispatcher.parameters(tool.name()).is_some() {
+                        warn!(
+                            "[Plugins] {} has the name of a built-in tool; skipped",
+                            tool.name()
+                        );
+                        continue;
+                    }
+                    dispatcher.register(Box::new(tool));
+                }
+            }
+            Err(e) => warn!("[Plugins] Unavailable: {}", e),
+        }
+    }
+
+    // Tools of the configured MCP servers, as <server>.<tool>
+    for tool in mcp::connect_all(&config.mcp).await {
+        if dispatcher.parameters(tool.name()).is_some() {
+            warn!("[MCP] {} is already registered; skipped", tool.name());
+            continue;
+        }
+        dispatcher.register(tool);
+    }
 
-    // Load History
-    let mut chat_history: Vec<Message> = memory.get_recent_history(50).await?;
+    // Programs from [[tools.external]] speaking JSON-RPC on stdin/stdout
+    for tool in external::start_all(&config.tools.external).await {
+        if dispatcher.parameters(tool.name()).is_some() {
+            warn!("[Tools] {} is already registered; skipped", tool.name());
+            continue;
+        }
+        dispatcher.register(tool);
+    }
 
     let tools_schema = dispatcher.get_tools_schema();
+    let tool_call_schema = dispatcher.tool_call_schema();
     let tools_prompt = format!(
-        "\nYou have access to the following tools: {}\n\nTo use a tool, respond with a JSON object in this format ONLY:\n{{ \"tool\": \"tool_name\", \"args\": {{ ... }} }}\nIf you use a tool, do not write anything else.",
+        "\nYou have access to the following tools: {}\n\nTo use a tool, respond with a JSON object in this format ONLY:\n{{ \"tool\": \"tool_name\", \"args\": {{ ... }} }}\nTo run several independent tools at once, list them instead:\n{{ \"tools\": [{{ \"tool\": \"tool_name\", \"args\": {{ ... }} }}, ...] }}\nIf you use a tool, do not write anything else.",
         tools_schema
     );
-    let full_system_prompt = format!("{}{}", persona.system_prompt, tools_prompt);
 
+    // After a crash, reopen the conversation that was active; on startup, resume the last one
+    // with the persona and model it was pinned to
+    let reopened = match memory.active_conversation() {
+        0 => None,
+        id => memory.get_conversation(id).await?,
+    };
+    let conversation = match reopened {
+        Some(conversation) => conversation,
+        None => {
+            memory
+                .resume_latest_conversation(&config.persona, &config.model)
+                .await?
+        }
+    };
+    let opened = open_conversation(&conversation, &config, &memory, &tools_prompt).await?;
+    let mut persona = opened.persona;
+    apply_persona(&app, tts.as_deref(), &stt_language, &persona);
+    client = opened.client;
+    // After a crash the conversation carries on where it was, unsaved messages included
     if chat_history.is_empty() {
-        let sys_msg = Message {
-            role: "system".to_string(),
-            content: full_system_prompt.clone(),
-            images: None,
-        };
-        memory.save_message(&sys_msg).await?;
-        chat_history.push(sys_msg);
+        *chat_history = opened.history;
+    }
+    for notice in &opened.notices {
+        emit_chat(&app, "system", notice);
     }
+    warm_up(&client);
+    emit_context_usage(&app, &config.context, client.count_tokens(&chat_history), 0);
 
-    println!(
+    info!(
         "Amadeus ({}) is ready. (Awaiting UI Input...)",
         persona.name
     );
@@ -173,120 +2346,1103 @@ async fn run_agent_loop(
     // Initial greeting
     emit_chat(&app, "assistant", "System online. Waiting for input...");
 
-    while let Some(mut input) = agent_rx.recv().await {
+    // Incremented per user message so the UI can thread a whole tool-using turn together.
+    // Carried across restarts so ids already shown in the UI aren't reused.
+    let mut turn_id = *last_turn_id;
+
+    // Whether the last assistant reply was cut off at the token limit (enables /continue)
+    let mut last_reply_truncated = false;
+
+    // Edits to personas/*.toml apply to the active persona without a restart
+    let mut persona_poll = tokio::time::interval(PERSONA_POLL_INTERVAL);
+    let mut persona_stamp = persona::dir_modified();
+
+    let mut reminder_poll = tokio::time::interval(REMINDER_POLL_INTERVAL);
+
+    let mut proactivity = Proactivity::new(config.proactive.clone());
+    let mut proactive_poll = tokio::time::interval(PROACTIVE_POLL_INTERVAL);
+
+    loop {
+        // Images attached to this turn's user message (from the region overlay or dropped)
+        let mut attached_images: Option<Vec<String>> = None;
+        // Set when proactive mode, not the user, starts this turn
+        let mut proactive_turn = false;
+        // Row id of the saved user message `/regenerate` or `/edit` answers again
+        let mut replayed: Option<i64> = None;
+        let mut input = tokio::select! {
+            Some(input) = agent_rx.recv() => {
+                let (text, images) = input.into_message_parts();
+                attached_images = images;
+                text
+            }
+            Some(region) = region_rx.recv() => {
+                *latest_image.lock().unwrap() = Some(region.clone());
+                attached_images = Some(vec![region]);
+                REGION_PROMPT.to_string()
+            }
+            Some(sampling) = sampling_rx.recv() => {
+                client = Arc::new((*client).clone().with_sampling(sampling.clone()));
+                config.sampling = sampling;
+                emit_status(&app, "Sampling settings applied", false);
+                continue;
+            }
+            _ = persona_poll.tick() => {
+                let stamp = persona::dir_modified();
+                if stamp == persona_stamp {
+                    continue;
+                }
+                persona_stamp = stamp;
+                let Some(reloaded) = Persona::by_name(&persona.name) else {
+                    continue;
+                };
+                persona = reloaded;
+                apply_persona(&app, tts.as_deref(), &stt_language, &persona);
+                emit_status(&app, &format!("Persona {} reloaded", persona.name), false);
+                continue;
+            }
+            _ = reminder_poll.tick() => {
+                let due = match memory.due_reminders().await {
+                    Ok(due) => due,
+                    Err(e) => {
+                        warn!("[Reminders] Failed to check: {}", e);
+                        continue;
+                    }
+                };
+                let in_own_words =
+                    proactive_enabled.load(Ordering::SeqCst) && config.proactive.on_reminder;
+                let mut to_announce = Vec::new();
+                for reminder in due {
+                    // Marked first so a failure below can't make it fire every poll
+                    if let Err(e) = memory.mark_reminder_fired(reminder.id).await {
+                        warn!("[Reminders] Failed to mark #{}: {}", reminder.id, e);
+                        continue;
+                    }
+                    if in_own_words {
+                        to_announce.push(reminder.message);
+                        continue;
+                    }
+                    let text = format!("⏰ Reminder: {}", reminder.message);
+                    let message = Message {
+                        role: "assistant".to_string(),
+                        content: text.clone(),
+                        images: None,
+                    };
+                    if let Err(e) = memory.save_message(&message).await {
+                        warn!("[Memory] Failed to save reminder: {}", e);
+                    }
+                    chat_history.push(message);
+                    emit_chat(&app, "assistant", &text);
+                    notify::send(&app, "Reminder", &reminder.message);
+                    if config.reminders.speak {
+                        speak(&reminder.message);
+                    }
+                }
+                if to_announce.is_empty() {
+                    continue;
+                }
+                proactive_turn = true;
+                Trigger::Reminder(to_announce).prompt()
+            }
+            Some(timer) = timer_rx.recv() => {
+                let text = match &timer.alarm_at {
+                    Some(at) => format!("⏰ {} ({})", timer.label, at),
+                    None => format!("⏰ Time's up: {}", timer.label),
+                };
+                let message = Message {
+                    role: "assistant".to_string(),
+                    content: text.clone(),
+                    images: None,
+                };
+                if let Err(e) = memory.save_message(&message).await {
+                    warn!("[Memory] Failed to save timer: {}", e);
+                }
+                chat_history.push(message);
+                emit_chat(&app, "assistant", &text);
+                // After the chat message, whose own emotion detection would override it
+                let _ = app.emit(
+                    "avatar-emotion",
+                    EmotionEvent {
+                        emotion: Emotion::Surprised,
+                    },
+                );
+                notify::send(&app, "Time's up", &text);
+                speak(&format!("Time's up! {}", timer.label));
+                continue;
+            }
+            _ = proactive_poll.tick() => {
+                if !proactive_enabled.load(Ordering::SeqCst) {
+                    continue;
+                }
+                let now = match memory.local_now().await {
+                    Ok(now) => now,
+                    Err(e) => {
+                        warn!("[Proactive] Failed to read the clock: {}", e);
+                        continue;
+                    }
+                };
+                let Some(trigger) = proactivity.poll(&now) else {
+                    continue;
+                };
+                proactive_turn = true;
+                trigger.prompt()
+            }
+            Some(frame) = screen_rx.recv() => {
+                // Keep only the latest watched frame in context to bound image cost
+                chat_history.retain(|m| !m.content.starts_with(SCREEN_UPDATE_PREFIX));
+                *latest_image.lock().unwrap() = Some(frame.clone());
+                chat_history.push(Message {
+                    role: "user".to_string(),
+                    content: format!(
+                        "{} The screen changed; the latest capture is attached.",
+                        SCREEN_UPDATE_PREFIX
+                    ),
+                    images: Some(vec![frame]),
+                });
+                let trigger = proactive_enabled
+                    .load(Ordering::SeqCst)
+                    .then(|| proactivity.screen_changed())
+                    .flatten();
+                let Some(trigger) = trigger else {
+                    emit_status(&app, "Screen updated", false);
+                    continue;
+                };
+                proactive_turn = true;
+                trigger.prompt()
+            }
+            else => break,
+        };
         input = input.trim().to_string();
         if input.is_empty() {
             continue;
         }
+        if !proactive_turn {
+            proactivity.user_active();
+        }
 
-        // Handle Clear Chat
-        if input == "__CLEAR__" {
-            chat_history.clear();
-            let sys_msg = Message {
-                role: "system".to_string(),
-                content: full_system_prompt.clone(),
-                images: None,
-            };
-            chat_history.push(sys_msg);
-            emit_chat(&app, "assistant", "대화 기록이 초기화되었습니다.");
-            continue;
+        // Slash commands are handled here and never reach the LLM
+        if let Some(parsed) = SlashCommand::parse(&input) {
+            match parsed {
+                Ok(SlashCommand::Clear) => {
+                    chat_history.clear();
+                    if let Err(e) = memory
+                        .set_conversation_summary(memory.active_conversation(), None)
+                        .await
+                    {
+                        warn!("[Memory] Failed to reset summary: {}", e);
+                    }
+                    last_reply_truncated = false;
+                    dispatcher.clear_cache();
+                    // Reload edits to amadeus.toml, but keep what was set from the UI
+                    let intensity = config.persona_intensity;
+                    let sampling = config.sampling.clone();
+                    config = AppConfig::load();
+                    config.persona_intensity = intensity;
+                    config.sampling = sampling;
+                    proactivity.set_settings(config.proactive.clone());
+                    set_system_prompt(
+                        chat_history,
+                        build_system_prompt(&config, &persona, &memory, &tools_prompt).await,
+                    );
+                    let _ = app.emit("chat-cleared", ());
+                    emit_context_usage(
+                        &app,
+                        &config.context,
+                        client.count_tokens(&chat_history),
+                        0,
+                    );
+                    emit_chat(&app, "assistant", "대화 기록이 초기화되었습니다.");
+                }
+                Ok(SlashCommand::Continue) => {
+                    if !last_reply_truncated {
+                        emit_chat(&app, "system", "Nothing to continue.");
+                        continue;
+                    }
+                    emit_status(&app, "Thinking", true);
+
+                    // The nudge is only sent to the model; the continuation is appended
+                    // to the cut-off reply so history holds one coherent message
+                    let mut messages = chat_history.clone();
+                    messages.push(Message {
+                        role: "user".to_string(),
+                        content: "Continue exactly where you left off. Do not repeat anything."
+                            .to_string(),
+                        images: None,
+                    });
+                    match client.chat(messages).await {
+                        Ok(reply) => {
+                            if let Some(last) = chat_history.last_mut() {
+                                last.content.push_str(&reply.content);
+                            }
+                            if let Err(e) = memory
+                                .append_to_last_message("assistant", &reply.content)
+                                .await
+                            {
+                                warn!("[Memory] Failed to save continuation: {}", e);
+                            }
+                            last_reply_truncated = reply.truncated;
+                            let _ = app.emit(
+                                "chat-append",
+                                ChatAppendEvent {
+                                    content: reply.content.clone(),
+                                    truncated: reply.truncated,
+                                },
+                            );
+                            emit_status(&app, "Online", false);
+                            speak(&reply.content);
+                        }
+                        Err(e) => {
+                            emit_chat(&app, "system", &format!("❌ LLM Error: {}", e));
+                            emit_status(&app, "Error - retry your message", false);
+                        }
+                    }
+                }
+                Ok(command @ (SlashCommand::Regenerate | SlashCommand::Edit(_))) => {
+                    let last_user = last_typed_message(chat_history);
+                    let saved = match memory.last_typed_message_id().await {
+                        Ok(id) => id,
+                        Err(e) => {
+                            emit_chat(&app, "system", &format!("❌ {}", e));
+                            continue;
+                        }
+                    };
+                    let (Some(index), Some(user_id)) = (last_user, saved) else {
+                        emit_chat(&app, "system", "Nothing to regenerate.");
+                        continue;
+                    };
+
+                    // The last turn is undone here and run again below as a new turn
+                    let user = chat_history[index].clone();
+                    chat_history.truncate(index);
+                    if let Err(e) = memory.delete_messages_after(user_id).await {
+                        warn!("[Memory] Failed to delete the old reply: {}", e);
+                    }
+                    input = match command {
+                        SlashCommand::Edit(text) => {
+                            if let Err(e) = memory.update_message(user_id, &text).await {
+                                warn!("[Memory] Failed to save the edit: {}", e);
+                            }
+                            text
+                        }
+                        _ => user.content,
+                    };
+                    attached_images = user.images;
+                    replayed = Some(user_id);
+                    last_reply_truncated = false;
+                    let _ = app.emit("chat-rewound", ());
+                }
+                Ok(SlashCommand::Region) => {
+                    if let Err(e) = open_region_select(&app).await {
+                        emit_chat(
+                            &app,
+                            "system",
+                            &format!("❌ Region selection failed: {}", e),
+                        );
+                    }
+                }
+                Ok(SlashCommand::Help) => {
+                    emit_chat(&app, "system", &commands::help_text());
+                }
+                Ok(SlashCommand::Tools) => {
+                    let listing: Vec<String> = dispatcher
+                        .list_tools()
+                        .iter()
+                        .map(|(name, description)| format!("- `{}` — {}", name, description))
+                        .collect();
+                    emit_chat(
+                        &app,
+                        "system",
+                        &format!("Enabled tools:\n{}", listing.join("\n")),
+                    );
+                }
+                Ok(SlashCommand::Export(format)) => {
+                    match export::export_history(&chat_history, format) {
+                        Ok(path) => emit_chat(
+                            &app,
+                            "system",
+                            &format!("Conversation exported to {}", path.display()),
+                        ),
+                        Err(e) => emit_chat(&app, "system", &format!("❌ Export failed: {}", e)),
+                    }
+                }
+                Ok(SlashCommand::Persona(name)) => match Persona::by_name(&name) {
+                    Some(p) => {
+                        persona = p;
+                        if let Err(e) = memory
+                            .set_conversation_setup(
+                                memory.active_conversation(),
+                                &persona.name,
+                                client.model(),
+                            )
+                            .await
+                        {
+                            warn!("[Memory] Failed to pin persona: {}", e);
+                        }
+                        apply_persona(&app, tts.as_deref(), &stt_language, &persona);
+                        emit_chat(
+                            &app,
+                            "system",
+                            &format!("Persona switched to {}", persona.name),
+                        );
+                        if let Some(greeting) = &persona.greeting {
+                            emit_chat(&app, "assistant", greeting);
+                            speak(greeting);
+                        }
+                    }
+                    None => emit_chat(&app, "system", &format!("Unknown persona: {}", name)),
+                },
+                Ok(SlashCommand::Intensity(intensity)) => {
+                    // Applies from the next turn; history is kept
+                    config.persona_intensity = intensity;
+                    client = Arc::new(
+                        (*client)
+                            .clone()
+                            .with_temperature(persona::intensity_temperature(intensity)),
+                    );
+                    emit_status(&app, &format!("Persona intensity {}%", intensity), false);
+                }
+                Ok(SlashCommand::Reload) => {
+                    emit_status(&app, "Reloading model...", true);
+                    // Pick up `[hardware]` and context size changes from the settings panel
+                    let saved = AppConfig::load();
+                    config.hardware = saved.hardware;
+                    config.context.window_tokens = saved.context.window_tokens;
+                    client = Arc::new(
+                        (*client)
+                            .clone()
+                            .with_hardware(config.hardware.clone())
+                            .with_context_window(config.context.window_tokens),
+                    );
+                    let reloaded = match client.health_check().await {
+                        Ok(true) => client.warm_up().await,
+                        _ => Err(anyhow::anyhow!("Ollama is not running")),
+                    };
+                    match reloaded {
+                        Ok(()) => {
+                            emit_status(&app, "Online", false);
+                            emit_chat(
+                                &app,
+                                "system",
+                                &format!("Model {} reloaded", client.model()),
+                            );
+                        }
+                        Err(e) => {
+                            emit_status(&app, "Ollama Offline", false);
+                            emit_chat(
+                                &app,
+                                "system",
+                                &format!("❌ Failed to reload {}: {}", client.model(), e),
+                            );
+                        }
+                    }
+                    setup::refresh(&app).await;
+                }
+                Ok(SlashCommand::Model(name)) => {
+                    client = Arc::new(model_client(&name, &config).with_temperature(
+                        persona::intensity_temperature(config.persona_intensity),
+                    ));
+                    if let Err(e) = memory
+                        .set_conversation_setup(memory.active_conversation(), &persona.name, &name)
+                        .await
+                    {
+                        warn!("[Memory] Failed to pin model: {}", e);
+                    }
+                    emit_chat(&app, "system", &format!("Model switched to {}", name));
+                    warm_up(&client);
+                }
+                Ok(SlashCommand::Conversations) => match memory.list_conversations().await {
+                    Ok(conversations) => {
+                        let active = memory.active_conversation();
+                        let listing: Vec<String> = conversations
+                            .iter()
+                            .map(|c| {
+                                let branch = c
+                                    .parent_id
+                                    .map(|p| format!(" · branch of `{}`", p))
+                                    .unwrap_or_default();
+                                format!(
+                                    "- `{}`{} — {} · {} · {}{}",
+                                    c.id,
+                                    if c.id == active { " (active)" } else { "" },
+                                    c.persona_name,
+                                    c.model,
+                                    c.created_at,
+                                    branch
+                                )
+                            })
+                            .collect();
+                        emit_chat(
+                            &app,
+                            "system",
+                            &format!("Conversations:\n{}", listing.join("\n")),
+                        );
+                    }
+                    Err(e) => emit_chat(&app, "system", &format!("❌ {}", e)),
+                },
+                Ok(SlashCommand::DeleteConversation(id)) => {
+                    // Deleting the open conversation would leave the loop without history
+                    if id == memory.active_conversation() {
+                        emit_chat(
+                            &app,
+                            "system",
+                            "Switch to another conversation before deleting this one.",
+                        );
+                        continue;
+                    }
+                    match memory.delete_conversation(id).await {
+                        Ok(true) => {
+                            emit_chat(&app, "system", &format!("Deleted conversation {}", id));
+                            let _ = app.emit(
+                                "conversations-changed",
+                                ConversationsChangedEvent {
+                                    active: memory.active_conversation(),
+                                },
+                            );
+                        }
+                        Ok(false) => emit_chat(&app, "system", "No conversation with that id."),
+                        Err(e) => emit_chat(&app, "system", &format!("❌ {}", e)),
+                    }
+                }
+                Ok(
+                    command @ (SlashCommand::NewConversation | SlashCommand::SwitchConversation(_)),
+                ) => {
+                    let target = match command {
+                        SlashCommand::SwitchConversation(id) => memory.get_conversation(id).await,
+                        _ => match memory
+                            .create_conversation(&persona.name, client.model())
+                            .await
+                        {
+                            Ok(id) => memory.get_conversation(id).await,
+                            Err(e) => Err(e),
+                        },
+                    };
+                    let conversation = match target {
+                        Ok(Some(conversation)) => conversation,
+                        Ok(None) => {
+                            emit_chat(&app, "system", "No conversation with that id.");
+                            continue;
+                        }
+                        Err(e) => {
+                            emit_chat(&app, "system", &format!("❌ {}", e));
+                            continue;
+                        }
+                    };
+                    match open_conversation(&conversation, &config, &memory, &tools_prompt).await {
+                        Ok(opened) => {
+                            persona = opened.persona;
+                            apply_persona(&app, tts.as_deref(), &stt_language, &persona);
+                            client = opened.client;
+                            *chat_history = opened.history;
+                            last_reply_truncated = false;
+
+                            let transcript: Vec<ChatEvent> = memory
+                                .get_history(None, HISTORY_PAGE_SIZE)
+                                .await
+                                .unwrap_or_default()
+                                .into_iter()
+                                .filter(|m| m.role != "system" && !proactive::is_prompt(&m.content))
+                                .map(|m| ChatEvent {
+                                    role: m.role,
+                                    content: m.content,
+                                    turn_id: None,
+                                    message_id: Some(m.id),
+                                    pinned: m.pinned,
+                                })
+                                .collect();
+                            let _ = app.emit("chat-history", transcript);
+                            emit_context_usage(
+                                &app,
+                                &config.context,
+                                client.count_tokens(&chat_history),
+                                0,
+                            );
+                            for notice in &opened.notices {
+                                emit_chat(&app, "system", notice);
+                            }
+                            emit_chat(
+                                &app,
+                                "system",
+                                &format!(
+                                    "Conversation {} — {} · {}",
+                                    conversation.id,
+                                    persona.name,
+                                    client.model()
+                                ),
+                            );
+                            let _ = app.emit(
+                                "conversations-changed",
+                                ConversationsChangedEvent {
+                                    active: conversation.id,
+                                },
+                            );
+                        }
+                        Err(e) => emit_chat(&app, "system", &format!("❌ {}", e)),
+                    }
+                }
+                Ok(SlashCommand::ClearCache) => {
+                    let count = dispatcher.clear_cache();
+                    emit_chat(
+                        &app,
+                        "system",
+                        &format!("Cleared {} cached tool result(s)", count),
+                    );
+                }
+                Err(msg) => emit_chat(&app, "system", &msg),
+            }
+            if replayed.is_none() {
+                continue;
+            }
         }
 
         // User message
+        turn_id += 1;
+        *last_turn_id = turn_id;
+        let turn_began = Instant::now();
+        // Picks up persona, tone, tool and scratchpad changes made since the last turn
+        set_system_prompt(
+            chat_history,
+            build_system_prompt(&config, &persona, &memory, &tools_prompt).await,
+        );
         let user_msg = Message {
             role: "user".to_string(),
             content: input.to_string(),
-            images: None,
+            images: attached_images,
         };
-        if let Err(e) = memory.save_message(&user_msg).await {
-            eprintln!("[Memory] Failed to save message: {}", e);
-        }
+        // Messages of this turn are persisted together once it completes
+        let mut turn_start = chat_history.len();
+        stop.send_replace(false);
         chat_history.push(user_msg);
+        last_reply_truncated = false;
 
-        // Echo user message to frontend (backend = single source of truth)
-        emit_chat(&app, "user", &input);
+        // Echo user message to frontend (backend = single source of truth); proactive
+        // prompts stay hidden so the reply reads as Amadeus speaking first
+        if !proactive_turn {
+            emit_turn(&app, Some(turn_id), "user", &input);
+        }
         emit_status(&app, "Thinking", true);
 
+        // Facts from earlier conversations that bear on this message
+        let recalled = match &embedder {
+            Some(embedder) if !proactive_turn => {
+                recall::recall(&memory, embedder, &input, &config.memory)
+                    .await
+                    .unwrap_or_else(|e| {
+                        warn!("[Memory] Recall failed: {}", e);
+                        Vec::new()
+                    })
+            }
+            _ => Vec::new(),
+        };
+
         // Chat Loop
+        let mut guard = LoopGuard::new(&config.tools);
+        let mut turn_metrics: Option<(String, ReplyMetrics)> = None;
         loop {
-            let messages_clone = chat_history.clone();
-            let client_clone = Arc::clone(&client);
+            // Ollama clips an oversized prompt silently, so drop old messages here instead
+            let dropped = context::trim(&client, chat_history, turn_start, &config.context);
+            let trimmed = dropped.len();
+            turn_start -= trimmed;
+            if trimmed > 0 {
+                let mut summarized = false;
+                if config.context.summarize {
+                    emit_status(&app, "Summarizing earlier conversation", true);
+                    let conversation = memory.active_conversation();
+                    let previous = memory
+                        .conversation_summary(conversation)
+                        .await
+                        .unwrap_or_default();
+                    match context::summarize(&client, previous.as_deref(), &dropped).await {
+                        Ok(summary) => {
+                            if context::apply_summary(chat_history, &summary) {
+                                turn_start += 1;
+                            }
+                            if let Err(e) = memory
+                                .set_conversation_summary(conversation, Some(&summary))
+                                .await
+                            {
+                                warn!("[Memory] Failed to save summary: {}", e);
+                            }
+                            summarized = true;
+                        }
+                        Err(e) => warn!("[LLM] Summarization failed: {}", e),
+                    }
+                }
+                let notice = if summarized {
+                    format!(
+                        "Context window full: summarized {} older message(s)",
+                        trimmed
+                    )
+                } else {
+                    format!(
+                        "Context window full: dropped {} older message(s) from the prompt",
+                        trimmed
+                    )
+                };
+                emit_turn(&app, Some(turn_id), "system", &notice);
+                emit_status(&app, "Thinking", true);
+            }
+            let estimate = client.count_tokens(&chat_history);
+            emit_context_usage(&app, &config.context, estimate, trimmed);
+
+            let mut messages_clone = chat_history.clone();
+            // Sent with every call this turn but kept out of history. It goes right before the
+            // turn, not into the system prompt, so Ollama can still reuse the cached prefix.
+            if !recalled.is_empty() {
+                messages_clone.insert(turn_start, recall::prompt_message(&recalled));
+            }
+            // A fresh image (screenshot, region, screen update) needs a vision model
+            let fresh_image = chat_history.last().is_some_and(|m| m.images.is_some());
+            let client_clone = match (&config.vision_model, fresh_image) {
+                (Some(vision_model), true) => Arc::new(
+                    model_client(vision_model, &config)
+                        .with_temperature(persona::intensity_temperature(config.persona_intensity)),
+                ),
+                _ => Arc::clone(&client),
+            };
+
+            // Tokens are forwarded as they arrive so the reply appears word by word,
+            // and each sentence is spoken as soon as it is complete
+            let mut sentences = tts::SentenceSplitter::default();
+            // The leading emotion tag is taken off before anything is shown or spoken
+            let mut tags = emotion::TagFilter::default();
+            let mut forward = |text: String, tags: &emotion::TagFilter| {
+                if text.is_empty() {
+                    return;
+                }
+                for sentence in sentences.push(&text) {
+                    let tone = tags.emotion().unwrap_or_else(|| Emotion::detect(&sentence));
+                    speak_as(&sentence, tone);
+                }
+                emit_event(
+                    &app,
+                    AgentEvent::AssistantDelta {
+                        turn_id: Some(turn_id),
+                        content: text,
+                    },
+                );
+            };
+            let streamed = client_clone
+                .chat_streaming(messages_clone, stop.subscribe(), |token| {
+                    let tagged = tags.emotion().is_some();
+                    let text = tags.push(token);
+                    // The face changes as soon as the tag is read, ahead of the words
+                    if let Some(emotion) = tags.emotion().filter(|_| !tagged) {
+                        let _ = app.emit("avatar-emotion", EmotionEvent { emotion });
+                    }
+                    forward(text, &tags);
+                })
+                .await;
+            let text = tags.finish();
+            forward(text, &tags);
+            if !*stop.borrow() {
+                if let Some(rest) = sentences.finish() {
+                    let tone = tags.emotion().unwrap_or_else(|| Emotion::detect(&rest));
+                    speak_as(&rest, tone);
+                }
+            }
+            emit_event(
+                &app,
+                AgentEvent::AssistantDone {
+                    turn_id: Some(turn_id),
+                    emotion: tags.emotion(),
+                },
+            );
 
-            let full_response = match client_clone.chat(messages_clone).await {
+            let mut reply = match streamed {
                 Ok(r) => r,
                 Err(e) => {
-                    let err_msg = format!("❌ LLM Error: {}", e);
-                    eprintln!("[LLM] {}", err_msg);
-                    emit_chat(&app, "system", &err_msg);
+                    let err_msg = format!("LLM Error: {}", e);
+                    error!("[LLM] {}", err_msg);
+                    emit_event(
+                        &app,
+                        AgentEvent::Error {
+                            turn_id: Some(turn_id),
+                            message: err_msg,
+                        },
+                    );
                     emit_status(&app, "Error - retry your message", false);
+                    // Drop the unanswered turn so a retry doesn't duplicate the user message
+                    chat_history.truncate(turn_start);
                     break;
                 }
             };
+            // History and the saved conversation keep the reply as the user saw it
+            emotion::strip_tag(&mut reply.content);
+            if let Some(metrics) = &reply.metrics {
+                turn_metrics
+                    .get_or_insert_with(|| (client_clone.model().to_string(), Default::default()))
+                    .1
+                    .add(metrics);
+            }
+            // Prefer the model's own count, unless a reused cache made it undercount
+            if let Some(measured) = reply.prompt_tokens {
+                // Whatever Ollama didn't evaluate was served from the KV cache of the last turn
+                info!(
+                    "[LLM] Prompt ~{} tokens, {} evaluated, ~{} reused from cache",
+                    estimate,
+                    measured,
+                    estimate.saturating_sub(measured as usize)
+                );
+                emit_context_usage(
+                    &app,
+                    &config.context,
+                    (measured as usize).max(estimate),
+                    trimmed,
+                );
+            }
+            if reply.cancelled {
+                // Keep what the user already saw; an empty answer leaves nothing to keep
+                if !reply.content.is_empty() {
+                    chat_history.push(Message {
+                        role: "assistant".to_string(),
+                        content: reply.content,
+                        images: None,
+                    });
+                } else if chat_history.len() == turn_start + 1 {
+                    chat_history.truncate(turn_start);
+                }
+                emit_turn(&app, Some(turn_id), "system", "⏹ Generation stopped");
+                emit_status(&app, "Online", false);
+                break;
+            }
+            let mut full_response = reply.content;
+
+            // The model tried to call a tool but produced broken JSON: ask again with
+            // sampling constrained to the tool-call schema so the call is well-formed
+            if looks_like_tool_call(&full_response)
+                && serde_json::from_str::<serde_json::Value>(&full_response).is_err()
+            {
+                emit_status(&app, "Repairing tool call", true);
+                match client_clone
+                    .chat_structured(chat_history.clone(), tool_call_schema.clone())
+                    .await
+                {
+                    Ok(repaired) => full_response = repaired,
+                    Err(e) => warn!("[LLM] Constrained tool call failed: {}", e),
+                }
+            }
 
             let assistant_msg = Message {
                 role: "assistant".to_string(),
                 content: full_response.clone(),
                 images: None,
             };
-            if let Err(e) = memory.save_message(&assistant_msg).await {
-                eprintln!("[Memory] Failed to save message: {}", e);
-            }
             chat_history.push(assistant_msg);
-            emit_chat(&app, "assistant", &full_response);
             emit_status(&app, "Online", false);
 
-            // TTS
-            if let Some(tts_manager) = &tts {
-                if !full_response.trim().starts_with('{') {
-                    let _ = tts_manager.speak(&full_response);
-                }
+            // A tool call may come fenced or wrapped in prose; the user only sees and hears
+            // the prose, while history keeps the full reply the model produced
+            let tool_request = toolcall::extract(&full_response, &dispatcher);
+            if let Some(request) = &tool_request {
+                let _ = app.emit(
+                    "chat-rewrite",
+                    ChatRewriteEvent {
+                        content: request.text.clone(),
+                    },
+                );
+            }
+
+            if reply.truncated {
+                last_reply_truncated = true;
+                let _ = app.emit("chat-truncated", ());
+                break;
             }
 
             // Tool Call Check
-            let maybe_tool_call: Option<serde_json::Value> =
-                serde_json::from_str(&full_response).ok();
-
-            if let Some(tool_json) = maybe_tool_call {
-                if let (Some(tool_name), Some(args)) = (
-                    tool_json.get("tool").and_then(|v| v.as_str()),
-                    tool_json.get("args"),
-                ) {
-                    println!("[System] Detected tool call: {}", tool_name);
-                    emit_chat(&app, "system", &format!("Tool '{}' を実行中...", tool_name));
+            if let Some(request) = tool_request {
+                let calls = &request.calls;
+                let names: Vec<&str> = calls.iter().map(|c| c.name.as_str()).collect();
+                info!("[System] Detected tool call: {}", names.join(", "));
+
+                // A model stuck in a tool loop would otherwise never hand the turn back
+                if let Some(reason) = calls.iter().find_map(|c| guard.check(&c.name, &c.args)) {
+                    warn!("[Agent] Stopping tool loop: {}", reason);
+                    emit_turn(&app, Some(turn_id), "system", &format!("⚠️ {}", reason));
+                    let stuck = format!(
+                        "I'm stuck: {}. I'll stop here - tell me how you'd like to proceed.",
+                        reason
+                    );
+                    emit_turn(&app, Some(turn_id), "assistant", &stuck);
+                    chat_history.push(Message {
+                        role: "assistant".to_string(),
+                        content: stuck,
+                        images: None,
+                    });
+                    emit_status(&app, "Online", false);
+                    break;
+                }
+
+                // Previews and approvals go one call at a time; the approved calls then run
+                // together and their results come back as one message, in call order
+                let mut approved = Vec::with_capacity(calls.len());
+                let mut stopped = false;
+                for call in calls {
+                    let tool_name = call.name.as_str();
+                    let args = &call.args;
+                    emit_event(
+                        &app,
+                        AgentEvent::ToolStarted {
+                            turn_id,
+                            tool: tool_name.to_string(),
+                            args: args.clone(),
+                        },
+                    );
                     emit_status(&app, &format!("Running tool: {}", tool_name), true);
 
-                    match dispatcher.execute(tool_name, args.clone()).await {
-                        Ok(result) => {
-                            emit_chat(&app, "system", &format!("✅ Tool '{}' 완료", tool_name));
-                            let result_msg = Message {
-                                role: "user".to_string(),
-                                content: format!("Tool Output: {}", result),
-                                images: None,
-                            };
-                            memory.save_message(&result_msg).await?;
-                            chat_history.push(result_msg);
+                    // Show what is about to change (e.g. a write_file diff)
+                    let preview = match dispatcher.preview(tool_name, args.clone()).await {
+                        Ok(preview) => preview,
+                        Err(e) => {
+                            warn!("[Tool] Preview failed for {}: {}", tool_name, e);
+                            None
+                        }
+                    };
+                    if let Some(preview) = &preview {
+                        emit_turn(&app, Some(turn_id), "system", preview);
+                    }
+
+                    // Typing, clicking, writing files and browsing wait for the user's OK
+                    let always_allowable = dispatcher.can_always_allow(tool_name);
+                    if dispatcher.requires_approval(tool_name, args)
+                        && !(always_allowable && approvals.is_always_allowed(tool_name))
+                    {
+                        let (id, decision_rx) = approvals.request(tool_name);
+                        let _ = app.emit(
+                            "tool-approval-request",
+                            ToolApprovalRequestEvent {
+                                id,
+                                tool: tool_name.to_string(),
+                                args: args.clone(),
+                                preview,
+                                turn_id,
+                                always_allowable,
+                            },
+                        );
+                        emit_status(&app, &format!("Waiting for approval: {}", tool_name), true);
+                        let mut stop_rx = stop.subscribe();
+                        let decision = tokio::select! {
+                            decision = decision_rx => decision.unwrap_or(Decision::Deny),
+                            _ = stop_rx.wait_for(|stopped| *stopped) => {
+                                approvals.cancel(id);
+                                Decision::Deny
+                            }
+                        };
+                        let _ =
+                            app.emit("tool-approval-resolved", ToolApprovalResolvedEvent { id });
+
+                        if decision == Decision::Deny {
+                            dispatcher.record_denied(tool_name, args).await;
+                            emit_event(
+                                &app,
+                                AgentEvent::ToolFailed {
+                                    turn_id,
+                                    tool: tool_name.to_string(),
+                                    error: "Denied by user".to_string(),
+                                    timed_out: false,
+                                },
+                            );
+                            approved.push(false);
+                            if *stop.borrow() {
+                                stopped = true;
+                                break;
+                            }
                             continue;
                         }
+                    }
+                    approved.push(true);
+                }
+
+                let to_run: Vec<(String, serde_json::Value)> = calls
+                    .iter()
+                    .zip(&approved)
+                    .filter(|(_, approved)| **approved)
+                    .map(|(call, _)| (call.name.clone(), call.args.clone()))
+                    .collect();
+                let outputs = if stopped || to_run.is_empty() {
+                    Vec::new()
+                } else {
+                    let running: Vec<&str> = to_run.iter().map(|(name, _)| name.as_str()).collect();
+                    emit_status(&app, &format!("Running tool: {}", running.join(", ")), true);
+                    dispatcher.execute_batch(to_run).await
+                };
+                let mut outputs = outputs.into_iter();
+
+                let mut results = Vec::with_capacity(approved.len());
+                let mut images = Vec::new();
+                for (call, approved) in calls.iter().zip(approved) {
+                    let tool_name = call.name.as_str();
+                    if !approved {
+                        results.push(format!(
+                            "Tool Error: The user did not allow {} to run.",
+                            tool_name
+                        ));
+                        continue;
+                    }
+                    // Approved, but the turn was stopped at a later call's approval
+                    let Some(outcome) = outputs.next() else {
+                        emit_event(
+                            &app,
+                            AgentEvent::ToolFailed {
+                                turn_id,
+                                tool: tool_name.to_string(),
+                                error: "Stopped".to_string(),
+                                timed_out: false,
+                            },
+                        );
+                        results.push(format!(
+                            "Tool Error: {} was stopped before it ran.",
+                            tool_name
+                        ));
+                        continue;
+                    };
+                    match outcome {
+                        Ok(output) => {
+                            let image = screenshot::parse_image_output(&output.content);
+                            emit_event(
+                                &app,
+                                AgentEvent::ToolFinished {
+                                    turn_id,
+                                    tool: tool_name.to_string(),
+                                    cached: output.cached,
+                                    image: image
+                                        .map(|(image, _)| screenshot::image_data_url(image)),
+                                },
+                            );
+                            // Images go to the model as attachments, not as base64 text
+                            match image {
+                                Some((image, caption)) => {
+                                    *latest_image.lock().unwrap() = Some(image.to_string());
+                                    images.push(image.to_string());
+                                    results.push(
+                                        format!("Tool Output: screenshot attached. {}", caption)
+                                            .trim_end()
+                                            .to_string(),
+                                    );
+                                }
+                                None => results.push(format!("Tool Output: {}", output.content)),
+                            }
+                        }
                         Err(e) => {
-                            emit_chat(
+                            emit_event(
                                 &app,
-                                "system",
-                                &format!("❌ Tool '{}' 오류: {}", tool_name, e),
+                                AgentEvent::ToolFailed {
+                                    turn_id,
+                                    tool: tool_name.to_string(),
+                                    error: e.to_string(),
+                                    timed_out: e.downcast_ref::<ToolTimeout>().is_some(),
+                                },
                             );
-                            let error_msg = Message {
-                                role: "user".to_string(),
-                                content: format!("Tool Error: {}", e),
-                                images: None,
-                            };
-                            memory.save_message(&error_msg).await?;
-                            chat_history.push(error_msg);
-                            continue;
+                            results.push(format!("Tool Error: {}", e));
                         }
                     }
                 }
+
+                // A single call keeps the plain format; a batch labels each result
+                let content = if calls.len() == 1 {
+                    results.concat()
+                } else {
+                    calls
+                        .iter()
+                        .zip(&results)
+                        .enumerate()
+                        .map(|(i, (call, result))| format!("[{}] {}: {}", i + 1, call.name, result))
+                        .collect::<Vec<_>>()
+                        .join("\n\n")
+                };
+                chat_history.push(Message {
+                    role: "user".to_string(),
+                    content,
+                    images: (!images.is_empty()).then_some(images),
+                });
+                if stopped {
+                    emit_status(&app, "Online", false);
+                    break;
+                }
+                continue;
             }
             break;
         }
+
+        if let Some((model, metrics)) = turn_metrics {
+            info!(
+                "[LLM] Turn {}: {} prompt + {} generated tokens, {:.1} tok/s",
+                turn_id,
+                metrics.prompt_tokens,
+                metrics.generated_tokens,
+                metrics.tokens_per_second()
+            );
+            let _ = app.emit(
+                "chat-metrics",
+                ChatMetricsEvent {
+                    turn_id,
+                    model: model.clone(),
+                    metrics,
+                    tokens_per_second: metrics.tokens_per_second(),
+                },
+            );
+            if let Err(e) = memory.add_metrics(&model, &metrics).await {
+                warn!("[Memory] Failed to save metrics: {}", e);
+            }
+        }
+
+        // A replayed user message is still saved; only its new reply is added
+        let save_from = turn_start + usize::from(replayed.is_some());
+        if chat_history.len() > save_from {
+            match memory.save_turn(&chat_history[save_from..]).await {
+                Ok(ids) => {
+                    let first_id = replayed.or(ids.first().copied());
+                    if let (Some(first_id), Some(&last_id)) = (first_id, ids.last()) {
+                        let _ = app.emit(
+                            "chat-turn-saved",
+                            TurnSavedEvent {
+                                turn_id,
+                                first_id,
+                                last_id,
+                            },
+                        );
+                    }
+                }
+                Err(e) => error!("[Memory] Failed to save turn: {}", e),
+            }
+        } else if let Some(id) = replayed.filter(|_| chat_history.len() <= turn_start) {
+            // The turn failed and its user message was dropped from history, as for any turn
+            if let Err(e) = memory.delete_message(id).await {
+                warn!("[Memory] Failed to delete message {}: {}", id, e);
+            }
+        }
+
+        // Pick out lasting facts in the background so the next message isn't held up
+        let reply = chat_history[turn_start..]
+            .iter()
+            .rev()
+            .find(|m| m.role == "assistant");
+        // Unasked-for replies, and ones the user may have stopped waiting on, also go to
+        // the desktop
+        if let Some(reply) = reply.filter(|m| !looks_like_tool_call(&m.content)) {
+            if proactive_turn || turn_began.elapsed() >= notify::long_task(&app) {
+                notify::send(&app, "Amadeus", &reply.content);
+            }
+        }
+        if let (Some(embedder), Some(reply)) = (&embedder, reply) {
+            let client = Arc::clone(&client);
+            let embedder = Arc::clone(embedder);
+            let memory = memory.clone();
+            let user = input.clone();
+            let reply = reply.content.clone();
+            tauri::async_runtime::spawn(async move {
+                let facts = match recall::extract_facts(&client, &user, &reply).await {
+                    Ok(facts) => facts,
+                    Err(e) => {
+                        warn!("[Memory] Fact extraction failed: {}", e);
+                        return;
+                    }
+                };
+                match recall::store_facts(&memory, &embedder, &facts).await {
+                    Ok(0) => {}
+                    Ok(count) => info!("[Memory] Remembered {} new fact(s)", count),
+                    Err(e) => warn!("[Memory] Failed to store facts: {}", e),
+                }
+            });
+        }
     }
     Ok(())
 }
@@ -297,22 +3453,266 @@ async fn run_agent_loop(
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .plugin(tauri_plugin_notification::init())
         .setup(|app| {
-            let (tx, rx) = mpsc::unbounded_channel::<String>();
+            let (tx, rx) = mpsc::unbounded_channel::<UserInput>();
+            let (region_tx, region_rx) = mpsc::unbounded_channel::<String>();
+            let (sampling_tx, sampling_rx) = mpsc::unbounded_channel::<SamplingConfig>();
+            let stop_tx = Arc::new(watch::channel(false).0);
 
-            let state = Arc::new(Mutex::new(AppState { tx }));
-            app.manage(state);
+            // The agent loop re-reads config on /clear so prompt changes apply to the next conversation
+            let config = AppConfig::load();
 
-            let app_handle = app.handle().clone();
-            tauri::async_runtime::spawn(async move {
-                if let Err(e) = run_agent_loop(app_handle, rx).await {
-                    eprintln!("Agent Loop Error: {}", e);
+            let log_dir = app
+                .path()
+                .app_log_dir()
+                .unwrap_or_else(|_| std::path::PathBuf::from("logs"));
+            if let Err(e) = std::fs::create_dir_all(&log_dir)
+                .map_err(anyhow::Error::from)
+                .and_then(|_| logging::init(&log_dir, &config.logging.level))
+            {
+                eprintln!("[Logging] Unavailable: {}", e);
+            }
+
+            // Initialize Memory
+            let memory = tauri::async_runtime::block_on(MemoryManager::new("amadeus.db"))?;
+
+            // Voice
+            let level_app = app.handle().clone();
+            let tts = match TtsManager::new(config.tts.clone(), move |level| {
+                let _ = level_app.emit(LIP_SYNC_EVENT, LipSyncEvent { level });
+            }) {
+                Ok(t) => Some(Arc::new(t)),
+                Err(e) => {
+                    info!("Voice Output Unavailable: {}", e);
+                    None
+                }
+            };
+
+            // A picked avatar lives outside the frontend, so the webview needs leave to load it
+            setup::allow_avatar(app.handle(), &config);
+
+            // So secrets are redacted from prompts even before a tool has read them
+            tauri::async_runtime::spawn_blocking(|| {
+                if let Err(e) = secrets::load_all() {
+                    warn!("[Secrets] {}", e);
                 }
             });
 
+            let browser = Arc::new(BrowserManager::new(config.browser.clone()));
+            tauri::async_runtime::spawn(Arc::clone(&browser).watch_idle());
+
+            let sandbox = Arc::new(Sandbox::new(&config.files.roots));
+
+            let approvals = Arc::new(ApprovalGate::new(&config.tools.always_allow));
+            let proactive = Arc::new(AtomicBool::new(config.proactive.enabled));
+            let (timer_tx, timer_rx) = mpsc::unbounded_channel();
+            let state = Arc::new(Mutex::new(AppState {
+                tx,
+                region_tx,
+                region_captures: Vec::new(),
+                sampling_tx,
+                sampling: config.sampling.clone(),
+                stop_tx: Arc::clone(&stop_tx),
+                approvals: Arc::clone(&approvals),
+                memory: memory.clone(),
+                browser: Arc::clone(&browser),
+                sandbox,
+                proactive: Arc::clone(&proactive),
+                timers: Arc::new(Timers::new(timer_tx)),
+                tts: tts.clone(),
+                stt: None,
+                stt_status: SttStatus {
+                    available: false,
+                    reason: Some("Loading speech model...".to_string()),
+                },
+                voice_listener: None,
+                push_to_talk: None,
+            }));
+            app.manage(state);
+            notify::init(app.handle(), config.notifications.clone());
+            register_hotkeys(app.handle(), &config.hotkeys);
+            if let Err(e) = tray::init(app.handle(), config.tts.enabled) {
+                warn!("[Tray] Unavailable: {}", e);
+            }
+
+            // Updated by the agent loop whenever the persona changes
+            let stt_language = Arc::new(SttLanguage::new(&config.voice_input));
+            init_stt(
+                app.handle().clone(),
+                config.stt_model_path.clone(),
+                Arc::clone(&stt_language),
+                config.voice_input.clone(),
+            );
+
+            let app_handle = app.handle().clone();
+            if let Some(options) = ReplayOptions::from_args() {
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = run_replay(app_handle, rx, options).await {
+                        error!("Replay Error: {}", e);
+                    }
+                });
+                return Ok(());
+            }
+            let channels = AgentChannels {
+                input: rx,
+                region: region_rx,
+                sampling: sampling_rx,
+                stop: stop_tx,
+                approvals,
+                proactive,
+                timers: timer_rx,
+            };
+            tauri::async_runtime::spawn(supervise_agent_loop(
+                app_handle,
+                channels,
+                memory,
+                config,
+                tts,
+                stt_language,
+            ));
+
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![send_message, clear_chat])
+        .invoke_handler(tauri::generate_handler![
+            send_message,
+            stop_generation,
+            close_browser,
+            get_file_roots,
+            add_file_root,
+            remove_file_root,
+            get_proactive_mode,
+            set_proactive_mode,
+            get_settings,
+            set_settings,
+            list_models,
+            load_model,
+            download_model,
+            get_setup_status,
+            pick_avatar,
+            request_permissions,
+            finish_setup,
+            set_timer,
+            list_timers,
+            cancel_timer,
+            get_email_accounts,
+            set_email_password,
+            list_secrets,
+            get_tool_audit,
+            set_secret,
+            delete_secret,
+            resolve_tool_approval,
+            send_structured,
+            translate_message,
+            clear_chat,
+            regenerate,
+            edit_last_user_message,
+            branch_conversation,
+            delete_message,
+            set_message_pinned,
+            list_conversations,
+            search_messages,
+            get_history,
+            new_conversation,
+            switch_conversation,
+            delete_conversation,
+            get_db_stats,
+            run_maintenance,
+            get_audio_outputs,
+            set_audio_output,
+            get_voice_settings,
+            set_speech_tuning,
+            set_piper_voice,
+            download_piper_voice,
+            delete_piper_voice,
+            get_avatar_settings,
+            get_persona_intensity,
+            get_sampling,
+            set_sampling,
+            set_persona_intensity,
+            reload_llm,
+            get_llm_info,
+            set_log_level,
+            list_personas,
+            list_slash_commands,
+            set_persona,
+            start_region_select,
+            get_region_background,
+            finish_region_select,
+            cancel_region_select,
+            get_stt_status,
+            is_continuous_listening,
+            set_continuous_listening,
+            listen_once,
+            release_push_to_talk,
+            send_quick_chat,
+            close_quick_chat
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::memory::test_memory;
+
+    #[tokio::test]
+    async fn system_prompt_puts_prefix_persona_suffix_then_tools() {
+        let memory = test_memory().await;
+        memory.kv_set("favorite_drink", "Dr Pepper").await.unwrap();
+        let config = AppConfig {
+            system_prompt_prefix: "PREFIX".to_string(),
+            system_prompt_suffix: "SUFFIX".to_string(),
+            ..AppConfig::default()
+        };
+        let persona = Persona {
+            system_prompt: "PERSONA".to_string(),
+            ..Persona::amadeus()
+        };
+
+        let prompt = build_system_prompt(&config, &persona, &memory, "TOOLS").await;
+
+        assert!(prompt.starts_with("PREFIX\n\nPERSONA\n\n"));
+        assert!(prompt.ends_with("\n\nTOOLS"));
+        let at = |part: &str| prompt.find(part).unwrap();
+        assert!(at("PERSONA") < at("SUFFIX"));
+        assert!(at("SUFFIX") < at("favorite_drink: Dr Pepper"));
+        assert!(at("favorite_drink: Dr Pepper") < at("TOOLS"));
+    }
+
+    #[tokio::test]
+    async fn regenerate_after_a_tool_turn_replays_what_the_user_typed() {
+        let memory = test_memory().await;
+        let mut history = vec![
+            Message::new("user", "What's in my downloads?"),
+            Message::new(
+                "assistant",
+                r#"{ "tool": "file_system", "args": { "action": "list" } }"#,
+            ),
+            Message::new("user", "Tool Output: report.pdf"),
+            Message::new("assistant", "Just report.pdf."),
+        ];
+        let ids = memory.save_turn(&history).await.unwrap();
+        // A proactive check-in after the turn is not something to regenerate either
+        let proactive = vec![
+            Message::new("user", &Trigger::Idle(30).prompt()),
+            Message::new("assistant", "Still there?"),
+        ];
+        memory.save_turn(&proactive).await.unwrap();
+        history.extend(proactive);
+
+        // What /regenerate looks up before undoing the turn
+        let index = last_typed_message(&history).unwrap();
+        let id = memory.last_typed_message_id().await.unwrap().unwrap();
+        assert_eq!(history[index].content, "What's in my downloads?");
+        assert_eq!(id, ids[0]);
+
+        memory.delete_messages_after(id).await.unwrap();
+        let kept = memory.get_history(None, 10).await.unwrap();
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].content, "What's in my downloads?");
+    }
+}
@@ -0,0 +1,77 @@
+use serde::Serialize;
+
+use super::ollama::{OllamaClient, RunningModel};
+use crate::config::AppConfig;
+
+/// GPU backend Ollama can run models on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Accelerator {
+    /// Apple Silicon
+    Metal,
+    /// NVIDIA
+    Cuda,
+    /// AMD
+    Rocm,
+    /// None found; models run on the CPU
+    Cpu,
+}
+
+/// What `get_llm_info` reports: the configured runtime settings and where the model
+/// actually ended up
+#[derive(Debug, Clone, Serialize)]
+pub struct LlmInfo {
+    pub model: String,
+    pub accelerator: Accelerator,
+    pub cpu_only: bool,
+    pub gpu_layers: Option<u32>,
+    pub context_window: u32,
+    pub batch_size: Option<u32>,
+    pub threads: Option<u32>,
+    /// Logical cores, the ceiling for `threads`
+    pub cpu_cores: usize,
+    /// Models Ollama has loaded; empty when none is or Ollama isn't running
+    pub running: Vec<RunningModel>,
+}
+
+/// Look for a GPU Ollama would use. Ollama doesn't say which backend it was built with,
+/// so this checks the machine: Apple Silicon has Metal, `nvidia-smi` finds CUDA devices
+/// and `/dev/kfd` is the ROCm driver.
+pub fn detect() -> Accelerator {
+    if cfg!(all(target_os = "macos", target_arch = "aarch64")) {
+        return Accelerator::Metal;
+    }
+    let nvidia = std::process::Command::new("nvidia-smi")
+        .arg("-L")
+        .output()
+        .is_ok_and(|out| out.status.success() && !out.stdout.is_empty());
+    if nvidia {
+        Accelerator::Cuda
+    } else if cfg!(target_os = "linux") && std::path::Path::new("/dev/kfd").exists() {
+        Accelerator::Rocm
+    } else {
+        Accelerator::Cpu
+    }
+}
+
+pub async fn info(config: &AppConfig) -> LlmInfo {
+    let accelerator = tokio::task::spawn_blocking(detect)
+        .await
+        .unwrap_or(Accelerator::Cpu);
+    let running = OllamaClient::new(&config.model)
+        .running_models()
+        .await
+        .unwrap_or_default();
+    let hardware = &config.hardware;
+    LlmInfo {
+        model: config.model.clone(),
+        accelerator,
+        cpu_only: hardware.cpu_only,
+        gpu_layers: hardware.gpu_layers,
+        context_window: config.context.window_tokens,
+        batch_size: hardware.batch_size,
+        threads: hardware.threads,
+        cpu_cores: std::thread::available_parallelism().map_or(1, |n| n.get()),
+        running,
+    }
+}
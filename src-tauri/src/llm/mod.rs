@@ -1,4 +1,7 @@
+pub mod hardware;
+pub mod models;
 pub mod ollama;
+pub mod template;
 
 // Re-export Message from ollama for backward compatibility
 pub use ollama::Message;
@@ -0,0 +1,287 @@
+use anyhow::Result;
+use reqwest::{header, redirect, StatusCode, Url};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tracing::{info, warn};
+
+use super::ollama::OllamaClient;
+use crate::config::ModelSettings;
+use crate::system::sandbox::expand_home;
+
+/// Folder levels searched below each of `[models] dirs`
+const SCAN_DEPTH: usize = 3;
+/// Least time between two download progress reports
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// A model the picker can switch to: one Ollama already has, or a GGUF file on disk
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelEntry {
+    /// Ollama model name; for a file not imported yet, the name it will get
+    pub name: String,
+    /// The `.gguf` file, for models found on disk
+    pub path: Option<String>,
+    /// Bytes on disk
+    pub size: u64,
+    /// Known to Ollama, so switching needs no import
+    pub installed: bool,
+}
+
+/// Ollama's models followed by GGUF files that aren't imported yet, each sorted by name.
+/// Without Ollama only the files are listed.
+pub async fn list(settings: &ModelSettings) -> Vec<ModelEntry> {
+    let tags = match OllamaClient::new("").model_tags().await {
+        Ok(tags) => tags,
+        Err(e) => {
+            warn!("[Models] Failed to list Ollama models: {}", e);
+            Vec::new()
+        }
+    };
+    let mut models: Vec<ModelEntry> = tags
+        .into_iter()
+        .map(|tag| ModelEntry {
+            name: tag.name,
+            path: None,
+            size: tag.size,
+            installed: true,
+        })
+        .collect();
+    models.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let dirs = settings.dirs.clone();
+    let files = tokio::task::spawn_blocking(move || gguf_files(&dirs))
+        .await
+        .unwrap_or_default();
+    let mut found: Vec<ModelEntry> = files
+        .into_iter()
+        .filter_map(|path| {
+            let name = import_name(&path);
+            if models.iter().any(|m| is_same_model(&m.name, &name)) {
+                return None;
+            }
+            Some(ModelEntry {
+                name,
+                size: std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0),
+                path: Some(path.display().to_string()),
+                installed: false,
+            })
+        })
+        .collect();
+    found.sort_by(|a, b| a.name.cmp(&b.name));
+    found.dedup_by(|a, b| a.name == b.name);
+    models.extend(found);
+    models
+}
+
+/// Make `entry` usable by Ollama and return the name to chat with. Files are hashed and
+/// registered under their import name; installed models are returned as they are.
+pub async fn prepare(entry: &ModelEntry) -> Result<String> {
+    let Some(path) = entry.path.as_ref().filter(|_| !entry.installed) else {
+        return Ok(entry.name.clone());
+    };
+    let path = PathBuf::from(path);
+    info!("[Models] Importing {} as {}", path.display(), entry.name);
+    let digest = {
+        let path = path.clone();
+        tokio::task::spawn_blocking(move || sha256_file(&path)).await??
+    };
+    OllamaClient::new(&entry.name)
+        .create_from_gguf(&entry.name, &path, &digest)
+        .await?;
+    Ok(entry.name.clone())
+}
+
+/// Hex SHA-256 of a file, read in chunks so multi-gigabyte models don't fill memory
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// `Qwen2.5-7B-Instruct-Q4_K_M.gguf` becomes `qwen2.5-7b-instruct-q4_k_m`, a name
+/// Ollama accepts
+fn import_name(path: &Path) -> String {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    stem.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+/// Ollama reports untagged models as `<name>:latest`
+fn is_same_model(installed: &str, name: &str) -> bool {
+    installed == name || installed.strip_suffix(":latest") == Some(name)
+}
+
+fn gguf_files(dirs: &[String]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for dir in dirs {
+        collect_gguf(&expand_home(dir), SCAN_DEPTH, &mut files);
+    }
+    files
+}
+
+fn collect_gguf(dir: &Path, depth: usize, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.is_dir() {
+            if depth > 0 {
+                collect_gguf(&path, depth - 1, files);
+            }
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("gguf"))
+        {
+            files.push(path);
+        }
+    }
+}
+
+/// Fetch a GGUF file from a Hugging Face `resolve` URL into the first of `[models] dirs`
+/// and return its path. Bytes go to a `.part` file first; a download that was cut off
+/// picks up where it stopped. The result is checked against `sha256` if given, otherwise
+/// against the hash Hugging Face publishes for the file, and deleted on a mismatch.
+/// `on_progress(downloaded, total)` is called as bytes arrive.
+pub async fn download<F>(
+    settings: &ModelSettings,
+    url: &str,
+    sha256: Option<String>,
+    on_progress: F,
+) -> Result<PathBuf>
+where
+    F: Fn(u64, Option<u64>),
+{
+    let url = Url::parse(url).map_err(|e| anyhow::anyhow!("Invalid url: {}", e))?;
+    let host = url.host_str().unwrap_or_default();
+    if url.scheme() != "https" || !matches!(host, "huggingface.co" | "hf.co") {
+        return Err(anyhow::anyhow!(
+            "Only https://huggingface.co download links are supported"
+        ));
+    }
+    let file_name = url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .filter(|name| name.to_lowercase().ends_with(".gguf"))
+        .ok_or_else(|| anyhow::anyhow!("The link doesn't point at a .gguf file"))?
+        .to_string();
+    let dir = expand_home(
+        settings
+            .dirs
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("No [models] dirs configured"))?,
+    );
+    let dest = dir.join(&file_name);
+    if dest.exists() {
+        return Err(anyhow::anyhow!("{} is already downloaded", file_name));
+    }
+    let expected = match sha256 {
+        Some(hash) => Some(hash.trim().to_lowercase()),
+        None => published_sha256(&url).await,
+    };
+
+    tokio::fs::create_dir_all(&dir).await?;
+    let partial = dir.join(format!("{}.part", file_name));
+    let resume_from = tokio::fs::metadata(&partial)
+        .await
+        .map(|m| m.len())
+        .unwrap_or(0);
+    let mut request = reqwest::Client::new().get(url.clone());
+    if resume_from > 0 {
+        request = request.header(header::RANGE, format!("bytes={}-", resume_from));
+    }
+    let mut response = request.send().await?;
+
+    // 416: the part file already holds everything
+    if response.status() != StatusCode::RANGE_NOT_SATISFIABLE {
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("Download failed ({})", response.status()));
+        }
+        // A server that ignores the range sends the whole file again
+        let resumed = response.status() == StatusCode::PARTIAL_CONTENT;
+        let mut downloaded = if resumed { resume_from } else { 0 };
+        let total = response.content_length().map(|len| len + downloaded);
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(resumed)
+            .truncate(!resumed)
+            .open(&partial)
+            .await?;
+        if resumed {
+            info!("[Models] Resuming {} at {} bytes", file_name, resume_from);
+        }
+
+        on_progress(downloaded, total);
+        let mut reported = Instant::now();
+        while let Some(chunk) = response.chunk().await? {
+            file.write_all(&chunk).await?;
+            downloaded += chunk.len() as u64;
+            if reported.elapsed() >= PROGRESS_INTERVAL {
+                on_progress(downloaded, total);
+                reported = Instant::now();
+            }
+        }
+        file.flush().await?;
+        on_progress(downloaded, total);
+        if total.is_some_and(|total| downloaded < total) {
+            return Err(anyhow::anyhow!(
+                "The connection dropped; download again to resume"
+            ));
+        }
+    }
+
+    match expected {
+        Some(expected) => {
+            let actual = {
+                let partial = partial.clone();
+                tokio::task::spawn_blocking(move || sha256_file(&partial)).await??
+            };
+            if actual != expected {
+                let _ = tokio::fs::remove_file(&partial).await;
+                return Err(anyhow::anyhow!(
+                    "Checksum mismatch for {} (expected {}, got {}); the file was deleted",
+                    file_name,
+                    expected,
+                    actual
+                ));
+            }
+        }
+        None => warn!("[Models] No checksum known for {}; not verified", file_name),
+    }
+    tokio::fs::rename(&partial, &dest).await?;
+    info!("[Models] Downloaded {}", dest.display());
+    Ok(dest)
+}
+
+/// The SHA-256 Hugging Face reports for a file stored in LFS. It is sent as the ETag of
+/// the redirect to the CDN, so the redirect is not followed.
+async fn published_sha256(url: &Url) -> Option<String> {
+    let client = reqwest::Client::builder()
+        .redirect(redirect::Policy::none())
+        .build()
+        .ok()?;
+    let response = client.head(url.clone()).send().await.ok()?;
+    let etag = response
+        .headers()
+        .get("x-linked-etag")
+        .or_else(|| response.headers().get(header::ETAG))?
+        .to_str()
+        .ok()?
+        .trim_start_matches("W/")
+        .trim_matches('"')
+        .to_lowercase();
+    (etag.len() == 64 && etag.chars().all(|c| c.is_ascii_hexdigit())).then_some(etag)
+}
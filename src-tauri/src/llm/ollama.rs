@@ -1,16 +1,35 @@
 use anyhow::Result;
-use futures_util::stream::Stream;
 use futures_util::StreamExt;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use std::pin::Pin;
+use serde_json::Value;
+use std::path::Path;
+use tokio::sync::watch;
+
+use super::template::PromptTemplate;
+use crate::system::secrets;
 
 const OLLAMA_API_BASE: &str = "http://localhost:11434/api";
 
+// Role markers and separators added around each message by chat templates
+const MESSAGE_OVERHEAD_TOKENS: usize = 4;
+
+// Rough cost of one attached image for vision models (e.g. LLaVA's 576 patches plus framing)
+const IMAGE_TOKENS: usize = 600;
+
 #[derive(Debug, Clone)]
 pub struct OllamaClient {
     client: Client,
     model: String,
+    template: PromptTemplate,
+    /// Context window requested from Ollama (`num_ctx`); `None` keeps the model default
+    context_window: Option<u32>,
+    /// Temperature from persona intensity; an explicit `sampling.temperature` wins
+    temperature: Option<f32>,
+    sampling: SamplingConfig,
+    /// How long Ollama keeps the model in memory after a request (e.g. "30m")
+    keep_alive: Option<String>,
+    hardware: HardwareConfig,
 }
 
 #[derive(Serialize, Debug)]
@@ -18,6 +37,155 @@ pub struct ChatRequest {
     pub model: String,
     pub messages: Vec<Message>,
     pub stream: bool,
+    /// `"json"` or a JSON Schema the reply must conform to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<ChatOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep_alive: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ChatOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_ctx: Option<u32>,
+    /// Layers offloaded to the GPU; 0 runs on the CPU
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_gpu: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_batch: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_thread: Option<u32>,
+    #[serde(flatten)]
+    pub sampling: SamplingConfig,
+}
+
+/// Sampler settings sent as Ollama options. `None` leaves the model's own default.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SamplingConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repeat_penalty: Option<f32>,
+    /// Fixed seed for reproducible replies; `None` samples a new one per request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+}
+
+/// Where Ollama runs the model and how it splits the work. These are load options:
+/// changing one makes Ollama load the model again. `None` leaves Ollama's own choice.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HardwareConfig {
+    /// Keep the model off the GPU, e.g. when it runs out of video memory
+    pub cpu_only: bool,
+    /// Layers offloaded to the GPU (`num_gpu`); by default as many as fit
+    pub gpu_layers: Option<u32>,
+    /// Prompt tokens processed per step (`num_batch`)
+    pub batch_size: Option<u32>,
+    /// CPU threads used for generation (`num_thread`)
+    pub threads: Option<u32>,
+}
+
+/// Raw completion request used when a prompt template is forced
+#[derive(Serialize, Debug)]
+struct GenerateRequest {
+    model: String,
+    prompt: String,
+    raw: bool,
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    images: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<Value>,
+    options: GenerateOptions,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<String>,
+}
+
+/// Empty generate request: loads the model without producing anything
+#[derive(Serialize, Debug)]
+struct LoadRequest {
+    model: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<ChatOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+struct GenerateOptions {
+    stop: Vec<String>,
+    #[serde(flatten)]
+    chat: ChatOptions,
+}
+
+#[derive(Deserialize, Debug)]
+struct GenerateResponse {
+    response: String,
+    done_reason: Option<String>,
+    #[serde(flatten)]
+    timings: Timings,
+}
+
+/// Counters Ollama sends with the end of a reply; durations are in nanoseconds
+#[derive(Deserialize, Debug, Default)]
+struct Timings {
+    /// Tokens in the evaluated prompt, as counted by the model's own tokenizer
+    prompt_eval_count: Option<u32>,
+    prompt_eval_duration: Option<u64>,
+    eval_count: Option<u32>,
+    eval_duration: Option<u64>,
+    load_duration: Option<u64>,
+}
+
+impl Timings {
+    fn metrics(&self) -> Option<ReplyMetrics> {
+        let ms = |ns: Option<u64>| ns.unwrap_or(0) / 1_000_000;
+        Some(ReplyMetrics {
+            prompt_tokens: self.prompt_eval_count.unwrap_or(0),
+            generated_tokens: self.eval_count?,
+            load_ms: ms(self.load_duration),
+            prompt_eval_ms: ms(self.prompt_eval_duration),
+            generation_ms: ms(self.eval_duration),
+        })
+    }
+}
+
+/// Token counts and timings of one or more replies
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct ReplyMetrics {
+    /// Prompt tokens evaluated; a prefix reused from the cache isn't counted
+    pub prompt_tokens: u32,
+    pub generated_tokens: u32,
+    /// Spent loading the model into memory first
+    pub load_ms: u64,
+    pub prompt_eval_ms: u64,
+    pub generation_ms: u64,
+}
+
+impl ReplyMetrics {
+    pub fn add(&mut self, other: &ReplyMetrics) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.generated_tokens += other.generated_tokens;
+        self.load_ms += other.load_ms;
+        self.prompt_eval_ms += other.prompt_eval_ms;
+        self.generation_ms += other.generation_ms;
+    }
+
+    /// Generation speed, not counting prompt evaluation
+    pub fn tokens_per_second(&self) -> f32 {
+        if self.generation_ms == 0 {
+            return 0.0;
+        }
+        self.generated_tokens as f32 * 1000.0 / self.generation_ms as f32
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -28,6 +196,17 @@ pub struct Message {
     pub images: Option<Vec<String>>,
 }
 
+#[cfg(test)]
+impl Message {
+    pub fn new(role: &str, content: &str) -> Self {
+        Self {
+            role: role.to_string(),
+            content: content.to_string(),
+            images: None,
+        }
+    }
+}
+
 #[allow(dead_code)]
 #[derive(Deserialize, Debug)]
 pub struct ChatResponse {
@@ -35,6 +214,80 @@ pub struct ChatResponse {
     pub created_at: Option<String>,
     pub message: Option<MessageRes>,
     pub done: Option<bool>,
+    /// "stop" for a natural end, "length" when the token limit cut the reply off
+    pub done_reason: Option<String>,
+    #[serde(flatten)]
+    timings: Timings,
+}
+
+/// One line of a streamed `/chat` (`message`) or `/generate` (`response`) reply
+#[derive(Deserialize, Debug)]
+struct StreamChunk {
+    message: Option<MessageRes>,
+    response: Option<String>,
+    done: Option<bool>,
+    done_reason: Option<String>,
+    #[serde(flatten)]
+    timings: Timings,
+    error: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+struct EmbedRequest<'a> {
+    model: String,
+    input: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct EmbedResponse {
+    embeddings: Vec<Vec<f32>>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TagsResponse {
+    models: Vec<ModelTag>,
+}
+
+/// A model Ollama has locally
+#[derive(Deserialize, Debug, Clone)]
+pub struct ModelTag {
+    pub name: String,
+    /// Bytes on disk
+    #[serde(default)]
+    pub size: u64,
+}
+
+#[derive(Deserialize, Debug)]
+struct PsResponse {
+    models: Vec<RunningModel>,
+}
+
+/// A model Ollama currently holds in memory
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RunningModel {
+    pub name: String,
+    /// Bytes in memory in total
+    #[serde(default)]
+    pub size: u64,
+    /// Of those, bytes in GPU memory; 0 when it runs on the CPU
+    #[serde(default)]
+    pub size_vram: u64,
+}
+
+/// Content of a non-streaming reply
+#[derive(Debug, Clone)]
+pub struct ChatReply {
+    pub content: String,
+    /// Generation stopped at the token limit rather than finishing naturally
+    pub truncated: bool,
+    /// Prompt tokens Ollama evaluated; can undercount when a cached prefix was reused
+    pub prompt_tokens: Option<u32>,
+    /// Token counts and timings; `None` when the reply was cut short
+    pub metrics: Option<ReplyMetrics>,
+    /// Generation was stopped by the user; `content` holds what arrived until then
+    pub cancelled: bool,
 }
 
 #[allow(dead_code)]
@@ -44,12 +297,156 @@ pub struct MessageRes {
     pub content: String,
 }
 
+/// Saved secrets (API keys, passwords) are masked in everything sent to the model
+fn redact(mut messages: Vec<Message>) -> Vec<Message> {
+    for message in &mut messages {
+        message.content = secrets::redact(&message.content);
+    }
+    messages
+}
+
+/// Estimate the tokens `message` takes up in a prompt.
+/// Ollama exposes no tokenize endpoint, so this approximates: ~4 ASCII characters
+/// per token, one token per other character (Hangul, kana, CJK), plus per-message framing.
+pub fn estimate_tokens(message: &Message) -> usize {
+    let (ascii, other) =
+        message.content.chars().fold(
+            (0, 0),
+            |(a, o), c| {
+                if c.is_ascii() {
+                    (a + 1, o)
+                } else {
+                    (a, o + 1)
+                }
+            },
+        );
+    let images = message.images.as_ref().map_or(0, |i| i.len()) * IMAGE_TOKENS;
+    MESSAGE_OVERHEAD_TOKENS + ascii.div_ceil(4) + other + images
+}
+
 impl OllamaClient {
     pub fn new(model_name: &str) -> Self {
         Self {
             client: Client::new(),
             model: model_name.to_string(),
+            template: PromptTemplate::Auto,
+            context_window: None,
+            temperature: None,
+            sampling: SamplingConfig::default(),
+            keep_alive: None,
+            hardware: HardwareConfig::default(),
+        }
+    }
+
+    /// Format prompts client-side with `template` instead of the model's bundled one
+    pub fn with_template(mut self, template: PromptTemplate) -> Self {
+        self.template = template;
+        self
+    }
+
+    /// Ask Ollama for a `tokens`-sized context instead of the model's default.
+    /// Keep it the same for every client: a different `num_ctx` makes Ollama reload the model.
+    pub fn with_context_window(mut self, tokens: u32) -> Self {
+        self.context_window = Some(tokens);
+        self
+    }
+
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    pub fn with_sampling(mut self, sampling: SamplingConfig) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    /// Keep the model loaded for `duration` after each request instead of Ollama's 5 minutes,
+    /// so a pause in the conversation doesn't cost a full reload
+    pub fn with_keep_alive(mut self, duration: &str) -> Self {
+        self.keep_alive = Some(duration.to_string());
+        self
+    }
+
+    pub fn with_hardware(mut self, hardware: HardwareConfig) -> Self {
+        self.hardware = hardware;
+        self
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Estimate the prompt size of `messages` in tokens
+    pub fn count_tokens(&self, messages: &[Message]) -> usize {
+        messages.iter().map(estimate_tokens).sum()
+    }
+
+    /// Names of the locally pulled models (e.g. "qwen2.5-coder:14b")
+    pub async fn list_models(&self) -> Result<Vec<String>> {
+        Ok(self
+            .model_tags()
+            .await?
+            .into_iter()
+            .map(|m| m.name)
+            .collect())
+    }
+
+    /// The locally pulled models with their sizes
+    pub async fn model_tags(&self) -> Result<Vec<ModelTag>> {
+        let tags: TagsResponse = self
+            .client
+            .get(format!("{}/tags", OLLAMA_API_BASE))
+            .send()
+            .await?
+            .json()
+            .await?;
+        Ok(tags.models)
+    }
+
+    /// Models loaded right now, with how much of each sits in GPU memory
+    pub async fn running_models(&self) -> Result<Vec<RunningModel>> {
+        let ps: PsResponse = self
+            .client
+            .get(format!("{}/ps", OLLAMA_API_BASE))
+            .send()
+            .await?
+            .json()
+            .await?;
+        Ok(ps.models)
+    }
+
+    /// Register a local GGUF file with Ollama as model `name`. `digest` is the file's
+    /// SHA-256 in hex; the upload is skipped if Ollama already has that blob.
+    pub async fn create_from_gguf(&self, name: &str, path: &Path, digest: &str) -> Result<()> {
+        let blob = format!("{}/blobs/sha256:{}", OLLAMA_API_BASE, digest);
+        let exists = self.client.head(&blob).send().await?.status().is_success();
+        if !exists {
+            let file = tokio::fs::File::open(path).await?;
+            let res = self.client.post(&blob).body(file).send().await?;
+            if !res.status().is_success() {
+                let error_text = res.text().await?;
+                return Err(anyhow::anyhow!("Ollama API error: {}", error_text));
+            }
+        }
+
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| format!("{}.gguf", name));
+        let mut files = serde_json::Map::new();
+        files.insert(file_name, Value::String(format!("sha256:{}", digest)));
+        let res = self
+            .client
+            .post(format!("{}/create", OLLAMA_API_BASE))
+            .json(&serde_json::json!({ "model": name, "files": files, "stream": false }))
+            .send()
+            .await?;
+        if !res.status().is_success() {
+            let error_text = res.text().await?;
+            return Err(anyhow::anyhow!("Ollama API error: {}", error_text));
         }
+        Ok(())
     }
 
     /// Check if Ollama is running and the model is available
@@ -66,12 +463,88 @@ impl OllamaClient {
         }
     }
 
+    /// Load the model into memory ahead of the first message.
+    /// Uses the same options as chat requests; a different `num_ctx` would load it again.
+    #[tracing::instrument(name = "llm_load", skip_all, fields(model = %self.model))]
+    pub async fn warm_up(&self) -> Result<()> {
+        let request = LoadRequest {
+            model: self.model.clone(),
+            options: Some(self.options()),
+            keep_alive: self.keep_alive.clone(),
+        };
+        let res = self
+            .client
+            .post(format!("{}/generate", OLLAMA_API_BASE))
+            .json(&request)
+            .send()
+            .await?;
+        if !res.status().is_success() {
+            let error_text = res.text().await?;
+            return Err(anyhow::anyhow!("Ollama API error: {}", error_text));
+        }
+        Ok(())
+    }
+
+    /// Embedding vectors for `inputs`, in order. The model must support embeddings
+    /// (e.g. "nomic-embed-text").
+    #[tracing::instrument(
+        name = "llm_embed",
+        skip_all,
+        fields(model = %self.model, inputs = inputs.len())
+    )]
+    pub async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>> {
+        let request = EmbedRequest {
+            model: self.model.clone(),
+            input: inputs,
+            keep_alive: self.keep_alive.clone(),
+        };
+        let res = self
+            .client
+            .post(format!("{}/embed", OLLAMA_API_BASE))
+            .json(&request)
+            .send()
+            .await?;
+        if !res.status().is_success() {
+            let error_text = res.text().await?;
+            return Err(anyhow::anyhow!("Ollama API error: {}", error_text));
+        }
+        let body: EmbedResponse = res.json().await?;
+        Ok(body.embeddings)
+    }
+
     /// Non-streaming chat: send messages, get full response
-    pub async fn chat(&self, messages: Vec<Message>) -> Result<String> {
+    pub async fn chat(&self, messages: Vec<Message>) -> Result<ChatReply> {
+        self.chat_with_format(messages, None).await
+    }
+
+    /// Non-streaming chat whose reply is constrained to `schema` by Ollama's structured outputs
+    pub async fn chat_structured(&self, messages: Vec<Message>, schema: Value) -> Result<String> {
+        let reply = self.chat_with_format(messages, Some(schema)).await?;
+        Ok(reply.content)
+    }
+
+    #[tracing::instrument(
+        name = "llm",
+        skip_all,
+        fields(model = %self.model, structured = format.is_some())
+    )]
+    async fn chat_with_format(
+        &self,
+        messages: Vec<Message>,
+        format: Option<Value>,
+    ) -> Result<ChatReply> {
+        let messages = redact(messages);
+        if let Some(prompt) = self.template.format(&messages) {
+            return self.generate_raw(prompt, &messages, format).await;
+        }
+
         let request = ChatRequest {
             model: self.model.clone(),
             messages,
             stream: false,
+            format,
+            options: Some(self.options()),
+            keep_alive: self.keep_alive.clone(),
         };
 
         let res = self
@@ -87,27 +560,31 @@ impl OllamaClient {
         }
 
         let response: ChatResponse = res.json().await?;
+        let truncated = response.done_reason.as_deref() == Some("length");
         match response.message {
-            Some(msg) => Ok(msg.content),
+            Some(msg) => Ok(ChatReply {
+                content: msg.content,
+                truncated,
+                prompt_tokens: response.timings.prompt_eval_count,
+                metrics: response.timings.metrics(),
+                cancelled: false,
+            }),
             None => Err(anyhow::anyhow!("No message in Ollama response")),
         }
     }
 
-    /// Streaming chat: returns a stream of content chunks
-    #[allow(dead_code)]
-    pub async fn chat_stream(
+    /// Completion on a prompt that is already formatted, bypassing Ollama's templating
+    async fn generate_raw(
         &self,
-        messages: Vec<Message>,
-    ) -> Result<Pin<Box<dyn Stream<Item = Result<String>> + Send>>> {
-        let request = ChatRequest {
-            model: self.model.clone(),
-            messages,
-            stream: true,
-        };
+        prompt: String,
+        messages: &[Message],
+        format: Option<Value>,
+    ) -> Result<ChatReply> {
+        let request = self.generate_request(prompt, messages, format, false);
 
         let res = self
             .client
-            .post(format!("{}/chat", OLLAMA_API_BASE))
+            .post(format!("{}/generate", OLLAMA_API_BASE))
             .json(&request)
             .send()
             .await?;
@@ -117,39 +594,172 @@ impl OllamaClient {
             return Err(anyhow::anyhow!("Ollama API error: {}", error_text));
         }
 
-        let stream = res.bytes_stream();
+        let response: GenerateResponse = res.json().await?;
+        Ok(ChatReply {
+            content: response.response.trim().to_string(),
+            truncated: response.done_reason.as_deref() == Some("length"),
+            prompt_tokens: response.timings.prompt_eval_count,
+            metrics: response.timings.metrics(),
+            cancelled: false,
+        })
+    }
+
+    fn generate_request(
+        &self,
+        prompt: String,
+        messages: &[Message],
+        format: Option<Value>,
+        stream: bool,
+    ) -> GenerateRequest {
+        // Raw prompts carry no per-message images, so attach the most recent ones
+        let images = messages.iter().rev().find_map(|m| m.images.clone());
+        GenerateRequest {
+            model: self.model.clone(),
+            prompt,
+            raw: true,
+            stream,
+            images,
+            format,
+            options: GenerateOptions {
+                stop: self
+                    .template
+                    .stop_tokens()
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect(),
+                chat: self.options(),
+            },
+            keep_alive: self.keep_alive.clone(),
+        }
+    }
+
+    fn options(&self) -> ChatOptions {
+        let mut sampling = self.sampling.clone();
+        sampling.temperature = sampling.temperature.or(self.temperature);
+        let hardware = &self.hardware;
+        ChatOptions {
+            num_ctx: self.context_window,
+            num_gpu: if hardware.cpu_only {
+                Some(0)
+            } else {
+                hardware.gpu_layers
+            },
+            num_batch: hardware.batch_size,
+            num_thread: hardware.threads,
+            sampling,
+        }
+    }
 
-        let mut buffer = String::new();
+    /// Streaming chat: `on_token` receives each piece as it arrives and the complete
+    /// reply is returned once Ollama reports it is done.
+    /// Setting `cancel` to true stops early; dropping the connection makes Ollama stop
+    /// generating, and the reply so far is returned with `cancelled` set.
+    #[tracing::instrument(name = "llm", skip_all, fields(model = %self.model, streaming = true))]
+    pub async fn chat_streaming<F>(
+        &self,
+        messages: Vec<Message>,
+        mut cancel: watch::Receiver<bool>,
+        mut on_token: F,
+    ) -> Result<ChatReply>
+    where
+        F: FnMut(&str),
+    {
+        let mut reply = ChatReply {
+            content: String::new(),
+            truncated: false,
+            prompt_tokens: None,
+            metrics: None,
+            cancelled: false,
+        };
 
-        let parsed_stream = stream.map(move |chunk_result| match chunk_result {
-            Ok(chunk) => {
-                let text = String::from_utf8_lossy(&chunk);
-                buffer.push_str(&text);
+        let messages = redact(messages);
+        let raw_prompt = self.template.format(&messages);
+        let raw = raw_prompt.is_some();
+        let request = match raw_prompt {
+            Some(prompt) => {
+                let request = self.generate_request(prompt, &messages, None, true);
+                self.client
+                    .post(format!("{}/generate", OLLAMA_API_BASE))
+                    .json(&request)
+            }
+            None => {
+                let request = ChatRequest {
+                    model: self.model.clone(),
+                    messages,
+                    stream: true,
+                    format: None,
+                    options: Some(self.options()),
+                    keep_alive: self.keep_alive.clone(),
+                };
+                self.client
+                    .post(format!("{}/chat", OLLAMA_API_BASE))
+                    .json(&request)
+            }
+        };
 
-                let mut output = String::new();
+        // Loading the model can take a while before the first byte, so this is cancellable too
+        let res = tokio::select! {
+            res = request.send() => res?,
+            _ = cancel.wait_for(|stop| *stop) => {
+                reply.cancelled = true;
+                return Ok(reply);
+            }
+        };
 
-                // Extract and process complete lines
-                while let Some(index) = buffer.find('\n') {
-                    let line = buffer[..index].to_string();
-                    buffer.drain(..=index);
+        if !res.status().is_success() {
+            let error_text = res.text().await?;
+            return Err(anyhow::anyhow!("Ollama API error: {}", error_text));
+        }
 
-                    let trimmed = line.trim();
-                    if trimmed.is_empty() {
-                        continue;
-                    }
+        let mut stream = res.bytes_stream();
+        // Bytes, not text: a multi-byte character (e.g. Hangul) can be split across chunks
+        let mut buffer: Vec<u8> = Vec::new();
+        loop {
+            let chunk = tokio::select! {
+                chunk = stream.next() => match chunk {
+                    Some(chunk) => chunk,
+                    None => break,
+                },
+                _ = cancel.wait_for(|stop| *stop) => {
+                    reply.cancelled = true;
+                    break;
+                }
+            };
+            buffer.extend_from_slice(&chunk.map_err(|e| anyhow::anyhow!("Stream error: {}", e))?);
 
-                    if let Ok(response) = serde_json::from_str::<ChatResponse>(trimmed) {
-                        if let Some(msg) = response.message {
-                            output.push_str(&msg.content);
-                        }
-                    }
+            // Each complete line is one JSON object
+            while let Some(index) = buffer.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=index).collect();
+                let line = String::from_utf8_lossy(&line);
+                let trimmed = line.trim();
+                if trimmed.is_empty() {
+                    continue;
                 }
 
-                Ok(output)
+                let piece: StreamChunk = serde_json::from_str(trimmed)?;
+                if let Some(error) = piece.error {
+                    return Err(anyhow::anyhow!("Ollama API error: {}", error));
+                }
+                let text = piece
+                    .message
+                    .map(|m| m.content)
+                    .or(piece.response)
+                    .unwrap_or_default();
+                if !text.is_empty() {
+                    on_token(&text);
+                    reply.content.push_str(&text);
+                }
+                if piece.done == Some(true) {
+                    reply.truncated = piece.done_reason.as_deref() == Some("length");
+                    reply.prompt_tokens = piece.timings.prompt_eval_count;
+                    reply.metrics = piece.timings.metrics();
+                }
             }
-            Err(e) => Err(anyhow::anyhow!("Stream error: {}", e)),
-        });
+        }
 
-        Ok(Box::pin(parsed_stream))
+        if raw {
+            reply.content = reply.content.trim().to_string();
+        }
+        Ok(reply)
     }
 }
@@ -0,0 +1,165 @@
+use serde::{Deserialize, Serialize};
+
+use super::ollama::Message;
+
+/// Chat format used to turn a message list into a raw prompt.
+///
+/// `Auto` leaves formatting to Ollama, which applies the chat template embedded in the
+/// model's GGUF metadata. The explicit variants bypass it for models whose bundled
+/// template is missing or wrong.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptTemplate {
+    #[default]
+    Auto,
+    /// `<|im_start|>` / `<|im_end|>` (Qwen, Yi, many fine-tunes)
+    ChatMl,
+    /// `<|start_header_id|>` / `<|eot_id|>` (Llama 3.x)
+    Llama3,
+    /// `[INST]` / `[/INST]` (Mistral, Mixtral)
+    Mistral,
+    /// `System:` / `User:` / `Assistant:` prefixes for base models
+    Plain,
+}
+
+impl PromptTemplate {
+    /// Render `messages` into a prompt that ends where the assistant's reply should begin.
+    /// Returns `None` for `Auto`, meaning the chat endpoint should be used instead.
+    pub fn format(&self, messages: &[Message]) -> Option<String> {
+        let prompt = match self {
+            PromptTemplate::Auto => return None,
+            PromptTemplate::ChatMl => {
+                let mut prompt = String::new();
+                for m in messages {
+                    prompt.push_str(&format!(
+                        "<|im_start|>{}\n{}<|im_end|>\n",
+                        m.role, m.content
+                    ));
+                }
+                prompt.push_str("<|im_start|>assistant\n");
+                prompt
+            }
+            PromptTemplate::Llama3 => {
+                let mut prompt = String::from("<|begin_of_text|>");
+                for m in messages {
+                    prompt.push_str(&format!(
+                        "<|start_header_id|>{}<|end_header_id|>\n\n{}<|eot_id|>",
+                        m.role, m.content
+                    ));
+                }
+                prompt.push_str("<|start_header_id|>assistant<|end_header_id|>\n\n");
+                prompt
+            }
+            PromptTemplate::Mistral => {
+                // No system role: the system prompt is folded into the first instruction
+                let mut prompt = String::from("<s>");
+                let mut pending_system = String::new();
+                for m in messages {
+                    match m.role.as_str() {
+                        "system" => {
+                            pending_system.push_str(&m.content);
+                            pending_system.push_str("\n\n");
+                        }
+                        "assistant" => prompt.push_str(&format!(" {}</s>", m.content)),
+                        _ => {
+                            prompt.push_str(&format!(
+                                "[INST] {}{} [/INST]",
+                                pending_system, m.content
+                            ));
+                            pending_system.clear();
+                        }
+                    }
+                }
+                prompt
+            }
+            PromptTemplate::Plain => {
+                let mut prompt = String::new();
+                for m in messages {
+                    let speaker = match m.role.as_str() {
+                        "system" => "System",
+                        "assistant" => "Assistant",
+                        _ => "User",
+                    };
+                    prompt.push_str(&format!("{}: {}\n\n", speaker, m.content));
+                }
+                prompt.push_str("Assistant:");
+                prompt
+            }
+        };
+        Some(prompt)
+    }
+
+    /// Sequences that end the assistant's turn in this format
+    pub fn stop_tokens(&self) -> &'static [&'static str] {
+        match self {
+            PromptTemplate::Auto => &[],
+            PromptTemplate::ChatMl => &["<|im_end|>", "<|im_start|>"],
+            PromptTemplate::Llama3 => &["<|eot_id|>", "<|start_header_id|>"],
+            PromptTemplate::Mistral => &["</s>", "[INST]"],
+            PromptTemplate::Plain => &["\nUser:", "\nSystem:"],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conversation() -> Vec<Message> {
+        [
+            ("system", "Be brief."),
+            ("user", "Hi"),
+            ("assistant", "Hello."),
+            ("user", "Bye?"),
+        ]
+        .into_iter()
+        .map(|(role, content)| Message::new(role, content))
+        .collect()
+    }
+
+    #[test]
+    fn auto_leaves_formatting_to_ollama() {
+        assert_eq!(PromptTemplate::Auto.format(&conversation()), None);
+    }
+
+    #[test]
+    fn chatml() {
+        assert_eq!(
+            PromptTemplate::ChatMl.format(&conversation()).unwrap(),
+            "<|im_start|>system\nBe brief.<|im_end|>\n\
+             <|im_start|>user\nHi<|im_end|>\n\
+             <|im_start|>assistant\nHello.<|im_end|>\n\
+             <|im_start|>user\nBye?<|im_end|>\n\
+             <|im_start|>assistant\n"
+        );
+    }
+
+    #[test]
+    fn llama3() {
+        assert_eq!(
+            PromptTemplate::Llama3.format(&conversation()).unwrap(),
+            "<|begin_of_text|>\
+             <|start_header_id|>system<|end_header_id|>\n\nBe brief.<|eot_id|>\
+             <|start_header_id|>user<|end_header_id|>\n\nHi<|eot_id|>\
+             <|start_header_id|>assistant<|end_header_id|>\n\nHello.<|eot_id|>\
+             <|start_header_id|>user<|end_header_id|>\n\nBye?<|eot_id|>\
+             <|start_header_id|>assistant<|end_header_id|>\n\n"
+        );
+    }
+
+    #[test]
+    fn mistral_folds_the_system_prompt_into_the_first_instruction() {
+        assert_eq!(
+            PromptTemplate::Mistral.format(&conversation()).unwrap(),
+            "<s>[INST] Be brief.\n\nHi [/INST] Hello.</s>[INST] Bye? [/INST]"
+        );
+    }
+
+    #[test]
+    fn plain_prompt_for_raw_generate() {
+        assert_eq!(
+            PromptTemplate::Plain.format(&conversation()).unwrap(),
+            "System: Be brief.\n\nUser: Hi\n\nAssistant: Hello.\n\nUser: Bye?\n\nAssistant:"
+        );
+    }
+}
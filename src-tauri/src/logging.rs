@@ -0,0 +1,77 @@
+use anyhow::Result;
+use std::path::Path;
+use std::sync::OnceLock;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, EnvFilter, Registry};
+
+/// Daily log files kept before the oldest is deleted
+const KEEP_FILES: usize = 7;
+
+/// Levels accepted on their own; anything else must be a full filter
+const LEVELS: &[&str] = &["trace", "debug", "info", "warn", "error"];
+
+/// Swaps the filter when `set_log_level` is called
+static FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+/// Flushes the file writer's buffer; lives as long as the app
+static FILE_GUARD: OnceLock<WorkerGuard> = OnceLock::new();
+
+/// Log to stderr and to `amadeus.<date>.log` in `dir`, one file per day. The files also
+/// get a line with the duration of every finished span (LLM calls, tools, memory at
+/// "debug"). An invalid `level` falls back to "info" so a typo still leaves logs.
+pub fn init(dir: &Path, level: &str) -> Result<()> {
+    let filter = parse(level).unwrap_or_else(|_| parse("info").expect("valid level"));
+    let (filter, handle) = reload::Layer::new(filter);
+    let file = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix("amadeus")
+        .filename_suffix("log")
+        .max_log_files(KEEP_FILES)
+        .build(dir)?;
+    let (file, guard) = tracing_appender::non_blocking(file);
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_writer(std::io::stderr))
+        .with(
+            fmt::layer()
+                .with_ansi(false)
+                .with_span_events(FmtSpan::CLOSE)
+                .with_writer(file),
+        )
+        .try_init()?;
+    let _ = FILTER.set(handle);
+    let _ = FILE_GUARD.set(guard);
+    tracing::info!("[Logging] Writing logs to {}", dir.display());
+    Ok(())
+}
+
+/// Change what gets logged from now on
+pub fn set_level(level: &str) -> Result<()> {
+    let filter = parse(level)?;
+    FILTER
+        .get()
+        .ok_or_else(|| anyhow::anyhow!("Logging is not set up"))?
+        .reload(filter)?;
+    Ok(())
+}
+
+/// A bare level applies to Amadeus itself while dependencies stay at "warn";
+/// a filter like "amadeus_lib=debug,sqlx=info" is used as written
+fn parse(level: &str) -> Result<EnvFilter> {
+    let level = level.trim().to_lowercase();
+    if LEVELS.contains(&level.as_str()) {
+        return Ok(EnvFilter::try_new(format!("warn,amadeus_lib={}", level))?);
+    }
+    if !level.contains('=') {
+        return Err(anyhow::anyhow!(
+            "Unknown log level '{}' (use one of {})",
+            level,
+            LEVELS.join(", ")
+        ));
+    }
+    Ok(EnvFilter::try_new(&level)?)
+}
@@ -0,0 +1,58 @@
+use std::time::Duration;
+use tauri::{AppHandle, Manager, UserAttentionType};
+use tauri_plugin_notification::NotificationExt;
+use tracing::warn;
+
+use crate::config::NotificationSettings;
+
+const MAIN_WINDOW: &str = "main";
+
+/// Notification bodies are cut to this many characters
+const MAX_BODY_CHARS: usize = 200;
+
+/// Make `[notifications]` available to `send`
+pub fn init(app: &AppHandle, settings: NotificationSettings) {
+    app.manage(settings);
+}
+
+/// How long a turn has to run before its reply is worth a notification
+pub fn long_task(app: &AppHandle) -> Duration {
+    let secs = app
+        .try_state::<NotificationSettings>()
+        .map_or(u64::MAX, |s| s.long_task_secs);
+    Duration::from_secs(secs)
+}
+
+/// Show a desktop notification, unless the chat window has focus and already shows the
+/// news. On desktop the notification plugin reports no clicks (action callbacks are
+/// mobile-only), so clicking it does not focus Amadeus; instead the window asks for
+/// attention, which flashes its taskbar or dock entry until it is brought back.
+pub fn send(app: &AppHandle, title: &str, body: &str) {
+    if !app
+        .try_state::<NotificationSettings>()
+        .is_some_and(|s| s.enabled)
+    {
+        return;
+    }
+    let window = app.get_webview_window(MAIN_WINDOW);
+    if let Some(window) = &window {
+        if window.is_focused().unwrap_or(false) {
+            return;
+        }
+        let _ = window.request_user_attention(Some(UserAttentionType::Informational));
+    }
+
+    let mut excerpt: String = body.chars().take(MAX_BODY_CHARS).collect();
+    if excerpt.len() < body.len() {
+        excerpt.push('…');
+    }
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title(title)
+        .body(excerpt)
+        .show()
+    {
+        warn!("[Notify] Failed to show notification: {}", e);
+    }
+}
@@ -0,0 +1,222 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::warn;
+
+use crate::config::AppConfig;
+use crate::llm::ollama::OllamaClient;
+
+/// Sent with a `SetupStatus` whenever something a new user has to set up changes
+pub const SETUP_EVENT: &str = "setup-status";
+
+/// Avatar shipped in the frontend's public folder
+const DEFAULT_AVATAR: &str = "model/vrm/KurisuMakise.vrm";
+
+/// VRM files picked in the walkthrough are copied here
+const AVATAR_DIR: &str = "avatars";
+
+/// First-run steps, in the order the walkthrough goes through them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SetupStep {
+    Ollama,
+    Model,
+    Whisper,
+    Avatar,
+    Microphone,
+    Accessibility,
+    Done,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SetupCheck {
+    pub step: SetupStep,
+    pub ready: bool,
+    /// Chat doesn't work without it; the other steps only turn one feature off
+    pub required: bool,
+    /// What is missing and how to fix it; empty once ready
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SetupStatus {
+    /// The first step that isn't ready, or `Done`
+    pub step: SetupStep,
+    pub checks: Vec<SetupCheck>,
+    /// The walkthrough was finished or skipped once; after that it only comes back for
+    /// required steps
+    pub dismissed: bool,
+}
+
+impl SetupCheck {
+    fn new(step: SetupStep, required: bool, missing: Option<String>) -> Self {
+        Self {
+            step,
+            ready: missing.is_none(),
+            required,
+            detail: missing.unwrap_or_default(),
+        }
+    }
+}
+
+/// Look at everything a new user may still be missing
+pub async fn check(app: &AppHandle) -> SetupStatus {
+    let config = AppConfig::load();
+
+    let (ollama, model) = match OllamaClient::new(&config.model).list_models().await {
+        Ok(models) => {
+            let installed = models.iter().any(|m| {
+                m == &config.model || m.strip_suffix(":latest") == Some(config.model.as_str())
+            });
+            let model = (!installed).then(|| {
+                format!(
+                    "The chat model '{}' isn't installed. Download one below, or run `ollama pull {}`.",
+                    config.model, config.model
+                )
+            });
+            (None, model)
+        }
+        Err(_) => (
+            Some(
+                "Ollama isn't running. Install it from https://ollama.com and start it with `ollama serve`."
+                    .to_string(),
+            ),
+            Some("Waiting for Ollama".to_string()),
+        ),
+    };
+
+    let whisper = (!Path::new(&config.stt_model_path).exists()).then(|| {
+        format!(
+            "Voice input needs a Whisper model at {}. Download ggml-base.en.bin from https://huggingface.co/ggerganov/whisper.cpp.",
+            config.stt_model_path
+        )
+    });
+
+    let checks = vec![
+        SetupCheck::new(SetupStep::Ollama, true, ollama),
+        SetupCheck::new(SetupStep::Model, true, model),
+        SetupCheck::new(SetupStep::Whisper, false, whisper),
+        SetupCheck::new(SetupStep::Avatar, false, missing_avatar(app, &config)),
+        SetupCheck::new(SetupStep::Microphone, false, missing_microphone()),
+        SetupCheck::new(SetupStep::Accessibility, false, missing_accessibility()),
+    ];
+    let step = checks
+        .iter()
+        .find(|check| !check.ready)
+        .map_or(SetupStep::Done, |check| check.step);
+    SetupStatus {
+        step,
+        checks,
+        dismissed: config.setup_complete,
+    }
+}
+
+/// Check again and tell the frontend
+pub async fn refresh(app: &AppHandle) -> SetupStatus {
+    let status = check(app).await;
+    let _ = app.emit(SETUP_EVENT, status.clone());
+    status
+}
+
+fn missing_avatar(app: &AppHandle, config: &AppConfig) -> Option<String> {
+    if let Some(model) = &config.avatar.model {
+        return (!Path::new(model).exists())
+            .then(|| format!("The avatar {} is gone. Pick a .vrm file.", model));
+    }
+    // Bundled into release builds; in development it is served from public/
+    let bundled = app
+        .asset_resolver()
+        .get(DEFAULT_AVATAR.to_string())
+        .is_some()
+        || Path::new("../public").join(DEFAULT_AVATAR).exists();
+    (!bundled).then(|| "No avatar model found. Pick a .vrm file to show one.".to_string())
+}
+
+fn missing_microphone() -> Option<String> {
+    use cpal::traits::HostTrait;
+    cpal::default_host()
+        .default_input_device()
+        .is_none()
+        .then(|| "No microphone found. Connect one to talk to Amadeus.".to_string())
+}
+
+/// macOS only lets apps send keystrokes and clicks (the input_control tool) once the
+/// user allows it under Privacy & Security → Accessibility
+#[cfg(target_os = "macos")]
+fn missing_accessibility() -> Option<String> {
+    #[link(name = "ApplicationServices", kind = "framework")]
+    extern "C" {
+        fn AXIsProcessTrusted() -> bool;
+    }
+    // SAFETY: takes no arguments and only reads the process's trust state
+    let trusted = unsafe { AXIsProcessTrusted() };
+    (!trusted).then(|| {
+        "Allow Amadeus under Privacy & Security → Accessibility so it can type and click for you."
+            .to_string()
+    })
+}
+
+#[cfg(not(target_os = "macos"))]
+fn missing_accessibility() -> Option<String> {
+    None
+}
+
+/// Open the system page where the user grants `step`'s permission
+pub fn request_permission(step: SetupStep) -> Result<()> {
+    let url = settings_page(step)
+        .ok_or_else(|| anyhow::anyhow!("Nothing to grant for {:?} on this system", step))?;
+    tauri_plugin_opener::open_url(url, None::<&str>)?;
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn settings_page(step: SetupStep) -> Option<&'static str> {
+    match step {
+        SetupStep::Microphone => {
+            Some("x-apple.systempreferences:com.apple.preference.security?Privacy_Microphone")
+        }
+        SetupStep::Accessibility => {
+            Some("x-apple.systempreferences:com.apple.preference.security?Privacy_Accessibility")
+        }
+        _ => None,
+    }
+}
+
+#[cfg(windows)]
+fn settings_page(step: SetupStep) -> Option<&'static str> {
+    (step == SetupStep::Microphone).then_some("ms-settings:privacy-microphone")
+}
+
+/// Linux desktops don't gate the microphone or input per app
+#[cfg(not(any(target_os = "macos", windows)))]
+fn settings_page(_step: SetupStep) -> Option<&'static str> {
+    None
+}
+
+/// Copy a picked `.vrm` into `avatars/` and return its absolute path
+pub fn import_avatar(path: &str) -> Result<PathBuf> {
+    let source = Path::new(path);
+    if !source
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("vrm"))
+    {
+        return Err(anyhow::anyhow!("{} is not a .vrm file", path));
+    }
+    let name = source
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Invalid path: {}", path))?;
+    std::fs::create_dir_all(AVATAR_DIR)?;
+    let dest = Path::new(AVATAR_DIR).join(name);
+    std::fs::copy(source, &dest)?;
+    Ok(dest.canonicalize()?)
+}
+
+/// Let the webview load the configured avatar file
+pub fn allow_avatar(app: &AppHandle, config: &AppConfig) {
+    if let Some(model) = &config.avatar.model {
+        if let Err(e) = app.asset_protocol_scope().allow_file(model) {
+            warn!("[Setup] Can't serve avatar {}: {}", model, e);
+        }
+    }
+}
@@ -0,0 +1,317 @@
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::future::Future;
+use std::pin::Pin;
+use tokio::process::Command;
+
+use crate::agent::tools::{Tool, ToolResult};
+#[cfg(target_os = "macos")]
+use crate::system::media::osascript;
+
+/// Launch apps and list, focus or close their windows.
+/// macOS goes through System Events (needs Accessibility permission), Windows through
+/// PowerShell, and Linux through `wmctrl`.
+pub struct AppControlTool;
+
+/// A top-level window
+#[derive(Debug, Clone, Serialize)]
+struct Window {
+    app: String,
+    title: String,
+    /// wmctrl window id on Linux, process id on Windows; macOS addresses app + title
+    #[serde(skip)]
+    id: String,
+}
+
+impl Tool for AppControlTool {
+    fn name(&self) -> &str {
+        "app_control"
+    }
+
+    fn description(&self) -> &str {
+        "Launch applications and manage their windows. Actions: 'launch' (name, e.g. 'Visual Studio Code'), 'list_windows', 'focus' (bring to front the first window whose title or app name contains 'window'), 'close' (same matching)."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["launch", "list_windows", "focus", "close"]
+                },
+                "name": { "type": "string", "description": "Application to launch" },
+                "window": { "type": "string", "description": "Part of a window title or app name, case-insensitive (focus, close)" }
+            },
+            "required": ["action"]
+        })
+    }
+
+    fn execute(&self, args: Value) -> Pin<Box<dyn Future<Output = ToolResult> + Send>> {
+        Box::pin(async move {
+            let action = args["action"]
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("Missing action"))?;
+            match action {
+                "launch" => {
+                    let name = args["name"]
+                        .as_str()
+                        .ok_or_else(|| anyhow::anyhow!("Missing name"))?;
+                    launch(name).await?;
+                    Ok(format!("Launched {}", name))
+                }
+                "list_windows" => {
+                    let windows = list_windows().await?;
+                    Ok(serde_json::to_string(&windows)?)
+                }
+                "focus" | "close" => {
+                    let query = args["window"]
+                        .as_str()
+                        .ok_or_else(|| anyhow::anyhow!("Missing window"))?;
+                    let windows = list_windows().await?;
+                    let window = find_window(&windows, query)
+                        .ok_or_else(|| anyhow::anyhow!("No window matches '{}'", query))?;
+                    if action == "focus" {
+                        focus(window).await?;
+                        Ok(format!("Focused {} — {}", window.app, window.title))
+                    } else {
+                        close(window).await?;
+                        Ok(format!("Closed {} — {}", window.app, window.title))
+                    }
+                }
+                _ => Err(anyhow::anyhow!("Unknown action: {}", action)),
+            }
+        })
+    }
+
+    /// Starting programs and closing windows (possibly with unsaved work) need the
+    /// user's OK; looking and switching don't
+    fn requires_approval(&self, args: &Value) -> bool {
+        matches!(args["action"].as_str(), Some("launch" | "close"))
+    }
+}
+
+/// Title matches win over app-name matches, so "terminal" prefers a window titled
+/// "Terminal" over any window of an app with "terminal" in its name
+fn find_window<'a>(windows: &'a [Window], query: &str) -> Option<&'a Window> {
+    let query = query.to_lowercase();
+    windows
+        .iter()
+        .find(|w| w.title.to_lowercase().contains(&query))
+        .or_else(|| {
+            windows
+                .iter()
+                .find(|w| w.app.to_lowercase().contains(&query))
+        })
+}
+
+/// Run `command` and return its trimmed stdout, failing with its stderr
+pub(crate) async fn run(mut command: Command) -> Result<String> {
+    let program = command
+        .as_std()
+        .get_program()
+        .to_string_lossy()
+        .into_owned();
+    #[cfg(windows)]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    let output = command
+        .output()
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to start {}: {}", program, e))?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(target_os = "macos")]
+fn applescript_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(target_os = "macos")]
+async fn launch(name: &str) -> Result<()> {
+    let mut command = Command::new("open");
+    command.arg("-a").arg(name);
+    run(command).await.map(|_| ())
+}
+
+#[cfg(target_os = "macos")]
+async fn list_windows() -> Result<Vec<Window>> {
+    let output = osascript(
+        "set out to \"\"
+        tell application \"System Events\"
+            repeat with p in (processes whose background only is false)
+                set appName to name of p
+                repeat with w in windows of p
+                    set out to out & appName & tab & (name of w) & linefeed
+                end repeat
+            end repeat
+        end tell
+        return out",
+    )
+    .await?;
+    Ok(output
+        .lines()
+        .filter_map(|line| {
+            let (app, title) = line.split_once('\t')?;
+            Some(Window {
+                app: app.to_string(),
+                title: title.to_string(),
+                id: String::new(),
+            })
+        })
+        .collect())
+}
+
+#[cfg(target_os = "macos")]
+async fn focus(window: &Window) -> Result<()> {
+    osascript(&format!(
+        "tell application \"System Events\"
+            tell process {}
+                set frontmost to true
+                perform action \"AXRaise\" of (first window whose name is {})
+            end tell
+        end tell",
+        applescript_string(&window.app),
+        applescript_string(&window.title)
+    ))
+    .await
+    .map(|_| ())
+}
+
+#[cfg(target_os = "macos")]
+async fn close(window: &Window) -> Result<()> {
+    osascript(&format!(
+        "tell application \"System Events\"
+            tell process {}
+                click (first button of (first window whose name is {}) whose subrole is \"AXCloseButton\")
+            end tell
+        end tell",
+        applescript_string(&window.app),
+        applescript_string(&window.title)
+    ))
+    .await
+    .map(|_| ())
+}
+
+#[cfg(windows)]
+pub(crate) async fn powershell(script: &str) -> Result<String> {
+    let mut command = Command::new("powershell");
+    command.args(["-NoProfile", "-NonInteractive", "-Command", script]);
+    run(command).await
+}
+
+#[cfg(windows)]
+async fn launch(name: &str) -> Result<()> {
+    powershell(&format!("Start-Process '{}'", name.replace('\'', "''")))
+        .await
+        .map(|_| ())
+}
+
+/// Windows lists each process's main window only
+#[cfg(windows)]
+async fn list_windows() -> Result<Vec<Window>> {
+    let output = powershell(
+        "[Console]::OutputEncoding = [Text.Encoding]::UTF8; \
+         Get-Process | Where-Object { $_.MainWindowTitle } | \
+         ForEach-Object { \"$($_.Id)`t$($_.ProcessName)`t$($_.MainWindowTitle)\" }",
+    )
+    .await?;
+    Ok(output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '\t');
+            Some(Window {
+                id: parts.next()?.to_string(),
+                app: parts.next()?.to_string(),
+                title: parts.next()?.to_string(),
+            })
+        })
+        .collect())
+}
+
+#[cfg(windows)]
+async fn focus(window: &Window) -> Result<()> {
+    let activated = powershell(&format!(
+        "(New-Object -ComObject WScript.Shell).AppActivate({})",
+        window.id
+    ))
+    .await?;
+    if activated != "True" {
+        return Err(anyhow::anyhow!("Windows refused to focus {}", window.title));
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+async fn close(window: &Window) -> Result<()> {
+    // Asks the window to close, like clicking X, so the app can offer to save
+    powershell(&format!(
+        "(Get-Process -Id {}).CloseMainWindow() | Out-Null",
+        window.id
+    ))
+    .await
+    .map(|_| ())
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+async fn launch(name: &str) -> Result<()> {
+    // Desktop ids ("code", "firefox") go through gtk-launch; anything else is run directly
+    let mut desktop = Command::new("gtk-launch");
+    desktop.arg(name);
+    if run(desktop).await.is_ok() {
+        return Ok(());
+    }
+    std::process::Command::new(name)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Failed to launch {}: {}", name, e))?;
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+async fn list_windows() -> Result<Vec<Window>> {
+    let mut command = Command::new("wmctrl");
+    command.arg("-lx");
+    let output = run(command).await?;
+    // <id> <desktop> <instance.Class> <host> <title...>
+    Ok(output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let id = parts.next()?.to_string();
+            let class = parts.nth(1)?;
+            let _host = parts.next()?;
+            Some(Window {
+                id,
+                app: class.rsplit('.').next().unwrap_or(class).to_string(),
+                title: parts.collect::<Vec<_>>().join(" "),
+            })
+        })
+        .collect())
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+async fn focus(window: &Window) -> Result<()> {
+    let mut command = Command::new("wmctrl");
+    command.args(["-i", "-a", &window.id]);
+    run(command).await.map(|_| ())
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+async fn close(window: &Window) -> Result<()> {
+    let mut command = Command::new("wmctrl");
+    command.args(["-i", "-c", &window.id]);
+    run(command).await.map(|_| ())
+}
@@ -1,19 +1,273 @@
 use chromiumoxide::browser::{Browser, BrowserConfig};
+use chromiumoxide::cdp::browser_protocol::page::CaptureScreenshotFormat;
+use chromiumoxide::element::Element;
+use chromiumoxide::page::ScreenshotParams;
+use chromiumoxide::Page;
 use futures_util::StreamExt;
 use serde_json::{json, Value};
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
 
 use crate::agent::tools::{Tool, ToolResult};
+use crate::config::BrowserSettings;
+use crate::system::screenshot;
 
-// Singleton browser instance logic would be better, but for simplicity we spin up for now
-// Or we can keep a static/shared reference if we want persistence.
-// For Phase 2, let's try to launch a headless browser each time? No, that's slow.
-// We need a shared browser manager. But `Tool` trait is stateless.
-// We'll wrap the browser in a lazy generic or pass it in.
-// For now, let's make it launch on demand, but note performance hit.
+/// How often the idle watcher looks at the browser
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
 
-pub struct BrowserTool;
+struct Session {
+    browser: Browser,
+    /// Drives the CDP connection; it ends when the browser exits or crashes
+    handler: JoinHandle<()>,
+    /// Tab the tool works in, kept across calls so later actions see the same site
+    page: Page,
+    last_used: Instant,
+}
+
+/// One Chromium shared by every browser_automation call. Launched on first use,
+/// relaunched if it crashed or was closed, and shut down after `idle_timeout_secs`.
+pub struct BrowserManager {
+    settings: BrowserSettings,
+    session: Mutex<Option<Session>>,
+}
+
+impl BrowserManager {
+    pub fn new(settings: BrowserSettings) -> Self {
+        Self {
+            settings,
+            session: Mutex::new(None),
+        }
+    }
+
+    /// The working tab, launching the browser first if needed
+    pub async fn page(&self) -> anyhow::Result<Page> {
+        let mut session = self.session.lock().await;
+        if let Some(running) = session.as_mut() {
+            if running.handler.is_finished() {
+                info!("[Browser] Browser exited, relaunching");
+            } else {
+                running.last_used = Instant::now();
+                // The user may have closed the tab
+                if running.page.url().await.is_err() {
+                    running.page = running
+                        .browser
+                        .new_page("about:blank")
+                        .await
+                        .map_err(|e| anyhow::anyhow!("Failed to create page: {}", e))?;
+                }
+                return Ok(running.page.clone());
+            }
+        }
+
+        let launched = self.launch().await?;
+        let page = launched.page.clone();
+        *session = Some(launched);
+        Ok(page)
+    }
+
+    async fn launch(&self) -> anyhow::Result<Session> {
+        // Launch browser (headful unless configured otherwise)
+        let (browser, mut handler) = Browser::launch(Self::browser_config(&self.settings)?)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to launch browser: {}", e))?;
+
+        // Spawn the handler loop
+        let handler = tokio::spawn(async move {
+            while let Some(h) = handler.next().await {
+                if h.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let page = browser
+            .new_page("about:blank")
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to create page: {}", e))?;
+        info!("[Browser] Launched");
+        Ok(Session {
+            browser,
+            handler,
+            page,
+            last_used: Instant::now(),
+        })
+    }
+
+    /// Close the browser if it is running. Returns whether it was.
+    pub async fn close(&self) -> bool {
+        let Some(mut session) = self.session.lock().await.take() else {
+            return false;
+        };
+        if let Err(e) = session.browser.close().await {
+            warn!("[Browser] Close failed: {}", e);
+        }
+        let _ = session.browser.wait().await;
+        session.handler.abort();
+        info!("[Browser] Closed");
+        true
+    }
+
+    /// Close the browser whenever it has sat unused for `idle_timeout_secs`.
+    /// Runs for the life of the app; a timeout of 0 keeps the browser open.
+    pub async fn watch_idle(self: Arc<Self>) {
+        if self.settings.idle_timeout_secs == 0 {
+            return;
+        }
+        let timeout = Duration::from_secs(self.settings.idle_timeout_secs);
+        loop {
+            tokio::time::sleep(IDLE_CHECK_INTERVAL).await;
+            let idle = self
+                .session
+                .lock()
+                .await
+                .as_ref()
+                .is_some_and(|s| s.last_used.elapsed() >= timeout);
+            if idle {
+                self.close().await;
+            }
+        }
+    }
+
+    fn browser_config(settings: &BrowserSettings) -> anyhow::Result<BrowserConfig> {
+        let mut builder = BrowserConfig::builder()
+            .user_data_dir(&settings.user_data_dir)
+            .window_size(settings.window_width, settings.window_height)
+            // Let pages fill the window instead of the fixed default viewport
+            .viewport(None);
+
+        if !settings.headless {
+            builder = builder.with_head(); // Ensure user sees it
+        }
+        if let Some(user_agent) = &settings.user_agent {
+            builder = builder.arg(format!("--user-agent={}", user_agent));
+        }
+
+        builder
+            .build()
+            .map_err(|e| anyhow::anyhow!("Failed to build browser config: {}", e))
+    }
+}
+
+/// Most text `get_text` hands back; longer pages are cut off
+const MAX_TEXT_CHARS: usize = 4000;
+/// Text excerpt included after `navigate` and `click`
+const EXCERPT_CHARS: usize = 300;
+/// Default for `wait_for`
+const DEFAULT_WAIT: Duration = Duration::from_secs(10);
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Time given to a click to start a navigation or re-render before the page is read
+const CLICK_SETTLE: Duration = Duration::from_millis(500);
+
+/// Readability-style extraction: the main content (or the `selector` element) without
+/// scripts, navigation and other page chrome, one block element per line
+const EXTRACT_TEXT_JS: &str = r#"(selector) => {
+    const root = selector
+        ? document.querySelector(selector)
+        : document.querySelector('article, main, [role=main]') || document.body;
+    if (!root) return null;
+    const clone = root.cloneNode(true);
+    clone.querySelectorAll(
+        'script, style, noscript, template, svg, iframe, nav, header, footer, aside, form, [aria-hidden=true]'
+    ).forEach((el) => el.remove());
+    clone.querySelectorAll(
+        'p, div, li, tr, br, h1, h2, h3, h4, h5, h6, pre, blockquote, section, article'
+    ).forEach((el) => el.append('\n'));
+    return clone.textContent
+        .split('\n')
+        .map((line) => line.replace(/\s+/g, ' ').trim())
+        .filter((line) => line.length > 0)
+        .join('\n');
+}"#;
+
+pub struct BrowserTool {
+    browser: Arc<BrowserManager>,
+}
+
+impl BrowserTool {
+    pub fn new(browser: Arc<BrowserManager>) -> Self {
+        Self { browser }
+    }
+}
+
+impl BrowserTool {
+    async fn page_text(page: &Page, selector: Option<&str>) -> anyhow::Result<String> {
+        let call = format!("({})({})", EXTRACT_TEXT_JS, json!(selector));
+        let text: Option<String> = page
+            .evaluate(call)
+            .await
+            .map_err(|e| anyhow::anyhow!("Text extraction failed: {}", e))?
+            .into_value()
+            .map_err(|e| anyhow::anyhow!("Text extraction failed: {}", e))?;
+        match (text, selector) {
+            (Some(text), _) => Ok(text),
+            (None, Some(selector)) => Err(anyhow::anyhow!("No element matches '{}'", selector)),
+            (None, None) => Ok(String::new()),
+        }
+    }
+
+    /// Title, URL and the start of the text, so the model knows where it ended up
+    async fn page_summary(page: &Page) -> anyhow::Result<String> {
+        let title = page.get_title().await.ok().flatten().unwrap_or_default();
+        let url = page.url().await.ok().flatten().unwrap_or_default();
+        let text = Self::page_text(page, None).await?;
+        Ok(format!(
+            "Title: {}\nURL: {}\n{}",
+            title,
+            url,
+            truncate(&text, EXCERPT_CHARS)
+        ))
+    }
+
+    async fn find(page: &Page, selector: &str) -> anyhow::Result<Element> {
+        page.find_element(selector)
+            .await
+            .map_err(|_| anyhow::anyhow!("No element matches '{}'", selector))
+    }
+
+    async fn screenshot(page: &Page, selector: Option<&str>) -> anyhow::Result<String> {
+        let png = match selector {
+            Some(selector) => Self::find(page, selector)
+                .await?
+                .screenshot(CaptureScreenshotFormat::Png)
+                .await
+                .map_err(|e| anyhow::anyhow!("Screenshot failed: {}", e))?,
+            None => page
+                .screenshot(
+                    ScreenshotParams::builder()
+                        .format(CaptureScreenshotFormat::Png)
+                        .build(),
+                )
+                .await
+                .map_err(|e| anyhow::anyhow!("Screenshot failed: {}", e))?,
+        };
+        let image = image::load_from_memory(&png)?;
+        Ok(format!(
+            "{}{}",
+            screenshot::IMAGE_OUTPUT_PREFIX,
+            screenshot::encode_for_llm(&image)?
+        ))
+    }
+}
+
+/// Cut `text` to `max` characters, saying how much was left out
+fn truncate(text: &str, max: usize) -> String {
+    let total = text.chars().count();
+    if total <= max {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(max).collect();
+    format!(
+        "{}\n... ({} of {} chars shown)",
+        kept.trim_end(),
+        max,
+        total
+    )
+}
 
 impl Tool for BrowserTool {
     fn name(&self) -> &str {
@@ -21,7 +275,11 @@ impl Tool for BrowserTool {
     }
 
     fn description(&self) -> &str {
-        "Automate web browser. Actions: 'navigate'. (Note: Starts a new browser instance per call for now)"
+        "Automate a web browser. Actions: 'navigate' (url), 'get_text' (readable page text, \
+         optionally of one selector), 'click' (selector), 'fill' (selector, text), \
+         'wait_for' (selector, optional timeout_ms), 'screenshot_page' (whole view, or one \
+         element by selector). Selectors are CSS. The browser and its tab stay open between \
+         calls."
     }
 
     fn parameters(&self) -> Value {
@@ -30,70 +288,114 @@ impl Tool for BrowserTool {
             "properties": {
                 "action": {
                     "type": "string",
-                    "enum": ["navigate"]
+                    "enum": ["navigate", "get_text", "click", "fill", "wait_for", "screenshot_page"]
                 },
-                "url": { "type": "string", "description": "URL to navigate to" }
+                "url": { "type": "string", "description": "URL to navigate to" },
+                "selector": { "type": "string", "description": "CSS selector of the target element" },
+                "text": { "type": "string", "description": "Text to type for 'fill'" },
+                "timeout_ms": { "type": "integer", "description": "How long 'wait_for' waits (default 10000)" }
             },
-            "required": ["action", "url"]
+            "required": ["action"]
         })
     }
 
     fn execute(&self, args: Value) -> Pin<Box<dyn Future<Output = ToolResult> + Send>> {
+        let browser = self.browser.clone();
         Box::pin(async move {
             let action = args["action"]
                 .as_str()
                 .ok_or_else(|| anyhow::anyhow!("Missing action"))?;
-            let url = args["url"]
-                .as_str()
-                .ok_or_else(|| anyhow::anyhow!("Missing URL"))?;
-
-            if action != "navigate" {
-                return Err(anyhow::anyhow!("Unknown action: {}", action));
-            }
+            let selector = args["selector"].as_str();
+            let require_selector =
+                || selector.ok_or_else(|| anyhow::anyhow!("'{}' needs a selector", action));
 
-            // Launch browser (Headless)
-            let (mut browser, mut handler) = Browser::launch(
-                BrowserConfig::builder()
-                    .with_head() // Ensure user sees it
-                    .build()
-                    .map_err(|e| anyhow::anyhow!("Failed to build browser config: {}", e))?,
-            )
-            .await
-            .map_err(|e| anyhow::anyhow!("Failed to launch browser: {}", e))?;
-
-            // Spawn the handler loop
-            let handle = tokio::spawn(async move {
-                while let Some(h) = handler.next().await {
-                    if h.is_err() {
-                        break;
+            let page = browser.page().await?;
+            match action {
+                "navigate" => {
+                    let url = args["url"]
+                        .as_str()
+                        .ok_or_else(|| anyhow::anyhow!("Missing URL"))?;
+                    page.goto(url)
+                        .await
+                        .map_err(|e| anyhow::anyhow!("Navigation failed: {}", e))?;
+                    Self::page_summary(&page).await
+                }
+                "get_text" => {
+                    let text = Self::page_text(&page, selector).await?;
+                    if text.is_empty() {
+                        return Ok("(no text)".to_string());
                     }
+                    Ok(truncate(&text, MAX_TEXT_CHARS))
                 }
-            });
-
-            let page = browser
-                .new_page(url)
-                .await
-                .map_err(|e| anyhow::anyhow!("Failed to create page: {}", e))?;
-
-            // Wait for load?
-            // content() waits for network idle usually? No, it just dumps DOM.
-            // Let's wait a bit or wait for element?
-            // Simple approach: just get content.
-
-            let content = page
-                .content()
-                .await
-                .map_err(|e| anyhow::anyhow!("Content failed: {}", e))?;
-            let title = page.get_title().await.ok().flatten().unwrap_or_default();
-
-            browser
-                .close()
-                .await
-                .map_err(|e| anyhow::anyhow!("Close failed: {}", e))?;
-            let _ = handle.await;
-
-            let summary = format!("Title: {}\nContent Length: {} chars", title, content.len());
-            Ok(summary)
+                "click" => {
+                    let selector = require_selector()?;
+                    Self::find(&page, selector)
+                        .await?
+                        .click()
+                        .await
+                        .map_err(|e| anyhow::anyhow!("Click failed: {}", e))?;
+                    tokio::time::sleep(CLICK_SETTLE).await;
+                    Ok(format!(
+                        "Clicked {}\n{}",
+                        selector,
+                        Self::page_summary(&page).await?
+                    ))
+                }
+                "fill" => {
+                    let selector = require_selector()?;
+                    let text = args["text"]
+                        .as_str()
+                        .ok_or_else(|| anyhow::anyhow!("'fill' needs text"))?;
+                    let element = Self::find(&page, selector).await?;
+                    // Replace whatever the field held instead of appending to it
+                    element
+                        .call_js_fn("function() { this.value = ''; this.focus(); }", false)
+                        .await
+                        .map_err(|e| anyhow::anyhow!("Fill failed: {}", e))?;
+                    element
+                        .type_str(text)
+                        .await
+                        .map_err(|e| anyhow::anyhow!("Fill failed: {}", e))?;
+                    Ok(format!(
+                        "Filled {} with {} chars",
+                        selector,
+                        text.chars().count()
+                    ))
+                }
+                "wait_for" => {
+                    let selector = require_selector()?;
+                    let timeout = args["timeout_ms"]
+                        .as_u64()
+                        .map(Duration::from_millis)
+                        .unwrap_or(DEFAULT_WAIT);
+                    let started = Instant::now();
+                    while page.find_element(selector).await.is_err() {
+                        if started.elapsed() >= timeout {
+                            return Err(anyhow::anyhow!(
+                                "'{}' did not appear within {} ms",
+                                selector,
+                                timeout.as_millis()
+                            ));
+                        }
+                        tokio::time::sleep(WAIT_POLL_INTERVAL).await;
+                    }
+                    Ok(format!(
+                        "'{}' appeared after {} ms",
+                        selector,
+                        started.elapsed().as_millis()
+                    ))
+                }
+                "screenshot_page" => Self::screenshot(&page, selector).await,
+                _ => Err(anyhow::anyhow!("Unknown action: {}", action)),
+            }
         })
     }
+
+    /// Reading the current page is harmless; navigating and interacting are not
+    fn requires_approval(&self, args: &Value) -> bool {
+        !matches!(
+            args["action"].as_str(),
+            Some("get_text" | "wait_for" | "screenshot_page")
+        )
+    }
 }
@@ -0,0 +1,100 @@
+use arboard::Clipboard;
+use image::{DynamicImage, RgbaImage};
+use serde_json::{json, Value};
+use std::future::Future;
+use std::pin::Pin;
+
+use crate::agent::tools::{Tool, ToolResult};
+use crate::system::screenshot;
+
+/// Longest clipboard text handed to the model
+const MAX_TEXT_CHARS: usize = 10000;
+
+/// Read and write the system clipboard
+pub struct ClipboardTool;
+
+impl ClipboardTool {
+    fn run(action: &str, text: Option<String>) -> Result<String, anyhow::Error> {
+        let mut clipboard =
+            Clipboard::new().map_err(|e| anyhow::anyhow!("Clipboard unavailable: {}", e))?;
+        match action {
+            "get_text" => {
+                let content = clipboard
+                    .get_text()
+                    .map_err(|_| anyhow::anyhow!("The clipboard holds no text"))?;
+                let total = content.chars().count();
+                if total > MAX_TEXT_CHARS {
+                    let kept: String = content.chars().take(MAX_TEXT_CHARS).collect();
+                    Ok(format!("{}...\n\n[Truncated: {} total chars]", kept, total))
+                } else {
+                    Ok(content)
+                }
+            }
+            "set_text" => {
+                let text = text.ok_or_else(|| anyhow::anyhow!("Missing text"))?;
+                clipboard
+                    .set_text(text.as_str())
+                    .map_err(|e| anyhow::anyhow!("Failed to copy: {}", e))?;
+                Ok(format!(
+                    "Copied {} chars to the clipboard",
+                    text.chars().count()
+                ))
+            }
+            "get_image" => {
+                let data = clipboard
+                    .get_image()
+                    .map_err(|_| anyhow::anyhow!("The clipboard holds no image"))?;
+                let image = RgbaImage::from_raw(
+                    data.width as u32,
+                    data.height as u32,
+                    data.bytes.into_owned(),
+                )
+                .ok_or_else(|| anyhow::anyhow!("Clipboard image has an unexpected size"))?;
+                let encoded = screenshot::encode_for_llm(&DynamicImage::ImageRgba8(image))?;
+                Ok(format!("{}{}", screenshot::IMAGE_OUTPUT_PREFIX, encoded))
+            }
+            other => Err(anyhow::anyhow!("Unknown action: {}", other)),
+        }
+    }
+}
+
+impl Tool for ClipboardTool {
+    fn name(&self) -> &str {
+        "clipboard"
+    }
+
+    fn description(&self) -> &str {
+        "Use the system clipboard. Actions: 'get_text' (what the user copied), 'set_text' (copy text for the user), 'get_image' (attach a copied image)."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["get_text", "set_text", "get_image"]
+                },
+                "text": { "type": "string", "description": "Text to copy (set_text)" }
+            },
+            "required": ["action"]
+        })
+    }
+
+    fn execute(&self, args: Value) -> Pin<Box<dyn Future<Output = ToolResult> + Send>> {
+        Box::pin(async move {
+            let action = args["action"]
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("Missing action"))?
+                .to_string();
+            let text = args["text"].as_str().map(str::to_string);
+            // Clipboard access blocks (and on Linux talks to the display server)
+            tokio::task::spawn_blocking(move || ClipboardTool::run(&action, text)).await?
+        })
+    }
+
+    /// Replacing what the user copied needs their OK; reading it doesn't
+    fn requires_approval(&self, args: &Value) -> bool {
+        args["action"].as_str() == Some("set_text")
+    }
+}
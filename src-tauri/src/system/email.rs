@@ -0,0 +1,356 @@
+use anyhow::Result;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use mail_parser::{MessageParser, MimeHeaders};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::future::Future;
+use std::net::TcpStream;
+use std::pin::Pin;
+
+use crate::agent::tools::{Tool, ToolPreview, ToolResult};
+use crate::config::{EmailAccount, EmailSettings};
+use crate::system::secrets;
+
+const DEFAULT_LIST_LIMIT: usize = 10;
+const MAX_LIST_LIMIT: usize = 50;
+/// Longest message body handed to the model
+const MAX_BODY_CHARS: usize = 8000;
+
+type ImapSession = imap::Session<native_tls::TlsStream<TcpStream>>;
+
+/// Read and send mail through the accounts in `[email]`, with passwords from the keychain
+pub struct EmailTool {
+    settings: EmailSettings,
+}
+
+/// An account as shown in settings: whether its password is in the keychain yet
+#[derive(Debug, Clone, Serialize)]
+pub struct EmailAccountStatus {
+    pub name: String,
+    pub address: String,
+    pub has_password: bool,
+}
+
+impl EmailTool {
+    pub fn new(settings: EmailSettings) -> Self {
+        Self { settings }
+    }
+}
+
+/// Keychain key holding an account's password
+pub fn password_key(account: &str) -> String {
+    format!("email:{}", account)
+}
+
+pub fn account_statuses(settings: &EmailSettings) -> Vec<EmailAccountStatus> {
+    settings
+        .accounts
+        .iter()
+        .map(|account| EmailAccountStatus {
+            name: account.name.clone(),
+            address: account.address.clone(),
+            has_password: matches!(secrets::get(&password_key(&account.name)), Ok(Some(_))),
+        })
+        .collect()
+}
+
+fn find_account(settings: &EmailSettings, name: Option<&str>) -> Result<EmailAccount> {
+    let account = match name {
+        Some(name) => settings
+            .accounts
+            .iter()
+            .find(|a| a.name.eq_ignore_ascii_case(name)),
+        None => settings.accounts.first(),
+    };
+    account.cloned().ok_or_else(|| match name {
+        Some(name) => anyhow::anyhow!("No email account named '{}'", name),
+        None => {
+            anyhow::anyhow!("No email accounts configured; add one under [email] in amadeus.toml")
+        }
+    })
+}
+
+fn password(account: &EmailAccount) -> Result<String> {
+    secrets::get(&password_key(&account.name))?.ok_or_else(|| {
+        anyhow::anyhow!(
+            "No password saved for '{}'; the user can add it in settings under Email accounts",
+            account.name
+        )
+    })
+}
+
+fn imap_session(account: &EmailAccount) -> Result<ImapSession> {
+    let password = password(account)?;
+    let tls = native_tls::TlsConnector::new()?;
+    let client = imap::connect(
+        (account.imap_host.as_str(), account.imap_port),
+        &account.imap_host,
+        &tls,
+    )
+    .map_err(|e| anyhow::anyhow!("Can't reach {}: {}", account.imap_host, e))?;
+    client
+        .login(account.username(), &password)
+        .map_err(|(e, _)| anyhow::anyhow!("IMAP login failed: {}", e))
+}
+
+/// Unread messages in the inbox, newest first. The mailbox is opened read-only, so
+/// nothing gets marked as read.
+fn list_unread(account: &EmailAccount, limit: usize) -> Result<String> {
+    let mut session = imap_session(account)?;
+    session.examine("INBOX")?;
+    let mut uids: Vec<u32> = session.uid_search("UNSEEN")?.into_iter().collect();
+    uids.sort_unstable_by(|a, b| b.cmp(a));
+    let total = uids.len();
+    uids.truncate(limit);
+    if uids.is_empty() {
+        let _ = session.logout();
+        return Ok(format!("No unread mail in {}", account.name));
+    }
+
+    let set: Vec<String> = uids.iter().map(u32::to_string).collect();
+    let fetches = session.uid_fetch(set.join(","), "(UID BODY.PEEK[HEADER])")?;
+    let mut lines: Vec<(u32, String)> = fetches
+        .iter()
+        .filter_map(|fetch| {
+            let uid = fetch.uid?;
+            let message = MessageParser::default().parse(fetch.header()?)?;
+            Some((
+                uid,
+                format!(
+                    "[uid {}] {} — {} — {}",
+                    uid,
+                    message.date().map(|d| d.to_rfc3339()).unwrap_or_default(),
+                    sender(&message),
+                    message.subject().unwrap_or("(no subject)")
+                ),
+            ))
+        })
+        .collect();
+    let _ = session.logout();
+    lines.sort_unstable_by(|a, b| b.0.cmp(&a.0));
+
+    let mut output: Vec<String> = lines.into_iter().map(|(_, line)| line).collect();
+    if total > output.len() {
+        output.push(format!(
+            "[{} unread, showing the newest {}]",
+            total,
+            output.len()
+        ));
+    }
+    Ok(output.join("\n"))
+}
+
+/// Headers and text of one message. Fetched with PEEK, so it stays unread.
+fn read_message(account: &EmailAccount, uid: u32) -> Result<String> {
+    let mut session = imap_session(account)?;
+    session.examine("INBOX")?;
+    let fetches = session.uid_fetch(uid.to_string(), "BODY.PEEK[]")?;
+    let _ = session.logout();
+    let raw = fetches
+        .iter()
+        .find_map(|fetch| fetch.body())
+        .ok_or_else(|| anyhow::anyhow!("No message with uid {}", uid))?;
+    let message = MessageParser::default()
+        .parse(raw)
+        .ok_or_else(|| anyhow::anyhow!("Couldn't parse message {}", uid))?;
+
+    let mut output = vec![
+        format!("From: {}", sender(&message)),
+        format!(
+            "Date: {}",
+            message.date().map(|d| d.to_rfc3339()).unwrap_or_default()
+        ),
+        format!("Subject: {}", message.subject().unwrap_or("(no subject)")),
+    ];
+    let attachments: Vec<String> = message
+        .attachments()
+        .filter_map(|part| part.attachment_name().map(str::to_string))
+        .collect();
+    if !attachments.is_empty() {
+        output.push(format!("Attachments: {}", attachments.join(", ")));
+    }
+    let body = message.body_text(0).unwrap_or_default();
+    let total = body.chars().count();
+    output.push(String::new());
+    if total > MAX_BODY_CHARS {
+        let kept: String = body.chars().take(MAX_BODY_CHARS).collect();
+        output.push(format!("{}...\n\n[Truncated: {} total chars]", kept, total));
+    } else {
+        output.push(body.into_owned());
+    }
+    Ok(output.join("\n"))
+}
+
+/// "Alice <alice@example.com>", or just the address
+fn sender(message: &mail_parser::Message) -> String {
+    let Some(addr) = message.from().and_then(|from| from.first()) else {
+        return "(unknown sender)".to_string();
+    };
+    match (addr.name(), addr.address()) {
+        (Some(name), Some(address)) => format!("{} <{}>", name, address),
+        (None, Some(address)) => address.to_string(),
+        (Some(name), None) => name.to_string(),
+        (None, None) => "(unknown sender)".to_string(),
+    }
+}
+
+/// Comma-separated recipients
+fn mailboxes(list: &str) -> Result<Vec<Mailbox>> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|a| !a.is_empty())
+        .map(|a| {
+            a.parse::<Mailbox>()
+                .map_err(|e| anyhow::anyhow!("Invalid address '{}': {}", a, e))
+        })
+        .collect()
+}
+
+async fn send(account: &EmailAccount, args: &Value) -> Result<String> {
+    let to = args["to"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Missing to"))?;
+    let subject = args["subject"].as_str().unwrap_or_default();
+    let body = args["body"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Missing body"))?;
+
+    let from: Mailbox = account
+        .address
+        .parse()
+        .map_err(|e| anyhow::anyhow!("Invalid sender address: {}", e))?;
+    let mut builder = Message::builder().from(from).subject(subject);
+    let recipients = mailboxes(to)?;
+    if recipients.is_empty() {
+        return Err(anyhow::anyhow!("No recipients"));
+    }
+    for mailbox in recipients {
+        builder = builder.to(mailbox);
+    }
+    for mailbox in mailboxes(args["cc"].as_str().unwrap_or_default())? {
+        builder = builder.cc(mailbox);
+    }
+    let message = builder.body(body.to_string())?;
+
+    let password = {
+        let account = account.clone();
+        tokio::task::spawn_blocking(move || password(&account)).await??
+    };
+    let relay = if account.smtp_port == 465 {
+        AsyncSmtpTransport::<Tokio1Executor>::relay(&account.smtp_host)?
+    } else {
+        AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&account.smtp_host)?
+    };
+    let transport = relay
+        .port(account.smtp_port)
+        .credentials(Credentials::new(account.username().to_string(), password))
+        .build();
+    transport
+        .send(message)
+        .await
+        .map_err(|e| anyhow::anyhow!("Sending failed: {}", e))?;
+    Ok(format!(
+        "Sent \"{}\" to {} from {}",
+        subject, to, account.address
+    ))
+}
+
+impl Tool for EmailTool {
+    fn name(&self) -> &str {
+        "email"
+    }
+
+    fn description(&self) -> &str {
+        "Read and send the user's email. Actions: 'list_accounts', 'list_unread' (optional limit), 'read' (uid from list_unread), 'send' (to, subject, body, optional cc; comma-separated addresses). 'account' picks an account by name; the first one is used otherwise."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["list_accounts", "list_unread", "read", "send"]
+                },
+                "account": { "type": "string", "description": "Account name" },
+                "limit": { "type": "integer", "description": "Most messages to list (list_unread, default 10)" },
+                "uid": { "type": "integer", "description": "Message to read" },
+                "to": { "type": "string" },
+                "cc": { "type": "string" },
+                "subject": { "type": "string" },
+                "body": { "type": "string", "description": "Plain-text message (send)" }
+            },
+            "required": ["action"]
+        })
+    }
+
+    fn execute(&self, args: Value) -> Pin<Box<dyn Future<Output = ToolResult> + Send>> {
+        let settings = self.settings.clone();
+        Box::pin(async move {
+            let action = args["action"]
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("Missing action"))?
+                .to_string();
+            if action == "list_accounts" {
+                let statuses =
+                    tokio::task::spawn_blocking(move || account_statuses(&settings)).await?;
+                return Ok(serde_json::to_string(&statuses)?);
+            }
+
+            let account = find_account(&settings, args["account"].as_str())?;
+            match action.as_str() {
+                "list_unread" => {
+                    let limit = args["limit"]
+                        .as_u64()
+                        .map_or(DEFAULT_LIST_LIMIT, |n| n as usize)
+                        .clamp(1, MAX_LIST_LIMIT);
+                    // The imap crate is blocking
+                    tokio::task::spawn_blocking(move || list_unread(&account, limit)).await?
+                }
+                "read" => {
+                    let uid = args["uid"]
+                        .as_u64()
+                        .ok_or_else(|| anyhow::anyhow!("Missing uid"))?
+                        as u32;
+                    tokio::task::spawn_blocking(move || read_message(&account, uid)).await?
+                }
+                "send" => send(&account, &args).await,
+                other => Err(anyhow::anyhow!("Unknown action: {}", other)),
+            }
+        })
+    }
+
+    /// The message as it will go out
+    fn preview(&self, args: Value) -> Pin<Box<dyn Future<Output = ToolPreview> + Send>> {
+        let from = find_account(&self.settings, args["account"].as_str())
+            .map(|a| a.address)
+            .unwrap_or_default();
+        Box::pin(async move {
+            if args["action"].as_str() != Some("send") {
+                return Ok(None);
+            }
+            let field = |key: &str| args[key].as_str().unwrap_or_default().to_string();
+            let mut preview = format!("From: {}\nTo: {}\n", from, field("to"));
+            if !field("cc").is_empty() {
+                preview.push_str(&format!("Cc: {}\n", field("cc")));
+            }
+            preview.push_str(&format!(
+                "Subject: {}\n\n{}",
+                field("subject"),
+                field("body")
+            ));
+            Ok(Some(preview))
+        })
+    }
+
+    /// Mail goes out in the user's name, so every send is approved on its own
+    fn requires_approval(&self, args: &Value) -> bool {
+        args["action"].as_str() == Some("send")
+    }
+
+    fn can_always_allow(&self) -> bool {
+        false
+    }
+}
@@ -1,55 +1,235 @@
 use serde_json::{json, Value};
+use similar::{ChangeTag, TextDiff};
 use std::future::Future;
 use std::path::{Path, PathBuf};
 use std::pin::Pin;
+use std::sync::Arc;
 use tokio::fs;
 
-use crate::agent::tools::{Tool, ToolResult};
+use crate::agent::tools::{Tool, ToolPreview, ToolResult};
+use crate::system::patch;
+use crate::system::sandbox::Sandbox;
 
-pub struct FileSystemTool;
+/// Most files `search` lists for a glob alone
+const MAX_SEARCH_FILES: usize = 200;
+/// Most matching lines `search` returns for a query
+const MAX_SEARCH_MATCHES: usize = 100;
+/// Matching lines longer than this are cut
+const MAX_MATCH_LINE_CHARS: usize = 200;
+
+pub struct FileSystemTool {
+    sandbox: Arc<Sandbox>,
+}
 
 impl FileSystemTool {
-    /// Validate that the given path is within the allowed workspace.
-    /// Prevents LLM from accessing sensitive system files like ~/.ssh, /etc, etc.
-    fn validate_path(path_str: &str) -> Result<PathBuf, anyhow::Error> {
-        let workspace_root = std::env::current_dir()?;
+    pub fn new(sandbox: Arc<Sandbox>) -> Self {
+        Self { sandbox }
+    }
+
+    /// (lines added, lines removed) between two versions of a file
+    fn line_changes(old: &str, new: &str) -> (usize, usize) {
+        let diff = TextDiff::from_lines(old, new);
+        let mut added = 0;
+        let mut removed = 0;
+        for change in diff.iter_all_changes() {
+            match change.tag() {
+                ChangeTag::Insert => added += 1,
+                ChangeTag::Delete => removed += 1,
+                ChangeTag::Equal => {}
+            }
+        }
+        (added, removed)
+    }
+
+    /// Unified diff for a change to an existing file, or the full content for a new file
+    fn describe_write(action: &str, path: &Path, old: Option<&str>, new: &str) -> String {
+        match old {
+            Some(old) => {
+                let (added, removed) = FileSystemTool::line_changes(old, new);
+                let name = path.display().to_string();
+                let diff = TextDiff::from_lines(old, new)
+                    .unified_diff()
+                    .context_radius(3)
+                    .header(&name, &name)
+                    .to_string();
+                format!(
+                    "{} {} (+{} -{} lines)\n```diff\n{}```",
+                    action, name, added, removed, diff
+                )
+            }
+            None => format!(
+                "New file {} ({} lines)\n```\n{}\n```",
+                path.display(),
+                new.lines().count(),
+                new
+            ),
+        }
+    }
+
+    fn line_arg(args: &Value, key: &str) -> Result<usize, anyhow::Error> {
+        args[key]
+            .as_u64()
+            .map(|n| n as usize)
+            .ok_or_else(|| anyhow::anyhow!("Missing {}", key))
+    }
 
-        let requested = if Path::new(path_str).is_absolute() {
-            PathBuf::from(path_str)
+    /// Replace lines `start..=end` (1-indexed) with `replacement`,
+    /// or insert `replacement` before line `start` when `end` is `None`.
+    fn apply_line_edit(
+        original: &str,
+        start: usize,
+        end: Option<usize>,
+        replacement: &str,
+    ) -> Result<String, anyhow::Error> {
+        let newline = if original.contains("\r\n") {
+            "\r\n"
         } else {
-            workspace_root.join(path_str)
+            "\n"
         };
+        let mut lines: Vec<&str> = original.lines().collect();
+        let total = lines.len();
 
-        // Canonicalize to resolve "..", symlinks, etc.
-        // For new files (write_file), parent must exist and be in workspace
-        let canonical = if requested.exists() {
-            requested.canonicalize()?
-        } else {
-            // For files that don't exist yet, validate the parent directory
-            let parent = requested
-                .parent()
-                .ok_or_else(|| anyhow::anyhow!("Invalid path: no parent directory"))?;
-            if !parent.exists() {
-                return Err(anyhow::anyhow!(
-                    "Parent directory does not exist: {}",
-                    parent.display()
-                ));
+        let range = match end {
+            Some(end) => {
+                if start == 0 || start > end || end > total {
+                    return Err(anyhow::anyhow!(
+                        "Invalid line range {}-{} (file has {} lines)",
+                        start,
+                        end,
+                        total
+                    ));
+                }
+                start - 1..end
+            }
+            None => {
+                if start == 0 || start > total + 1 {
+                    return Err(anyhow::anyhow!(
+                        "Invalid line {} (file has {} lines; use {} to append)",
+                        start,
+                        total,
+                        total + 1
+                    ));
+                }
+                start - 1..start - 1
             }
-            let canonical_parent = parent.canonicalize()?;
-            canonical_parent.join(requested.file_name().unwrap_or_default())
         };
+        lines.splice(range, replacement.lines());
 
-        let canonical_root = workspace_root.canonicalize()?;
+        let mut result = lines.join(newline);
+        if (original.is_empty() || original.ends_with('\n')) && !result.is_empty() {
+            result.push_str(newline);
+        }
+        Ok(result)
+    }
 
-        if !canonical.starts_with(&canonical_root) {
-            return Err(anyhow::anyhow!(
-                "Access denied: path '{}' is outside the workspace ({})",
-                path_str,
-                canonical_root.display()
-            ));
+    /// The file content an editing action would produce (None for read-only actions)
+    fn planned_content(
+        action: &str,
+        args: &Value,
+        old: Option<&str>,
+    ) -> Result<Option<String>, anyhow::Error> {
+        let content = args["content"].as_str().unwrap_or("");
+        match action {
+            "write_file" => Ok(Some(content.to_string())),
+            "append_file" => {
+                let mut appended = old.unwrap_or("").to_string();
+                if !appended.is_empty() && !appended.ends_with('\n') {
+                    appended.push('\n');
+                }
+                appended.push_str(content);
+                Ok(Some(appended))
+            }
+            "apply_patch" => {
+                let old = old.ok_or_else(|| anyhow::anyhow!("File does not exist"))?;
+                Ok(Some(patch::apply(old, content)?))
+            }
+            "edit_file" | "insert_at_line" => {
+                let old = old.ok_or_else(|| anyhow::anyhow!("File does not exist"))?;
+                let edited = if action == "edit_file" {
+                    FileSystemTool::apply_line_edit(
+                        old,
+                        FileSystemTool::line_arg(args, "start_line")?,
+                        Some(FileSystemTool::line_arg(args, "end_line")?),
+                        content,
+                    )?
+                } else {
+                    FileSystemTool::apply_line_edit(
+                        old,
+                        FileSystemTool::line_arg(args, "line")?,
+                        None,
+                        content,
+                    )?
+                };
+                Ok(Some(edited))
+            }
+            _ => Ok(None),
         }
+    }
+}
+
+impl FileSystemTool {
+    /// Files under `root` whose relative path matches `glob`, and with `query` the lines
+    /// containing it (case-insensitive). Gitignored and hidden files are skipped.
+    fn search(
+        root: &Path,
+        glob: Option<&str>,
+        query: Option<&str>,
+    ) -> Result<String, anyhow::Error> {
+        let matcher = glob
+            .map(|g| globset::Glob::new(g).map(|g| g.compile_matcher()))
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("Invalid glob: {}", e))?;
+        let query = query.map(str::to_lowercase);
 
-        Ok(canonical)
+        let mut files = Vec::new();
+        let mut matches = Vec::new();
+        let mut truncated = false;
+        for entry in ignore::WalkBuilder::new(root).build().flatten() {
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                continue;
+            }
+            let path = entry.path();
+            let relative = path.strip_prefix(root).unwrap_or(path);
+            if matcher.as_ref().is_some_and(|m| !m.is_match(relative)) {
+                continue;
+            }
+            let Some(query) = &query else {
+                if files.len() == MAX_SEARCH_FILES {
+                    truncated = true;
+                    break;
+                }
+                files.push(relative.display().to_string());
+                continue;
+            };
+            // Binary and non-UTF-8 files can't contain the query as text
+            let Ok(content) = std::fs::read_to_string(path) else {
+                continue;
+            };
+            for (i, line) in content.lines().enumerate() {
+                if !line.to_lowercase().contains(query.as_str()) {
+                    continue;
+                }
+                if matches.len() == MAX_SEARCH_MATCHES {
+                    truncated = true;
+                    break;
+                }
+                let line: String = line.trim().chars().take(MAX_MATCH_LINE_CHARS).collect();
+                matches.push(format!("{}:{}: {}", relative.display(), i + 1, line));
+            }
+            if truncated {
+                break;
+            }
+        }
+
+        let results = if query.is_some() { matches } else { files };
+        if results.is_empty() {
+            return Ok("No matches".to_string());
+        }
+        let mut output = results.join("\n");
+        if truncated {
+            output.push_str(&format!("\n[Truncated: first {} results]", results.len()));
+        }
+        Ok(output)
     }
 }
 
@@ -59,7 +239,7 @@ impl Tool for FileSystemTool {
     }
 
     fn description(&self) -> &str {
-        "Access file system (sandboxed to project directory). Actions: 'read_file', 'write_file', 'list_dir'."
+        "Access file system (sandboxed to the workspace folders; relative paths start in the first one). Actions: 'read_file', 'write_file', 'append_file', 'list_dir', 'edit_file' (replace lines start_line..end_line, 1-indexed inclusive), 'insert_at_line' (insert before line), 'apply_patch' (unified diff for one file in content), 'delete_file', 'move_file' (to destination), 'search' (files under path matching glob, and with query the lines containing it). Prefer edit_file or apply_patch over rewriting whole files."
     }
 
     fn parameters(&self) -> Value {
@@ -68,16 +248,23 @@ impl Tool for FileSystemTool {
             "properties": {
                 "action": {
                     "type": "string",
-                    "enum": ["read_file", "write_file", "list_dir"]
+                    "enum": ["read_file", "write_file", "append_file", "list_dir", "edit_file", "insert_at_line", "apply_patch", "delete_file", "move_file", "search"]
                 },
-                "path": { "type": "string", "description": "File or directory path (relative to project root)" },
-                "content": { "type": "string", "description": "Content to write (for write_file)" }
+                "path": { "type": "string", "description": "File or directory path (relative to the workspace)" },
+                "content": { "type": "string", "description": "Content to write (write_file, append_file), replacement lines (edit_file), lines to insert (insert_at_line) or a unified diff (apply_patch)" },
+                "destination": { "type": "string", "description": "New path (move_file)" },
+                "glob": { "type": "string", "description": "File pattern relative to path, e.g. '**/*.rs' (search)" },
+                "query": { "type": "string", "description": "Text to find, case-insensitive (search)" },
+                "start_line": { "type": "integer", "description": "First line to replace, 1-indexed (edit_file)" },
+                "end_line": { "type": "integer", "description": "Last line to replace, inclusive (edit_file)" },
+                "line": { "type": "integer", "description": "Insert before this line, 1-indexed; use line count + 1 to append (insert_at_line)" }
             },
             "required": ["action", "path"]
         })
     }
 
     fn execute(&self, args: Value) -> Pin<Box<dyn Future<Output = ToolResult> + Send>> {
+        let sandbox = self.sandbox.clone();
         Box::pin(async move {
             let action = args["action"]
                 .as_str()
@@ -87,7 +274,7 @@ impl Tool for FileSystemTool {
                 .ok_or_else(|| anyhow::anyhow!("Missing path"))?;
 
             // Sandbox validation
-            let safe_path = FileSystemTool::validate_path(path_str)?;
+            let safe_path = sandbox.resolve(path_str)?;
 
             match action {
                 "read_file" => {
@@ -105,8 +292,83 @@ impl Tool for FileSystemTool {
                 }
                 "write_file" => {
                     let content = args["content"].as_str().unwrap_or("");
+                    let old = fs::read_to_string(&safe_path).await.ok();
                     fs::write(&safe_path, content).await?;
-                    Ok(format!("Successfully wrote to {}", safe_path.display()))
+                    match old {
+                        Some(old) => {
+                            let (added, removed) = FileSystemTool::line_changes(&old, content);
+                            Ok(format!(
+                                "Successfully wrote to {} (+{} -{} lines)",
+                                safe_path.display(),
+                                added,
+                                removed
+                            ))
+                        }
+                        None => Ok(format!(
+                            "Successfully created {} ({} lines)",
+                            safe_path.display(),
+                            content.lines().count()
+                        )),
+                    }
+                }
+                "append_file" => {
+                    let old = fs::read_to_string(&safe_path).await.ok();
+                    let new = FileSystemTool::planned_content(action, &args, old.as_deref())?
+                        .unwrap_or_default();
+                    fs::write(&safe_path, &new).await?;
+                    Ok(format!(
+                        "Appended {} lines to {}",
+                        args["content"].as_str().unwrap_or("").lines().count(),
+                        safe_path.display()
+                    ))
+                }
+                "edit_file" | "insert_at_line" | "apply_patch" => {
+                    let old = fs::read_to_string(&safe_path).await?;
+                    let new = FileSystemTool::planned_content(action, &args, Some(&old))?
+                        .unwrap_or_default();
+                    fs::write(&safe_path, &new).await?;
+                    Ok(FileSystemTool::describe_write(
+                        action,
+                        &safe_path,
+                        Some(&old),
+                        &new,
+                    ))
+                }
+                "delete_file" => {
+                    if safe_path.is_dir() {
+                        return Err(anyhow::anyhow!(
+                            "{} is a directory; delete_file only removes files",
+                            safe_path.display()
+                        ));
+                    }
+                    fs::remove_file(&safe_path).await?;
+                    Ok(format!("Deleted {}", safe_path.display()))
+                }
+                "move_file" => {
+                    let destination = args["destination"]
+                        .as_str()
+                        .ok_or_else(|| anyhow::anyhow!("Missing destination"))?;
+                    let safe_destination = sandbox.resolve(destination)?;
+                    if safe_destination.exists() {
+                        return Err(anyhow::anyhow!(
+                            "{} already exists",
+                            safe_destination.display()
+                        ));
+                    }
+                    fs::rename(&safe_path, &safe_destination).await?;
+                    Ok(format!(
+                        "Moved {} to {}",
+                        safe_path.display(),
+                        safe_destination.display()
+                    ))
+                }
+                "search" => {
+                    let glob = args["glob"].as_str().map(str::to_string);
+                    let query = args["query"].as_str().map(str::to_string);
+                    tokio::task::spawn_blocking(move || {
+                        FileSystemTool::search(&safe_path, glob.as_deref(), query.as_deref())
+                    })
+                    .await?
                 }
                 "list_dir" => {
                     let mut entries = fs::read_dir(&safe_path).await?;
@@ -126,4 +388,59 @@ impl Tool for FileSystemTool {
             }
         })
     }
+
+    fn preview(&self, args: Value) -> Pin<Box<dyn Future<Output = ToolPreview> + Send>> {
+        let sandbox = self.sandbox.clone();
+        Box::pin(async move {
+            let action = args["action"].as_str().unwrap_or("");
+            if !matches!(
+                action,
+                "write_file"
+                    | "append_file"
+                    | "edit_file"
+                    | "insert_at_line"
+                    | "apply_patch"
+                    | "delete_file"
+                    | "move_file"
+            ) {
+                return Ok(None);
+            }
+            let path_str = args["path"]
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("Missing path"))?;
+            let safe_path = sandbox.resolve(path_str)?;
+            match action {
+                "delete_file" => {
+                    return Ok(Some(format!("Delete {}", safe_path.display())));
+                }
+                "move_file" => {
+                    let destination = args["destination"].as_str().unwrap_or("");
+                    return Ok(Some(format!(
+                        "Move {} to {}",
+                        safe_path.display(),
+                        sandbox.resolve(destination)?.display()
+                    )));
+                }
+                _ => {}
+            }
+            let old = fs::read_to_string(&safe_path).await.ok();
+
+            let preview =
+                FileSystemTool::planned_content(action, &args, old.as_deref())?.map(|new| {
+                    FileSystemTool::describe_write(action, &safe_path, old.as_deref(), &new)
+                });
+            Ok(preview)
+        })
+    }
+
+    fn is_cacheable(&self, args: &Value) -> bool {
+        matches!(
+            args["action"].as_str(),
+            Some("read_file" | "list_dir" | "search")
+        )
+    }
+
+    fn requires_approval(&self, args: &Value) -> bool {
+        !self.is_cacheable(args)
+    }
 }
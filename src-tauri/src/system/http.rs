@@ -0,0 +1,233 @@
+use reqwest::{redirect, Client, Method, Url};
+use serde_json::{json, Value};
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+use crate::agent::tools::{Tool, ToolResult};
+use crate::config::HttpSettings;
+
+const MAX_REDIRECTS: usize = 5;
+
+/// Plain HTTP calls to local services and public APIs (weather, home automation, ...)
+/// on the hosts listed in `[http] allowed_domains`
+pub struct HttpRequestTool {
+    settings: HttpSettings,
+    client: Client,
+}
+
+impl HttpRequestTool {
+    pub fn new(settings: HttpSettings) -> Self {
+        // Redirects are checked too, so an allowed host can't bounce a request elsewhere
+        let allowed = settings.allowed_domains.clone();
+        let client = Client::builder()
+            .timeout(Duration::from_secs(settings.timeout_secs))
+            .redirect(redirect::Policy::custom(move |attempt| {
+                if attempt.previous().len() >= MAX_REDIRECTS {
+                    attempt.error("too many redirects")
+                } else if host_allowed(&allowed, attempt.url()) {
+                    attempt.follow()
+                } else {
+                    let host = attempt.url().host_str().unwrap_or_default().to_string();
+                    attempt.error(format!("redirect to {} is not allowed", host))
+                }
+            }))
+            .build()
+            .unwrap_or_default();
+        Self { settings, client }
+    }
+}
+
+/// Whether `url` points at an allowed host or one of its subdomains
+fn host_allowed(allowed: &[String], url: &Url) -> bool {
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    let host = host.trim_end_matches('.').to_lowercase();
+    allowed.iter().any(|domain| {
+        let domain = domain.trim().trim_start_matches("*.").to_lowercase();
+        host == domain || host.ends_with(&format!(".{}", domain))
+    })
+}
+
+/// Bodies worth showing the model; images, archives and the like are only summarized
+fn is_textual(content_type: &str) -> bool {
+    content_type.is_empty()
+        || [
+            "text/",
+            "json",
+            "xml",
+            "javascript",
+            "x-www-form-urlencoded",
+        ]
+        .iter()
+        .any(|kind| content_type.contains(kind))
+}
+
+impl Tool for HttpRequestTool {
+    fn name(&self) -> &str {
+        "http_request"
+    }
+
+    fn description(&self) -> &str {
+        "Make an HTTP request to a REST API or local service (only hosts the user has allowed). Returns the status, content type and body (truncated if long). Requests other than GET/HEAD need the user's approval."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "method": {
+                    "type": "string",
+                    "enum": ["GET", "HEAD", "POST", "PUT", "PATCH", "DELETE"],
+                    "description": "Default GET"
+                },
+                "url": { "type": "string" },
+                "headers": {
+                    "type": "object",
+                    "additionalProperties": { "type": "string" }
+                },
+                "body": {
+                    "description": "A JSON object/array (sent as application/json) or a raw string"
+                }
+            },
+            "required": ["url"]
+        })
+    }
+
+    fn execute(&self, args: Value) -> Pin<Box<dyn Future<Output = ToolResult> + Send>> {
+        let settings = self.settings.clone();
+        let client = self.client.clone();
+        Box::pin(async move {
+            let method = args["method"].as_str().unwrap_or("GET").to_uppercase();
+            let method = Method::from_bytes(method.as_bytes())
+                .map_err(|_| anyhow::anyhow!("Invalid method: {}", method))?;
+            let url = args["url"]
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("Missing url"))?;
+            let url = Url::parse(url).map_err(|e| anyhow::anyhow!("Invalid url: {}", e))?;
+            if !matches!(url.scheme(), "http" | "https") {
+                return Err(anyhow::anyhow!("Only http and https URLs are supported"));
+            }
+            if !host_allowed(&settings.allowed_domains, &url) {
+                return Err(anyhow::anyhow!(
+                    "{} is not an allowed host ({}); the user can add it to [http] allowed_domains in amadeus.toml",
+                    url.host_str().unwrap_or_default(),
+                    settings.allowed_domains.join(", ")
+                ));
+            }
+
+            let mut request = client.request(method, url);
+            if let Some(headers) = args["headers"].as_object() {
+                for (name, value) in headers {
+                    let value = value
+                        .as_str()
+                        .map(str::to_string)
+                        .unwrap_or_else(|| value.to_string());
+                    request = request.header(name.as_str(), value);
+                }
+            }
+            request = match &args["body"] {
+                Value::Null => request,
+                Value::String(text) => request.body(text.clone()),
+                body => request.json(body),
+            };
+
+            let mut response = request
+                .send()
+                .await
+                .map_err(|e| anyhow::anyhow!("Request failed: {}", e))?;
+            let status = response.status();
+            let content_type = response
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default()
+                .to_string();
+
+            // Read only up to the cap so a huge download doesn't have to finish first
+            let mut body = Vec::new();
+            let mut truncated = false;
+            while let Some(chunk) = response.chunk().await? {
+                let room = settings.max_response_bytes.saturating_sub(body.len());
+                if chunk.len() > room {
+                    body.extend_from_slice(&chunk[..room]);
+                    truncated = true;
+                    break;
+                }
+                body.extend_from_slice(&chunk);
+            }
+
+            let mut output = format!("HTTP {}", status);
+            if !content_type.is_empty() {
+                output.push_str(&format!("\nContent-Type: {}", content_type));
+            }
+            if body.is_empty() {
+                return Ok(output);
+            }
+            if !is_textual(&content_type) {
+                output.push_str(&format!(
+                    "\n\n[{}{} bytes of binary content not shown]",
+                    if truncated { "over " } else { "" },
+                    body.len()
+                ));
+                return Ok(output);
+            }
+            output.push_str("\n\n");
+            output.push_str(&String::from_utf8_lossy(&body));
+            if truncated {
+                output.push_str(&format!(
+                    "\n\n[Truncated at {} bytes]",
+                    settings.max_response_bytes
+                ));
+            }
+            Ok(output)
+        })
+    }
+
+    /// Reading is harmless; anything that can change state on the other end (turning
+    /// on lights, posting data) needs the user's OK
+    fn requires_approval(&self, args: &Value) -> bool {
+        !matches!(
+            args["method"]
+                .as_str()
+                .unwrap_or("GET")
+                .to_uppercase()
+                .as_str(),
+            "GET" | "HEAD"
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowed(url: &str) -> bool {
+        let domains = ["example.com".to_string(), "*.api.test".to_string()];
+        host_allowed(&domains, &Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn listed_domain_and_its_subdomains() {
+        assert!(allowed("https://example.com/weather"));
+        assert!(allowed("https://a.example.com"));
+        assert!(allowed("http://EXAMPLE.com./path"));
+        assert!(allowed("https://v1.api.test/status"));
+        assert!(allowed("https://api.test"));
+    }
+
+    #[test]
+    fn lookalike_hosts_are_rejected() {
+        assert!(!allowed("https://evil-example.com"));
+        assert!(!allowed("https://example.com.evil.net"));
+        assert!(!allowed("https://notexample.com"));
+        assert!(!allowed("https://example.com@evil.net"));
+    }
+
+    #[test]
+    fn urls_without_a_host_are_rejected() {
+        assert!(!allowed("file:///etc/passwd"));
+        assert!(!allowed("data:text/plain,example.com"));
+    }
+}
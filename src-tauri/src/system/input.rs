@@ -102,4 +102,8 @@ impl Tool for InputTool {
             }
         })
     }
+
+    fn requires_approval(&self, _args: &Value) -> bool {
+        true
+    }
 }
@@ -0,0 +1,391 @@
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::future::Future;
+use std::pin::Pin;
+
+use crate::agent::tools::{Tool, ToolResult};
+#[cfg(windows)]
+use crate::system::apps::powershell;
+#[cfg(not(any(target_os = "macos", windows)))]
+use crate::system::apps::run;
+#[cfg(not(any(target_os = "macos", windows)))]
+use tokio::process::Command;
+
+/// Players that can be scripted over AppleScript, in the order they are checked
+#[cfg(target_os = "macos")]
+const PLAYERS: &[&str] = &["Spotify", "Music"];
+
+/// Percentage points per volume_up / volume_down, about one press of the volume keys
+const VOLUME_STEP: u8 = 6;
+
+/// Play/pause/skip, now-playing and system volume.
+/// macOS scripts Spotify and Apple Music over AppleScript (both expose the same verbs),
+/// Linux drives any MPRIS player through `playerctl` and the volume through `pactl`, and
+/// Windows uses the system media session (SMTC) and the volume keys via PowerShell.
+pub struct MediaControlTool;
+
+enum Volume {
+    Get,
+    Set(u8),
+    Up,
+    Down,
+    ToggleMute,
+}
+
+impl Tool for MediaControlTool {
+    fn name(&self) -> &str {
+        "media_control"
+    }
+
+    fn description(&self) -> &str {
+        "Control music and video playback and the system volume. Actions: 'now_playing', 'play', 'pause', 'toggle', 'next', 'previous', 'get_volume', 'set_volume' (level 0-100), 'volume_up', 'volume_down', 'mute' (toggles)."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": [
+                        "now_playing", "play", "pause", "toggle", "next", "previous",
+                        "get_volume", "set_volume", "volume_up", "volume_down", "mute"
+                    ]
+                },
+                "level": { "type": "integer", "description": "Volume percentage (set_volume)" }
+            },
+            "required": ["action"]
+        })
+    }
+
+    fn execute(&self, args: Value) -> Pin<Box<dyn Future<Output = ToolResult> + Send>> {
+        Box::pin(async move {
+            let action = args["action"]
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("Missing action"))?
+                .to_string();
+
+            let volume_change = match action.as_str() {
+                "get_volume" => Some(Volume::Get),
+                "set_volume" => {
+                    let level = args["level"]
+                        .as_u64()
+                        .ok_or_else(|| anyhow::anyhow!("Missing level"))?;
+                    Some(Volume::Set(level.min(100) as u8))
+                }
+                "volume_up" => Some(Volume::Up),
+                "volume_down" => Some(Volume::Down),
+                "mute" => Some(Volume::ToggleMute),
+                _ => None,
+            };
+            if let Some(change) = volume_change {
+                return Ok(volume(change).await?.to_string());
+            }
+
+            match action.as_str() {
+                "now_playing" | "play" | "pause" | "toggle" | "next" | "previous" => {
+                    Ok(playback(&action).await?.to_string())
+                }
+                other => Err(anyhow::anyhow!("Unknown action: {}", other)),
+            }
+        })
+    }
+
+    /// Looking, and play/pause, run freely; skipping tracks and changing the volume need
+    /// the user's OK
+    fn requires_approval(&self, args: &Value) -> bool {
+        !matches!(
+            args["action"].as_str(),
+            Some("now_playing" | "get_volume" | "play" | "pause" | "toggle")
+        )
+    }
+}
+
+/// Run a playback action (or just look, for now_playing) and report player state and
+/// current track
+#[cfg(target_os = "macos")]
+async fn playback(action: &str) -> Result<Value> {
+    let verb = match action {
+        "play" => Some("play"),
+        "pause" => Some("pause"),
+        "toggle" => Some("playpause"),
+        "next" => Some("next track"),
+        "previous" => Some("previous track"),
+        _ => None,
+    };
+
+    let player = match running_player().await? {
+        Some(player) => player,
+        None => return Ok(json!({ "player": null, "state": "stopped" })),
+    };
+
+    if let Some(verb) = verb {
+        osascript(&format!("tell application \"{}\" to {}", player, verb)).await?;
+    }
+    now_playing(player).await
+}
+
+/// First supported player that is currently running. Checked via System Events so
+/// `tell application` doesn't launch a player that was closed.
+#[cfg(target_os = "macos")]
+async fn running_player() -> Result<Option<&'static str>> {
+    for &player in PLAYERS {
+        let running = osascript(&format!(
+            "tell application \"System Events\" to (name of processes) contains \"{}\"",
+            player
+        ))
+        .await?;
+        if running == "true" {
+            return Ok(Some(player));
+        }
+    }
+    Ok(None)
+}
+
+/// Player state and current track as compact JSON
+#[cfg(target_os = "macos")]
+async fn now_playing(player: &str) -> Result<Value> {
+    let script = format!(
+        "tell application \"{}\"
+            set s to player state as string
+            if s is \"stopped\" then return s
+            return s & linefeed & (name of current track) & linefeed & (artist of current track) & linefeed & (album of current track)
+        end tell",
+        player
+    );
+    let output = osascript(&script).await?;
+    let mut lines = output.lines();
+    let state = lines.next().unwrap_or("stopped");
+    let mut result = json!({ "player": player, "state": state });
+    if let (Some(track), Some(artist), Some(album)) = (lines.next(), lines.next(), lines.next()) {
+        result["track"] = json!(track);
+        result["artist"] = json!(artist);
+        result["album"] = json!(album);
+    }
+    Ok(result)
+}
+
+#[cfg(target_os = "macos")]
+async fn volume(change: Volume) -> Result<Value> {
+    let command = match change {
+        Volume::Get => String::new(),
+        Volume::Set(level) => format!("set volume output volume {}", level),
+        Volume::Up => format!(
+            "set volume output volume ((output volume of (get volume settings)) + {})",
+            VOLUME_STEP
+        ),
+        Volume::Down => format!(
+            "set volume output volume ((output volume of (get volume settings)) - {})",
+            VOLUME_STEP
+        ),
+        Volume::ToggleMute => {
+            "set volume output muted not (output muted of (get volume settings))".to_string()
+        }
+    };
+    let output = osascript(&format!(
+        "{}
+        set v to get volume settings
+        return (output volume of v as string) & \",\" & (output muted of v as string)",
+        command
+    ))
+    .await?;
+    let (level, muted) = output
+        .split_once(',')
+        .ok_or_else(|| anyhow::anyhow!("Unexpected volume settings: {}", output))?;
+    Ok(json!({ "volume": level.parse::<u8>().ok(), "muted": muted == "true" }))
+}
+
+#[cfg(target_os = "macos")]
+pub(crate) async fn osascript(script: &str) -> Result<String> {
+    let output = tokio::process::Command::new("osascript")
+        .arg("-e")
+        .arg(script)
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "AppleScript failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Loads the WinRT media session API and picks the session Windows shows in its media
+/// flyout; `Await` turns WinRT async operations into blocking calls
+#[cfg(windows)]
+const SMTC_PRELUDE: &str = "[Console]::OutputEncoding = [Text.Encoding]::UTF8
+Add-Type -AssemblyName System.Runtime.WindowsRuntime
+$asTask = [System.WindowsRuntimeSystemExtensions].GetMethods() | Where-Object {
+    $_.Name -eq 'AsTask' -and $_.GetParameters().Count -eq 1 -and
+    $_.GetParameters()[0].ParameterType.Name -eq 'IAsyncOperation`1' } | Select-Object -First 1
+function Await($op, $type) {
+    $task = $asTask.MakeGenericMethod($type).Invoke($null, @($op))
+    $task.Wait(-1) | Out-Null
+    $task.Result
+}
+$manager = [Windows.Media.Control.GlobalSystemMediaTransportControlsSessionManager,Windows.Media.Control,ContentType=WindowsRuntime]
+$session = (Await ($manager::RequestAsync()) ($manager)).GetCurrentSession()
+if (-not $session) { 'none'; exit }
+";
+
+#[cfg(windows)]
+async fn playback(action: &str) -> Result<Value> {
+    let command = match action {
+        "play" => "Await ($session.TryPlayAsync()) ([bool]) | Out-Null",
+        "pause" => "Await ($session.TryPauseAsync()) ([bool]) | Out-Null",
+        "toggle" => "Await ($session.TryTogglePlayPauseAsync()) ([bool]) | Out-Null",
+        "next" => "Await ($session.TrySkipNextAsync()) ([bool]) | Out-Null",
+        "previous" => "Await ($session.TrySkipPreviousAsync()) ([bool]) | Out-Null",
+        _ => "",
+    };
+    // The player takes a moment to report its new state and track
+    let settle = if command.is_empty() {
+        ""
+    } else {
+        "Start-Sleep -Milliseconds 400"
+    };
+    let output = powershell(&format!(
+        "{}{}
+        {}
+        $props = Await ($session.TryGetMediaPropertiesAsync()) ([Windows.Media.Control.GlobalSystemMediaTransportControlsSessionMediaProperties])
+        \"$($session.SourceAppUserModelId)`t$($session.GetPlaybackInfo().PlaybackStatus)`t$($props.Title)`t$($props.Artist)`t$($props.AlbumTitle)\"",
+        SMTC_PRELUDE, command, settle
+    ))
+    .await?;
+    if output == "none" {
+        return Ok(json!({ "player": null, "state": "stopped" }));
+    }
+    let fields: Vec<&str> = output.split('\t').collect();
+    let field = |i: usize| fields.get(i).copied().unwrap_or("");
+    let mut result = json!({ "player": field(0), "state": field(1).to_lowercase() });
+    if !field(2).is_empty() {
+        result["track"] = json!(field(2));
+        result["artist"] = json!(field(3));
+        result["album"] = json!(field(4));
+    }
+    Ok(result)
+}
+
+/// Windows has no volume command line, so this presses the volume keys (2% per press)
+#[cfg(windows)]
+async fn volume(change: Volume) -> Result<Value> {
+    let presses = |key: u32, count: u32| {
+        // `1..0` counts down in PowerShell, so no presses means no script
+        if count == 0 {
+            return String::new();
+        }
+        format!(
+            "$shell = New-Object -ComObject WScript.Shell; 1..{} | ForEach-Object {{ $shell.SendKeys([char]{}) }}",
+            count, key
+        )
+    };
+    const VOLUME_MUTE: u32 = 173;
+    const VOLUME_DOWN: u32 = 174;
+    const VOLUME_UP: u32 = 175;
+    let (script, result) = match change {
+        Volume::Get => {
+            return Err(anyhow::anyhow!(
+                "Reading the volume isn't supported on Windows"
+            ))
+        }
+        // From silence, so the level is known
+        Volume::Set(level) => (
+            format!(
+                "{}; {}",
+                presses(VOLUME_DOWN, 50),
+                presses(VOLUME_UP, (level as u32 + 1) / 2)
+            ),
+            json!({ "volume": level }),
+        ),
+        Volume::Up => (
+            presses(VOLUME_UP, VOLUME_STEP as u32 / 2),
+            json!({ "changed": "up" }),
+        ),
+        Volume::Down => (
+            presses(VOLUME_DOWN, VOLUME_STEP as u32 / 2),
+            json!({ "changed": "down" }),
+        ),
+        Volume::ToggleMute => (presses(VOLUME_MUTE, 1), json!({ "changed": "mute" })),
+    };
+    powershell(&script).await?;
+    Ok(result)
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+async fn playerctl(args: &[&str]) -> Result<String> {
+    let mut command = Command::new("playerctl");
+    command.args(args);
+    run(command).await
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+async fn playback(action: &str) -> Result<Value> {
+    let command = match action {
+        "play" => Some("play"),
+        "pause" => Some("pause"),
+        "toggle" => Some("play-pause"),
+        "next" => Some("next"),
+        "previous" => Some("previous"),
+        _ => None,
+    };
+    if let Some(command) = command {
+        // Fails with "No players found" when nothing is playing; reported as stopped below
+        let _ = playerctl(&[command]).await;
+    }
+    let output = match playerctl(&[
+        "metadata",
+        "--format",
+        "{{playerName}}\t{{lc(status)}}\t{{title}}\t{{artist}}\t{{album}}",
+    ])
+    .await
+    {
+        Ok(output) => output,
+        Err(_) => return Ok(json!({ "player": null, "state": "stopped" })),
+    };
+    let fields: Vec<&str> = output.split('\t').collect();
+    let field = |i: usize| fields.get(i).copied().unwrap_or("");
+    let mut result = json!({ "player": field(0), "state": field(1) });
+    if !field(2).is_empty() {
+        result["track"] = json!(field(2));
+        result["artist"] = json!(field(3));
+        result["album"] = json!(field(4));
+    }
+    Ok(result)
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+async fn pactl(args: &[&str]) -> Result<String> {
+    let mut command = Command::new("pactl");
+    command.args(args);
+    run(command).await
+}
+
+/// PulseAudio / PipeWire default output, through `pactl`
+#[cfg(not(any(target_os = "macos", windows)))]
+async fn volume(change: Volume) -> Result<Value> {
+    const SINK: &str = "@DEFAULT_SINK@";
+    let current = || async {
+        // "Volume: front-left: 32768 /  50% / -18.06 dB,   front-right: ..."
+        let output = pactl(&["get-sink-volume", SINK]).await?;
+        output
+            .split_whitespace()
+            .find_map(|word| word.strip_suffix('%')?.parse::<u8>().ok())
+            .ok_or_else(|| anyhow::anyhow!("Unexpected pactl output: {}", output))
+    };
+    let target = match change {
+        Volume::Get | Volume::ToggleMute => None,
+        Volume::Set(level) => Some(level),
+        // pactl happily goes past 100%, so steps are clamped here
+        Volume::Up => Some(current().await?.saturating_add(VOLUME_STEP).min(100)),
+        Volume::Down => Some(current().await?.saturating_sub(VOLUME_STEP)),
+    };
+    if let Some(level) = target {
+        pactl(&["set-sink-volume", SINK, &format!("{}%", level)]).await?;
+    }
+    if matches!(change, Volume::ToggleMute) {
+        pactl(&["set-sink-mute", SINK, "toggle"]).await?;
+    }
+    let muted = pactl(&["get-sink-mute", SINK]).await?;
+    Ok(json!({ "volume": current().await?, "muted": muted.ends_with("yes") }))
+}
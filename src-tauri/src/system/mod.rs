@@ -1,4 +1,18 @@
+pub mod apps;
 pub mod browser;
+pub mod clipboard;
+pub mod email;
 pub mod files;
+pub mod http;
 pub mod input;
+pub mod media;
+pub mod ocr;
+pub mod patch;
+pub mod quick_chat;
+pub mod region_select;
+pub mod sandbox;
+pub mod screen_watch;
 pub mod screenshot;
+pub mod secrets;
+pub mod shell;
+pub mod system_info;
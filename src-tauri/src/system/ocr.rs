@@ -0,0 +1,246 @@
+use anyhow::Result;
+use base64::{engine::general_purpose, Engine as _};
+use image::{DynamicImage, ImageFormat};
+use serde_json::{json, Value};
+use std::future::Future;
+use std::pin::Pin;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::agent::tools::{Tool, ToolResult};
+use crate::config::OcrSettings;
+use crate::system::screenshot;
+
+/// Most text lines returned; a full screen of small print can run to hundreds
+const MAX_LINES: usize = 200;
+
+/// The last image attached to the conversation (screenshot, region, screen watch), as
+/// base64 JPEG. The agent loop fills it so `ocr` can read an image the model has seen.
+pub type LatestImage = Arc<Mutex<Option<String>>>;
+
+/// Reads on-screen text with the local `tesseract` CLI, so text can be read without a
+/// vision model
+pub struct OcrTool {
+    settings: OcrSettings,
+    latest_image: LatestImage,
+}
+
+/// One recognized line and its box in image pixels
+struct Line {
+    left: u32,
+    top: u32,
+    right: u32,
+    bottom: u32,
+    words: Vec<String>,
+}
+
+impl OcrTool {
+    pub fn new(settings: OcrSettings, latest_image: LatestImage) -> Self {
+        Self {
+            settings,
+            latest_image,
+        }
+    }
+
+    /// The image to read and where its top-left corner is on screen (0,0 for images)
+    fn source_image(args: &Value, latest_image: &LatestImage) -> Result<(DynamicImage, u32, u32)> {
+        match args["source"].as_str().unwrap_or("screen") {
+            "screen" => {
+                let screen = screenshot::capture_primary_screen()?;
+                let region = &args["region"];
+                if region.is_null() {
+                    return Ok((screen, 0, 0));
+                }
+                let field = |key: &str| {
+                    region[key]
+                        .as_u64()
+                        .map(|v| v as u32)
+                        .ok_or_else(|| anyhow::anyhow!("region needs x, y, width and height"))
+                };
+                let (x, y) = (field("x")?, field("y")?);
+                let width = field("width")?.min(screen.width().saturating_sub(x));
+                let height = field("height")?.min(screen.height().saturating_sub(y));
+                if width == 0 || height == 0 {
+                    return Err(anyhow::anyhow!("region is outside the screen"));
+                }
+                Ok((screen.crop_imm(x, y, width, height), x, y))
+            }
+            "last_image" => {
+                let encoded = latest_image
+                    .lock()
+                    .unwrap()
+                    .clone()
+                    .ok_or_else(|| anyhow::anyhow!("No image has been attached yet"))?;
+                Ok((decode(&encoded)?, 0, 0))
+            }
+            "image" => {
+                let encoded = args["image"]
+                    .as_str()
+                    .ok_or_else(|| anyhow::anyhow!("Missing image"))?;
+                Ok((decode(encoded)?, 0, 0))
+            }
+            other => Err(anyhow::anyhow!("Unknown source: {}", other)),
+        }
+    }
+}
+
+fn decode(encoded: &str) -> Result<DynamicImage> {
+    let encoded = screenshot::parse_image_output(encoded).map_or(encoded, |(image, _)| image);
+    let bytes = general_purpose::STANDARD
+        .decode(encoded.trim())
+        .map_err(|e| anyhow::anyhow!("Invalid base64 image: {}", e))?;
+    Ok(image::load_from_memory(&bytes)?)
+}
+
+/// Run tesseract on `image` and return its TSV output (one row per word)
+async fn tesseract(settings: &OcrSettings, image: &DynamicImage) -> Result<String> {
+    let mut png = Vec::new();
+    image.write_to(&mut std::io::Cursor::new(&mut png), ImageFormat::Png)?;
+
+    let mut command = Command::new(&settings.tesseract_binary);
+    command
+        .args(["stdin", "stdout", "-l", &settings.languages, "tsv"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(windows)]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    let mut child = command.spawn().map_err(|e| {
+        anyhow::anyhow!(
+            "Failed to start {} (is tesseract installed?): {}",
+            settings.tesseract_binary,
+            e
+        )
+    })?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(&png).await?;
+        // Dropping stdin closes it so tesseract sees the end of the image
+    }
+    let output = child.wait_with_output().await?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "tesseract failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Group tesseract's words into lines. TSV columns: level page block par line word
+/// left top width height conf text
+fn parse_lines(tsv: &str) -> Vec<Line> {
+    let mut lines: Vec<Line> = Vec::new();
+    let mut current_key = None;
+    for row in tsv.lines().skip(1) {
+        let cols: Vec<&str> = row.split('\t').collect();
+        if cols.len() < 12 {
+            continue;
+        }
+        let text = cols[11].trim();
+        // Rows above word level (blocks, paragraphs, lines) have conf -1
+        let confidence: f32 = cols[10].parse().unwrap_or(-1.0);
+        if text.is_empty() || confidence < 0.0 {
+            continue;
+        }
+        let num = |i: usize| cols[i].parse::<u32>().unwrap_or(0);
+        let (left, top, width, height) = (num(6), num(7), num(8), num(9));
+        let key = (num(1), num(2), num(3), num(4));
+        match lines.last_mut() {
+            Some(line) if current_key == Some(key) => {
+                line.left = line.left.min(left);
+                line.top = line.top.min(top);
+                line.right = line.right.max(left + width);
+                line.bottom = line.bottom.max(top + height);
+                line.words.push(text.to_string());
+            }
+            _ => {
+                current_key = Some(key);
+                lines.push(Line {
+                    left,
+                    top,
+                    right: left + width,
+                    bottom: top + height,
+                    words: vec![text.to_string()],
+                });
+            }
+        }
+    }
+    lines
+}
+
+impl Tool for OcrTool {
+    fn name(&self) -> &str {
+        "ocr"
+    }
+
+    fn description(&self) -> &str {
+        "Read text on screen with OCR. Sources: 'screen' (default; optional region in screen pixels), 'last_image' (the most recent screenshot or image in this conversation), 'image' (base64). Returns one line per row of text with its box as [x,y,width,height] in screen pixels (image pixels for the other sources)."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "source": {
+                    "type": "string",
+                    "enum": ["screen", "last_image", "image"]
+                },
+                "region": {
+                    "type": "object",
+                    "description": "Part of the screen to read (source 'screen')",
+                    "properties": {
+                        "x": { "type": "integer" },
+                        "y": { "type": "integer" },
+                        "width": { "type": "integer" },
+                        "height": { "type": "integer" }
+                    }
+                },
+                "image": { "type": "string", "description": "Base64 PNG/JPEG (source 'image')" }
+            },
+            "required": []
+        })
+    }
+
+    fn execute(&self, args: Value) -> Pin<Box<dyn Future<Output = ToolResult> + Send>> {
+        let settings = self.settings.clone();
+        let latest_image = self.latest_image.clone();
+        Box::pin(async move {
+            let (image, offset_x, offset_y) =
+                tokio::task::spawn_blocking(move || OcrTool::source_image(&args, &latest_image))
+                    .await??;
+            let tsv = tesseract(&settings, &image).await?;
+            let lines = parse_lines(&tsv);
+            if lines.is_empty() {
+                return Ok("No text found".to_string());
+            }
+
+            let mut output: Vec<String> = lines
+                .iter()
+                .take(MAX_LINES)
+                .map(|line| {
+                    format!(
+                        "[{},{},{},{}] {}",
+                        line.left + offset_x,
+                        line.top + offset_y,
+                        line.right - line.left,
+                        line.bottom - line.top,
+                        line.words.join(" ")
+                    )
+                })
+                .collect();
+            if lines.len() > MAX_LINES {
+                output.push(format!(
+                    "[Truncated: {} of {} lines]",
+                    MAX_LINES,
+                    lines.len()
+                ));
+            }
+            Ok(output.join("\n"))
+        })
+    }
+}
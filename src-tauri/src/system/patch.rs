@@ -0,0 +1,198 @@
+use anyhow::Result;
+
+/// One `@@ -start,len +start,len @@` section of a unified diff
+struct Hunk {
+    /// 1-indexed line the hunk claims to start at in the original
+    old_start: usize,
+    /// 0-indexed line the hunk's old lines should start at
+    index: usize,
+    /// Context and removed lines, as they must appear in the original
+    old_lines: Vec<String>,
+    /// Context and added lines, as they replace `old_lines`
+    new_lines: Vec<String>,
+}
+
+/// Apply a unified diff for a single file to `original`. File headers (`---`/`+++`) are
+/// optional. Line numbers in hunk headers are treated as hints: each hunk is applied where
+/// its context matches, searching outward from the stated line, because diffs written by
+/// hand (or by a model) are often a few lines off.
+pub fn apply(original: &str, patch: &str) -> Result<String> {
+    let hunks = parse(patch)?;
+    let newline = if original.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let mut lines: Vec<String> = original.lines().map(str::to_string).collect();
+
+    // Hunks are in file order; each one is searched for after where the last one ended
+    let mut floor = 0;
+    // How far applied hunks have shifted the lines after them
+    let mut shift: isize = 0;
+    for (i, hunk) in hunks.iter().enumerate() {
+        let expected = (hunk.index as isize + shift).max(0) as usize;
+        let at = find(&lines, &hunk.old_lines, expected, floor).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Hunk {} does not match the file (expected near line {})",
+                i + 1,
+                hunk.old_start
+            )
+        })?;
+        lines.splice(
+            at..at + hunk.old_lines.len(),
+            hunk.new_lines.iter().cloned(),
+        );
+        floor = at + hunk.new_lines.len();
+        shift += hunk.new_lines.len() as isize - hunk.old_lines.len() as isize;
+    }
+
+    let mut result = lines.join(newline);
+    if (original.is_empty() || original.ends_with('\n')) && !result.is_empty() {
+        result.push_str(newline);
+    }
+    Ok(result)
+}
+
+fn parse(patch: &str) -> Result<Vec<Hunk>> {
+    let mut hunks: Vec<Hunk> = Vec::new();
+    for line in patch.lines() {
+        if line.starts_with("@@") {
+            let (old_start, old_len) = hunk_range(line)?;
+            hunks.push(Hunk {
+                old_start,
+                // A hunk that only adds lines names the line it goes after
+                index: if old_len == 0 {
+                    old_start
+                } else {
+                    old_start.saturating_sub(1)
+                },
+                old_lines: Vec::new(),
+                new_lines: Vec::new(),
+            });
+            continue;
+        }
+        let Some(hunk) = hunks.last_mut() else {
+            // Headers (diff, index, ---, +++) before the first hunk
+            continue;
+        };
+        if let Some(removed) = line.strip_prefix('-') {
+            hunk.old_lines.push(removed.to_string());
+        } else if let Some(added) = line.strip_prefix('+') {
+            hunk.new_lines.push(added.to_string());
+        } else if line.starts_with('\\') {
+            // "\ No newline at end of file"
+        } else {
+            // Context; some tools drop the leading space from empty lines
+            let context = line.strip_prefix(' ').unwrap_or(line);
+            hunk.old_lines.push(context.to_string());
+            hunk.new_lines.push(context.to_string());
+        }
+    }
+    if hunks.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Patch has no hunks (lines starting with @@)"
+        ));
+    }
+    Ok(hunks)
+}
+
+/// Old start line and length of a `@@ -12,5 +12,7 @@` header. Without numbers (`@@ @@`)
+/// the hunk is searched for from the top.
+fn hunk_range(header: &str) -> Result<(usize, usize)> {
+    let Some(old) = header.split_whitespace().find_map(|w| w.strip_prefix('-')) else {
+        return Ok((1, 1));
+    };
+    let invalid = || anyhow::anyhow!("Invalid hunk header: {}", header);
+    let (start, len) = old.split_once(',').unwrap_or((old, "1"));
+    Ok((
+        start.parse().map_err(|_| invalid())?,
+        len.parse().map_err(|_| invalid())?,
+    ))
+}
+
+/// Where `needle` occurs in `lines` at or after `floor`, closest to `expected`.
+/// Exact matches win; otherwise trailing whitespace is ignored.
+fn find(lines: &[String], needle: &[String], expected: usize, floor: usize) -> Option<usize> {
+    if needle.is_empty() {
+        // Pure insertion: goes where the header says
+        return Some(expected.clamp(floor, lines.len()));
+    }
+    let last = lines.len().checked_sub(needle.len())?;
+    if floor > last {
+        return None;
+    }
+    let exact = |at: usize| lines[at..at + needle.len()] == *needle;
+    let loose = |at: usize| {
+        lines[at..at + needle.len()]
+            .iter()
+            .zip(needle)
+            .all(|(a, b)| a.trim_end() == b.trim_end())
+    };
+    let expected = expected.clamp(floor, last);
+    // floor..=last, nearest to expected first
+    let mut order: Vec<usize> = (floor..=last).collect();
+    order.sort_by_key(|at| at.abs_diff(expected));
+    order
+        .iter()
+        .copied()
+        .find(|&at| exact(at))
+        .or_else(|| order.iter().copied().find(|&at| loose(at)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATCH: &str =
+        "--- a/notes.txt\n+++ b/notes.txt\n@@ -1,3 +1,3 @@\n alpha\n-beta\n+BETA\n gamma\n";
+
+    #[test]
+    fn exact_match() {
+        let patched = apply("alpha\nbeta\ngamma\n", PATCH).unwrap();
+        assert_eq!(patched, "alpha\nBETA\ngamma\n");
+    }
+
+    #[test]
+    fn hunk_found_away_from_its_stated_line() {
+        let patched = apply("one\ntwo\nthree\nalpha\nbeta\ngamma\ndelta\n", PATCH).unwrap();
+        assert_eq!(patched, "one\ntwo\nthree\nalpha\nBETA\ngamma\ndelta\n");
+    }
+
+    #[test]
+    fn trailing_whitespace_is_tolerated() {
+        let patched = apply("alpha  \nbeta\ngamma\n", PATCH).unwrap();
+        assert_eq!(patched, "alpha\nBETA\ngamma\n");
+    }
+
+    #[test]
+    fn mismatched_context_is_rejected() {
+        let err = apply("alpha\nbeta\nepsilon\n", PATCH).unwrap_err();
+        assert!(err.to_string().contains("Hunk 1 does not match"));
+    }
+
+    #[test]
+    fn later_hunks_follow_earlier_ones() {
+        let patch = "@@ -1,2 +1,3 @@\n a\n+inserted\n b\n@@ -4,2 +5,2 @@\n d\n-e\n+E\n";
+        let patched = apply("a\nb\nc\nd\ne\n", patch).unwrap();
+        assert_eq!(patched, "a\ninserted\nb\nc\nd\nE\n");
+    }
+
+    #[test]
+    fn pure_insertion_goes_after_the_named_line() {
+        let patched = apply("a\nb\n", "@@ -1,0 +2,1 @@\n+new\n").unwrap();
+        assert_eq!(patched, "a\nnew\nb\n");
+    }
+
+    #[test]
+    fn malformed_patches_are_rejected() {
+        assert!(apply("a\n", "just some text\n-a\n+b\n").is_err());
+        let err = apply("a\n", "@@ -x,1 +1,1 @@\n-a\n+b\n").unwrap_err();
+        assert!(err.to_string().contains("Invalid hunk header"));
+    }
+
+    #[test]
+    fn keeps_crlf_and_missing_final_newline() {
+        let patched = apply("alpha\r\nbeta\r\ngamma", PATCH).unwrap();
+        assert_eq!(patched, "alpha\r\nBETA\r\ngamma");
+    }
+}
@@ -0,0 +1,34 @@
+use anyhow::Result;
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+
+const WINDOW_LABEL: &str = "quick-chat";
+
+/// Open the quick-chat box, a small always-on-top input for messaging Amadeus without
+/// switching to the main window, or close it if it is already open
+pub fn toggle(app: &AppHandle) -> Result<()> {
+    if let Some(window) = app.get_webview_window(WINDOW_LABEL) {
+        window.close()?;
+        return Ok(());
+    }
+    WebviewWindowBuilder::new(
+        app,
+        WINDOW_LABEL,
+        WebviewUrl::App("index.html#quick-chat".into()),
+    )
+    .title("Amadeus")
+    .inner_size(560.0, 64.0)
+    .center()
+    .decorations(false)
+    .resizable(false)
+    .always_on_top(true)
+    .skip_taskbar(true)
+    .focused(true)
+    .build()?;
+    Ok(())
+}
+
+pub fn close(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window(WINDOW_LABEL) {
+        let _ = window.close();
+    }
+}
@@ -0,0 +1,63 @@
+use anyhow::Result;
+use image::DynamicImage;
+use serde::Deserialize;
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+
+use crate::system::screenshot::DisplayCapture;
+
+/// Overlay windows are labelled `region-select-<display id>`
+const OVERLAY_LABEL_PREFIX: &str = "region-select-";
+
+/// Selected rectangle as fractions (0.0 - 1.0) of the overlay's size.
+/// Fractions sidestep per-monitor scale factors: the frozen capture is cropped proportionally.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct Selection {
+    pub left: f64,
+    pub top: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Open a borderless, always-on-top overlay over every display.
+/// Each overlay shows the frozen capture of its display so the user selects on a still image.
+pub fn open_overlays(app: &AppHandle, captures: &[DisplayCapture]) -> Result<()> {
+    for capture in captures {
+        let url =
+            WebviewUrl::App(format!("index.html#region-select?display={}", capture.id).into());
+        WebviewWindowBuilder::new(app, format!("{}{}", OVERLAY_LABEL_PREFIX, capture.id), url)
+            .title("Select a region")
+            .position(capture.x as f64, capture.y as f64)
+            .inner_size(capture.width as f64, capture.height as f64)
+            .decorations(false)
+            .resizable(false)
+            .always_on_top(true)
+            .skip_taskbar(true)
+            .focused(true)
+            .build()?;
+    }
+    Ok(())
+}
+
+pub fn close_overlays(app: &AppHandle) {
+    for (label, window) in app.webview_windows() {
+        if label.starts_with(OVERLAY_LABEL_PREFIX) {
+            let _ = window.close();
+        }
+    }
+}
+
+/// Cut the selection out of a full display capture
+pub fn crop(image: &DynamicImage, selection: Selection) -> Result<DynamicImage> {
+    let (width, height) = (image.width() as f64, image.height() as f64);
+    let x = (selection.left.clamp(0.0, 1.0) * width).round() as u32;
+    let y = (selection.top.clamp(0.0, 1.0) * height).round() as u32;
+    let w = (selection.width.clamp(0.0, 1.0) * width).round() as u32;
+    let h = (selection.height.clamp(0.0, 1.0) * height).round() as u32;
+    let w = w.min(image.width().saturating_sub(x));
+    let h = h.min(image.height().saturating_sub(y));
+
+    if w < 2 || h < 2 {
+        return Err(anyhow::anyhow!("Selected region is too small"));
+    }
+    Ok(image.crop_imm(x, y, w, h))
+}
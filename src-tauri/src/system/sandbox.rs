@@ -0,0 +1,172 @@
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tracing::warn;
+
+/// Folders the file_system and shell tools may touch, from `[files] roots`.
+/// Relative paths resolve against the first root; absolute paths must lie inside one.
+pub struct Sandbox {
+    roots: RwLock<Vec<PathBuf>>,
+}
+
+impl Sandbox {
+    pub fn new(roots: &[String]) -> Self {
+        let sandbox = Self {
+            roots: RwLock::new(Vec::new()),
+        };
+        sandbox.set_roots(roots);
+        sandbox
+    }
+
+    /// Replace the roots, creating any that don't exist yet (e.g. the default
+    /// ~/AmadeusWorkspace on first launch)
+    pub fn set_roots(&self, roots: &[String]) {
+        let resolved = roots
+            .iter()
+            .filter_map(|root| {
+                let path = expand_home(root);
+                if let Err(e) = std::fs::create_dir_all(&path) {
+                    warn!("[Files] Can't use root {}: {}", path.display(), e);
+                    return None;
+                }
+                path.canonicalize().ok()
+            })
+            .collect();
+        *self.roots.write().unwrap() = resolved;
+    }
+
+    pub fn roots(&self) -> Vec<PathBuf> {
+        self.roots.read().unwrap().clone()
+    }
+
+    /// Where relative paths and shell commands start
+    pub fn primary(&self) -> Result<PathBuf, anyhow::Error> {
+        self.roots
+            .read()
+            .unwrap()
+            .first()
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("No workspace folders; add one in settings"))
+    }
+
+    /// Validate that the given path is within an allowed root.
+    /// Prevents LLM from accessing sensitive system files like ~/.ssh, /etc, etc.
+    pub fn resolve(&self, path_str: &str) -> Result<PathBuf, anyhow::Error> {
+        let workspace_root = self.primary()?;
+
+        let expanded = expand_home(path_str);
+        let requested = if expanded.is_absolute() {
+            expanded
+        } else {
+            workspace_root.join(expanded)
+        };
+
+        // Canonicalize to resolve "..", symlinks, etc.
+        // For new files (write_file), parent must exist and be in workspace
+        let canonical = if requested.exists() {
+            requested.canonicalize()?
+        } else {
+            // For files that don't exist yet, validate the parent directory
+            let parent = requested
+                .parent()
+                .ok_or_else(|| anyhow::anyhow!("Invalid path: no parent directory"))?;
+            if !parent.exists() {
+                return Err(anyhow::anyhow!(
+                    "Parent directory does not exist: {}",
+                    parent.display()
+                ));
+            }
+            let canonical_parent = parent.canonicalize()?;
+            canonical_parent.join(requested.file_name().unwrap_or_default())
+        };
+
+        let roots = self.roots();
+        if !roots.iter().any(|root| canonical.starts_with(root)) {
+            let allowed: Vec<String> = roots.iter().map(|r| r.display().to_string()).collect();
+            return Err(anyhow::anyhow!(
+                "Access denied: path '{}' is outside the workspace ({})",
+                path_str,
+                allowed.join(", ")
+            ));
+        }
+
+        Ok(canonical)
+    }
+}
+
+/// `~` and `~/...` in config and tool paths mean the user's home folder
+pub fn expand_home(path: &str) -> PathBuf {
+    let rest = match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => rest,
+        _ => return PathBuf::from(path),
+    };
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"));
+    match home {
+        Some(home) => Path::new(&home).join(rest.trim_start_matches(['/', '\\'])),
+        None => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh temp folder with a `workspace` root next to an `outside` folder holding `secret.txt`
+    fn scratch(name: &str) -> (PathBuf, Sandbox) {
+        let base = std::env::temp_dir().join(format!("amadeus-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&base);
+        std::fs::create_dir_all(base.join("workspace/notes")).unwrap();
+        std::fs::create_dir_all(base.join("outside")).unwrap();
+        std::fs::write(base.join("outside/secret.txt"), "hunter2").unwrap();
+        std::fs::write(base.join("workspace/notes/todo.txt"), "").unwrap();
+        let root = base.join("workspace").to_string_lossy().into_owned();
+        (base, Sandbox::new(&[root]))
+    }
+
+    #[test]
+    fn relative_and_absolute_paths_inside_the_root() {
+        let (base, sandbox) = scratch("inside");
+        let root = sandbox.primary().unwrap();
+        let todo = root.join("notes/todo.txt");
+        assert_eq!(sandbox.resolve("notes/todo.txt").unwrap(), todo);
+        assert_eq!(sandbox.resolve(todo.to_str().unwrap()).unwrap(), todo);
+        assert_eq!(sandbox.resolve("notes/../notes/todo.txt").unwrap(), todo);
+        std::fs::remove_dir_all(base).unwrap();
+    }
+
+    #[test]
+    fn parent_dir_cannot_leave_the_root() {
+        let (base, sandbox) = scratch("dotdot");
+        assert!(sandbox.resolve("../outside/secret.txt").is_err());
+        assert!(sandbox.resolve("notes/../../outside/secret.txt").is_err());
+        // Doesn't exist yet, but its parent is outside
+        assert!(sandbox.resolve("../outside/new.txt").is_err());
+        let outside = base.join("outside/secret.txt");
+        assert!(sandbox.resolve(outside.to_str().unwrap()).is_err());
+        std::fs::remove_dir_all(base).unwrap();
+    }
+
+    #[test]
+    fn new_files_need_an_existing_parent_inside_the_root() {
+        let (base, sandbox) = scratch("new");
+        let root = sandbox.primary().unwrap();
+        assert_eq!(
+            sandbox.resolve("notes/draft.txt").unwrap(),
+            root.join("notes/draft.txt")
+        );
+        let err = sandbox.resolve("missing/draft.txt").unwrap_err();
+        assert!(err.to_string().contains("Parent directory does not exist"));
+        std::fs::remove_dir_all(base).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_pointing_outside_are_denied() {
+        let (base, sandbox) = scratch("symlink");
+        let root = sandbox.primary().unwrap();
+        std::os::unix::fs::symlink(base.join("outside"), root.join("escape")).unwrap();
+        assert!(sandbox.resolve("escape/secret.txt").is_err());
+        // Writing through the link is caught via the parent
+        assert!(sandbox.resolve("escape/new.txt").is_err());
+        std::fs::remove_dir_all(base).unwrap();
+    }
+}
@@ -0,0 +1,205 @@
+use image::DynamicImage;
+use serde_json::{json, Value};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+use crate::agent::tools::{Tool, ToolResult};
+use crate::system::screenshot::{capture_primary_screen, encode_for_llm};
+
+const DEFAULT_INTERVAL_SECS: u64 = 10;
+const MIN_INTERVAL_SECS: u64 = 2;
+const DEFAULT_MAX_PER_MINUTE: usize = 4;
+
+// The frame is split into GRID x GRID regions; a capture counts as "changed"
+// once at least CHANGED_REGIONS of them differ from the last sent frame.
+const GRID: u32 = 8;
+const CELL: u32 = 16;
+const CHANGED_REGIONS: usize = 3;
+
+/// Periodically re-captures the screen and forwards frames that meaningfully changed.
+/// Frames are sent as base64 JPEGs to the agent loop, which attaches them to the conversation.
+pub struct ScreenWatchTool {
+    frames_tx: mpsc::UnboundedSender<String>,
+    task: Arc<Mutex<Option<JoinHandle<()>>>>,
+}
+
+impl ScreenWatchTool {
+    pub fn new(frames_tx: mpsc::UnboundedSender<String>) -> Self {
+        Self {
+            frames_tx,
+            task: Arc::new(Mutex::new(None)),
+        }
+    }
+}
+
+/// Per-region hashes of a downscaled grayscale copy of the frame.
+/// Pixels are quantized so compression noise and cursor blinks don't register as changes.
+fn region_hashes(img: &DynamicImage) -> Vec<u64> {
+    let size = GRID * CELL;
+    let small = img
+        .resize_exact(size, size, image::imageops::FilterType::Triangle)
+        .to_luma8();
+
+    let mut hashes = Vec::with_capacity((GRID * GRID) as usize);
+    for gy in 0..GRID {
+        for gx in 0..GRID {
+            let mut hasher = DefaultHasher::new();
+            for y in 0..CELL {
+                for x in 0..CELL {
+                    let pixel = small.get_pixel(gx * CELL + x, gy * CELL + y);
+                    (pixel.0[0] >> 4).hash(&mut hasher);
+                }
+            }
+            hashes.push(hasher.finish());
+        }
+    }
+    hashes
+}
+
+async fn watch_loop(
+    interval: Duration,
+    max_per_minute: usize,
+    frames_tx: mpsc::UnboundedSender<String>,
+) {
+    let mut previous: Option<Vec<u64>> = None;
+    let mut sent_at: VecDeque<Instant> = VecDeque::new();
+    let mut ticker = tokio::time::interval(interval);
+
+    loop {
+        ticker.tick().await;
+
+        let img = match tokio::task::spawn_blocking(capture_primary_screen).await {
+            Ok(Ok(img)) => img,
+            Ok(Err(e)) => {
+                warn!("[ScreenWatch] Capture failed: {}", e);
+                continue;
+            }
+            Err(e) => {
+                warn!("[ScreenWatch] Capture task failed: {}", e);
+                continue;
+            }
+        };
+
+        let hashes = region_hashes(&img);
+        let changed = match &previous {
+            Some(prev) => prev.iter().zip(&hashes).filter(|(a, b)| a != b).count(),
+            None => hashes.len(),
+        };
+        if changed < CHANGED_REGIONS {
+            continue;
+        }
+
+        // Rate cap: drop the oldest timestamps outside the one-minute window
+        let now = Instant::now();
+        while sent_at
+            .front()
+            .map_or(false, |t| now.duration_since(*t) >= Duration::from_secs(60))
+        {
+            sent_at.pop_front();
+        }
+        if sent_at.len() >= max_per_minute {
+            // Keep `previous` as-is so the change is picked up once the window frees up
+            continue;
+        }
+
+        let encoded = match encode_for_llm(&img) {
+            Ok(encoded) => encoded,
+            Err(e) => {
+                warn!("[ScreenWatch] Encode failed: {}", e);
+                continue;
+            }
+        };
+        if frames_tx.send(encoded).is_err() {
+            // Agent loop is gone
+            break;
+        }
+        previous = Some(hashes);
+        sent_at.push_back(now);
+    }
+}
+
+impl Tool for ScreenWatchTool {
+    fn name(&self) -> &str {
+        "screen_watch"
+    }
+
+    fn description(&self) -> &str {
+        "Watch the user's screen in the background. While active, a new screenshot is attached to the conversation whenever the screen meaningfully changes. Actions: 'start', 'stop', 'status'."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["start", "stop", "status"]
+                },
+                "interval_secs": { "type": "integer", "description": "Seconds between captures (default 10, min 2)" },
+                "max_per_minute": { "type": "integer", "description": "Maximum screenshots attached per minute (default 4)" }
+            },
+            "required": ["action"]
+        })
+    }
+
+    fn execute(&self, args: Value) -> Pin<Box<dyn Future<Output = ToolResult> + Send>> {
+        let frames_tx = self.frames_tx.clone();
+        let task = Arc::clone(&self.task);
+        Box::pin(async move {
+            let action = args["action"]
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("Missing action"))?;
+
+            let mut task = task.lock().unwrap();
+            let running = task.as_ref().map_or(false, |h| !h.is_finished());
+
+            match action {
+                "start" => {
+                    let interval_secs = args["interval_secs"]
+                        .as_u64()
+                        .unwrap_or(DEFAULT_INTERVAL_SECS)
+                        .max(MIN_INTERVAL_SECS);
+                    let max_per_minute = args["max_per_minute"]
+                        .as_u64()
+                        .map(|n| n.max(1) as usize)
+                        .unwrap_or(DEFAULT_MAX_PER_MINUTE);
+
+                    // Restart with the new settings if already running
+                    if let Some(handle) = task.take() {
+                        handle.abort();
+                    }
+                    *task = Some(tokio::spawn(watch_loop(
+                        Duration::from_secs(interval_secs),
+                        max_per_minute,
+                        frames_tx,
+                    )));
+                    Ok(format!(
+                        "Screen watch started (every {}s, max {} captures/min)",
+                        interval_secs, max_per_minute
+                    ))
+                }
+                "stop" => match task.take() {
+                    Some(handle) => {
+                        handle.abort();
+                        Ok("Screen watch stopped".to_string())
+                    }
+                    None => Ok("Screen watch was not running".to_string()),
+                },
+                "status" => Ok(if running {
+                    "Screen watch is running".to_string()
+                } else {
+                    "Screen watch is stopped".to_string()
+                }),
+                _ => Err(anyhow::anyhow!("Unknown action: {}", action)),
+            }
+        })
+    }
+}
@@ -7,58 +7,281 @@ use std::pin::Pin;
 
 use crate::agent::tools::{Tool, ToolResult};
 
+/// Marks tool output that is a base64 JPEG; the agent loop moves it into `Message::images`.
+/// A caption for the model may follow the image on the next line.
+pub const IMAGE_OUTPUT_PREFIX: &str = "IMAGE_BASE64:";
+
+/// Largest image sent to the model; bigger captures are scaled down to fit
+const LLM_MAX_WIDTH: u32 = 1024;
+const LLM_MAX_HEIGHT: u32 = 768;
+
 pub struct ScreenshotTool;
 
+/// A full capture of one display and where it sits in the virtual desktop (logical points)
+pub struct DisplayCapture {
+    pub id: u32,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub image: DynamicImage,
+}
+
+/// Capture every connected display
+pub fn capture_all_screens() -> anyhow::Result<Vec<DisplayCapture>> {
+    let screens =
+        screenshots::Screen::all().map_err(|e| anyhow::anyhow!("Failed to get screens: {}", e))?;
+
+    screens
+        .iter()
+        .map(|screen| {
+            let info = screen.display_info;
+            let image_buffer = screen
+                .capture()
+                .map_err(|e| anyhow::anyhow!("Failed to capture screen {}: {}", info.id, e))?;
+            Ok(DisplayCapture {
+                id: info.id,
+                x: info.x,
+                y: info.y,
+                width: info.width,
+                height: info.height,
+                image: to_dynamic_image(image_buffer)?,
+            })
+        })
+        .collect()
+}
+
+/// Capture the primary screen as an RGBA image
+pub fn capture_primary_screen() -> anyhow::Result<DynamicImage> {
+    // Using screenshots crate for cross-platform support
+    let screens =
+        screenshots::Screen::all().map_err(|e| anyhow::anyhow!("Failed to get screens: {}", e))?;
+    let screen = screens
+        .first()
+        .ok_or_else(|| anyhow::anyhow!("No screens found"))?;
+
+    let image_buffer = screen
+        .capture()
+        .map_err(|e| anyhow::anyhow!("Failed to capture screen: {}", e))?;
+
+    to_dynamic_image(image_buffer)
+}
+
+fn to_dynamic_image(image_buffer: screenshots::image::RgbaImage) -> anyhow::Result<DynamicImage> {
+    // Convert ImageBuffer from screenshots crate to our local image crate type
+    // This avoids type mismatch if multiple image crate versions are present
+    let width = image_buffer.width();
+    let height = image_buffer.height();
+    let raw = image_buffer.into_raw();
+
+    let img_buffer = image::ImageBuffer::<image::Rgba<u8>, _>::from_raw(width, height, raw)
+        .ok_or_else(|| anyhow::anyhow!("Failed to construct image buffer"))?;
+
+    Ok(DynamicImage::ImageRgba8(img_buffer))
+}
+
+/// Downscale and JPEG-encode an image for the LLM, returning base64
+pub fn encode_for_llm(img: &DynamicImage) -> anyhow::Result<String> {
+    encode_jpeg(&shrink_for_llm(img))
+}
+
+/// Resize image to reduce token usage and latency (max 1024x768)
+fn shrink_for_llm(img: &DynamicImage) -> DynamicImage {
+    img.resize(
+        LLM_MAX_WIDTH,
+        LLM_MAX_HEIGHT,
+        image::imageops::FilterType::Lanczos3,
+    )
+}
+
+/// Tool output carrying an image and a caption for the model
+pub fn image_output(base64: &str, caption: &str) -> String {
+    format!("{}{}\n{}", IMAGE_OUTPUT_PREFIX, base64, caption)
+}
+
+/// The base64 image and caption (possibly empty) of image tool output
+pub fn parse_image_output(content: &str) -> Option<(&str, &str)> {
+    let rest = content.strip_prefix(IMAGE_OUTPUT_PREFIX)?;
+    Some(rest.split_once('\n').unwrap_or((rest, "")))
+}
+
+/// A `data:` URL for showing tool image output in the chat. Images are JPEG except those
+/// passed through from MCP servers, which may be PNG.
+pub fn image_data_url(base64: &str) -> String {
+    let mime = if base64.starts_with("iVBORw0KGgo") {
+        "image/png"
+    } else {
+        "image/jpeg"
+    };
+    format!("data:{};base64,{}", mime, base64)
+}
+
+/// One line per display, for the tool schema: "0: 2560x1440 at (0, 0), primary"
+fn display_summary() -> String {
+    let Ok(screens) = screenshots::Screen::all() else {
+        return "unknown".to_string();
+    };
+    screens
+        .iter()
+        .enumerate()
+        .map(|(i, screen)| {
+            let info = screen.display_info;
+            format!(
+                "{}: {}x{} at ({}, {}){}",
+                i,
+                info.width,
+                info.height,
+                info.x,
+                info.y,
+                if info.is_primary { ", primary" } else { "" }
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Capture display `index`, or the primary one
+fn capture_display(index: Option<usize>) -> anyhow::Result<(DynamicImage, String)> {
+    let screens =
+        screenshots::Screen::all().map_err(|e| anyhow::anyhow!("Failed to get screens: {}", e))?;
+    let (index, screen) = match index {
+        Some(index) => (
+            index,
+            screens.get(index).ok_or_else(|| {
+                anyhow::anyhow!("No display {} ({} connected)", index, screens.len())
+            })?,
+        ),
+        None => screens
+            .iter()
+            .enumerate()
+            .find(|(_, s)| s.display_info.is_primary)
+            .or_else(|| screens.iter().enumerate().next())
+            .ok_or_else(|| anyhow::anyhow!("No screens found"))?,
+    };
+    let image_buffer = screen
+        .capture()
+        .map_err(|e| anyhow::anyhow!("Failed to capture screen: {}", e))?;
+    Ok((
+        to_dynamic_image(image_buffer)?,
+        format!("Display {}", index),
+    ))
+}
+
+/// Capture the first visible window whose title contains `title` (case-insensitive)
+fn capture_window(title: &str) -> anyhow::Result<(DynamicImage, String)> {
+    let query = title.to_lowercase();
+    let windows =
+        xcap::Window::all().map_err(|e| anyhow::anyhow!("Failed to list windows: {}", e))?;
+    let window = windows
+        .iter()
+        .find(|w| !w.is_minimized() && w.title().to_lowercase().contains(&query))
+        .ok_or_else(|| anyhow::anyhow!("No open window titled '{}'", title))?;
+    let captured = window
+        .capture_image()
+        .map_err(|e| anyhow::anyhow!("Failed to capture window: {}", e))?;
+    let (width, height) = (captured.width(), captured.height());
+    let image = image::RgbaImage::from_raw(width, height, captured.into_raw())
+        .ok_or_else(|| anyhow::anyhow!("Failed to construct image buffer"))?;
+    Ok((
+        DynamicImage::ImageRgba8(image),
+        format!("Window '{}' ({})", window.title(), window.app_name()),
+    ))
+}
+
+/// The capture a take_screenshot call asks for, and a label describing it
+fn capture_for(args: &Value) -> anyhow::Result<(DynamicImage, String)> {
+    let (image, label) = match args["window_title"].as_str() {
+        Some(title) => capture_window(title)?,
+        None => capture_display(args["display_index"].as_u64().map(|i| i as usize))?,
+    };
+    let region = &args["region"];
+    if region.is_null() {
+        return Ok((image, label));
+    }
+    let field = |key: &str| {
+        region[key]
+            .as_u64()
+            .map(|v| v as u32)
+            .ok_or_else(|| anyhow::anyhow!("region needs x, y, width and height"))
+    };
+    let (x, y) = (field("x")?, field("y")?);
+    let width = field("width")?.min(image.width().saturating_sub(x));
+    let height = field("height")?.min(image.height().saturating_sub(y));
+    if width < 2 || height < 2 {
+        return Err(anyhow::anyhow!(
+            "region is outside the {}x{} capture",
+            image.width(),
+            image.height()
+        ));
+    }
+    Ok((
+        image.crop_imm(x, y, width, height),
+        format!("{}, region at ({}, {})", label, x, y),
+    ))
+}
+
+/// JPEG-encode an image at full resolution, returning base64
+pub fn encode_jpeg(img: &DynamicImage) -> anyhow::Result<String> {
+    // JPEG has no alpha channel
+    let rgb = DynamicImage::ImageRgb8(img.to_rgb8());
+    let mut bytes: Vec<u8> = Vec::new();
+    rgb.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Jpeg)?;
+
+    Ok(general_purpose::STANDARD.encode(&bytes))
+}
+
 impl Tool for ScreenshotTool {
     fn name(&self) -> &str {
         "take_screenshot"
     }
 
     fn description(&self) -> &str {
-        "Captures the current screen content and returns it as a base64 encoded string. Use this to see what is on the user's screen."
+        "Captures the screen and attaches it to the conversation as an image. Use this to see what is on the user's screen. Defaults to the whole primary display; pick another display, one window by title, or a region (in pixels of that capture)."
     }
 
     fn parameters(&self) -> Value {
         json!({
             "type": "object",
-            "properties": {},
+            "properties": {
+                "display_index": {
+                    "type": "integer",
+                    "description": format!("Display to capture. Connected: {}", display_summary())
+                },
+                "window_title": {
+                    "type": "string",
+                    "description": "Capture only the first window whose title contains this (case-insensitive)"
+                },
+                "region": {
+                    "type": "object",
+                    "description": "Crop to this rectangle, in pixels of the display or window capture",
+                    "properties": {
+                        "x": { "type": "integer" },
+                        "y": { "type": "integer" },
+                        "width": { "type": "integer" },
+                        "height": { "type": "integer" }
+                    }
+                }
+            },
             "required": []
         })
     }
 
-    fn execute(&self, _args: Value) -> Pin<Box<dyn Future<Output = ToolResult> + Send>> {
+    fn execute(&self, args: Value) -> Pin<Box<dyn Future<Output = ToolResult> + Send>> {
         Box::pin(async move {
-            // Using screenshots crate for cross-platform support
-            let screens = screenshots::Screen::all()
-                .map_err(|e| anyhow::anyhow!("Failed to get screens: {}", e))?;
-            let screen = screens
-                .first()
-                .ok_or_else(|| anyhow::anyhow!("No screens found"))?;
-
-            let image_buffer = screen
-                .capture()
-                .map_err(|e| anyhow::anyhow!("Failed to capture screen: {}", e))?;
-
-            // Convert ImageBuffer from screenshots crate to our local image crate type
-            // This avoids type mismatch if multiple image crate versions are present
-            let width = image_buffer.width();
-            let height = image_buffer.height();
-            let raw = image_buffer.into_raw();
-
-            let img_buffer = image::ImageBuffer::<image::Rgba<u8>, _>::from_raw(width, height, raw)
-                .ok_or_else(|| anyhow::anyhow!("Failed to construct image buffer"))?;
-
-            let img = DynamicImage::ImageRgba8(img_buffer);
-
-            // Resize image to reduce token usage and latency (e.g., max 1024x768)
-            let resized = img.resize(1024, 768, image::imageops::FilterType::Lanczos3);
-
-            let mut bytes: Vec<u8> = Vec::new();
-            resized.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Jpeg)?;
-
-            let base64_string = general_purpose::STANDARD.encode(&bytes);
+            let (img, label) = tokio::task::spawn_blocking(move || capture_for(&args)).await??;
+            let sent = shrink_for_llm(&img);
+            let base64_string = encode_jpeg(&sent)?;
 
-            Ok(format!("IMAGE_BASE64:{}", base64_string))
+            // The model sees the scaled copy; the real size lets it map positions back
+            let caption = format!(
+                "{}: {}x{} px, shown at {}x{}",
+                label,
+                img.width(),
+                img.height(),
+                sent.width(),
+                sent.height()
+            );
+            Ok(image_output(&base64_string, &caption))
         })
     }
 }
@@ -0,0 +1,134 @@
+use anyhow::Result;
+use keyring::Entry;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// Keychain service every secret is filed under. Credentials live in the OS keychain
+/// (macOS Keychain, Windows Credential Manager, the Secret Service on Linux) so they never
+/// sit in amadeus.toml; keys are namespaced by their user, e.g. "email:work".
+const SERVICE: &str = "Amadeus";
+
+/// Keychains can't be enumerated, so the names of saved keys are kept under this one
+const INDEX_KEY: &str = "_index";
+
+/// Values shorter than this aren't redacted; replacing every "1234" in a prompt does more harm
+const MIN_REDACT_LEN: usize = 6;
+
+/// Every secret read or saved by this process, by key, so `redact` can find them in text
+static KNOWN: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+fn entry(key: &str) -> Result<Entry> {
+    Entry::new(SERVICE, key).map_err(|e| anyhow::anyhow!("Keychain unavailable: {}", e))
+}
+
+fn read(key: &str) -> Result<Option<String>> {
+    match entry(key)?.get_password() {
+        Ok(secret) => Ok(Some(secret)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(anyhow::anyhow!(
+            "Failed to read {} from the keychain: {}",
+            key,
+            e
+        )),
+    }
+}
+
+fn write(key: &str, secret: &str) -> Result<()> {
+    entry(key)?
+        .set_password(secret)
+        .map_err(|e| anyhow::anyhow!("Failed to save {} to the keychain: {}", key, e))
+}
+
+fn write_index(keys: &[String]) -> Result<()> {
+    write(INDEX_KEY, &serde_json::to_string(keys)?)
+}
+
+/// Names of every saved secret, sorted
+pub fn list() -> Result<Vec<String>> {
+    let Some(index) = read(INDEX_KEY)? else {
+        return Ok(Vec::new());
+    };
+    let mut keys: Vec<String> = serde_json::from_str(&index).unwrap_or_default();
+    keys.sort();
+    keys.dedup();
+    Ok(keys)
+}
+
+/// The stored secret, or `None` if nothing has been saved under `key`
+pub fn get(key: &str) -> Result<Option<String>> {
+    let secret = read(key)?;
+    if let Some(secret) = &secret {
+        KNOWN
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), secret.clone());
+    }
+    Ok(secret)
+}
+
+pub fn set(key: &str, secret: &str) -> Result<()> {
+    if key.trim().is_empty() || key == INDEX_KEY {
+        return Err(anyhow::anyhow!("Invalid secret name: '{}'", key));
+    }
+    write(key, secret)?;
+    KNOWN
+        .lock()
+        .unwrap()
+        .insert(key.to_string(), secret.to_string());
+    let mut keys = list()?;
+    if !keys.iter().any(|k| k == key) {
+        keys.push(key.to_string());
+        write_index(&keys)?;
+    }
+    Ok(())
+}
+
+/// Forget `key`; deleting something that isn't there is not an error
+pub fn delete(key: &str) -> Result<()> {
+    match entry(key)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => {}
+        Err(e) => {
+            return Err(anyhow::anyhow!(
+                "Failed to remove {} from the keychain: {}",
+                key,
+                e
+            ))
+        }
+    }
+    KNOWN.lock().unwrap().remove(key);
+    let mut keys = list()?;
+    if keys.iter().any(|k| k == key) {
+        keys.retain(|k| k != key);
+        write_index(&keys)?;
+    }
+    Ok(())
+}
+
+/// Read every saved secret once so `redact` knows them before any tool has asked.
+/// Called at startup off the main thread; the keychain may prompt for access.
+pub fn load_all() -> Result<()> {
+    for key in list()? {
+        get(&key)?;
+    }
+    Ok(())
+}
+
+/// `text` with every known secret value replaced by `[secret:<name>]`, so an API key a
+/// tool echoed back (or the user pasted) never reaches the model
+pub fn redact(text: &str) -> String {
+    let known = KNOWN.lock().unwrap();
+    // Longest first, so a secret containing another is replaced whole
+    let mut secrets: Vec<(&String, &String)> = known
+        .iter()
+        .filter(|(_, value)| value.chars().count() >= MIN_REDACT_LEN)
+        .collect();
+    secrets.sort_by_key(|(_, value)| std::cmp::Reverse(value.len()));
+
+    let mut text = text.to_string();
+    for (key, value) in secrets {
+        if text.contains(value.as_str()) {
+            text = text.replace(value.as_str(), &format!("[secret:{}]", key));
+        }
+    }
+    text
+}
@@ -0,0 +1,302 @@
+use serde_json::{json, Value};
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::process::Command;
+
+use crate::agent::tools::{Tool, ToolResult};
+use crate::config::ShellSettings;
+use crate::system::sandbox::Sandbox;
+
+/// Runs one program with arguments in a workspace folder. There is no shell in between, so
+/// pipes, globs and `&&` aren't available and the allowlist sees exactly what runs.
+pub struct ShellTool {
+    settings: ShellSettings,
+    sandbox: Arc<Sandbox>,
+}
+
+impl ShellTool {
+    pub fn new(settings: ShellSettings, sandbox: Arc<Sandbox>) -> Self {
+        Self { settings, sandbox }
+    }
+
+    /// Program and arguments from a call
+    fn command_line(args: &Value) -> Result<Vec<String>, anyhow::Error> {
+        let program = args["command"]
+            .as_str()
+            .filter(|c| !c.trim().is_empty())
+            .ok_or_else(|| anyhow::anyhow!("Missing command"))?;
+        let mut line = vec![program.trim().to_string()];
+        if let Some(rest) = args["args"].as_array() {
+            for arg in rest {
+                let arg = arg
+                    .as_str()
+                    .ok_or_else(|| anyhow::anyhow!("args must be strings"))?;
+                line.push(arg.to_string());
+            }
+        }
+        Ok(line)
+    }
+
+    /// `git` for `git`, `/usr/bin/git` or `git.exe`, so a path can't dodge the lists
+    fn program_name(program: &str) -> String {
+        let name = Path::new(program)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        name.strip_suffix(".exe").unwrap_or(&name).to_string()
+    }
+
+    /// A deny entry also covers versioned names, so `python` denies `python3.12`
+    fn is_denied(&self, line: &[String]) -> bool {
+        let program = Self::program_name(&line[0]);
+        self.settings.deny.iter().any(|denied| {
+            program
+                .strip_prefix(denied.to_lowercase().as_str())
+                .is_some_and(|version| version.chars().all(|c| c.is_ascii_digit() || c == '.'))
+        })
+    }
+
+    /// An allowlist entry matches when its words start the command line, so `git` allows
+    /// every git command and `git status` only that one
+    fn is_allowed(&self, line: &[String]) -> bool {
+        let program = Self::program_name(&line[0]);
+        self.settings.allow.iter().any(|entry| {
+            let mut words = entry.split_whitespace();
+            words.next() == Some(program.as_str())
+                && words
+                    .enumerate()
+                    .all(|(i, word)| line.get(i + 1).is_some_and(|arg| arg == word))
+        })
+    }
+
+    /// Arguments that may reach outside the workspace, which an allowlisted command
+    /// must not do without asking. Paths glued to a flag (`--output=/x`, `-C/x`) count too.
+    fn escapes_workspace(line: &[String]) -> bool {
+        let outside = |value: &str| {
+            let drive = value.as_bytes().get(1) == Some(&b':')
+                && value
+                    .chars()
+                    .next()
+                    .is_some_and(|c| c.is_ascii_alphabetic());
+            Path::new(value).is_absolute()
+                || value.starts_with(['/', '\\', '~'])
+                || value.contains("..")
+                || drive
+        };
+        line[1..].iter().any(|arg| {
+            let after_equals = arg.split_once('=').map(|(_, v)| v);
+            let after_short_flag = arg
+                .strip_prefix('-')
+                .filter(|rest| !rest.starts_with('-'))
+                .and_then(|rest| rest.get(1..));
+            outside(arg)
+                || after_equals.is_some_and(outside)
+                || after_short_flag.is_some_and(outside)
+        })
+    }
+
+    /// Keep the last `max` characters; build and test output ends with what matters
+    fn tail(text: &str, max: usize) -> String {
+        let total = text.chars().count();
+        if total <= max {
+            return text.trim_end().to_string();
+        }
+        let kept: String = text.chars().skip(total - max).collect();
+        format!(
+            "[Truncated: last {} of {} chars]\n{}",
+            max,
+            total,
+            kept.trim_end()
+        )
+    }
+}
+
+impl Tool for ShellTool {
+    fn name(&self) -> &str {
+        "shell"
+    }
+
+    fn description(&self) -> &str {
+        "Run a program in the workspace folder, e.g. builds, tests and git. Give the program \
+         as 'command' and each argument separately in 'args'; there is no shell, so pipes, \
+         redirection and && don't work. Returns the exit code and output."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "command": { "type": "string", "description": "Program to run, e.g. 'git'" },
+                "args": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Arguments, e.g. [\"log\", \"--oneline\", \"-5\"]"
+                },
+                "cwd": { "type": "string", "description": "Working directory relative to the workspace (default: its first folder)" }
+            },
+            "required": ["command"]
+        })
+    }
+
+    fn execute(&self, args: Value) -> Pin<Box<dyn Future<Output = ToolResult> + Send>> {
+        let settings = self.settings.clone();
+        let sandbox = self.sandbox.clone();
+        let line = Self::command_line(&args);
+        let denied = line.as_ref().is_ok_and(|line| self.is_denied(line));
+        Box::pin(async move {
+            let line = line?;
+            if denied {
+                return Err(anyhow::anyhow!(
+                    "'{}' is not allowed ([shell] deny)",
+                    Self::program_name(&line[0])
+                ));
+            }
+            let cwd = sandbox.resolve(args["cwd"].as_str().unwrap_or("."))?;
+            if !cwd.is_dir() {
+                return Err(anyhow::anyhow!("Not a directory: {}", cwd.display()));
+            }
+
+            let mut command = Command::new(&line[0]);
+            command
+                .args(&line[1..])
+                .current_dir(&cwd)
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                // A timed-out command is killed when its future is dropped
+                .kill_on_drop(true);
+            #[cfg(windows)]
+            {
+                const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+                command.creation_flags(CREATE_NO_WINDOW);
+            }
+
+            let child = command
+                .spawn()
+                .map_err(|e| anyhow::anyhow!("Failed to start {}: {}", line[0], e))?;
+            let timeout = Duration::from_secs(settings.timeout_secs);
+            let output = tokio::time::timeout(timeout, child.wait_with_output())
+                .await
+                .map_err(|_| {
+                    anyhow::anyhow!(
+                        "'{}' timed out after {}s and was stopped",
+                        line.join(" "),
+                        settings.timeout_secs
+                    )
+                })??;
+
+            let exit = output
+                .status
+                .code()
+                .map_or("killed by signal".to_string(), |c| c.to_string());
+            let mut result = format!("$ {}\nExit code: {}", line.join(" "), exit);
+            for (label, bytes) in [("stdout", &output.stdout), ("stderr", &output.stderr)] {
+                let text = String::from_utf8_lossy(bytes);
+                if !text.trim().is_empty() {
+                    result.push_str(&format!(
+                        "\n--- {} ---\n{}",
+                        label,
+                        Self::tail(&text, settings.max_output_chars)
+                    ));
+                }
+            }
+            Ok(result)
+        })
+    }
+
+    /// `[shell] timeout_secs` governs commands; the margin lets its own, more specific
+    /// timeout message come through first
+    fn timeout(&self) -> Option<Duration> {
+        Some(Duration::from_secs(self.settings.timeout_secs + 5))
+    }
+
+    fn requires_approval(&self, args: &Value) -> bool {
+        match Self::command_line(args) {
+            Ok(line) => !self.is_allowed(&line) || Self::escapes_workspace(&line),
+            Err(_) => true,
+        }
+    }
+
+    /// Commands outside the allowlist are approved one at a time
+    fn can_always_allow(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shell() -> ShellTool {
+        ShellTool::new(ShellSettings::default(), Arc::new(Sandbox::new(&[])))
+    }
+
+    fn line(command: &str) -> Vec<String> {
+        command.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn program_name_ignores_paths_case_and_exe() {
+        assert_eq!(ShellTool::program_name("git"), "git");
+        assert_eq!(ShellTool::program_name("/usr/bin/git"), "git");
+        assert_eq!(ShellTool::program_name("Git.EXE"), "git");
+        assert_eq!(ShellTool::program_name("./node_modules/.bin/tsc"), "tsc");
+    }
+
+    #[test]
+    fn allowlist_matches_leading_words() {
+        let shell = shell();
+        assert!(shell.is_allowed(&line("git status")));
+        assert!(shell.is_allowed(&line("/usr/bin/git log --oneline -5")));
+        assert!(shell.is_allowed(&line("ls -la src")));
+        assert!(!shell.is_allowed(&line("git")));
+        assert!(!shell.is_allowed(&line("git push origin main")));
+        assert!(!shell.is_allowed(&line("git -c core.pager=less log")));
+        assert!(!shell.is_allowed(&line("cargo build")));
+        assert!(!shell.is_allowed(&line("npm run build")));
+    }
+
+    #[test]
+    fn denylist_covers_shells_interpreters_and_wrappers() {
+        let shell = shell();
+        for command in [
+            "bash -c ls",
+            "/bin/sh",
+            "PowerShell.exe",
+            "env rm -rf .",
+            "xargs rm",
+            "python3 -c print(1)",
+            "python3.12 script.py",
+            "node -e 1",
+            "perl -e 1",
+            "busybox rm x",
+        ] {
+            assert!(shell.is_denied(&line(command)), "{}", command);
+        }
+        assert!(!shell.is_denied(&line("git status")));
+        assert!(!shell.is_denied(&line("shutdown-helper")));
+        assert!(!shell.is_denied(&line("nodemon")));
+    }
+
+    #[test]
+    fn paths_outside_the_workspace_are_flagged() {
+        for command in [
+            "ls /etc",
+            "ls ~/.ssh",
+            "ls ../other",
+            "git diff --output=/tmp/x",
+            "git -C/etc status",
+            "ls C:\\Windows",
+            "ls \\\\server\\share",
+        ] {
+            assert!(ShellTool::escapes_workspace(&line(command)), "{}", command);
+        }
+        for command in ["ls", "ls src", "git log --oneline -5", "git diff HEAD~1"] {
+            assert!(!ShellTool::escapes_workspace(&line(command)), "{}", command);
+        }
+    }
+}
@@ -0,0 +1,277 @@
+use serde_json::{json, Value};
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+use sysinfo::{Components, Disks, System};
+
+use crate::agent::tools::{Tool, ToolResult};
+
+/// CPU usage is measured between two refreshes this far apart
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+const DEFAULT_TOP_PROCESSES: usize = 5;
+const MAX_TOP_PROCESSES: usize = 20;
+const GB: f64 = 1024.0 * 1024.0 * 1024.0;
+/// Live stats go stale quickly, so a cached report is only reused briefly
+const CACHE_TTL: Duration = Duration::from_secs(10);
+
+/// Live machine stats (CPU, memory, disks, battery, temperatures, busiest processes),
+/// so questions like "why is my fan so loud?" get answered from real numbers
+pub struct SystemInfoTool;
+
+struct Battery {
+    percent: u32,
+    /// e.g. "charging", "discharging", "plugged in"
+    state: String,
+}
+
+impl SystemInfoTool {
+    fn report(sections: &[String], top: usize, sort_by_memory: bool) -> String {
+        let wants = |section: &str| sections.is_empty() || sections.iter().any(|s| s == section);
+        let mut out = Vec::new();
+
+        let mut sys = System::new_all();
+        if wants("cpu") || wants("processes") {
+            std::thread::sleep(SAMPLE_INTERVAL);
+            sys.refresh_all();
+        }
+
+        if wants("cpu") {
+            let load = System::load_average();
+            let mut line = format!(
+                "CPU: {:.0}% across {} cores",
+                sys.global_cpu_usage(),
+                sys.cpus().len()
+            );
+            if load.one > 0.0 {
+                line.push_str(&format!(
+                    " (load average {:.2} {:.2} {:.2})",
+                    load.one, load.five, load.fifteen
+                ));
+            }
+            out.push(line);
+        }
+
+        if wants("memory") {
+            let (used, total) = (sys.used_memory() as f64, sys.total_memory() as f64);
+            let mut line = format!(
+                "Memory: {:.1} / {:.1} GB used ({:.0}%)",
+                used / GB,
+                total / GB,
+                percent(used, total)
+            );
+            if sys.total_swap() > 0 {
+                line.push_str(&format!(
+                    ", swap {:.1} / {:.1} GB",
+                    sys.used_swap() as f64 / GB,
+                    sys.total_swap() as f64 / GB
+                ));
+            }
+            out.push(line);
+        }
+
+        if wants("disks") {
+            let disks = Disks::new_with_refreshed_list();
+            out.push("Disks:".to_string());
+            for disk in disks.list() {
+                let total = disk.total_space() as f64;
+                if total == 0.0 {
+                    continue;
+                }
+                let used = total - disk.available_space() as f64;
+                out.push(format!(
+                    "- {}: {:.1} / {:.1} GB used ({:.0}%)",
+                    disk.mount_point().display(),
+                    used / GB,
+                    total / GB,
+                    percent(used, total)
+                ));
+            }
+        }
+
+        if wants("battery") {
+            out.push(match battery() {
+                Some(b) => format!("Battery: {}%, {}", b.percent, b.state),
+                None => "Battery: none found".to_string(),
+            });
+        }
+
+        if wants("temperatures") {
+            let components = Components::new_with_refreshed_list();
+            let readings: Vec<String> = components
+                .list()
+                .iter()
+                .filter(|c| c.temperature() > 0.0)
+                .map(|c| format!("{} {:.0}°C", c.label(), c.temperature()))
+                .collect();
+            if !readings.is_empty() {
+                out.push(format!("Temperatures: {}", readings.join(", ")));
+            } else if !sections.is_empty() {
+                out.push("Temperatures: no sensors readable".to_string());
+            }
+        }
+
+        if wants("processes") {
+            let mut processes: Vec<_> = sys.processes().values().collect();
+            if sort_by_memory {
+                processes.sort_by_key(|p| std::cmp::Reverse(p.memory()));
+            } else {
+                processes.sort_by(|a, b| b.cpu_usage().total_cmp(&a.cpu_usage()));
+            }
+            out.push(format!(
+                "Top processes by {} (CPU 100% = one core):",
+                if sort_by_memory { "memory" } else { "CPU" }
+            ));
+            for process in processes.into_iter().take(top) {
+                out.push(format!(
+                    "- {} (pid {}): {:.0}% CPU, {:.2} GB",
+                    process.name().to_string_lossy(),
+                    process.pid(),
+                    process.cpu_usage(),
+                    process.memory() as f64 / GB
+                ));
+            }
+        }
+
+        out.join("\n")
+    }
+}
+
+fn percent(part: f64, whole: f64) -> f64 {
+    if whole > 0.0 {
+        part / whole * 100.0
+    } else {
+        0.0
+    }
+}
+
+/// `pmset -g batt`: "... -InternalBattery-0 (id=...)	85%; discharging; 3:12 remaining ..."
+#[cfg(target_os = "macos")]
+fn battery() -> Option<Battery> {
+    let output = std::process::Command::new("pmset")
+        .args(["-g", "batt"])
+        .output()
+        .ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let line = text.lines().find(|l| l.contains("InternalBattery"))?;
+    let mut fields = line.split('\t').nth(1)?.split(';').map(str::trim);
+    let percent = fields.next()?.trim_end_matches('%').parse().ok()?;
+    let state = fields.next().unwrap_or("unknown").to_string();
+    Some(Battery { percent, state })
+}
+
+#[cfg(windows)]
+fn battery() -> Option<Battery> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    let output = std::process::Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-NonInteractive",
+            "-Command",
+            "Get-CimInstance Win32_Battery | Select-Object -First 1 | \
+             ForEach-Object { \"$($_.EstimatedChargeRemaining)`t$($_.BatteryStatus)\" }",
+        ])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let (percent, status) = text.trim().split_once('\t')?;
+    // Win32_Battery.BatteryStatus: 1 = discharging, 2 = on AC, 6-9 = charging
+    let state = match status {
+        "1" => "discharging",
+        "2" => "plugged in",
+        "6" | "7" | "8" | "9" => "charging",
+        _ => "unknown",
+    };
+    Some(Battery {
+        percent: percent.parse().ok()?,
+        state: state.to_string(),
+    })
+}
+
+/// The first battery under /sys/class/power_supply
+#[cfg(not(any(target_os = "macos", windows)))]
+fn battery() -> Option<Battery> {
+    let entries = std::fs::read_dir("/sys/class/power_supply").ok()?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let kind = std::fs::read_to_string(path.join("type")).unwrap_or_default();
+        if kind.trim() != "Battery" {
+            continue;
+        }
+        let Ok(capacity) = std::fs::read_to_string(path.join("capacity")) else {
+            continue;
+        };
+        let status = std::fs::read_to_string(path.join("status")).unwrap_or_default();
+        return Some(Battery {
+            percent: capacity.trim().parse().ok()?,
+            state: status.trim().to_lowercase(),
+        });
+    }
+    None
+}
+
+impl Tool for SystemInfoTool {
+    fn name(&self) -> &str {
+        "system_info"
+    }
+
+    fn description(&self) -> &str {
+        "Live stats about this computer: CPU load, memory, disk usage, battery, temperatures and the busiest processes. Use it to explain slowness, fan noise or low disk space with real numbers."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "sections": {
+                    "type": "array",
+                    "items": {
+                        "type": "string",
+                        "enum": ["cpu", "memory", "disks", "battery", "temperatures", "processes"]
+                    },
+                    "description": "What to report; all when omitted"
+                },
+                "top": { "type": "integer", "description": "How many processes to list (default 5)" },
+                "sort_by": {
+                    "type": "string",
+                    "enum": ["cpu", "memory"],
+                    "description": "Process ordering (default cpu)"
+                }
+            },
+            "required": []
+        })
+    }
+
+    fn execute(&self, args: Value) -> Pin<Box<dyn Future<Output = ToolResult> + Send>> {
+        Box::pin(async move {
+            let sections: Vec<String> = args["sections"]
+                .as_array()
+                .map(|list| {
+                    list.iter()
+                        .filter_map(|s| s.as_str().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default();
+            let top = args["top"]
+                .as_u64()
+                .map_or(DEFAULT_TOP_PROCESSES, |n| n as usize)
+                .min(MAX_TOP_PROCESSES);
+            let sort_by_memory = args["sort_by"].as_str() == Some("memory");
+            // Sampling sleeps and the battery query spawns a process
+            Ok(tokio::task::spawn_blocking(move || {
+                SystemInfoTool::report(&sections, top, sort_by_memory)
+            })
+            .await?)
+        })
+    }
+
+    /// Every query only reads, so a repeat within a few seconds reuses the report
+    fn is_cacheable(&self, _args: &Value) -> bool {
+        true
+    }
+
+    fn cache_ttl(&self) -> Duration {
+        CACHE_TTL
+    }
+}
@@ -0,0 +1,118 @@
+use anyhow::Result;
+use std::sync::Arc;
+use tauri::menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Emitter, Manager, Wry};
+use tokio::sync::Mutex;
+use tracing::warn;
+
+use crate::config::AppConfig;
+use crate::AppState;
+
+/// Sent after the tray changes a setting so the settings panel reloads
+pub const SETTINGS_CHANGED_EVENT: &str = "settings-changed";
+
+const TRAY_ID: &str = "main";
+const MAIN_WINDOW: &str = "main";
+
+/// Menu items that change after the tray is built
+struct TrayMenu {
+    status: MenuItem<Wry>,
+    mute: CheckMenuItem<Wry>,
+}
+
+/// Put Amadeus in the system tray: the agent status, plus show/hide, mute, new session
+/// and quit
+pub fn init(app: &AppHandle, tts_enabled: bool) -> Result<()> {
+    let status = MenuItem::with_id(app, "status", "Starting...", false, None::<&str>)?;
+    let toggle = MenuItem::with_id(app, "toggle", "Show/Hide window", true, None::<&str>)?;
+    let mute = CheckMenuItem::with_id(app, "mute", "Mute voice", true, !tts_enabled, None::<&str>)?;
+    let new = MenuItem::with_id(app, "new", "New session", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+    let menu = Menu::with_items(
+        app,
+        &[
+            &status,
+            &PredefinedMenuItem::separator(app)?,
+            &toggle,
+            &mute,
+            &new,
+            &PredefinedMenuItem::separator(app)?,
+            &quit,
+        ],
+    )?;
+
+    let mut tray = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("Amadeus")
+        .menu(&menu)
+        .on_menu_event(|app, event| match event.id().as_ref() {
+            "toggle" => toggle_window(app),
+            "mute" => {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = toggle_voice(&app).await {
+                        warn!("[Tray] Failed to toggle voice: {}", e);
+                    }
+                });
+            }
+            "new" => {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    let state = app.state::<Arc<Mutex<AppState>>>();
+                    let _ = state.lock().await.tx.send("/new".into());
+                });
+            }
+            "quit" => app.exit(0),
+            _ => {}
+        });
+    if let Some(icon) = app.default_window_icon() {
+        tray = tray.icon(icon.clone());
+    }
+    tray.build(app)?;
+    app.manage(TrayMenu { status, mute });
+    Ok(())
+}
+
+/// Show the agent status (the same line as the chat header) in the tray
+pub fn set_status(app: &AppHandle, status: &str) {
+    let status = if status == "Online" { "Idle" } else { status };
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_tooltip(Some(format!("Amadeus - {}", status)));
+    }
+    if let Some(menu) = app.try_state::<TrayMenu>() {
+        let _ = menu.status.set_text(status);
+    }
+}
+
+/// Keep the mute check in step with the settings panel
+pub fn set_muted(app: &AppHandle, muted: bool) {
+    if let Some(menu) = app.try_state::<TrayMenu>() {
+        let _ = menu.mute.set_checked(muted);
+    }
+}
+
+fn toggle_window(app: &AppHandle) {
+    let Some(window) = app.get_webview_window(MAIN_WINDOW) else {
+        return;
+    };
+    if window.is_visible().unwrap_or(false) {
+        let _ = window.hide();
+    } else {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// Flip `[tts] enabled`, saving it like the settings panel does
+async fn toggle_voice(app: &AppHandle) -> Result<()> {
+    let mut settings = AppConfig::load().settings();
+    settings.tts_enabled = !settings.tts_enabled;
+    AppConfig::save_settings(&settings)?;
+    let state = app.state::<Arc<Mutex<AppState>>>();
+    if let Some(tts) = &state.lock().await.tts {
+        tts.set_enabled(settings.tts_enabled);
+    }
+    set_muted(app, !settings.tts_enabled);
+    let _ = app.emit(SETTINGS_CHANGED_EVENT, ());
+    Ok(())
+}
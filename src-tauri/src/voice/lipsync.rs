@@ -0,0 +1,81 @@
+use rodio::Source;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Tauri event carrying `LipSyncEvent`s while speech plays
+pub const LIP_SYNC_EVENT: &str = "lip-sync";
+
+/// Level updates per second, about one per rendered frame
+const LEVELS_PER_SECOND: u32 = 30;
+
+/// Speech RMS rarely goes past ~0.25 of full scale; scale it up so the mouth opens fully
+const LEVEL_GAIN: f32 = 4.0;
+
+/// How far the avatar's mouth should be open, 0.0 - 1.0
+#[derive(Clone, Serialize)]
+pub struct LipSyncEvent {
+    pub level: f32,
+}
+
+/// Receives the level of each window of played audio
+pub type LevelCallback = Arc<dyn Fn(f32) + Send + Sync>;
+
+/// Passes samples through unchanged while reporting the RMS level of every
+/// 1/`LEVELS_PER_SECOND` second window
+pub struct LevelMeter<S> {
+    inner: S,
+    window: usize,
+    sum_squares: f32,
+    count: usize,
+    on_level: LevelCallback,
+}
+
+impl<S: Source<Item = i16>> LevelMeter<S> {
+    pub fn new(inner: S, on_level: LevelCallback) -> Self {
+        let samples_per_second = inner.sample_rate() * inner.channels() as u32;
+        Self {
+            window: (samples_per_second / LEVELS_PER_SECOND).max(1) as usize,
+            inner,
+            sum_squares: 0.0,
+            count: 0,
+            on_level,
+        }
+    }
+}
+
+impl<S: Source<Item = i16>> Iterator for LevelMeter<S> {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        let sample = self.inner.next()?;
+        let x = sample as f32 / i16::MAX as f32;
+        self.sum_squares += x * x;
+        self.count += 1;
+        if self.count >= self.window {
+            let rms = (self.sum_squares / self.count as f32).sqrt();
+            (self.on_level)((rms * LEVEL_GAIN).min(1.0));
+            self.sum_squares = 0.0;
+            self.count = 0;
+        }
+        Some(sample)
+    }
+}
+
+impl<S: Source<Item = i16>> Source for LevelMeter<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+}
@@ -0,0 +1,289 @@
+use anyhow::Result;
+use cpal::traits::StreamTrait;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc as tokio_mpsc;
+
+use crate::config::VoiceInputSettings;
+use crate::voice::stt;
+
+/// Tauri event reporting `ListenState` changes
+pub const VOICE_LISTENING_EVENT: &str = "voice-listening";
+
+/// Tauri event carrying `SttCaption`s
+pub const STT_CAPTION_EVENT: &str = "stt-caption";
+
+/// Tauri event carrying `MicLevel`s while the microphone is open
+pub const MIC_LEVEL_EVENT: &str = "mic-level";
+
+/// While speech continues, the utterance so far is re-transcribed this often
+const PARTIAL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// After the wake word alone, the next utterance within this window is the message
+pub const WAKE_WINDOW: Duration = Duration::from_secs(8);
+
+/// Analysis frame for voice activity detection
+const FRAME_MS: u32 = 30;
+/// Audio kept from before speech was detected, so the first syllable isn't clipped
+const PREROLL_MS: u32 = 300;
+/// Speech is this many times louder than the tracked background noise
+const NOISE_RATIO: f32 = 3.0;
+
+/// How often the microphone level is reported
+const MIC_LEVEL_INTERVAL: Duration = Duration::from_millis(50);
+/// Microphone speech RMS sits far below full scale; scale it up so the meter uses its range
+const MIC_LEVEL_GAIN: f32 = 8.0;
+
+/// What continuous listening is doing, for the UI
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ListenState {
+    Off,
+    /// Waiting for speech
+    Listening,
+    /// The wake word was heard; the next utterance is the message
+    Awake,
+    /// Speech in progress
+    Hearing,
+    Transcribing,
+}
+
+/// Live caption of what the user is saying
+#[derive(Debug, Clone, Serialize)]
+pub struct SttCaption {
+    pub text: String,
+    /// The utterance has ended; this is what gets sent
+    #[serde(rename = "final")]
+    pub is_final: bool,
+}
+
+/// How loud the microphone input is, 0.0 - 1.0
+#[derive(Debug, Clone, Serialize)]
+pub struct MicLevel {
+    pub level: f32,
+}
+
+/// One endpointed stretch of speech, or a snapshot of one still in progress
+pub struct Utterance {
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+    /// Speech so far, for a live caption; the complete utterance follows later
+    pub partial: bool,
+}
+
+/// Energy-based voice activity detection with endpointing. Frames louder than both
+/// `min_level` and a multiple of the background noise count as speech; an utterance
+/// starts after `min_speech_ms` of it and ends after `end_silence_ms` of silence.
+pub struct VoiceActivityDetector {
+    frame_len: usize,
+    start_frames: usize,
+    end_frames: usize,
+    max_samples: usize,
+    min_level: f32,
+    noise_floor: Option<f32>,
+    /// Samples not yet making up a whole frame
+    partial: Vec<f32>,
+    preroll: VecDeque<f32>,
+    preroll_len: usize,
+    speech: Vec<f32>,
+    speech_frames: usize,
+    silence_frames: usize,
+    in_speech: bool,
+}
+
+impl VoiceActivityDetector {
+    pub fn new(sample_rate: u32, settings: &VoiceInputSettings) -> Self {
+        let frame_len = (sample_rate * FRAME_MS / 1000).max(1) as usize;
+        let frames = |ms: u32| (ms / FRAME_MS).max(1) as usize;
+        Self {
+            frame_len,
+            start_frames: frames(settings.min_speech_ms),
+            end_frames: frames(settings.end_silence_ms),
+            max_samples: (sample_rate * settings.max_utterance_secs) as usize,
+            min_level: settings.min_level,
+            noise_floor: None,
+            partial: Vec::new(),
+            preroll: VecDeque::new(),
+            preroll_len: (sample_rate * PREROLL_MS / 1000) as usize,
+            speech: Vec::new(),
+            speech_frames: 0,
+            silence_frames: 0,
+            in_speech: false,
+        }
+    }
+
+    /// True while an utterance is being recorded
+    pub fn in_speech(&self) -> bool {
+        self.in_speech
+    }
+
+    /// The utterance recorded so far; empty outside speech
+    pub fn current(&self) -> &[f32] {
+        &self.speech
+    }
+
+    /// End the utterance in progress now instead of waiting for silence. Returns what
+    /// was recorded, empty if speech hadn't started.
+    pub fn finish(&mut self) -> Vec<f32> {
+        self.in_speech = false;
+        self.speech_frames = 0;
+        self.silence_frames = 0;
+        std::mem::take(&mut self.speech)
+    }
+
+    /// Feed mono samples; returns the utterances they completed
+    pub fn push(&mut self, samples: &[f32]) -> Vec<Vec<f32>> {
+        self.partial.extend_from_slice(samples);
+        let mut finished = Vec::new();
+        while self.partial.len() >= self.frame_len {
+            let frame: Vec<f32> = self.partial.drain(..self.frame_len).collect();
+            if let Some(utterance) = self.push_frame(&frame) {
+                finished.push(utterance);
+            }
+        }
+        finished
+    }
+
+    fn push_frame(&mut self, frame: &[f32]) -> Option<Vec<f32>> {
+        let rms = (frame.iter().map(|x| x * x).sum::<f32>() / frame.len() as f32).sqrt();
+        let noise = *self.noise_floor.get_or_insert(rms);
+        let voiced = rms > self.min_level.max(noise * NOISE_RATIO);
+        if !voiced {
+            // Track the background slowly so a fan or music doesn't count as speech
+            self.noise_floor = Some(noise * 0.95 + rms * 0.05);
+        }
+
+        if self.in_speech {
+            self.speech.extend_from_slice(frame);
+            self.silence_frames = if voiced { 0 } else { self.silence_frames + 1 };
+            if self.silence_frames >= self.end_frames || self.speech.len() >= self.max_samples {
+                self.in_speech = false;
+                self.silence_frames = 0;
+                return Some(std::mem::take(&mut self.speech));
+            }
+            return None;
+        }
+
+        if voiced {
+            self.speech_frames += 1;
+            if self.speech_frames >= self.start_frames {
+                self.in_speech = true;
+                self.speech_frames = 0;
+                self.speech = self.preroll.drain(..).collect();
+                self.speech.extend_from_slice(frame);
+                return None;
+            }
+        } else {
+            self.speech_frames = 0;
+        }
+        self.preroll.extend(frame);
+        while self.preroll.len() > self.preroll_len {
+            self.preroll.pop_front();
+        }
+        None
+    }
+}
+
+/// Capture from the default microphone on a dedicated thread (cpal streams aren't Send)
+/// until `stop` is set. Finished utterances go to `utterances`, preceded by a partial
+/// snapshot every `PARTIAL_INTERVAL` while speech goes on; `on_state` hears when speech starts
+/// and `on_level` gets the input level every `MIC_LEVEL_INTERVAL`. Setting `finish` ends the
+/// current utterance right away (an empty one if no speech had started).
+pub fn spawn_capture<F, L>(
+    settings: VoiceInputSettings,
+    stop: Arc<AtomicBool>,
+    finish: Arc<AtomicBool>,
+    utterances: tokio_mpsc::UnboundedSender<Utterance>,
+    on_state: F,
+    on_level: L,
+) -> Result<()>
+where
+    F: Fn(ListenState) + Send + 'static,
+    L: Fn(f32) + Send + 'static,
+{
+    // The thread reports whether the microphone opened before the caller returns
+    let (ready_tx, ready_rx) = mpsc::sync_channel::<Result<()>>(1);
+    std::thread::spawn(move || {
+        let (audio_tx, audio_rx) = mpsc::channel::<Vec<f32>>();
+        let opened = stt::open_input(move |mono| {
+            let _ = audio_tx.send(mono.to_vec());
+        })
+        .and_then(|(stream, sample_rate)| {
+            stream.play()?;
+            Ok((stream, sample_rate))
+        });
+        let (_stream, sample_rate) = match opened {
+            Ok(opened) => {
+                let _ = ready_tx.send(Ok(()));
+                opened
+            }
+            Err(e) => {
+                let _ = ready_tx.send(Err(e));
+                return;
+            }
+        };
+
+        let mut vad = VoiceActivityDetector::new(sample_rate, &settings);
+        let mut last_partial = Instant::now();
+        let mut last_level = Instant::now();
+        let (mut sum_squares, mut level_samples) = (0.0f32, 0usize);
+        while !stop.load(Ordering::SeqCst) {
+            if finish.swap(false, Ordering::SeqCst) {
+                let _ = utterances.send(Utterance {
+                    samples: vad.finish(),
+                    sample_rate,
+                    partial: false,
+                });
+            }
+            let Ok(samples) = audio_rx.recv_timeout(Duration::from_millis(100)) else {
+                continue;
+            };
+            sum_squares += samples.iter().map(|x| x * x).sum::<f32>();
+            level_samples += samples.len();
+            if last_level.elapsed() >= MIC_LEVEL_INTERVAL && level_samples > 0 {
+                let rms = (sum_squares / level_samples as f32).sqrt();
+                on_level((rms * MIC_LEVEL_GAIN).min(1.0));
+                (sum_squares, level_samples) = (0.0, 0);
+                last_level = Instant::now();
+            }
+            let was_speaking = vad.in_speech();
+            for samples in vad.push(&samples) {
+                let _ = utterances.send(Utterance {
+                    samples,
+                    sample_rate,
+                    partial: false,
+                });
+            }
+            if vad.in_speech() && !was_speaking {
+                on_state(ListenState::Hearing);
+                last_partial = Instant::now();
+            } else if vad.in_speech() && last_partial.elapsed() >= PARTIAL_INTERVAL {
+                let _ = utterances.send(Utterance {
+                    samples: vad.current().to_vec(),
+                    sample_rate,
+                    partial: true,
+                });
+                last_partial = Instant::now();
+            }
+        }
+    });
+    ready_rx.recv()?
+}
+
+/// Check `text` for the wake word. Returns `None` if it isn't there, otherwise what was
+/// said after it (empty when the wake word was said on its own).
+pub fn after_wake_word(text: &str, wake_word: &str) -> Option<String> {
+    let lower = text.to_lowercase();
+    let at = lower.find(&wake_word.to_lowercase())?;
+    // Lowercasing can change byte lengths, so map the match end back through char counts
+    let skip = lower[..at + wake_word.to_lowercase().len()].chars().count();
+    let rest: String = text.chars().skip(skip).collect();
+    Some(
+        rest.trim_start_matches(|c: char| c.is_whitespace() || c.is_ascii_punctuation())
+            .trim()
+            .to_string(),
+    )
+}
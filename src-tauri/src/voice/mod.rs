@@ -1,2 +1,6 @@
+pub mod lipsync;
+pub mod listener;
+pub mod piper;
 pub mod stt;
 pub mod tts;
+pub mod tts_backend;
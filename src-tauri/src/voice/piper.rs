@@ -0,0 +1,153 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Downloaded piper voices live here as `<id>.onnx` + `<id>.onnx.json`
+pub const VOICE_DIR: &str = "voices";
+
+/// Where `download` fetches voices from, laid out as `<family>/<locale>/<name>/<quality>/`
+const VOICE_REPO: &str = "https://huggingface.co/rhasspy/piper-voices/resolve/main";
+
+/// An installed voice model
+#[derive(Debug, Clone, Serialize)]
+pub struct PiperVoice {
+    /// e.g. `en_US-amy-medium`
+    pub id: String,
+    /// Speaker names of a multi-speaker model, sorted by id; empty for single-speaker ones
+    pub speakers: Vec<String>,
+}
+
+/// The parts of a model's `.onnx.json` that playback needs
+#[derive(Debug, Deserialize)]
+pub struct VoiceConfig {
+    pub audio: AudioConfig,
+    #[serde(default)]
+    pub speaker_id_map: HashMap<String, i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AudioConfig {
+    pub sample_rate: u32,
+}
+
+impl VoiceConfig {
+    /// Read the config that sits next to `model`
+    pub fn load(model: &Path) -> Result<Self> {
+        let path = config_path(model);
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    /// Numeric id of speaker `name`, if the model has one by that name
+    pub fn speaker_id(&self, name: &str) -> Option<i64> {
+        self.speaker_id_map.get(name).copied()
+    }
+}
+
+/// `[tts] piper_model` may be an installed voice id or a path to an `.onnx` file
+pub fn model_path(model: &str) -> PathBuf {
+    if model.ends_with(".onnx") {
+        PathBuf::from(model)
+    } else {
+        Path::new(VOICE_DIR).join(format!("{}.onnx", model))
+    }
+}
+
+fn config_path(model: &Path) -> PathBuf {
+    let mut path = model.as_os_str().to_owned();
+    path.push(".json");
+    PathBuf::from(path)
+}
+
+/// Voices in `voices/` that have both the model and its config
+pub fn installed() -> Vec<PiperVoice> {
+    let Ok(entries) = std::fs::read_dir(VOICE_DIR) else {
+        return Vec::new();
+    };
+    let mut voices: Vec<PiperVoice> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension()? != "onnx" {
+                return None;
+            }
+            let config = VoiceConfig::load(&path).ok()?;
+            let mut speakers: Vec<(String, i64)> = config.speaker_id_map.into_iter().collect();
+            speakers.sort_by_key(|(_, id)| *id);
+            Some(PiperVoice {
+                id: path.file_stem()?.to_string_lossy().into_owned(),
+                speakers: speakers.into_iter().map(|(name, _)| name).collect(),
+            })
+        })
+        .collect();
+    voices.sort_by(|a, b| a.id.cmp(&b.id));
+    voices
+}
+
+/// Fetch voice `id` (`<locale>-<name>-<quality>`, e.g. `en_US-amy-medium`) from the
+/// piper-voices repository into `voices/`
+pub async fn download(id: &str) -> Result<()> {
+    check_id(id)?;
+    let parts: Vec<&str> = id.split('-').collect();
+    let (locale, quality) = match parts.as_slice() {
+        [locale, _, .., quality] => (*locale, *quality),
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Voice ids look like en_US-amy-medium, got '{}'",
+                id
+            ))
+        }
+    };
+    let name = parts[1..parts.len() - 1].join("-");
+    let family = locale.split('_').next().unwrap_or(locale);
+    let base = format!(
+        "{}/{}/{}/{}/{}/{}",
+        VOICE_REPO, family, locale, name, quality, id
+    );
+
+    std::fs::create_dir_all(VOICE_DIR)?;
+    let model = model_path(id);
+    fetch(&format!("{}.onnx.json", base), &config_path(&model)).await?;
+    fetch(&format!("{}.onnx", base), &model).await?;
+    info!("[TTS] Downloaded piper voice {}", id);
+    Ok(())
+}
+
+/// Download `url` to `dest` through a `.part` file, so an interrupted download is never
+/// mistaken for a finished one
+async fn fetch(url: &str, dest: &Path) -> Result<()> {
+    let response = reqwest::get(url).await?;
+    if !response.status().is_success() {
+        return Err(anyhow::anyhow!(
+            "Download failed ({}): {}",
+            response.status(),
+            url
+        ));
+    }
+    let bytes = response.bytes().await?;
+    let mut partial = dest.as_os_str().to_owned();
+    partial.push(".part");
+    tokio::fs::write(&partial, &bytes).await?;
+    tokio::fs::rename(&partial, dest).await?;
+    Ok(())
+}
+
+/// Remove an installed voice
+pub fn delete(id: &str) -> Result<()> {
+    check_id(id)?;
+    let model = model_path(id);
+    std::fs::remove_file(config_path(&model))?;
+    std::fs::remove_file(&model)?;
+    Ok(())
+}
+
+/// Ids come from the UI and become file names under `voices/`
+fn check_id(id: &str) -> Result<()> {
+    if id.is_empty() || id.contains(['/', '\\', '.']) {
+        return Err(anyhow::anyhow!("Invalid voice id: {}", id));
+    }
+    Ok(())
+}
@@ -1,94 +1,193 @@
 use anyhow::Result;
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::traits::{DeviceTrait, HostTrait};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{info, warn};
 use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
 
-#[allow(dead_code)]
-const MODEL_PATH: &str = "models/ggml-base.en.bin";
+use crate::config::VoiceInputSettings;
+use crate::voice::listener;
+
+pub const DEFAULT_MODEL_PATH: &str = "models/ggml-base.en.bin";
+
+/// Push-to-talk gives up if no speech starts within this long
+const NO_SPEECH_TIMEOUT: Duration = Duration::from_secs(8);
+
+/// Which language Whisper transcribes. Shared between the model, which loads in the
+/// background, and the agent loop, which updates it when the persona changes.
+pub struct SttLanguage {
+    /// `[voice_input] language`: a code like "ko", or "auto"
+    configured: String,
+    follow_persona: bool,
+    persona: Mutex<Option<&'static str>>,
+}
+
+impl SttLanguage {
+    pub fn new(settings: &VoiceInputSettings) -> Self {
+        Self {
+            configured: settings.language.clone(),
+            follow_persona: settings.follow_persona_language,
+            persona: Mutex::new(None),
+        }
+    }
+
+    /// Track the active persona's `language` ("Korean", "ja", ...); unknown names are ignored
+    pub fn set_persona_language(&self, language: Option<&str>) {
+        *self.persona.lock().unwrap() = language.and_then(language_code);
+    }
+
+    /// Whisper language code, or "auto" to detect it
+    pub fn current(&self) -> String {
+        let persona = *self.persona.lock().unwrap();
+        match persona {
+            Some(code) if self.follow_persona => code.to_string(),
+            _ => self.configured.clone(),
+        }
+    }
+}
+
+/// Whisper code for a language given by name or code
+pub fn language_code(language: &str) -> Option<&'static str> {
+    const LANGUAGES: &[(&str, &str)] = &[
+        ("en", "english"),
+        ("ko", "korean"),
+        ("ja", "japanese"),
+        ("zh", "chinese"),
+        ("es", "spanish"),
+        ("fr", "french"),
+        ("de", "german"),
+        ("it", "italian"),
+        ("pt", "portuguese"),
+        ("ru", "russian"),
+        ("vi", "vietnamese"),
+        ("th", "thai"),
+        ("id", "indonesian"),
+        ("ar", "arabic"),
+        ("hi", "hindi"),
+    ];
+    let language = language.trim().to_lowercase();
+    LANGUAGES
+        .iter()
+        .find(|(code, name)| *code == language || *name == language)
+        .map(|(code, _)| *code)
+}
 
-#[allow(dead_code)]
 pub struct SttManager {
     ctx: WhisperContext,
+    language: Arc<SttLanguage>,
+    /// `*.en` models only know English, whatever language is configured
+    english_only: bool,
 }
 
-#[allow(dead_code)]
 impl SttManager {
-    pub fn new(model_path: &str) -> Result<Self> {
-        // Just check if file exists, if not warn but try loading or error out
-        // The user might put it in a different place.
-        // For MVP, if path provided, use it. If not, use default.
+    pub fn new(model_path: &str, language: Arc<SttLanguage>) -> Result<Self> {
+        // Fail early with an actionable message instead of whisper's generic load error
+        if !Path::new(model_path).exists() {
+            return Err(anyhow::anyhow!(
+                "Whisper model not found at {} — download ggml-base.en.bin from https://huggingface.co/ggerganov/whisper.cpp or set stt_model_path in amadeus.toml",
+                model_path
+            ));
+        }
 
         let ctx = WhisperContext::new_with_params(model_path, WhisperContextParameters::default())
             .map_err(|e| {
                 anyhow::anyhow!("Failed to load Whisper model from '{}': {}", model_path, e)
             })?;
 
-        Ok(Self { ctx })
-    }
-
-    pub async fn listen_once(&self, duration_secs: u64) -> Result<String> {
-        let host = cpal::default_host();
-        let device = host
-            .default_input_device()
-            .ok_or_else(|| anyhow::anyhow!("No input device"))?;
-        let config = device.default_input_config()?;
-
-        let sample_rate = config.sample_rate().0;
-        let channels = config.channels();
-
-        let recorded_samples = Arc::new(Mutex::new(Vec::new()));
-        let samples_clone = recorded_samples.clone();
+        let english_only = model_path.contains(".en.");
+        if english_only && language.current() != "en" {
+            info!(
+                "[STT] {} is English-only; use a multilingual model (e.g. ggml-base.bin) for other languages",
+                model_path
+            );
+        }
 
-        let err_fn = move |err| {
-            eprintln!("an error occurred on stream: {}", err);
-        };
+        Ok(Self {
+            ctx,
+            language,
+            english_only,
+        })
+    }
 
-        let stream = device.build_input_stream(
-            &config.into(),
-            move |data: &[f32], _: &_| {
-                let mut guard = samples_clone.lock().unwrap();
-                if channels == 2 {
-                    // Simple stereo to mono mix
-                    for chunk in data.chunks(2) {
-                        if chunk.len() == 2 {
-                            let mono = (chunk[0] + chunk[1]) / 2.0;
-                            guard.push(mono);
-                        }
-                    }
-                } else {
-                    guard.extend_from_slice(data);
-                }
-            },
-            err_fn,
-            None,
+    /// Record one utterance, ending at the first pause, and transcribe it. While the user
+    /// speaks, the utterance so far is re-transcribed every second and passed to `on_partial`
+    /// as a live caption, and `on_level` hears the microphone level. Setting `finish` ends
+    /// the recording early with what was said so far. Returns an empty string if nothing is
+    /// said for `NO_SPEECH_TIMEOUT`.
+    pub async fn listen_streaming<F, L>(
+        &self,
+        settings: &VoiceInputSettings,
+        finish: Arc<AtomicBool>,
+        on_partial: F,
+        on_level: L,
+    ) -> Result<String>
+    where
+        F: Fn(&str),
+        L: Fn(f32) + Send + 'static,
+    {
+        let stop = Arc::new(AtomicBool::new(false));
+        let heard = Arc::new(AtomicBool::new(false));
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let heard_flag = heard.clone();
+        listener::spawn_capture(
+            settings.clone(),
+            stop.clone(),
+            finish,
+            tx,
+            move |_| heard_flag.store(true, Ordering::SeqCst),
+            on_level,
         )?;
+        info!("Listening...");
 
-        stream.play()?;
-
-        println!("Listening for {} seconds...", duration_secs);
-        tokio::time::sleep(std::time::Duration::from_secs(duration_secs)).await;
-
-        drop(stream);
-        println!("Processing audio...");
-
-        let raw_samples = {
-            let guard = recorded_samples.lock().unwrap();
-            guard.clone()
+        let result = loop {
+            let next = match tokio::time::timeout(NO_SPEECH_TIMEOUT, rx.recv()).await {
+                Ok(next) => next,
+                Err(_) if heard.load(Ordering::SeqCst) => continue,
+                Err(_) => break Ok(String::new()),
+            };
+            let Some(utterance) = next else {
+                break Ok(String::new());
+            };
+            if !utterance.partial {
+                // Released before any speech started
+                if utterance.samples.is_empty() {
+                    break Ok(String::new());
+                }
+                break self.transcribe(&utterance.samples, utterance.sample_rate);
+            }
+            // Skip stale snapshots when transcription falls behind
+            if rx.is_empty() {
+                if let Ok(text) = self.transcribe(&utterance.samples, utterance.sample_rate) {
+                    on_partial(&text);
+                }
+            }
         };
+        stop.store(true, Ordering::SeqCst);
+        result
+    }
 
+    /// Transcribe mono `samples` recorded at `sample_rate`. Blocks while Whisper runs.
+    pub fn transcribe(&self, samples: &[f32], sample_rate: u32) -> Result<String> {
         // Resample logic to 16000 Hz
-        let samples = if sample_rate != 16000 {
-            self.resample(&raw_samples, sample_rate, 16000)
-        } else {
-            raw_samples
-        };
+        let samples = self.resample(samples, sample_rate, 16000);
 
         // Whisper Inference
-        let mut state = self.ctx.create_state().expect("failed into create state");
+        let mut state = self
+            .ctx
+            .create_state()
+            .map_err(|e| anyhow::anyhow!("Failed to create Whisper state: {}", e))?;
 
+        let language = if self.english_only {
+            "en".to_string()
+        } else {
+            self.language.current()
+        };
         let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
         params.set_n_threads(4);
-        params.set_language(Some("en"));
+        // "auto" makes Whisper detect the language of each utterance
+        params.set_language(Some(&language));
         params.set_print_special(false);
         params.set_print_progress(false);
         params.set_print_realtime(false);
@@ -107,11 +206,11 @@ impl SttManager {
             }
         }
 
-        Ok(text.trim().to_string())
+        Ok(clean_transcript(&text))
     }
 
     fn resample(&self, input: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
-        if from_rate == to_rate {
+        if from_rate == to_rate || input.is_empty() {
             return input.to_vec();
         }
 
@@ -132,3 +231,56 @@ impl SttManager {
         output
     }
 }
+
+/// Drop the annotations Whisper produces for non-speech, e.g. "[BLANK_AUDIO]" or "(music)"
+fn clean_transcript(text: &str) -> String {
+    let mut cleaned = String::new();
+    let mut depth = 0usize;
+    for c in text.chars() {
+        match c {
+            '[' | '(' => depth += 1,
+            ']' | ')' => depth = depth.saturating_sub(1),
+            _ if depth == 0 => cleaned.push(c),
+            _ => {}
+        }
+    }
+    cleaned.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Input stream on the default device that hands `on_samples` mono audio.
+/// Returns the (not yet playing) stream and its sample rate.
+pub fn open_input<F>(mut on_samples: F) -> Result<(cpal::Stream, u32)>
+where
+    F: FnMut(&[f32]) + Send + 'static,
+{
+    let host = cpal::default_host();
+    let device = host
+        .default_input_device()
+        .ok_or_else(|| anyhow::anyhow!("No input device"))?;
+    let config = device.default_input_config()?;
+
+    let sample_rate = config.sample_rate().0;
+    let channels = config.channels();
+
+    let err_fn = move |err| {
+        warn!("an error occurred on stream: {}", err);
+    };
+
+    let mut mono = Vec::new();
+    let stream = device.build_input_stream(
+        &config.into(),
+        move |data: &[f32], _: &_| {
+            if channels == 2 {
+                // Simple stereo to mono mix
+                mono.clear();
+                mono.extend(data.chunks_exact(2).map(|c| (c[0] + c[1]) / 2.0));
+                on_samples(&mono);
+            } else {
+                on_samples(data);
+            }
+        },
+        err_fn,
+        None,
+    )?;
+    Ok((stream, sample_rate))
+}
@@ -1,17 +1,282 @@
 use anyhow::Result;
-use std::process::Command;
+use cpal::traits::{DeviceTrait, HostTrait};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use tracing::warn;
 
-pub struct TtsManager;
+use crate::agent::emotion::Emotion;
+use crate::config::{TtsEngine, TtsSettings};
+use crate::voice::lipsync::LevelCallback;
+use crate::voice::tts_backend::{self, SpeechRequest, TtsBackend};
+
+/// Rate multiplier and pitch offset applied on top of the base voice settings
+struct Prosody {
+    rate: f32,
+    pitch: f32,
+}
+
+impl Prosody {
+    fn for_emotion(emotion: Emotion) -> Self {
+        match emotion {
+            Emotion::Happy => Self {
+                rate: 1.08,
+                pitch: 4.0,
+            },
+            Emotion::Sad => Self {
+                rate: 0.85,
+                pitch: -4.0,
+            },
+            Emotion::Angry => Self {
+                rate: 1.2,
+                pitch: -2.0,
+            },
+            Emotion::Surprised => Self {
+                rate: 1.1,
+                pitch: 6.0,
+            },
+            Emotion::Neutral => Self {
+                rate: 1.0,
+                pitch: 0.0,
+            },
+        }
+    }
+}
+
+/// One queued sentence
+struct Utterance {
+    request: SpeechRequest,
+    /// `cancel` generation it was queued in; stale ones are skipped
+    epoch: u64,
+    on_speaking: Box<dyn Fn(bool) + Send>,
+}
+
+pub struct TtsManager {
+    settings: Mutex<TtsSettings>,
+    /// Voice of the active persona; `None` uses the backend's default
+    voice: Mutex<Option<String>>,
+    /// Utterances queued or playing; speaking only ends when this drops to zero
+    active: Arc<AtomicUsize>,
+    /// Bumped by `cancel` so everything queued before it is dropped
+    epoch: Arc<AtomicU64>,
+    /// Played one at a time by a worker thread, so sentences never talk over each other
+    queue: mpsc::Sender<Utterance>,
+    /// Locked by the worker for each sentence, so a swap waits for the current one to end
+    backend: Arc<Mutex<Box<dyn TtsBackend>>>,
+}
+
+/// Names of the available audio output devices
+pub fn list_output_devices() -> Result<Vec<String>> {
+    let host = cpal::default_host();
+    let devices = host.output_devices()?;
+    Ok(devices.filter_map(|d| d.name().ok()).collect())
+}
 
 impl TtsManager {
-    pub fn new() -> Result<Self> {
-        Ok(Self)
+    /// `on_level` receives the loudness of the audio being played (0.0 - 1.0) about 30 times
+    /// a second, and 0.0 after each sentence, to drive lip sync
+    pub fn new<L>(settings: TtsSettings, on_level: L) -> Result<Self>
+    where
+        L: Fn(f32) + Send + Sync + 'static,
+    {
+        let on_level: LevelCallback = Arc::new(on_level);
+        let active = Arc::new(AtomicUsize::new(0));
+        let epoch = Arc::new(AtomicU64::new(0));
+        let backend = Arc::new(Mutex::new(tts_backend::create(&settings)?));
+        let (queue, rx) = mpsc::channel::<Utterance>();
+
+        let worker_active = active.clone();
+        let worker_epoch = epoch.clone();
+        let worker_backend = backend.clone();
+        std::thread::spawn(move || {
+            for utterance in rx {
+                if utterance.epoch == worker_epoch.load(Ordering::SeqCst) {
+                    (utterance.on_speaking)(true);
+                    let backend = worker_backend.lock().unwrap();
+                    if let Err(e) = backend.speak(&utterance.request, &on_level) {
+                        warn!("[TTS] {} failed: {}", backend.name(), e);
+                    }
+                    on_level(0.0);
+                }
+                if worker_active.fetch_sub(1, Ordering::SeqCst) == 1 {
+                    (utterance.on_speaking)(false);
+                }
+            }
+        });
+
+        Ok(Self {
+            settings: Mutex::new(settings),
+            voice: Mutex::new(None),
+            active,
+            epoch,
+            queue,
+            backend,
+        })
+    }
+
+    /// Name of the engine in use, e.g. "piper"
+    pub fn backend_name(&self) -> &'static str {
+        self.backend.lock().unwrap().name()
+    }
+
+    /// True while anything is queued or playing
+    pub fn is_speaking(&self) -> bool {
+        self.active.load(Ordering::SeqCst) > 0
+    }
+
+    /// Drop queued speech; the sentence being spoken finishes
+    pub fn cancel(&self) {
+        self.epoch.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Route speech to `device` (None = system default) at `volume` (0.0 - 1.0)
+    pub fn set_output(&self, device: Option<String>, volume: f32) {
+        let mut settings = self.settings.lock().unwrap();
+        settings.output_device = device;
+        settings.volume = volume.clamp(0.0, 1.0);
     }
 
-    pub fn speak(&self, text: &str) -> Result<()> {
-        // Use macOS 'say' command
-        // This is non-blocking if we use spawn()
-        Command::new("say").arg(text).spawn()?;
+    /// Mute or unmute replies. Speech already queued still plays.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.settings.lock().unwrap().enabled = enabled;
+    }
+
+    /// Base speaking rate (words per minute) and pitch offset, before emotion prosody
+    pub fn set_tuning(&self, rate: u32, pitch: f32) {
+        let mut settings = self.settings.lock().unwrap();
+        settings.rate = rate.clamp(80, 400);
+        settings.pitch = pitch.clamp(-10.0, 10.0);
+    }
+
+    /// Switch the piper voice model and speaker. Takes effect from the next sentence; on
+    /// error (e.g. the model is missing) the current voice is kept.
+    pub fn set_piper_voice(&self, model: Option<String>, speaker: Option<String>) -> Result<()> {
+        let current = self.settings();
+        let mut next = current.clone();
+        next.piper_model = model;
+        next.piper_speaker = speaker;
+        // Without a [tts] backend override, setting or clearing the model can change engines
+        if tts_backend::engine(&next) == TtsEngine::Piper
+            || tts_backend::engine(&current) == TtsEngine::Piper
+        {
+            let backend = tts_backend::create(&next)?;
+            *self.backend.lock().unwrap() = backend;
+        }
+        let mut settings = self.settings.lock().unwrap();
+        settings.piper_model = next.piper_model;
+        settings.piper_speaker = next.piper_speaker;
+        Ok(())
+    }
+
+    pub fn set_voice(&self, voice: Option<String>) {
+        *self.voice.lock().unwrap() = voice;
+    }
+
+    pub fn settings(&self) -> TtsSettings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    /// Queue `text` to be spoken after anything already queued, without blocking.
+    /// `on_speaking(true)` fires as its playback starts and `on_speaking(false)` once the
+    /// queue has run dry.
+    pub fn speak_with_emotion<F>(&self, text: &str, emotion: Emotion, on_speaking: F) -> Result<()>
+    where
+        F: Fn(bool) + Send + 'static,
+    {
+        let settings = self.settings();
+        if !settings.enabled {
+            return Ok(());
+        }
+        let prosody = Prosody::for_emotion(emotion);
+        let rate = (settings.rate as f32 * prosody.rate).round() as u32;
+        let pitch = settings.pitch + prosody.pitch;
+
+        let request = SpeechRequest {
+            text: text.to_string(),
+            rate,
+            pitch,
+            volume: settings.volume,
+            voice: self.voice.lock().unwrap().clone(),
+            output_device: settings.output_device,
+        };
+
+        self.active.fetch_add(1, Ordering::SeqCst);
+        let utterance = Utterance {
+            request,
+            epoch: self.epoch.load(Ordering::SeqCst),
+            on_speaking: Box::new(on_speaking),
+        };
+        if self.queue.send(utterance).is_err() {
+            self.active.fetch_sub(1, Ordering::SeqCst);
+            return Err(anyhow::anyhow!("TTS worker has stopped"));
+        }
         Ok(())
     }
 }
+
+/// Cuts a streamed reply into sentences so each can be spoken as soon as it is complete,
+/// while the rest is still being generated
+#[derive(Default)]
+pub struct SentenceSplitter {
+    buffer: String,
+    /// Set once a tool call or code block starts; nothing after it is spoken
+    muted: bool,
+}
+
+impl SentenceSplitter {
+    /// Add the next streamed token; returns the sentences it completed
+    pub fn push(&mut self, token: &str) -> Vec<String> {
+        if self.muted {
+            return Vec::new();
+        }
+        self.buffer.push_str(token);
+
+        // JSON and code aren't meant to be read aloud
+        let mut sentences = Vec::new();
+        if let Some(at) = self.buffer.find(['{', '`']) {
+            self.buffer.truncate(at);
+            self.muted = true;
+            sentences.extend(self.complete_sentences());
+            sentences.extend(self.finish());
+            return sentences;
+        }
+        self.complete_sentences()
+    }
+
+    /// Whatever is left once the reply has finished
+    pub fn finish(&mut self) -> Option<String> {
+        let rest = std::mem::take(&mut self.buffer);
+        let rest = rest.trim();
+        (!rest.is_empty()).then(|| rest.to_string())
+    }
+
+    fn complete_sentences(&mut self) -> Vec<String> {
+        let mut sentences = Vec::new();
+        while let Some(end) = sentence_end(&self.buffer) {
+            let sentence: String = self.buffer.drain(..end).collect();
+            let sentence = sentence.trim();
+            if !sentence.is_empty() {
+                sentences.push(sentence.to_string());
+            }
+        }
+        sentences
+    }
+}
+
+/// Byte offset just past the first sentence terminator in `text`.
+/// Western punctuation only counts once followed by whitespace, so "3.14" or a "."
+/// at the very end of the text so far doesn't cut a sentence short.
+fn sentence_end(text: &str) -> Option<usize> {
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '。' | '！' | '？' | '\n' => return Some(i + c.len_utf8()),
+            '.' | '!' | '?' | '…' => {
+                if chars.peek().is_some_and(|(_, next)| next.is_whitespace()) {
+                    return Some(i + c.len_utf8());
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
@@ -0,0 +1,327 @@
+use anyhow::Result;
+use cpal::traits::{DeviceTrait, HostTrait};
+use std::io::{BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tracing::info;
+
+use crate::config::{TtsEngine, TtsSettings};
+use crate::voice::lipsync::{LevelCallback, LevelMeter};
+use crate::voice::piper::{self, VoiceConfig};
+
+/// One sentence to speak, with emotion prosody already applied
+#[derive(Debug, Clone)]
+pub struct SpeechRequest {
+    pub text: String,
+    /// Words per minute
+    pub rate: u32,
+    /// Offset from the voice's baseline pitch
+    pub pitch: f32,
+    /// 0.0 - 1.0
+    pub volume: f32,
+    /// Voice name in the backend's own naming; `None` uses its default
+    pub voice: Option<String>,
+    pub output_device: Option<String>,
+}
+
+/// A speech engine. `speak` blocks until playback has finished, so the TTS worker can
+/// play sentences back to back. Audio is played through rodio, which reports its level to
+/// `on_level` for lip sync.
+pub trait TtsBackend: Send {
+    fn name(&self) -> &'static str;
+    fn speak(&self, request: &SpeechRequest, on_level: &LevelCallback) -> Result<()>;
+}
+
+/// The configured backend, or the platform's built-in one when `[tts] backend` is unset
+pub fn create(settings: &TtsSettings) -> Result<Box<dyn TtsBackend>> {
+    let backend: Box<dyn TtsBackend> = match engine(settings) {
+        TtsEngine::Say => Box::new(Say),
+        TtsEngine::Sapi => Box::new(Sapi),
+        TtsEngine::Espeak => Box::new(Espeak),
+        TtsEngine::Piper => {
+            let model = settings
+                .piper_model
+                .as_deref()
+                .map(piper::model_path)
+                .ok_or_else(|| anyhow::anyhow!("The piper backend needs [tts] piper_model"))?;
+            let config = VoiceConfig::load(&model)?;
+            Box::new(Piper {
+                binary: settings.piper_binary.clone(),
+                model,
+                config,
+                speaker: settings.piper_speaker.clone(),
+            })
+        }
+    };
+    info!("[TTS] Using {} backend", backend.name());
+    Ok(backend)
+}
+
+/// The engine `create` picks for `settings`
+pub fn engine(settings: &TtsSettings) -> TtsEngine {
+    if let Some(engine) = settings.backend {
+        return engine;
+    }
+    if cfg!(target_os = "macos") {
+        TtsEngine::Say
+    } else if cfg!(windows) {
+        TtsEngine::Sapi
+    } else if settings.piper_model.is_some() {
+        TtsEngine::Piper
+    } else {
+        TtsEngine::Espeak
+    }
+}
+
+/// Run `command` to completion, writing `input` to its stdin if given
+fn run(mut command: Command, input: Option<&str>) -> Result<()> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    if input.is_some() {
+        command.stdin(Stdio::piped());
+    }
+    hide_window(&mut command);
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Failed to start {}: {}", program, e))?;
+    if let (Some(text), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin.write_all(text.as_bytes())?;
+        // Dropping stdin closes it so the engine sees the end of the text
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(anyhow::anyhow!("{} exited with {}", program, status));
+    }
+    Ok(())
+}
+
+/// Don't flash a console window for every sentence on Windows
+fn hide_window(command: &mut Command) {
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    #[cfg(not(windows))]
+    let _ = command;
+}
+
+/// macOS `say`
+struct Say;
+
+impl TtsBackend for Say {
+    fn name(&self) -> &'static str {
+        "say"
+    }
+
+    fn speak(&self, request: &SpeechRequest, on_level: &LevelCallback) -> Result<()> {
+        let wav = wav_path();
+        // Pitch is set with an embedded `[[pbas]]` command
+        let mut command = Command::new("say");
+        command
+            .arg("-o")
+            .arg(&wav)
+            .arg("--data-format=LEI16@22050")
+            .arg("-r")
+            .arg(request.rate.to_string());
+        if let Some(voice) = &request.voice {
+            command.arg("-v").arg(voice);
+        }
+        command.arg(format!("[[pbas {:+.1}]] {}", request.pitch, request.text));
+        run(command, None)?;
+        play_wav(&wav, request, on_level)
+    }
+}
+
+/// Windows SAPI through PowerShell's System.Speech. SAPI has no pitch control outside SSML.
+struct Sapi;
+
+impl TtsBackend for Sapi {
+    fn name(&self) -> &'static str {
+        "sapi"
+    }
+
+    fn speak(&self, request: &SpeechRequest, on_level: &LevelCallback) -> Result<()> {
+        let wav = wav_path();
+        // SAPI rates run from -10 to 10 with 0 at roughly 175 wpm
+        let rate = ((request.rate as i32 - 175) / 15).clamp(-10, 10);
+        let select_voice = request
+            .voice
+            .as_deref()
+            .map(|v| format!("$s.SelectVoice('{}');", v.replace('\'', "''")))
+            .unwrap_or_default();
+        // The text arrives on stdin so it needs no quoting
+        let script = format!(
+            "[Console]::InputEncoding = [Text.Encoding]::UTF8; \
+             Add-Type -AssemblyName System.Speech; \
+             $s = New-Object System.Speech.Synthesis.SpeechSynthesizer; \
+             $s.Rate = {}; {} \
+             $s.SetOutputToWaveFile('{}'); \
+             $s.Speak([Console]::In.ReadToEnd()); $s.Dispose()",
+            rate,
+            select_voice,
+            wav.display().to_string().replace('\'', "''")
+        );
+        let mut command = Command::new("powershell");
+        command.args(["-NoProfile", "-NonInteractive", "-Command", &script]);
+        run(command, Some(&request.text))?;
+        play_wav(&wav, request, on_level)
+    }
+}
+
+/// espeak-ng, available on most Linux distributions
+struct Espeak;
+
+impl TtsBackend for Espeak {
+    fn name(&self) -> &'static str {
+        "espeak-ng"
+    }
+
+    fn speak(&self, request: &SpeechRequest, on_level: &LevelCallback) -> Result<()> {
+        let wav = wav_path();
+        // Pitch runs 0 - 99 around a default of 50
+        let pitch = (50.0 + request.pitch * 3.0).clamp(0.0, 99.0).round() as u32;
+        let mut command = Command::new("espeak-ng");
+        command
+            .arg("-w")
+            .arg(&wav)
+            .arg("-s")
+            .arg(request.rate.to_string())
+            .arg("-p")
+            .arg(pitch.to_string());
+        if let Some(voice) = &request.voice {
+            command.arg("-v").arg(voice);
+        }
+        command.arg("--stdin");
+        run(command, Some(&request.text))?;
+        play_wav(&wav, request, on_level)
+    }
+}
+
+/// Bytes read from piper per chunk handed to the sink: ~0.1s of 22 kHz 16-bit audio
+const PIPER_CHUNK_BYTES: usize = 4096;
+
+/// Piper neural TTS. Raw PCM is streamed from piper's stdout into a rodio sink as it is
+/// synthesized, so playback starts before the sentence is finished.
+struct Piper {
+    binary: String,
+    model: PathBuf,
+    config: VoiceConfig,
+    /// Speaker of a multi-speaker model; a persona voice naming one of its speakers wins
+    speaker: Option<String>,
+}
+
+impl TtsBackend for Piper {
+    fn name(&self) -> &'static str {
+        "piper"
+    }
+
+    fn speak(&self, request: &SpeechRequest, on_level: &LevelCallback) -> Result<()> {
+        // Piper can't shift pitch, so synthesize slower and play faster by the same ratio:
+        // the duration is unchanged and the pitch rises (or falls) with the playback speed.
+        // Two pitch steps make a semitone so emotion offsets stay subtle.
+        let pitch_ratio = 2f32.powf(request.pitch / 24.0);
+        // length_scale is the inverse of speed; 1.0 is the model's natural pace
+        let length_scale = 175.0 / request.rate.max(1) as f32 * pitch_ratio;
+
+        let mut command = Command::new(&self.binary);
+        command
+            .arg("--model")
+            .arg(&self.model)
+            .arg("--length_scale")
+            .arg(format!("{:.2}", length_scale))
+            .arg("--output-raw")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null());
+        let speaker = request
+            .voice
+            .as_deref()
+            .and_then(|v| self.config.speaker_id(v))
+            .or_else(|| {
+                self.speaker
+                    .as_deref()
+                    .and_then(|s| self.config.speaker_id(s))
+            });
+        if let Some(id) = speaker {
+            command.arg("--speaker").arg(id.to_string());
+        }
+        hide_window(&mut command);
+
+        let mut child = command
+            .spawn()
+            .map_err(|e| anyhow::anyhow!("Failed to start {}: {}", self.binary, e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            // One line is one utterance for piper
+            writeln!(stdin, "{}", request.text.replace('\n', " "))?;
+        }
+        let mut stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("piper has no stdout"))?;
+
+        let (_stream, handle) = output_stream(request.output_device.as_deref())?;
+        let sink = rodio::Sink::try_new(&handle)?;
+        sink.set_volume(request.volume);
+        sink.set_speed(pitch_ratio);
+
+        let sample_rate = self.config.audio.sample_rate;
+        let mut buf = vec![0u8; PIPER_CHUNK_BYTES];
+        // A read can end halfway through a 16-bit sample
+        let mut pending: Vec<u8> = Vec::new();
+        loop {
+            let n = stdout.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            pending.extend_from_slice(&buf[..n]);
+            let whole = pending.len() / 2 * 2;
+            let samples: Vec<i16> = pending[..whole]
+                .chunks_exact(2)
+                .map(|b| i16::from_le_bytes([b[0], b[1]]))
+                .collect();
+            pending.drain(..whole);
+            let chunk = rodio::buffer::SamplesBuffer::new(1, sample_rate, samples);
+            sink.append(LevelMeter::new(chunk, on_level.clone()));
+        }
+        let status = child.wait()?;
+        sink.sleep_until_end();
+        if !status.success() {
+            return Err(anyhow::anyhow!("{} exited with {}", self.binary, status));
+        }
+        Ok(())
+    }
+}
+
+/// The other backends render each sentence here before playing it; the worker speaks one
+/// sentence at a time, so a single file is enough
+fn wav_path() -> PathBuf {
+    std::env::temp_dir().join(format!("amadeus-tts-{}.wav", std::process::id()))
+}
+
+/// Play a rendered sentence and wait for it to finish
+fn play_wav(path: &Path, request: &SpeechRequest, on_level: &LevelCallback) -> Result<()> {
+    let (_stream, handle) = output_stream(request.output_device.as_deref())?;
+    let sink = rodio::Sink::try_new(&handle)?;
+    sink.set_volume(request.volume);
+    let file = std::fs::File::open(path)?;
+    let source = rodio::Decoder::new(BufReader::new(file))?;
+    sink.append(LevelMeter::new(source, on_level.clone()));
+    sink.sleep_until_end();
+    Ok(())
+}
+
+/// Stream on `device` (None = system default); it must be kept alive while playing
+fn output_stream(device: Option<&str>) -> Result<(rodio::OutputStream, rodio::OutputStreamHandle)> {
+    let device = device.and_then(|name| {
+        cpal::default_host()
+            .output_devices()
+            .ok()?
+            .find(|d| d.name().is_ok_and(|n| n == name))
+    });
+    Ok(match &device {
+        Some(device) => rodio::OutputStream::try_from_device(device)?,
+        None => rodio::OutputStream::try_default()?,
+    })
+}
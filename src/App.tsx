@@ -1,27 +1,68 @@
 import { ChatPanel } from "./components/ChatPanel";
 import { AvatarCanvas } from "./components/AvatarCanvas";
 import { useChat } from "./hooks/useChat";
+import { useAvatarSettings } from "./hooks/useAvatarSettings";
+import { usePersona, DEFAULT_AVATAR_MODEL } from "./hooks/usePersona";
+import { SetupGuide } from "./components/SetupGuide";
 import "./App.css";
 
 function App() {
-    const { messages, status, avatarState, emotion, sendMessage, clearChat } =
-        useChat();
+    const {
+        messages,
+        status,
+        avatarState,
+        emotion,
+        dbSize,
+        contextUsage,
+        metrics,
+        hasOlder,
+        sendMessage,
+        clearChat,
+        stopGeneration,
+        regenerate,
+        editLastMessage,
+        branchFrom,
+        deleteMessage,
+        setPinned,
+        loadOlder,
+        runMaintenance,
+    } = useChat();
+    const { targetFps, customModel } = useAvatarSettings();
+    const { avatarModel } = usePersona();
 
     return (
         <div className="app">
             <div className="app-layout">
                 {/* Left: Avatar */}
                 <div className="avatar-wrapper">
-                    <AvatarCanvas avatarState={avatarState} emotion={emotion} />
+                    <AvatarCanvas
+                        avatarState={avatarState}
+                        emotion={emotion}
+                        targetFps={targetFps}
+                        modelUrl={avatarModel ?? customModel ?? DEFAULT_AVATAR_MODEL}
+                    />
                 </div>
 
                 {/* Right: Chat */}
                 <div className="chat-section">
+                    <SetupGuide />
                     <ChatPanel
                         messages={messages}
                         status={status}
+                        dbSize={dbSize}
+                        contextUsage={contextUsage}
+                        metrics={metrics}
+                        hasOlder={hasOlder}
                         onSend={sendMessage}
                         onClear={clearChat}
+                        onStop={stopGeneration}
+                        onRegenerate={regenerate}
+                        onEdit={editLastMessage}
+                        onBranch={branchFrom}
+                        onDelete={deleteMessage}
+                        onPin={setPinned}
+                        onLoadOlder={loadOlder}
+                        onMaintenance={runMaintenance}
                     />
                 </div>
             </div>
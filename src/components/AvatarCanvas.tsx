@@ -1,10 +1,12 @@
 import React, { useRef, useEffect, useState } from "react";
 import { Canvas, useFrame, useThree } from "@react-three/fiber";
 import { OrbitControls } from "@react-three/drei";
+import { listen } from "@tauri-apps/api/event";
 import * as THREE from "three";
 import { GLTFLoader } from "three/examples/jsm/loaders/GLTFLoader.js";
 import { VRMLoaderPlugin, VRM, VRMLookAt } from "@pixiv/three-vrm";
 import { loadMixamoAnimation } from "../utils/loadMixamoAnimation";
+import { DEFAULT_AVATAR_MODEL } from "../hooks/usePersona";
 
 // Avatar state types
 export type AvatarState = "idle" | "thinking" | "speaking";
@@ -13,6 +15,7 @@ export type AvatarEmotion = "neutral" | "happy" | "angry" | "surprised" | "sad";
 interface VrmModelProps {
     avatarState: AvatarState;
     emotion: AvatarEmotion;
+    modelUrl: string;
 }
 
 // Smooth interpolation helper
@@ -20,6 +23,66 @@ function lerp(current: number, target: number, speed: number): number {
     return current + (target - current) * speed;
 }
 
+// Expression weights closer than this to their target are treated as settled
+const SETTLE_EPSILON = 0.001;
+
+// Without a lip-sync level for this long, the mouth falls back to a procedural cycle
+const LIP_SYNC_STALE_MS = 250;
+
+// ===== Blink State Machine =====
+const BLINK_DURATION = 0.15; // close-hold-open, seconds
+const DOUBLE_BLINK_CHANCE = 0.15;
+const DOUBLE_BLINK_GAP = 0.12;
+
+interface BlinkState {
+    untilNext: number; // seconds until the next blink starts
+    elapsed: number; // progress through the current blink, -1 when eyes are open
+    doublePending: boolean;
+}
+
+function randomBlinkInterval(): number {
+    return 2 + Math.random() * 4; // 2-6s
+}
+
+// Close quickly, hold briefly, open slightly slower
+function blinkCurve(p: number): number {
+    if (p < 0.35) return Math.sin((p / 0.35) * (Math.PI / 2));
+    if (p < 0.5) return 1;
+    return Math.cos(((p - 0.5) / 0.5) * (Math.PI / 2));
+}
+
+// Advance the blink by `delta` seconds and return the blink weight (0 = open, 1 = closed)
+function updateBlink(state: BlinkState, delta: number, intervalScale: number): number {
+    if (state.elapsed < 0) {
+        state.untilNext -= delta;
+        if (state.untilNext > 0) return 0;
+        state.elapsed = 0;
+    }
+
+    state.elapsed += delta;
+    const p = state.elapsed / BLINK_DURATION;
+    if (p < 1) return blinkCurve(p);
+
+    // Blink finished: schedule the next one (occasionally a quick double-blink)
+    state.elapsed = -1;
+    if (!state.doublePending && Math.random() < DOUBLE_BLINK_CHANCE) {
+        state.doublePending = true;
+        state.untilNext = DOUBLE_BLINK_GAP;
+    } else {
+        state.doublePending = false;
+        state.untilNext = randomBlinkInterval() * intervalScale;
+    }
+    return 0;
+}
+
+// Resolve which blink expressions this model actually provides
+function resolveBlinkExpressions(vrm: VRM): string[] {
+    const em = vrm.expressionManager;
+    if (!em) return [];
+    if (em.getExpression("blink")) return ["blink"];
+    return ["blinkLeft", "blinkRight"].filter((name) => em.getExpression(name));
+}
+
 // ===== VRMSmoothLookAt for Head Tracking =====
 class VRMSmoothLookAt extends VRMLookAt {
     smoothFactor: number;
@@ -61,7 +124,7 @@ class VRMSmoothLookAt extends VRMLookAt {
     }
 }
 
-const VrmModel: React.FC<VrmModelProps & { lookAtTarget: THREE.Object3D }> = ({ avatarState, emotion, lookAtTarget }) => {
+const VrmModel: React.FC<VrmModelProps & { lookAtTarget: THREE.Object3D }> = ({ avatarState, emotion, modelUrl, lookAtTarget }) => {
     const [vrm, setVrm] = useState<VRM | null>(null);
     const { scene } = useThree();
     const clockRef = useRef(new THREE.Clock());
@@ -79,15 +142,39 @@ const VrmModel: React.FC<VrmModelProps & { lookAtTarget: THREE.Object3D }> = ({
         sad: 0,
     });
 
+    const blinkRef = useRef<BlinkState>({
+        untilNext: randomBlinkInterval(),
+        elapsed: -1,
+        doublePending: false,
+    });
+    const blinkExpressionsRef = useRef<string[]>([]);
+    // Loudness of the speech being played, sent by the backend ~30 times a second
+    const lipSyncRef = useRef({ level: 0, at: 0 });
+    // True once expressions match their targets, so per-frame setValue calls can be skipped
+    const expressionsSettledRef = useRef(false);
+
+    useEffect(() => {
+        const unlisten = listen<{ level: number }>("lip-sync", (event) => {
+            lipSyncRef.current = { level: event.payload.level, at: performance.now() };
+        });
+        return () => {
+            unlisten.then((fn) => fn());
+        };
+    }, []);
+
     useEffect(() => {
         const loader = new GLTFLoader();
         loader.register((parser) => new VRMLoaderPlugin(parser));
+        // The model can change with the persona; the cleanup must remove what this run added
+        let loaded: VRM | null = null;
+        let cancelled = false;
 
         loader.load(
-            "/model/vrm/KurisuMakise.vrm",
+            modelUrl,
             async (gltf) => {
                 const vrmData = gltf.userData.vrm as VRM;
-                if (!vrmData) return;
+                if (!vrmData || cancelled) return;
+                loaded = vrmData;
 
                 vrmData.scene.rotation.y = Math.PI;
 
@@ -116,6 +203,8 @@ const VrmModel: React.FC<VrmModelProps & { lookAtTarget: THREE.Object3D }> = ({
                     vrmData.lookAt.target = lookAtTarget;
                 }
 
+                blinkExpressionsRef.current = resolveBlinkExpressions(vrmData);
+
                 scene.add(vrmData.scene);
                 setVrm(vrmData);
                 console.log("[VRM] Model loaded");
@@ -161,9 +250,11 @@ const VrmModel: React.FC<VrmModelProps & { lookAtTarget: THREE.Object3D }> = ({
         );
 
         return () => {
-            if (vrm) {
-                scene.remove(vrm.scene);
-                vrm.scene.traverse((obj) => {
+            cancelled = true;
+            if (loaded) {
+                const model = loaded;
+                scene.remove(model.scene);
+                model.scene.traverse((obj) => {
                     if ((obj as THREE.Mesh).geometry) {
                         (obj as THREE.Mesh).geometry.dispose();
                     }
@@ -171,9 +262,13 @@ const VrmModel: React.FC<VrmModelProps & { lookAtTarget: THREE.Object3D }> = ({
             }
             if (mixerRef.current) {
                 mixerRef.current.stopAllAction();
+                mixerRef.current = null;
             }
+            actionsRef.current = {};
+            currentActionRef.current = null;
+            setVrm(null);
         };
-    }, []);
+    }, [modelUrl, scene, lookAtTarget]);
 
     // Handle State Changes (Crossfade Animations)
     useEffect(() => {
@@ -197,6 +292,10 @@ const VrmModel: React.FC<VrmModelProps & { lookAtTarget: THREE.Object3D }> = ({
         }
     }, [avatarState]);
 
+    useEffect(() => {
+        expressionsSettledRef.current = false;
+    }, [avatarState, emotion]);
+
     // Handle Frame updates (Mixer & Emotion Blending)
     useFrame(() => {
         if (!vrm) return;
@@ -223,35 +322,53 @@ const VrmModel: React.FC<VrmModelProps & { lookAtTarget: THREE.Object3D }> = ({
         s.angry = lerp(s.angry, emotionTargets.angry, lerpSpeed);
         s.surprised = lerp(s.surprised, emotionTargets.surprised, lerpSpeed);
         s.sad = lerp(s.sad, emotionTargets.sad, lerpSpeed);
+        const emotionsSettled =
+            Math.abs(s.happy - emotionTargets.happy) < SETTLE_EPSILON &&
+            Math.abs(s.angry - emotionTargets.angry) < SETTLE_EPSILON &&
+            Math.abs(s.surprised - emotionTargets.surprised) < SETTLE_EPSILON &&
+            Math.abs(s.sad - emotionTargets.sad) < SETTLE_EPSILON;
 
         // ===== Lip Sync (When speaking) =====
         let mouthOpen = 0;
         if (avatarState === "speaking") {
-            const vowelCycle = Math.abs(Math.sin(t * 12.0));
-            const consonantPause = Math.sin(t * 3.0) > 0.3 ? 1.0 : 0.2;
-            mouthOpen = vowelCycle * consonantPause * 0.6;
+            const lipSync = lipSyncRef.current;
+            if (performance.now() - lipSync.at < LIP_SYNC_STALE_MS) {
+                // Follow the audio actually playing, so pauses close the mouth
+                mouthOpen = lipSync.level * 0.75;
+            } else {
+                const vowelCycle = Math.abs(Math.sin(t * 12.0));
+                const consonantPause = Math.sin(t * 3.0) > 0.3 ? 1.0 : 0.2;
+                mouthOpen = vowelCycle * consonantPause * 0.6;
+            }
         }
 
-        // ===== Blink (active always, procedural) =====
-        const blinkInterval = emotion === "surprised" ? 6.0 : 4.0;
-        const blinkCycle = t % blinkInterval;
-        let blinkWeight = 0;
-        // Faster procedural blinks
-        if (blinkCycle < 0.1) {
-            blinkWeight = Math.sin((blinkCycle / 0.1) * Math.PI);
-        }
+        // ===== Blink (randomized, driven by accumulated delta) =====
+        // Surprised eyes stay open longer between blinks
+        const blinkWeight = updateBlink(
+            blinkRef.current,
+            delta,
+            emotion === "surprised" ? 1.5 : 1.0
+        );
 
         // ===== Apply Expressions =====
+        // Skip when nothing is moving: not talking, eyes open, emotions at rest
+        const idle =
+            avatarState !== "speaking" && blinkWeight === 0 && emotionsSettled;
         const em = vrm.expressionManager;
-        if (em) {
+        if (em && !(idle && expressionsSettledRef.current)) {
             em.setValue("aa", mouthOpen * 0.8);
             em.setValue("oh", mouthOpen * 0.3 * Math.sin(t * 6.0 + 1.0));
-            em.setValue("blink", emotion === "surprised" ? blinkWeight * 0.3 : blinkWeight);
+            const blinkValue = emotion === "surprised" ? blinkWeight * 0.3 : blinkWeight;
+            for (const name of blinkExpressionsRef.current) {
+                em.setValue(name, blinkValue);
+            }
             em.setValue("happy", s.happy);
             em.setValue("angry", s.angry);
             em.setValue("surprised", s.surprised);
             em.setValue("relaxed", s.sad);
         }
+        // One more full pass after going idle so the final values land exactly
+        expressionsSettledRef.current = idle;
 
         vrm.update(delta);
     });
@@ -274,16 +391,34 @@ const MouseTracking: React.FC<{ target: THREE.Object3D }> = ({ target }) => {
     return null;
 };
 
+// ===== Frame Limiter =====
+// With frameloop="demand" nothing renders unless invalidated; tick at the target rate
+const FrameLimiter: React.FC<{ fps: number }> = ({ fps }) => {
+    const { invalidate } = useThree();
+
+    useEffect(() => {
+        const id = setInterval(() => invalidate(), 1000 / Math.max(fps, 1));
+        return () => clearInterval(id);
+    }, [fps, invalidate]);
+
+    return null;
+};
+
 // ===== Main Component =====
 
 interface AvatarCanvasProps {
     avatarState?: AvatarState;
     emotion?: AvatarEmotion;
+    targetFps?: number;
+    // VRM model of the active persona
+    modelUrl?: string;
 }
 
 export const AvatarCanvas: React.FC<AvatarCanvasProps> = ({
     avatarState = "idle",
     emotion = "neutral",
+    targetFps = 30,
+    modelUrl = DEFAULT_AVATAR_MODEL,
 }) => {
     const lookAtTargetRef = useRef(new THREE.Object3D());
 
@@ -298,10 +433,17 @@ export const AvatarCanvas: React.FC<AvatarCanvasProps> = ({
                 }}
                 gl={{ alpha: true, antialias: true }}
                 style={{ background: "transparent" }}
+                frameloop="demand"
             >
+                <FrameLimiter fps={targetFps} />
                 <ambientLight intensity={0.6} />
                 <directionalLight position={[4, 10, 4]} intensity={1.2} />
-                <VrmModel avatarState={avatarState} emotion={emotion} lookAtTarget={lookAtTargetRef.current} />
+                <VrmModel
+                    avatarState={avatarState}
+                    emotion={emotion}
+                    modelUrl={modelUrl}
+                    lookAtTarget={lookAtTargetRef.current}
+                />
                 <MouseTracking target={lookAtTargetRef.current} />
                 <OrbitControls
                     target={[0, 1.0, 0]}
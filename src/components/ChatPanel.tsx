@@ -1,32 +1,308 @@
 import React, { useState, useRef, useEffect } from "react";
-import ReactMarkdown from "react-markdown";
-import rehypeHighlight from "rehype-highlight";
-import "highlight.js/styles/github-dark.css";
-import { ChatMessage, ChatStatus } from "../hooks/useChat";
+import { invoke } from "@tauri-apps/api/core";
+import {
+    ChatMessage,
+    ChatStatus,
+    ContextUsage,
+    ChatMetrics,
+    MaintenanceReport,
+    ToolActivity,
+} from "../hooks/useChat";
+import { useAudioOutput } from "../hooks/useAudioOutput";
+import { useVoiceSettings } from "../hooks/useVoiceSettings";
+import { useFileRoots } from "../hooks/useFileRoots";
+import { useEmailAccounts } from "../hooks/useEmailAccounts";
+import { useSecrets } from "../hooks/useSecrets";
+import { useProactiveMode } from "../hooks/useProactiveMode";
+import { useVoiceInput, ListenState } from "../hooks/useVoiceInput";
+import { useTranslation } from "../hooks/useTranslation";
+import { useConversations } from "../hooks/useConversations";
+import { useHistorySearch } from "../hooks/useHistorySearch";
+import { useAttachments, fileName } from "../hooks/useAttachments";
+import { usePersona } from "../hooks/usePersona";
+import { useSlashCommands } from "../hooks/useSlashCommands";
+import { usePersonaIntensity } from "../hooks/usePersonaIntensity";
+import { useSampling, SamplingConfig } from "../hooks/useSampling";
+import { useSettings, HardwareConfig } from "../hooks/useSettings";
+import { useLlmInfo, LlmInfo } from "../hooks/useLlmInfo";
+import { useModels, DownloadProgress } from "../hooks/useModels";
+import { useToolApproval } from "../hooks/useToolApproval";
+import { useToolAudit, ToolAuditEntry } from "../hooks/useToolAudit";
+import { stabilizePartialMarkdown } from "../utils/markdown";
+import { splitAttachments } from "../utils/attachments";
+import { WorldLines } from "./WorldLines";
+import { Markdown } from "./Markdown";
 
 interface ChatPanelProps {
     messages: ChatMessage[];
     status: ChatStatus;
-    onSend: (text: string) => void;
+    dbSize: number | null;
+    contextUsage: ContextUsage | null;
+    metrics: ChatMetrics | null;
+    hasOlder: boolean;
+    onSend: (text: string, attachments?: string[]) => void;
     onClear: () => void;
+    onStop: () => void;
+    onRegenerate: () => void;
+    onEdit: (content: string) => void;
+    onBranch: (messageId: number) => void;
+    onDelete: (messageId: number) => void;
+    onPin: (messageId: number, pinned: boolean) => void;
+    onLoadOlder: () => void;
+    onMaintenance: (pruneDays?: number) => Promise<MaintenanceReport | null>;
+}
+
+// Prune threshold offered in the settings panel
+const PRUNE_DAYS = 30;
+
+// Right-click menu on a message
+interface MessageMenu {
+    x: number;
+    y: number;
+    text: string;
+    markdown: string;
+    messageId?: number;
+}
+
+const TOOL_STATE_ICONS: Record<ToolActivity["state"], string> = {
+    running: "⏳",
+    done: "✅",
+    failed: "❌",
+};
+
+const AUDIT_STATUS_ICONS: Record<ToolAuditEntry["status"], string> = {
+    ok: "✅",
+    cached: "♻️",
+    error: "❌",
+    timeout: "⏱",
+    denied: "🚫",
+};
+
+const LISTEN_STATE_LABELS: Record<ListenState, string> = {
+    off: "off",
+    listening: "listening",
+    awake: "wake word heard, go ahead",
+    hearing: "hearing you",
+    transcribing: "transcribing",
+};
+
+// Search snippets mark matched words with **; show them highlighted
+function highlightSnippet(snippet: string): React.ReactNode[] {
+    return snippet
+        .split("**")
+        .map((part, i) => (i % 2 === 1 ? <mark key={i}>{part}</mark> : part));
+}
+
+// Sampler fields shown in settings; an empty box means "model default"
+const SAMPLING_FIELDS: { key: keyof SamplingConfig; label: string; step: number }[] = [
+    { key: "temperature", label: "Temperature", step: 0.05 },
+    { key: "top_p", label: "Top-p", step: 0.05 },
+    { key: "top_k", label: "Top-k", step: 1 },
+    { key: "repeat_penalty", label: "Repeat penalty", step: 0.05 },
+    { key: "seed", label: "Seed", step: 1 },
+];
+
+const HARDWARE_FIELDS: { key: keyof Omit<HardwareConfig, "cpu_only">; label: string }[] = [
+    { key: "gpu_layers", label: "GPU layers" },
+    { key: "batch_size", label: "Batch size" },
+    { key: "threads", label: "Threads" },
+];
+
+const ACCELERATOR_LABELS: Record<LlmInfo["accelerator"], string> = {
+    metal: "Metal",
+    cuda: "CUDA",
+    rocm: "ROCm",
+    cpu: "No GPU found",
+};
+
+type MessageItem =
+    | { kind: "single"; message: ChatMessage }
+    | { kind: "turn"; messages: ChatMessage[] };
+
+// Assistant and system messages from the same turn render as one threaded block
+function groupTurns(messages: ChatMessage[]): MessageItem[] {
+    const items: MessageItem[] = [];
+    for (const message of messages) {
+        if (message.turnId !== undefined && message.role !== "user") {
+            const last = items[items.length - 1];
+            if (last?.kind === "turn" && last.messages[0].turnId === message.turnId) {
+                last.messages.push(message);
+            } else {
+                items.push({ kind: "turn", messages: [message] });
+            }
+        } else {
+            items.push({ kind: "single", message });
+        }
+    }
+    return items;
+}
+
+// Name of the tool if `content` is a `{ "tool": ..., "args": ... }` call, or the names of
+// a `{ "tools": [...] }` batch
+function toolCallName(content: string): string | null {
+    if (!content.trim().startsWith("{")) return null;
+    try {
+        const parsed = JSON.parse(content);
+        if (Array.isArray(parsed?.tools)) {
+            const names = parsed.tools.map((call: { tool?: unknown }) => call?.tool);
+            return names.every((name: unknown) => typeof name === "string")
+                ? names.join(", ")
+                : null;
+        }
+        return typeof parsed?.tool === "string" ? parsed.tool : null;
+    } catch {
+        return null;
+    }
+}
+
+function selectRegion() {
+    invoke("start_region_select").catch((e) => console.error("Region selection failed:", e));
+}
+
+function formatBytes(bytes: number): string {
+    if (bytes < 1024) return `${bytes} B`;
+    if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(1)} KB`;
+    return `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
+}
+
+// "Metal · 100% in GPU memory" for the model Ollama has loaded
+function describeLlm(info: LlmInfo): string {
+    const loaded = info.running.find((m) => m.name === info.model) ?? info.running[0];
+    const accelerator = ACCELERATOR_LABELS[info.accelerator];
+    if (!loaded || loaded.size === 0) return `${accelerator} · model not loaded`;
+    const share = Math.round((loaded.size_vram / loaded.size) * 100);
+    return `${accelerator} · ${share}% of ${loaded.name} in GPU memory`;
+}
+
+function formatProgress({ downloaded, total }: DownloadProgress): string {
+    if (total === null) return formatBytes(downloaded);
+    const percent = Math.floor((downloaded / total) * 100);
+    return `${formatBytes(downloaded)} / ${formatBytes(total)} (${percent}%)`;
 }
 
 export const ChatPanel: React.FC<ChatPanelProps> = ({
     messages,
     status,
+    dbSize,
+    contextUsage,
+    metrics,
+    hasOlder,
     onSend,
     onClear,
+    onStop,
+    onRegenerate,
+    onEdit,
+    onBranch,
+    onDelete,
+    onPin,
+    onLoadOlder,
+    onMaintenance,
 }) => {
     const [input, setInput] = useState("");
+    // Draft of the last user message while it is being edited
+    const [editing, setEditing] = useState<string | null>(null);
     const [showSettings, setShowSettings] = useState(false);
-    const [ttsEnabled, setTtsEnabled] = useState(true);
+    const [showConversations, setShowConversations] = useState(false);
+    // Show the branches of the open conversation instead of every conversation
+    const [showWorldLines, setShowWorldLines] = useState(false);
+    const [showActivity, setShowActivity] = useState(false);
+    const [menu, setMenu] = useState<MessageMenu | null>(null);
+    // Tool image shown full size over the chat
+    const [zoomedImage, setZoomedImage] = useState<string | null>(null);
+    const conversationList = useConversations();
+    const search = useHistorySearch();
+    const audit = useToolAudit(showActivity);
+    const appSettings = useSettings();
+    const llm = useLlmInfo();
+    const [maintenanceResult, setMaintenanceResult] = useState<string | null>(null);
+    const [browserResult, setBrowserResult] = useState<string | null>(null);
+    const [selectedSuggestion, setSelectedSuggestion] = useState(0);
+    const slashCommands = useSlashCommands();
+    const audio = useAudioOutput();
+    const speech = useVoiceSettings();
+    const [voiceToDownload, setVoiceToDownload] = useState("");
+    const [modelUrl, setModelUrl] = useState("");
+    const piperSpeakers =
+        speech.settings?.voices.find((v) => v.id === speech.settings?.model)?.speakers ?? [];
+    const fileRoots = useFileRoots();
+    const email = useEmailAccounts();
+    const secrets = useSecrets();
+    const voice = useVoiceInput();
+    const translation = useTranslation();
+    const personaPicker = usePersona();
+    const modelPicker = useModels();
+    const personality = usePersonaIntensity();
+    const proactive = useProactiveMode();
+    const sampler = useSampling();
+    const approval = useToolApproval();
+    const attachments = useAttachments();
     const messagesEndRef = useRef<HTMLDivElement>(null);
     const textareaRef = useRef<HTMLTextAreaElement>(null);
 
-    // Auto-scroll to bottom
+    // Device list can change (headset plugged in), so reload whenever settings open
+    useEffect(() => {
+        if (showSettings) {
+            audio.refresh();
+            speech.refresh();
+            fileRoots.refresh();
+            email.refresh();
+            secrets.refresh();
+            modelPicker.refresh();
+        }
+    }, [
+        showSettings,
+        audio.refresh,
+        speech.refresh,
+        fileRoots.refresh,
+        email.refresh,
+        secrets.refresh,
+        modelPicker.refresh,
+    ]);
+
+    // Ctrl/Cmd+Shift+S opens the region-select overlay
+    useEffect(() => {
+        const handleKeyDown = (e: KeyboardEvent) => {
+            if ((e.ctrlKey || e.metaKey) && e.shiftKey && e.key.toLowerCase() === "s") {
+                e.preventDefault();
+                selectRegion();
+            }
+        };
+        window.addEventListener("keydown", handleKeyDown);
+        return () => window.removeEventListener("keydown", handleKeyDown);
+    }, []);
+
+    // Escape closes the enlarged image
+    useEffect(() => {
+        if (!zoomedImage) return;
+        const onKey = (e: KeyboardEvent) => {
+            if (e.key === "Escape") setZoomedImage(null);
+        };
+        window.addEventListener("keydown", onKey);
+        return () => window.removeEventListener("keydown", onKey);
+    }, [zoomedImage]);
+
+    // Any click elsewhere, scrolling or Escape closes the message menu
+    useEffect(() => {
+        if (!menu) return;
+        const close = () => setMenu(null);
+        const onKey = (e: KeyboardEvent) => {
+            if (e.key === "Escape") close();
+        };
+        window.addEventListener("click", close);
+        window.addEventListener("scroll", close, true);
+        window.addEventListener("keydown", onKey);
+        return () => {
+            window.removeEventListener("click", close);
+            window.removeEventListener("scroll", close, true);
+            window.removeEventListener("keydown", onKey);
+        };
+    }, [menu]);
+
+    // Auto-scroll to bottom when something arrives there; older pages put in front don't
+    const lastMessage = messages[messages.length - 1];
     useEffect(() => {
         messagesEndRef.current?.scrollIntoView({ behavior: "smooth" });
-    }, [messages, status.isThinking]);
+    }, [lastMessage, status.isThinking]);
 
     // Auto-resize textarea
     useEffect(() => {
@@ -37,21 +313,238 @@ export const ChatPanel: React.FC<ChatPanelProps> = ({
         }
     }, [input]);
 
+    // Autocomplete while the user is typing the command name itself
+    const suggestions =
+        input.startsWith("/") && !input.includes(" ")
+            ? slashCommands.filter((c) => c.command.startsWith(input.toLowerCase()))
+            : [];
+
+    useEffect(() => {
+        setSelectedSuggestion(0);
+    }, [input]);
+
+    const applySuggestion = (index: number) => {
+        const suggestion = suggestions[index];
+        if (suggestion) {
+            setInput(suggestion.command + " ");
+            textareaRef.current?.focus();
+        }
+    };
+
     const handleSend = () => {
         const text = input.trim();
-        if (text && !status.isThinking) {
-            onSend(text);
+        if ((text || attachments.paths.length > 0) && !status.isThinking) {
+            onSend(text, attachments.paths);
             setInput("");
+            attachments.clear();
+        }
+    };
+
+    const handleMaintenance = async (pruneDays?: number) => {
+        setMaintenanceResult("Running...");
+        const report = await onMaintenance(pruneDays);
+        setMaintenanceResult(
+            report
+                ? `${formatBytes(report.size_before)} → ${formatBytes(report.size_after)}` +
+                      (pruneDays ? ` (${report.pruned} messages pruned)` : "")
+                : "Maintenance failed"
+        );
+    };
+
+    const handleCloseBrowser = async () => {
+        try {
+            const wasOpen = await invoke<boolean>("close_browser");
+            setBrowserResult(wasOpen ? "Closed" : "Not running");
+        } catch (e) {
+            setBrowserResult(String(e));
         }
     };
 
     const handleKeyDown = (e: React.KeyboardEvent) => {
+        if (suggestions.length > 0) {
+            if (e.key === "ArrowDown" || e.key === "ArrowUp") {
+                e.preventDefault();
+                const step = e.key === "ArrowDown" ? 1 : -1;
+                setSelectedSuggestion(
+                    (i) => (i + step + suggestions.length) % suggestions.length
+                );
+                return;
+            }
+            if (e.key === "Tab") {
+                e.preventDefault();
+                applySuggestion(selectedSuggestion);
+                return;
+            }
+        }
+        if (e.key === "Escape" && status.isThinking) {
+            e.preventDefault();
+            onStop();
+            return;
+        }
         if (e.key === "Enter" && !e.shiftKey) {
             e.preventDefault();
             handleSend();
         }
     };
 
+    const renderMarkdown = (msg: ChatMessage) => (
+        <Markdown>{msg.partial ? stabilizePartialMarkdown(msg.content) : msg.content}</Markdown>
+    );
+
+    // Translate toggle plus the inline result, for assistant answers
+    const translateControls = (msg: ChatMessage) => {
+        const translated = translation.translations[msg.content];
+        return (
+            <>
+                {translated && (
+                    <div className="message-content translation">
+                        <Markdown>{translated}</Markdown>
+                    </div>
+                )}
+                {!translated && !msg.partial && (
+                    <button
+                        className="branch-btn"
+                        onClick={() => translation.translate(msg.content)}
+                        disabled={translation.pending[msg.content]}
+                        title="Translate this reply"
+                    >
+                        {translation.pending[msg.content] ? "🌐 translating..." : "🌐 translate"}
+                    </button>
+                )}
+            </>
+        );
+    };
+
+    // Plain text is what the message shows on screen; markdown is its source
+    const openMenu = (e: React.MouseEvent<HTMLElement>, markdown: string, messageId?: number) => {
+        e.preventDefault();
+        const text = Array.from(
+            e.currentTarget.querySelectorAll<HTMLElement>(".message-content")
+        )
+            .map((el) => el.innerText)
+            .join("\n\n");
+        setMenu({ x: e.clientX, y: e.clientY, text, markdown, messageId });
+    };
+
+    const menuPinned =
+        menu?.messageId !== undefined &&
+        messages.some((m) => m.messageId === menu.messageId && m.pinned);
+
+    const pinMarker = (
+        <span className="pin-marker" title="Pinned: kept when old messages are pruned">
+            📌
+        </span>
+    );
+
+    const copyFromMenu = async (content: string) => {
+        setMenu(null);
+        try {
+            await navigator.clipboard.writeText(content);
+        } catch (e) {
+            console.error("Failed to copy message:", e);
+        }
+    };
+
+    // Typed text, then a chip per attached file instead of its inlined contents
+    const userContent = (content: string) => {
+        const { text, attachments: attached } = splitAttachments(content);
+        return (
+            <>
+                {text}
+                {attached.length > 0 && (
+                    <div className="attachment-chips">
+                        {attached.map((a, i) => (
+                            <span key={i} className="attachment-chip">
+                                {a.kind === "image" ? "🖼" : "📄"} {a.name}
+                            </span>
+                        ))}
+                    </div>
+                )}
+            </>
+        );
+    };
+
+    const branchButton = (messageId?: number) =>
+        messageId !== undefined && (
+            <button
+                className="branch-btn"
+                onClick={() => onBranch(messageId)}
+                disabled={status.isThinking}
+                title="Fork a new conversation from this point"
+            >
+                ⑂ branch here
+            </button>
+        );
+
+    const continueButton = (
+        <button
+            className="continue-btn"
+            onClick={() => onSend("/continue")}
+            disabled={status.isThinking}
+            title="Reply was cut off at the token limit"
+        >
+            ⋯ continue?
+        </button>
+    );
+
+    const regenerateButton = (
+        <button
+            className="branch-btn"
+            onClick={onRegenerate}
+            disabled={status.isThinking}
+            title="Drop this reply and answer the last message again"
+        >
+            ↻ regenerate
+        </button>
+    );
+
+    const submitEdit = () => {
+        if (editing?.trim() && !status.isThinking) {
+            onEdit(editing.trim());
+            setEditing(null);
+        }
+    };
+
+    const editForm = (
+        <div className="message-edit">
+            <textarea
+                className="message-edit-input"
+                value={editing ?? ""}
+                autoFocus
+                onChange={(e) => setEditing(e.target.value)}
+                onKeyDown={(e) => {
+                    if (e.key === "Enter" && !e.shiftKey) {
+                        e.preventDefault();
+                        submitEdit();
+                    } else if (e.key === "Escape") {
+                        setEditing(null);
+                    }
+                }}
+            />
+            <button
+                className="continue-btn"
+                onClick={submitEdit}
+                disabled={!editing?.trim() || status.isThinking}
+            >
+                Send
+            </button>
+            <button className="continue-btn" onClick={() => setEditing(null)}>
+                Cancel
+            </button>
+        </div>
+    );
+
+    // Only the latest exchange can be answered again or edited
+    const items = groupTurns(messages);
+    const lastUser = items
+        .map((item) => item.kind === "single" && item.message.role === "user")
+        .lastIndexOf(true);
+    const canRegenerate = (item: MessageItem, i: number) =>
+        lastUser !== -1 &&
+        i > lastUser &&
+        i === items.length - 1 &&
+        (item.kind === "turn" || item.message.role === "assistant");
+
     return (
         <div className="chat-panel">
             {/* Toolbar */}
@@ -60,6 +553,30 @@ export const ChatPanel: React.FC<ChatPanelProps> = ({
                     <button className="tool-btn" onClick={onClear} title="Clear chat">
                         🗑 Clear
                     </button>
+                    <button
+                        className="tool-btn"
+                        onClick={selectRegion}
+                        title="Select a screen region (Ctrl+Shift+S)"
+                    >
+                        ✂ Region
+                    </button>
+                    <button
+                        className="tool-btn"
+                        onClick={() => {
+                            if (!showConversations) conversationList.refresh();
+                            setShowConversations(!showConversations);
+                        }}
+                        title="Saved conversations"
+                    >
+                        🗂 Chats
+                    </button>
+                    <button
+                        className="tool-btn"
+                        onClick={() => setShowActivity(!showActivity)}
+                        title="Every tool call Amadeus has made"
+                    >
+                        📜 Activity
+                    </button>
                     <div className="toolbar-divider" />
                     <button
                         className="tool-btn"
@@ -69,6 +586,39 @@ export const ChatPanel: React.FC<ChatPanelProps> = ({
                     </button>
                 </div>
                 <div className="toolbar-right">
+                    {contextUsage && (
+                        <div
+                            className={`context-meter${
+                                contextUsage.used >= contextUsage.warnAt ? " warning" : ""
+                            }`}
+                            title={
+                                contextUsage.trimmed > 0
+                                    ? `${contextUsage.trimmed} older message(s) were dropped to fit the context window`
+                                    : "Prompt size against the model's context window"
+                            }
+                        >
+                            <div className="context-bar">
+                                <div
+                                    className="context-fill"
+                                    style={{
+                                        width: `${Math.min(100, (contextUsage.used / contextUsage.limit) * 100)}%`,
+                                    }}
+                                />
+                            </div>
+                            <span className="context-text">
+                                {contextUsage.used} / {contextUsage.limit} tokens
+                                {contextUsage.trimmed > 0 && " · trimmed"}
+                            </span>
+                        </div>
+                    )}
+                    {metrics && (
+                        <span
+                            className="context-text"
+                            title={`${metrics.model}: ${metrics.promptTokens} prompt tokens in ${metrics.promptEvalMs} ms, ${metrics.generatedTokens} generated in ${metrics.generationMs} ms`}
+                        >
+                            ⚡ {metrics.tokensPerSecond.toFixed(1)} tok/s
+                        </span>
+                    )}
                     <span
                         className="status-dot"
                         style={{
@@ -79,6 +629,158 @@ export const ChatPanel: React.FC<ChatPanelProps> = ({
                 </div>
             </div>
 
+            {/* Conversations */}
+            {showConversations && (
+                <div className="settings-panel conversations-panel">
+                    <div className="conversations-header">
+                        <h4>{showWorldLines ? "World lines" : "Conversations"}</h4>
+                        <div>
+                            <button
+                                className="tool-btn"
+                                onClick={() => setShowWorldLines(!showWorldLines)}
+                                title="Branches of this conversation"
+                            >
+                                {showWorldLines ? "☰ All" : "⑂ World lines"}
+                            </button>
+                            <button className="tool-btn" onClick={conversationList.create}>
+                                + New
+                            </button>
+                        </div>
+                    </div>
+                    <input
+                        type="search"
+                        className="history-search"
+                        placeholder="Search all conversations..."
+                        value={search.query}
+                        onChange={(e) => search.setQuery(e.target.value)}
+                    />
+                    {search.query.trim() ? (
+                        <div className="conversation-list">
+                            {search.results.length === 0 && (
+                                <div className="conversation-meta">No matches</div>
+                            )}
+                            {search.results.map((hit) => (
+                                <div
+                                    key={hit.message_id}
+                                    className="conversation-item"
+                                    onClick={() => {
+                                        if (
+                                            hit.conversation_id !== null &&
+                                            hit.conversation_id !== conversationList.active
+                                        ) {
+                                            conversationList.open(hit.conversation_id);
+                                        }
+                                    }}
+                                >
+                                    <div className="conversation-preview">
+                                        {highlightSnippet(hit.snippet)}
+                                    </div>
+                                    <div className="conversation-meta">
+                                        {hit.role === "assistant" ? "Amadeus" : "Guest"} ·{" "}
+                                        {hit.timestamp}
+                                        {hit.conversation_id !== null && ` · #${hit.conversation_id}`}
+                                    </div>
+                                </div>
+                            ))}
+                        </div>
+                    ) : showWorldLines ? (
+                        <WorldLines
+                            conversations={conversationList.conversations}
+                            active={conversationList.active}
+                            onOpen={conversationList.open}
+                        />
+                    ) : (
+                        <div className="conversation-list">
+                            {conversationList.conversations.map((c) => (
+                                <div
+                                    key={c.id}
+                                    className={`conversation-item${
+                                        c.id === conversationList.active ? " active" : ""
+                                    }`}
+                                    onClick={() => {
+                                        if (c.id !== conversationList.active) conversationList.open(c.id);
+                                    }}
+                                >
+                                    <div className="conversation-preview">
+                                        {c.preview ?? "(empty)"}
+                                    </div>
+                                    <div className="conversation-meta">
+                                        #{c.id} · {c.persona_name} · {c.created_at}
+                                        {c.parent_id !== null && ` · branch of #${c.parent_id}`}
+                                    </div>
+                                    {c.id !== conversationList.active && (
+                                        <button
+                                            className="conversation-delete"
+                                            title="Delete conversation"
+                                            onClick={(e) => {
+                                                e.stopPropagation();
+                                                if (window.confirm(`Delete conversation #${c.id}?`)) {
+                                                    conversationList.remove(c.id);
+                                                }
+                                            }}
+                                        >
+                                            ✕
+                                        </button>
+                                    )}
+                                </div>
+                            ))}
+                        </div>
+                    )}
+                </div>
+            )}
+
+            {/* Tool activity */}
+            {showActivity && (
+                <div className="settings-panel conversations-panel">
+                    <div className="conversations-header">
+                        <h4>Tool activity{audit.tool && ` · ${audit.tool}`}</h4>
+                        {audit.tool && (
+                            <button className="tool-btn" onClick={() => audit.setTool(null)}>
+                                All tools
+                            </button>
+                        )}
+                    </div>
+                    <div className="conversation-list">
+                        {audit.entries.length === 0 && (
+                            <div className="conversation-meta">No tool calls yet</div>
+                        )}
+                        {audit.entries.map((entry) => (
+                            <div key={entry.id} className="conversation-item" title={entry.output}>
+                                <div className="conversation-preview">
+                                    {AUDIT_STATUS_ICONS[entry.status]}{" "}
+                                    <strong
+                                        onClick={() => audit.setTool(entry.tool)}
+                                        title="Show only this tool"
+                                    >
+                                        {entry.tool}
+                                    </strong>{" "}
+                                    <code>{entry.args}</code>
+                                </div>
+                                <div className="conversation-meta">
+                                    {entry.timestamp} · {entry.status} · {entry.duration_ms} ms
+                                    {entry.status !== "denied" && (
+                                        <>
+                                            {" · "}
+                                            <button
+                                                className="tool-btn"
+                                                title="Ask Amadeus to run this call again"
+                                                onClick={() =>
+                                                    onSend(
+                                                        `Run ${entry.tool} again with these arguments: ${entry.args}`
+                                                    )
+                                                }
+                                            >
+                                                ↻ Replay
+                                            </button>
+                                        </>
+                                    )}
+                                </div>
+                            </div>
+                        ))}
+                    </div>
+                </div>
+            )}
+
             {/* Settings */}
             {showSettings && (
                 <div className="settings-panel">
@@ -86,43 +788,610 @@ export const ChatPanel: React.FC<ChatPanelProps> = ({
                     <label className="setting-item">
                         <input
                             type="checkbox"
-                            checked={ttsEnabled}
-                            onChange={() => setTtsEnabled(!ttsEnabled)}
+                            checked={appSettings.settings?.tts_enabled ?? true}
+                            onChange={() =>
+                                appSettings.update({
+                                    tts_enabled: !(appSettings.settings?.tts_enabled ?? true),
+                                })
+                            }
                         />
                         🔊 Voice Output (TTS)
                     </label>
+                    {appSettings.error && (
+                        <span className="setting-hint">{appSettings.error}</span>
+                    )}
+                    <label className="setting-item">
+                        🧠 Model
+                        <select
+                            className="setting-select"
+                            value={modelPicker.loading ?? modelPicker.active ?? ""}
+                            disabled={modelPicker.loading !== null}
+                            onChange={(e) => modelPicker.load(e.target.value)}
+                        >
+                            {!modelPicker.models.some((m) => m.name === modelPicker.active) && (
+                                <option value={modelPicker.active ?? ""}>
+                                    {modelPicker.active ?? "—"}
+                                </option>
+                            )}
+                            {modelPicker.models.map((model) => (
+                                <option key={model.name} value={model.name}>
+                                    {model.installed ? "" : "📦 "}
+                                    {model.name} ({formatBytes(model.size)})
+                                </option>
+                            ))}
+                        </select>
+                        {modelPicker.loading && (
+                            <span className="setting-hint">Loading {modelPicker.loading}...</span>
+                        )}
+                    </label>
+                    <div className="setting-item">
+                        <input
+                            className="setting-select"
+                            placeholder="https://huggingface.co/.../resolve/main/model.gguf"
+                            value={modelUrl}
+                            onChange={(e) => setModelUrl(e.target.value)}
+                        />
+                        <button
+                            className="tool-btn"
+                            disabled={!modelUrl.trim() || modelPicker.download !== null}
+                            onClick={() => modelPicker.fetchModel(modelUrl.trim())}
+                            title="Download a GGUF model from Hugging Face"
+                        >
+                            {modelPicker.download ? "Downloading…" : "Download"}
+                        </button>
+                        {modelPicker.download && (
+                            <span className="setting-hint">
+                                {formatProgress(modelPicker.download)}
+                            </span>
+                        )}
+                        {modelPicker.error && (
+                            <span className="setting-hint">{modelPicker.error}</span>
+                        )}
+                    </div>
+                    <label className="setting-item">
+                        👤 Persona
+                        <select
+                            className="setting-select"
+                            value={personaPicker.active ?? ""}
+                            onChange={(e) => personaPicker.select(e.target.value)}
+                        >
+                            {personaPicker.active === null && <option value="">—</option>}
+                            {personaPicker.personas.map((name) => (
+                                <option key={name} value={name}>
+                                    {name}
+                                </option>
+                            ))}
+                        </select>
+                    </label>
+                    <label className="setting-item">
+                        🎭 Personality
+                        <span className="setting-hint">Professional</span>
+                        <input
+                            type="range"
+                            min={0}
+                            max={100}
+                            step={10}
+                            value={personality.intensity}
+                            onChange={(e) => personality.apply(Number(e.target.value))}
+                        />
+                        <span className="setting-hint">Tsundere</span>
+                    </label>
+                    <label className="setting-item">
+                        <input type="checkbox" checked={proactive.enabled} onChange={proactive.toggle} />
+                        💡 Proactive mode
+                        <span className="setting-hint">Speaks up without being asked</span>
+                    </label>
+                    <div className="setting-item sampling-grid">
+                        {SAMPLING_FIELDS.map(({ key, label, step }) => (
+                            <label key={key} className="sampling-field">
+                                <span className="setting-hint">{label}</span>
+                                <input
+                                    type="number"
+                                    className="setting-number"
+                                    step={step}
+                                    placeholder="default"
+                                    value={sampler.sampling[key] ?? ""}
+                                    onChange={(e) =>
+                                        sampler.apply({
+                                            ...sampler.sampling,
+                                            [key]:
+                                                e.target.value === ""
+                                                    ? null
+                                                    : Number(e.target.value),
+                                        })
+                                    }
+                                />
+                            </label>
+                        ))}
+                    </div>
+                    {appSettings.settings && (
+                        <div className="setting-item sampling-grid">
+                            <label className="sampling-field">
+                                <span className="setting-hint">CPU only</span>
+                                <input
+                                    type="checkbox"
+                                    checked={appSettings.settings.hardware.cpu_only}
+                                    onChange={(e) =>
+                                        appSettings
+                                            .update({
+                                                hardware: {
+                                                    ...appSettings.settings!.hardware,
+                                                    cpu_only: e.target.checked,
+                                                },
+                                            })
+                                            .then(llm.refresh)
+                                    }
+                                />
+                            </label>
+                            <label className="sampling-field">
+                                <span className="setting-hint">Context</span>
+                                <input
+                                    type="number"
+                                    className="setting-number"
+                                    step={1024}
+                                    key={`ctx-${appSettings.settings.context_window}`}
+                                    defaultValue={appSettings.settings.context_window}
+                                    onBlur={(e) => {
+                                        const value = Number(e.target.value);
+                                        if (value !== appSettings.settings?.context_window) {
+                                            appSettings.update({ context_window: value });
+                                        }
+                                    }}
+                                />
+                            </label>
+                            {/* Changing these reloads the model, so they apply on leaving the field */}
+                            {HARDWARE_FIELDS.map(({ key, label }) => (
+                                <label key={key} className="sampling-field">
+                                    <span className="setting-hint">{label}</span>
+                                    <input
+                                        type="number"
+                                        className="setting-number"
+                                        min={0}
+                                        step={1}
+                                        placeholder="auto"
+                                        disabled={
+                                            key === "gpu_layers" &&
+                                            appSettings.settings!.hardware.cpu_only
+                                        }
+                                        key={`${key}-${appSettings.settings!.hardware[key]}`}
+                                        defaultValue={appSettings.settings!.hardware[key] ?? ""}
+                                        onBlur={(e) => {
+                                            const value =
+                                                e.target.value === ""
+                                                    ? null
+                                                    : Number(e.target.value);
+                                            const hardware = appSettings.settings!.hardware;
+                                            if (value !== hardware[key]) {
+                                                appSettings
+                                                    .update({
+                                                        hardware: { ...hardware, [key]: value },
+                                                    })
+                                                    .then(llm.refresh);
+                                            }
+                                        }}
+                                    />
+                                </label>
+                            ))}
+                            {llm.info && (
+                                <span
+                                    className="setting-hint"
+                                    title={`${llm.info.cpu_cores} CPU cores`}
+                                >
+                                    {describeLlm(llm.info)}
+                                </span>
+                            )}
+                        </div>
+                    )}
+                    {audio.available && (
+                        <>
+                            <label className="setting-item">
+                                🎧 Output
+                                <select
+                                    className="setting-select"
+                                    value={audio.device ?? ""}
+                                    onChange={(e) =>
+                                        audio.apply(e.target.value || null, audio.volume)
+                                    }
+                                >
+                                    <option value="">System default</option>
+                                    {audio.devices.map((d) => (
+                                        <option key={d} value={d}>
+                                            {d}
+                                        </option>
+                                    ))}
+                                </select>
+                            </label>
+                            <label className="setting-item">
+                                🔉 Volume
+                                <input
+                                    type="range"
+                                    min={0}
+                                    max={100}
+                                    value={Math.round(audio.volume * 100)}
+                                    onChange={(e) =>
+                                        audio.apply(audio.device, Number(e.target.value) / 100)
+                                    }
+                                />
+                                <span className="setting-hint">
+                                    {Math.round(audio.volume * 100)}%
+                                </span>
+                            </label>
+                        </>
+                    )}
+                    {speech.settings && (
+                        <>
+                            <label className="setting-item">
+                                🗣 Speed
+                                <input
+                                    type="range"
+                                    min={80}
+                                    max={400}
+                                    step={5}
+                                    value={speech.settings.rate}
+                                    onChange={(e) =>
+                                        speech.tune(Number(e.target.value), speech.settings!.pitch)
+                                    }
+                                />
+                                <span className="setting-hint">{speech.settings.rate} wpm</span>
+                            </label>
+                            <label className="setting-item">
+                                🎵 Pitch
+                                <input
+                                    type="range"
+                                    min={-10}
+                                    max={10}
+                                    step={0.5}
+                                    value={speech.settings.pitch}
+                                    onChange={(e) =>
+                                        speech.tune(speech.settings!.rate, Number(e.target.value))
+                                    }
+                                />
+                                <span className="setting-hint">
+                                    {speech.settings.pitch > 0 ? "+" : ""}
+                                    {speech.settings.pitch}
+                                </span>
+                            </label>
+                            <label className="setting-item">
+                                🔊 Neural voice
+                                <select
+                                    className="setting-select"
+                                    value={speech.settings.model ?? ""}
+                                    onChange={(e) =>
+                                        speech.selectVoice(e.target.value || null, null)
+                                    }
+                                >
+                                    <option value="">
+                                        {speech.settings.backend === "piper"
+                                            ? "None"
+                                            : `Off (${speech.settings.backend})`}
+                                    </option>
+                                    {speech.settings.voices.map((v) => (
+                                        <option key={v.id} value={v.id}>
+                                            {v.id}
+                                        </option>
+                                    ))}
+                                </select>
+                                {speech.settings.model && (
+                                    <button
+                                        className="tool-btn"
+                                        onClick={() => speech.remove(speech.settings!.model!)}
+                                        title="Delete this voice"
+                                    >
+                                        🗑
+                                    </button>
+                                )}
+                            </label>
+                            {piperSpeakers.length > 1 && (
+                                <label className="setting-item">
+                                    👥 Speaker
+                                    <select
+                                        className="setting-select"
+                                        value={speech.settings.speaker ?? ""}
+                                        onChange={(e) =>
+                                            speech.selectVoice(
+                                                speech.settings!.model,
+                                                e.target.value || null
+                                            )
+                                        }
+                                    >
+                                        <option value="">Default</option>
+                                        {piperSpeakers.map((name) => (
+                                            <option key={name} value={name}>
+                                                {name}
+                                            </option>
+                                        ))}
+                                    </select>
+                                </label>
+                            )}
+                            <div className="setting-item">
+                                <input
+                                    className="setting-select"
+                                    placeholder="en_US-amy-medium"
+                                    value={voiceToDownload}
+                                    onChange={(e) => setVoiceToDownload(e.target.value)}
+                                />
+                                <button
+                                    className="tool-btn"
+                                    disabled={!voiceToDownload.trim() || speech.downloading !== null}
+                                    onClick={() => speech.download(voiceToDownload.trim())}
+                                    title="Download a piper voice"
+                                >
+                                    {speech.downloading ? "Downloading…" : "Download"}
+                                </button>
+                                {speech.error && (
+                                    <span className="setting-hint">{speech.error}</span>
+                                )}
+                            </div>
+                        </>
+                    )}
+                    <div className="setting-item">
+                        💾 Database: {dbSize !== null ? formatBytes(dbSize) : "—"}
+                    </div>
+                    <div className="setting-item">
+                        <button className="tool-btn" onClick={() => handleMaintenance()}>
+                            Vacuum
+                        </button>
+                        <button
+                            className="tool-btn"
+                            onClick={() => handleMaintenance(PRUNE_DAYS)}
+                            title={`Delete unpinned messages older than ${PRUNE_DAYS} days`}
+                        >
+                            Prune &gt; {PRUNE_DAYS}d
+                        </button>
+                        {maintenanceResult && (
+                            <span className="setting-hint">{maintenanceResult}</span>
+                        )}
+                    </div>
+                    <div className="setting-item">
+                        📁 Workspace folders
+                        <button className="tool-btn" onClick={fileRoots.add}>
+                            Add…
+                        </button>
+                        {fileRoots.error && <span className="setting-hint">{fileRoots.error}</span>}
+                    </div>
+                    {fileRoots.roots.map((root, i) => (
+                        <div key={root} className="setting-item">
+                            <span
+                                className="setting-hint"
+                                title={i === 0 ? "Relative paths start here" : undefined}
+                            >
+                                {root}
+                                {i === 0 && " (default)"}
+                            </span>
+                            {fileRoots.roots.length > 1 && (
+                                <button
+                                    className="tool-btn"
+                                    onClick={() => fileRoots.remove(root)}
+                                    title="Stop allowing this folder"
+                                >
+                                    ✕
+                                </button>
+                            )}
+                        </div>
+                    ))}
+                    {email.accounts.length > 0 && (
+                        <div className="setting-item">
+                            ✉️ Email accounts
+                            {email.error && <span className="setting-hint">{email.error}</span>}
+                        </div>
+                    )}
+                    {email.accounts.map((account) => (
+                        <div key={account.name} className="setting-item">
+                            <span className="setting-hint" title={account.address}>
+                                {account.name} {account.has_password ? "🔑" : "(no password)"}
+                            </span>
+                            <input
+                                type="password"
+                                className="setting-number"
+                                placeholder={account.has_password ? "Replace password" : "Password"}
+                                value={email.drafts[account.name] ?? ""}
+                                onChange={(e) => email.setDraft(account.name, e.target.value)}
+                            />
+                            <button
+                                className="tool-btn"
+                                disabled={!email.drafts[account.name]}
+                                onClick={() =>
+                                    email.save(account.name, email.drafts[account.name] ?? "")
+                                }
+                                title="Save to the system keychain"
+                            >
+                                Save
+                            </button>
+                            {account.has_password && (
+                                <button
+                                    className="tool-btn"
+                                    onClick={() => email.save(account.name, "")}
+                                    title="Remove the saved password"
+                                >
+                                    ✕
+                                </button>
+                            )}
+                        </div>
+                    ))}
+                    <div
+                        className="setting-item"
+                        title="Stored in the system keychain and hidden from the model"
+                    >
+                        🔑 API keys
+                        {secrets.error && <span className="setting-hint">{secrets.error}</span>}
+                    </div>
+                    {secrets.names.map((name) => (
+                        <div key={name} className="setting-item">
+                            <span className="setting-hint">{name}</span>
+                            <button
+                                className="tool-btn"
+                                onClick={() => secrets.remove(name)}
+                                title="Remove from the keychain"
+                            >
+                                ✕
+                            </button>
+                        </div>
+                    ))}
+                    <div className="setting-item">
+                        <input
+                            className="setting-number"
+                            placeholder="Name"
+                            value={secrets.newName}
+                            onChange={(e) => secrets.setNewName(e.target.value)}
+                        />
+                        <input
+                            type="password"
+                            className="setting-number"
+                            placeholder="Value"
+                            value={secrets.newValue}
+                            onChange={(e) => secrets.setNewValue(e.target.value)}
+                        />
+                        <button
+                            className="tool-btn"
+                            disabled={!secrets.newName.trim() || !secrets.newValue}
+                            onClick={secrets.save}
+                        >
+                            Save
+                        </button>
+                    </div>
+                    <div className="setting-item">
+                        🌐 Browser
+                        <button className="tool-btn" onClick={handleCloseBrowser}>
+                            Close
+                        </button>
+                        {browserResult && <span className="setting-hint">{browserResult}</span>}
+                    </div>
                 </div>
             )}
 
             {/* Messages */}
             <div className="messages-container">
-                {messages.map((msg, i) => (
-                    <div key={i} className={`message message-${msg.role}`}>
-                        <div className="message-header">
-                            {msg.role === "user" && (
-                                <span className="sender user-sender">Guest ❯</span>
-                            )}
-                            {msg.role === "assistant" && (
+                {hasOlder && (
+                    <button className="continue-btn load-older-btn" onClick={onLoadOlder}>
+                        ↑ Load older messages
+                    </button>
+                )}
+                {items.map((item, i) =>
+                    item.kind === "turn" ? (
+                        <div
+                            key={i}
+                            className="message message-assistant message-turn"
+                            onContextMenu={(e) =>
+                                openMenu(
+                                    e,
+                                    item.messages
+                                        .filter(
+                                            (m) =>
+                                                m.role === "assistant" &&
+                                                !m.tool &&
+                                                !toolCallName(m.content)
+                                        )
+                                        .map((m) => m.content)
+                                        .join("\n\n"),
+                                    [...item.messages].reverse().find((m) => m.messageId !== undefined)
+                                        ?.messageId
+                                )
+                            }
+                        >
+                            <div className="message-header">
                                 <span className="sender assistant-sender">Amadeus ❯</span>
-                            )}
-                            {msg.role === "system" && (
-                                <span className="sender system-sender">⚙ System</span>
+                                {item.messages.some((m) => m.pinned) && pinMarker}
+                            </div>
+                            {item.messages.map((msg, j) => {
+                                const tool =
+                                    msg.role === "assistant" ? toolCallName(msg.content) : null;
+                                if (msg.tool) {
+                                    return (
+                                        <div
+                                            key={j}
+                                            className={`message-content turn-activity tool-activity ${msg.tool.state}`}
+                                        >
+                                            {TOOL_STATE_ICONS[msg.tool.state]} {msg.content}
+                                            {msg.tool.image && (
+                                                <img
+                                                    className="chat-image"
+                                                    src={msg.tool.image}
+                                                    alt={`${msg.tool.name} output`}
+                                                    title="Click to enlarge"
+                                                    onClick={() =>
+                                                        setZoomedImage(msg.tool?.image ?? null)
+                                                    }
+                                                />
+                                            )}
+                                        </div>
+                                    );
+                                }
+                                return tool ? (
+                                    <details key={j} className="turn-tool-call">
+                                        <summary>🔧 {tool}</summary>
+                                        <pre>{msg.content}</pre>
+                                    </details>
+                                ) : (
+                                    <React.Fragment key={j}>
+                                        <div
+                                            className={`message-content${msg.role === "system" ? " turn-activity" : ""}${msg.partial ? " streaming" : ""}`}
+                                        >
+                                            {renderMarkdown(msg)}
+                                        </div>
+                                        {msg.role === "assistant" && translateControls(msg)}
+                                    </React.Fragment>
+                                );
+                            })}
+                            {item.messages[item.messages.length - 1].truncated && continueButton}
+                            {canRegenerate(item, i) && regenerateButton}
+                            {branchButton(
+                                [...item.messages].reverse().find((m) => m.messageId !== undefined)
+                                    ?.messageId
                             )}
                         </div>
-                        <div className="message-content">
-                            {msg.role === "assistant" ? (
-                                <ReactMarkdown rehypePlugins={[rehypeHighlight]}>
-                                    {msg.content}
-                                </ReactMarkdown>
+                    ) : (
+                        <div
+                            key={i}
+                            className={`message message-${item.message.role}`}
+                            onContextMenu={(e) =>
+                                openMenu(e, item.message.content, item.message.messageId)
+                            }
+                        >
+                            <div className="message-header">
+                                {item.message.role === "user" && (
+                                    <span className="sender user-sender">Guest ❯</span>
+                                )}
+                                {item.message.role === "assistant" && (
+                                    <span className="sender assistant-sender">Amadeus ❯</span>
+                                )}
+                                {item.message.role === "system" && (
+                                    <span className="sender system-sender">⚙ System</span>
+                                )}
+                                {item.message.pinned && pinMarker}
+                            </div>
+                            {i === lastUser && editing !== null ? (
+                                editForm
                             ) : (
-                                msg.content
+                                <div
+                                    className={`message-content${item.message.partial ? " streaming" : ""}`}
+                                >
+                                    {item.message.role === "assistant" ||
+                                    item.message.role === "system"
+                                        ? renderMarkdown(item.message)
+                                        : userContent(item.message.content)}
+                                </div>
+                            )}
+                            {item.message.role === "assistant" &&
+                                translateControls(item.message)}
+                            {item.message.truncated && continueButton}
+                            {canRegenerate(item, i) && regenerateButton}
+                            {i === lastUser && editing === null && (
+                                <button
+                                    className="branch-btn"
+                                    onClick={() => setEditing(item.message.content)}
+                                    disabled={status.isThinking}
+                                    title="Change this message and get a new answer"
+                                >
+                                    ✎ edit
+                                </button>
                             )}
+                            {branchButton(item.message.messageId)}
                         </div>
-                    </div>
-                ))}
+                    )
+                )}
 
-                {/* Typing indicator */}
-                {status.isThinking && (
+                {/* Typing indicator; a reply already streaming in shows a caret instead */}
+                {status.isThinking && !messages[messages.length - 1]?.partial && (
                     <div className="message message-thinking">
                         <div className="typing-indicator">
                             <div className="dot" />
@@ -136,24 +1405,181 @@ export const ChatPanel: React.FC<ChatPanelProps> = ({
                 <div ref={messagesEndRef} />
             </div>
 
+            {zoomedImage && (
+                <div className="image-lightbox" onClick={() => setZoomedImage(null)}>
+                    <img src={zoomedImage} alt="Enlarged tool output" />
+                </div>
+            )}
+
+            {menu && (
+                <div className="message-menu" style={{ left: menu.x, top: menu.y }}>
+                    <button onClick={() => copyFromMenu(menu.text)}>⧉ Copy text</button>
+                    <button onClick={() => copyFromMenu(menu.markdown)}>⧉ Copy as markdown</button>
+                    {menu.messageId !== undefined && (
+                        <button
+                            onClick={() => {
+                                if (menu.messageId !== undefined) {
+                                    onPin(menu.messageId, !menuPinned);
+                                }
+                                setMenu(null);
+                            }}
+                        >
+                            📌 {menuPinned ? "Unpin message" : "Pin message"}
+                        </button>
+                    )}
+                    {menu.messageId !== undefined && (
+                        <button
+                            className="danger"
+                            onClick={() => {
+                                if (menu.messageId !== undefined) onDelete(menu.messageId);
+                                setMenu(null);
+                            }}
+                            disabled={status.isThinking}
+                        >
+                            🗑 Delete message
+                        </button>
+                    )}
+                </div>
+            )}
+
+            {/* Tool call waiting for the user's OK */}
+            {approval.pending && (
+                <div className="approval-card">
+                    <div className="approval-title">
+                        Amadeus wants to run <code>{approval.pending.tool}</code>
+                    </div>
+                    <pre className="approval-args">
+                        {approval.pending.preview ??
+                            JSON.stringify(approval.pending.args, null, 2)}
+                    </pre>
+                    <div className="approval-actions">
+                        <button
+                            className="approval-btn allow"
+                            onClick={() => approval.respond(approval.pending!.id, "allow")}
+                        >
+                            Allow
+                        </button>
+                        {approval.pending.always_allowable && (
+                            <button
+                                className="approval-btn"
+                                onClick={() =>
+                                    approval.respond(approval.pending!.id, "always_allow")
+                                }
+                                title={`Don't ask again for ${approval.pending.tool} this session`}
+                            >
+                                Always allow
+                            </button>
+                        )}
+                        <button
+                            className="approval-btn deny"
+                            onClick={() => approval.respond(approval.pending!.id, "deny")}
+                        >
+                            Deny
+                        </button>
+                    </div>
+                </div>
+            )}
+
             {/* Input */}
-            <div className="input-container">
+            <div className={`input-container${attachments.dragging ? " drop-target" : ""}`}>
+                {/* Push-to-talk writes its live transcript into the input box instead */}
+                {voice.caption && !voice.recording && (
+                    <div className="voice-caption">🎙 {voice.caption}</div>
+                )}
+                {attachments.paths.length > 0 && (
+                    <div className="attachment-chips pending">
+                        {attachments.paths.map((path) => (
+                            <span key={path} className="attachment-chip" title={path}>
+                                📎 {fileName(path)}
+                                <button
+                                    onClick={() => attachments.remove(path)}
+                                    title="Remove attachment"
+                                >
+                                    ×
+                                </button>
+                            </span>
+                        ))}
+                    </div>
+                )}
+                {suggestions.length > 0 && (
+                    <div className="command-suggestions">
+                        {suggestions.map((c, i) => (
+                            <div
+                                key={c.command}
+                                className={`command-suggestion${i === selectedSuggestion ? " selected" : ""}`}
+                                onMouseDown={(e) => {
+                                    e.preventDefault();
+                                    applySuggestion(i);
+                                }}
+                            >
+                                <span className="command-usage">{c.usage}</span>
+                                <span className="command-description">{c.description}</span>
+                            </div>
+                        ))}
+                    </div>
+                )}
                 <textarea
                     ref={textareaRef}
-                    value={input}
+                    value={voice.recording ? voice.caption ?? "" : input}
                     onChange={(e) => setInput(e.target.value)}
                     onKeyDown={handleKeyDown}
-                    placeholder="메시지를 입력하세요... (Shift+Enter로 줄바꿈)"
+                    placeholder={
+                        voice.recording
+                            ? "듣고 있어요..."
+                            : "메시지를 입력하세요... (Shift+Enter로 줄바꿈)"
+                    }
                     rows={1}
                     disabled={status.isThinking}
+                    readOnly={voice.recording}
                 />
+                {(voice.recording || voice.listenState === "hearing") && (
+                    <div className="mic-meter">
+                        {voice.levels.map((level, i) => (
+                            <span key={i} style={{ height: `${Math.max(8, level * 100)}%` }} />
+                        ))}
+                    </div>
+                )}
+                <button
+                    className={`mic-btn${voice.recording ? " recording" : ""}`}
+                    onPointerDown={() => {
+                        if (!voice.recording) voice.listen();
+                    }}
+                    onPointerUp={voice.release}
+                    onPointerLeave={voice.release}
+                    disabled={!voice.available || status.isThinking}
+                    title={
+                        voice.available
+                            ? "Voice input: click to speak until you pause, or hold and let go to send"
+                            : voice.reason ?? "Voice input unavailable"
+                    }
+                >
+                    🎤
+                </button>
                 <button
-                    className="send-btn"
-                    onClick={handleSend}
-                    disabled={!input.trim() || status.isThinking}
+                    className={`mic-btn${voice.continuous ? ` listening ${voice.listenState}` : ""}`}
+                    onClick={voice.toggleContinuous}
+                    disabled={!voice.available}
+                    title={
+                        voice.continuous
+                            ? `Hands-free: ${LISTEN_STATE_LABELS[voice.listenState]} (click to stop)`
+                            : "Hands-free listening"
+                    }
                 >
-                    Send
+                    👂
                 </button>
+                {status.isThinking ? (
+                    <button className="send-btn stop-btn" onClick={onStop} title="Stop (Esc)">
+                        ■ Stop
+                    </button>
+                ) : (
+                    <button
+                        className="send-btn"
+                        onClick={handleSend}
+                        disabled={!input.trim() && attachments.paths.length === 0}
+                    >
+                        Send
+                    </button>
+                )}
             </div>
         </div>
     );
@@ -0,0 +1,40 @@
+import React, { useRef, useState } from "react";
+import { ExtraProps } from "react-markdown";
+
+// How long the button reads "Copied" after a click
+const COPIED_MS = 1500;
+
+// A fenced code block from a reply, with a button that copies the code
+export const CodeBlock: React.FC<React.ComponentProps<"pre"> & ExtraProps> = ({
+    node: _node,
+    children,
+    ...props
+}) => {
+    const preRef = useRef<HTMLPreElement>(null);
+    const [copied, setCopied] = useState(false);
+
+    const copy = async () => {
+        try {
+            await navigator.clipboard.writeText(preRef.current?.innerText ?? "");
+            setCopied(true);
+            setTimeout(() => setCopied(false), COPIED_MS);
+        } catch (e) {
+            console.error("Failed to copy code:", e);
+        }
+    };
+
+    return (
+        <div className="code-block">
+            {/* Label is drawn by CSS so copying the whole message leaves it out */}
+            <button
+                className="code-copy-btn"
+                onClick={copy}
+                title="Copy code"
+                data-label={copied ? "✓ copied" : "⧉ copy"}
+            />
+            <pre ref={preRef} {...props}>
+                {children}
+            </pre>
+        </div>
+    );
+};
@@ -0,0 +1,45 @@
+import React from "react";
+import ReactMarkdown, { ExtraProps } from "react-markdown";
+import remarkGfm from "remark-gfm";
+import rehypeHighlight from "rehype-highlight";
+import { openUrl } from "@tauri-apps/plugin-opener";
+import "highlight.js/styles/github-dark.css";
+import { CodeBlock } from "./CodeBlock";
+
+// Links go to the system browser; following them inside the webview would replace the app
+const MarkdownLink: React.FC<React.ComponentProps<"a"> & ExtraProps> = ({
+    node: _node,
+    href,
+    children,
+    ...props
+}) => (
+    <a
+        {...props}
+        href={href}
+        title={href}
+        onClick={(e) => {
+            e.preventDefault();
+            if (href) {
+                openUrl(href).catch((err) => console.error("Failed to open link:", err));
+            }
+        }}
+    >
+        {children}
+    </a>
+);
+
+// GFM adds tables, strikethrough, task lists and bare URLs as links
+const REMARK_PLUGINS = [remarkGfm];
+const REHYPE_PLUGINS = [rehypeHighlight];
+const COMPONENTS = { pre: CodeBlock, a: MarkdownLink };
+
+// Markdown as Amadeus writes it: headers, lists, tables, links and highlighted code
+export const Markdown: React.FC<{ children: string }> = ({ children }) => (
+    <ReactMarkdown
+        remarkPlugins={REMARK_PLUGINS}
+        rehypePlugins={REHYPE_PLUGINS}
+        components={COMPONENTS}
+    >
+        {children}
+    </ReactMarkdown>
+);
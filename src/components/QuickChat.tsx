@@ -0,0 +1,38 @@
+import React, { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+// The small always-on-top box opened by the quick-chat hotkey. Replies show up (and are
+// spoken) in the main window as usual.
+export const QuickChat: React.FC = () => {
+    const [input, setInput] = useState("");
+
+    // Clicking anywhere else dismisses it, like a launcher
+    useEffect(() => {
+        const close = () => invoke("close_quick_chat");
+        window.addEventListener("blur", close);
+        return () => window.removeEventListener("blur", close);
+    }, []);
+
+    const handleKeyDown = (e: React.KeyboardEvent<HTMLInputElement>) => {
+        if (e.key === "Escape") {
+            invoke("close_quick_chat");
+        } else if (e.key === "Enter" && input.trim()) {
+            invoke("send_quick_chat", { message: input.trim() }).catch((err) =>
+                console.error("Failed to send message:", err)
+            );
+        }
+    };
+
+    return (
+        <div className="quick-chat">
+            <span className="sender assistant-sender">Amadeus ❯</span>
+            <input
+                autoFocus
+                value={input}
+                onChange={(e) => setInput(e.target.value)}
+                onKeyDown={handleKeyDown}
+                placeholder="메시지를 입력하세요... (Esc로 닫기)"
+            />
+        </div>
+    );
+};
@@ -0,0 +1,88 @@
+import React, { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+interface Point {
+    x: number;
+    y: number;
+}
+
+// Drags smaller than this (in CSS px) are treated as stray clicks
+const MIN_SELECTION = 8;
+
+// Overlay windows are opened with `#region-select?display=<id>`
+function displayIdFromHash(): number {
+    const query = window.location.hash.split("?")[1] ?? "";
+    return Number(new URLSearchParams(query).get("display") ?? 0);
+}
+
+function toRect(a: Point, b: Point) {
+    return {
+        left: Math.min(a.x, b.x),
+        top: Math.min(a.y, b.y),
+        width: Math.abs(a.x - b.x),
+        height: Math.abs(a.y - b.y),
+    };
+}
+
+export const RegionSelect: React.FC = () => {
+    const [displayId] = useState(displayIdFromHash);
+    const [background, setBackground] = useState<string | null>(null);
+    const [start, setStart] = useState<Point | null>(null);
+    const [current, setCurrent] = useState<Point | null>(null);
+
+    useEffect(() => {
+        invoke<string>("get_region_background", { displayId })
+            .then(setBackground)
+            .catch((e) => console.error("Failed to load region background:", e));
+    }, [displayId]);
+
+    // Escape cancels on every display at once
+    useEffect(() => {
+        const handleKeyDown = (e: KeyboardEvent) => {
+            if (e.key === "Escape") invoke("cancel_region_select");
+        };
+        window.addEventListener("keydown", handleKeyDown);
+        return () => window.removeEventListener("keydown", handleKeyDown);
+    }, []);
+
+    const handleMouseUp = () => {
+        if (!start || !current) return;
+        const rect = toRect(start, current);
+        setStart(null);
+        setCurrent(null);
+        if (rect.width < MIN_SELECTION || rect.height < MIN_SELECTION) return;
+
+        invoke("finish_region_select", {
+            displayId,
+            selection: {
+                left: rect.left / window.innerWidth,
+                top: rect.top / window.innerHeight,
+                width: rect.width / window.innerWidth,
+                height: rect.height / window.innerHeight,
+            },
+        }).catch((e) => console.error("Region selection failed:", e));
+    };
+
+    const rect = start && current ? toRect(start, current) : null;
+
+    return (
+        <div
+            className="region-select"
+            style={background ? { backgroundImage: `url(data:image/jpeg;base64,${background})` } : undefined}
+            onMouseDown={(e) => {
+                setStart({ x: e.clientX, y: e.clientY });
+                setCurrent({ x: e.clientX, y: e.clientY });
+            }}
+            onMouseMove={(e) => {
+                if (start) setCurrent({ x: e.clientX, y: e.clientY });
+            }}
+            onMouseUp={handleMouseUp}
+        >
+            {rect ? (
+                <div className="region-select-rect" style={rect} />
+            ) : (
+                <div className="region-select-hint">Drag to select a region · Esc to cancel</div>
+            )}
+        </div>
+    );
+};
@@ -0,0 +1,150 @@
+import React, { useState } from "react";
+import { useSetup, SetupCheck } from "../hooks/useSetup";
+import { useModels } from "../hooks/useModels";
+
+const STEP_LABELS: Record<string, string> = {
+    ollama: "🦙 Ollama",
+    model: "🧠 Chat model",
+    whisper: "🎤 Voice input model",
+    avatar: "🧍 Avatar",
+    microphone: "🎙️ Microphone",
+    accessibility: "🖱️ Accessibility",
+};
+
+// A small model that runs on most machines; ~2 GB
+const STARTER_MODEL_URL =
+    "https://huggingface.co/Qwen/Qwen2.5-3B-Instruct-GGUF/resolve/main/qwen2.5-3b-instruct-q4_k_m.gguf";
+
+// Walks a new user through what's missing instead of leaving chat silently broken.
+// Shown until finished or skipped, and again whenever a required step breaks.
+export const SetupGuide: React.FC = () => {
+    const setup = useSetup();
+    const models = useModels();
+    const [modelUrl, setModelUrl] = useState(STARTER_MODEL_URL);
+
+    const status = setup.status;
+    if (!status || status.step === "done") return null;
+    const requiredMissing = status.checks.some((c) => c.required && !c.ready);
+    if (status.dismissed && !requiredMissing) return null;
+
+    const downloadAndLoad = async () => {
+        const list = await models.fetchModel(modelUrl.trim());
+        const file = modelUrl.trim().split("/").pop()?.toLowerCase() ?? "";
+        const downloaded = list?.find((m) => m.path?.toLowerCase().endsWith(file));
+        if (downloaded) await models.load(downloaded.name);
+        setup.refresh();
+    };
+
+    const action = (check: SetupCheck) => {
+        switch (check.step) {
+            case "ollama":
+                return (
+                    <button className="approval-btn" onClick={setup.reconnect}>
+                        Check again
+                    </button>
+                );
+            case "model":
+                return (
+                    <div className="setup-model">
+                        {models.models.length > 0 && (
+                            <select
+                                className="setting-select"
+                                value=""
+                                disabled={models.loading !== null}
+                                onChange={(e) => models.load(e.target.value)}
+                            >
+                                <option value="">Use an installed model…</option>
+                                {models.models.map((m) => (
+                                    <option key={m.name} value={m.name}>
+                                        {m.installed ? "" : "📦 "}
+                                        {m.name}
+                                    </option>
+                                ))}
+                            </select>
+                        )}
+                        <input
+                            className="setting-select"
+                            value={modelUrl}
+                            onChange={(e) => setModelUrl(e.target.value)}
+                        />
+                        <button
+                            className="approval-btn allow"
+                            disabled={!modelUrl.trim() || models.download !== null}
+                            onClick={downloadAndLoad}
+                        >
+                            {models.download ? "Downloading…" : "Download"}
+                        </button>
+                        {models.download?.total ? (
+                            <span className="setting-hint">
+                                {Math.floor(
+                                    (models.download.downloaded / models.download.total) * 100,
+                                )}
+                                %
+                            </span>
+                        ) : null}
+                        {models.loading && (
+                            <span className="setting-hint">Loading {models.loading}...</span>
+                        )}
+                        {models.error && <span className="setting-hint">{models.error}</span>}
+                    </div>
+                );
+            case "avatar":
+                return (
+                    <button className="approval-btn" onClick={setup.pickAvatar}>
+                        Choose .vrm…
+                    </button>
+                );
+            case "microphone":
+            case "accessibility":
+                return (
+                    <button
+                        className="approval-btn"
+                        onClick={() => setup.requestPermission(check.step)}
+                    >
+                        Open settings
+                    </button>
+                );
+            default:
+                return null;
+        }
+    };
+
+    return (
+        <div className="approval-card setup-guide">
+            <div className="approval-title">Welcome! A few things before Amadeus is fully ready:</div>
+            <ul className="setup-steps">
+                {status.checks.map((check) => (
+                    <li
+                        key={check.step}
+                        className={`setup-step${check.step === status.step ? " current" : ""}`}
+                    >
+                        <span>
+                            {check.ready ? "✅" : check.required ? "❌" : "⚠️"}{" "}
+                            {STEP_LABELS[check.step]}
+                        </span>
+                        {!check.ready && (
+                            <>
+                                <span className="setting-hint">{check.detail}</span>
+                                {action(check)}
+                            </>
+                        )}
+                    </li>
+                ))}
+            </ul>
+            {setup.error && <span className="setting-hint">{setup.error}</span>}
+            <div className="approval-actions">
+                <button className="approval-btn" onClick={setup.refresh}>
+                    ↻ Check again
+                </button>
+                <button
+                    className="approval-btn"
+                    disabled={requiredMissing}
+                    onClick={setup.finish}
+                    title={requiredMissing ? "Chat needs Ollama and a model first" : undefined}
+                >
+                    Skip the rest
+                </button>
+            </div>
+        </div>
+    );
+};
@@ -0,0 +1,60 @@
+import React from "react";
+import { Conversation } from "../hooks/useConversations";
+
+interface WorldLinesProps {
+    conversations: Conversation[];
+    active: number | null;
+    onOpen: (id: number) => void;
+}
+
+// Indent per level of branching
+const INDENT_PX = 16;
+
+// The branches around the active conversation as a tree of world lines: the original
+// conversation at the top and every fork indented under the line it split from
+export const WorldLines: React.FC<WorldLinesProps> = ({ conversations, active, onOpen }) => {
+    const byId = new Map(conversations.map((c) => [c.id, c]));
+    const children = new Map<number, Conversation[]>();
+    for (const c of [...conversations].sort((a, b) => a.id - b.id)) {
+        // A branch whose parent was deleted starts a tree of its own
+        if (c.parent_id !== null && byId.has(c.parent_id)) {
+            children.set(c.parent_id, [...(children.get(c.parent_id) ?? []), c]);
+        }
+    }
+
+    // Parents are always older than their branches, so this ends
+    let root = active !== null ? byId.get(active) : undefined;
+    while (root?.parent_id != null && byId.has(root.parent_id)) {
+        root = byId.get(root.parent_id);
+    }
+    if (!root) {
+        return <div className="conversation-meta">No conversation open</div>;
+    }
+
+    const renderLine = (c: Conversation, depth: number): React.ReactNode => (
+        <React.Fragment key={c.id}>
+            <div
+                className={`conversation-item${c.id === active ? " active" : ""}`}
+                style={{ paddingLeft: 8 + depth * INDENT_PX }}
+                onClick={() => {
+                    if (c.id !== active) onOpen(c.id);
+                }}
+            >
+                <div className="conversation-preview">
+                    {depth > 0 && "↳ "}
+                    {c.parent_id !== null
+                        ? c.branch_preview ?? "(branch point deleted)"
+                        : c.preview ?? "(empty)"}
+                </div>
+                <div className="conversation-meta">
+                    World line #{c.id} · {c.created_at}
+                    {c.parent_id !== null && ` · split from #${c.parent_id}`}
+                    {c.id === active && " · you are here"}
+                </div>
+            </div>
+            {(children.get(c.id) ?? []).map((child) => renderLine(child, depth + 1))}
+        </React.Fragment>
+    );
+
+    return <div className="conversation-list">{renderLine(root, 0)}</div>;
+};
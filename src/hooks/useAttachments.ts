@@ -0,0 +1,46 @@
+import { useState, useEffect, useCallback } from "react";
+import { getCurrentWebview } from "@tauri-apps/api/webview";
+
+// File name from a dropped path, on any platform
+export function fileName(path: string): string {
+    return path.split(/[\\/]/).pop() ?? path;
+}
+
+// Files dropped onto the window, waiting to go out with the next message. Only paths are
+// kept; the backend reads them when the message is sent.
+export function useAttachments() {
+    const [paths, setPaths] = useState<string[]>([]);
+    // A drag is hovering over the window
+    const [dragging, setDragging] = useState(false);
+
+    useEffect(() => {
+        const unlistenPromise = getCurrentWebview().onDragDropEvent((event) => {
+            switch (event.payload.type) {
+                case "enter":
+                case "over":
+                    setDragging(true);
+                    break;
+                case "drop": {
+                    const dropped = event.payload.paths;
+                    setDragging(false);
+                    setPaths((prev) => [...prev, ...dropped.filter((p) => !prev.includes(p))]);
+                    break;
+                }
+                case "leave":
+                    setDragging(false);
+                    break;
+            }
+        });
+        return () => {
+            unlistenPromise.then((unlisten) => unlisten());
+        };
+    }, []);
+
+    const remove = useCallback((path: string) => {
+        setPaths((prev) => prev.filter((p) => p !== path));
+    }, []);
+
+    const clear = useCallback(() => setPaths([]), []);
+
+    return { paths, dragging, remove, clear };
+}
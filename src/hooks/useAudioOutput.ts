@@ -0,0 +1,40 @@
+import { useState, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+interface AudioOutputSettings {
+    devices: string[];
+    selected: string | null;
+    volume: number;
+}
+
+export function useAudioOutput() {
+    const [devices, setDevices] = useState<string[]>([]);
+    const [device, setDevice] = useState<string | null>(null);
+    const [volume, setVolume] = useState(1);
+    const [available, setAvailable] = useState(true);
+
+    const refresh = useCallback(async () => {
+        try {
+            const settings = await invoke<AudioOutputSettings>("get_audio_outputs");
+            setDevices(settings.devices);
+            setDevice(settings.selected);
+            setVolume(settings.volume);
+            setAvailable(true);
+        } catch (e) {
+            console.error("Failed to load audio outputs:", e);
+            setAvailable(false);
+        }
+    }, []);
+
+    const apply = useCallback(async (nextDevice: string | null, nextVolume: number) => {
+        setDevice(nextDevice);
+        setVolume(nextVolume);
+        try {
+            await invoke("set_audio_output", { device: nextDevice, volume: nextVolume });
+        } catch (e) {
+            console.error("Failed to set audio output:", e);
+        }
+    }, []);
+
+    return { devices, device, volume, available, refresh, apply };
+}
@@ -0,0 +1,34 @@
+import { useState, useEffect } from "react";
+import { invoke, convertFileSrc } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+
+interface AvatarSettings {
+    target_fps: number;
+    model: string | null;
+}
+
+const DEFAULT_TARGET_FPS = 30;
+
+export function useAvatarSettings() {
+    const [targetFps, setTargetFps] = useState(DEFAULT_TARGET_FPS);
+    // A .vrm picked during setup, as a URL the webview can load
+    const [customModel, setCustomModel] = useState<string | null>(null);
+
+    useEffect(() => {
+        const load = () =>
+            invoke<AvatarSettings>("get_avatar_settings")
+                .then((settings) => {
+                    setTargetFps(settings.target_fps);
+                    setCustomModel(settings.model ? convertFileSrc(settings.model) : null);
+                })
+                .catch((e) => console.error("Failed to load avatar settings:", e));
+        load();
+        // Picking an avatar in the setup guide changes the model
+        const unlisten = listen("setup-status", load);
+        return () => {
+            unlisten.then((fn) => fn());
+        };
+    }, []);
+
+    return { targetFps, customModel };
+}
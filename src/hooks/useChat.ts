@@ -6,6 +6,66 @@ import type { AvatarState, AvatarEmotion } from "../components/AvatarCanvas";
 export interface ChatMessage {
     role: string;
     content: string;
+    // True while the message is still being streamed in
+    partial?: boolean;
+    // True if generation stopped at the token limit; `/continue` resumes it
+    truncated?: boolean;
+    // Shared by every message produced in response to one user message
+    turnId?: number;
+    // Database row id once persisted; messages with an id can be branched from
+    messageId?: number;
+    // Kept when old messages are pruned
+    pinned?: boolean;
+    // Set on the system message tracking one tool call
+    tool?: ToolActivity;
+}
+
+export interface ToolActivity {
+    name: string;
+    state: "running" | "done" | "failed";
+    cached?: boolean;
+    error?: string;
+    // Failed by running past its time limit
+    timedOut?: boolean;
+    // Data URL of an image the tool returned, e.g. a screenshot
+    image?: string;
+}
+
+// Mirrors AgentEvent in src-tauri/src/agent/events.rs
+type AgentEvent =
+    | { type: "assistant_delta"; turn_id?: number; content: string }
+    | { type: "assistant_done"; turn_id?: number; emotion?: AvatarEmotion }
+    | { type: "tool_started"; turn_id: number; tool: string; args: unknown }
+    | { type: "tool_finished"; turn_id: number; tool: string; cached: boolean; image?: string }
+    | { type: "tool_failed"; turn_id: number; tool: string; error: string; timed_out: boolean }
+    | { type: "status"; status: string; is_thinking: boolean }
+    | { type: "error"; turn_id?: number; message: string };
+
+function describeTool(tool: ToolActivity): string {
+    switch (tool.state) {
+        case "running":
+            return `Running ${tool.name}...`;
+        case "done":
+            return tool.cached ? `${tool.name} finished (cached)` : `${tool.name} finished`;
+        case "failed":
+            return tool.timedOut ? `${tool.name} timed out` : `${tool.name} failed: ${tool.error}`;
+    }
+}
+
+// Resolve the latest still-running call of `name`
+function finishTool(
+    messages: ChatMessage[],
+    name: string,
+    update: Omit<ToolActivity, "name">
+): ChatMessage[] {
+    const index = messages
+        .map((m) => m.tool?.name === name && m.tool.state === "running")
+        .lastIndexOf(true);
+    if (index === -1) return messages;
+    const tool = { name, ...update };
+    const next = [...messages];
+    next[index] = { ...next[index], content: describeTool(tool), tool };
+    return next;
 }
 
 export interface ChatStatus {
@@ -13,6 +73,40 @@ export interface ChatStatus {
     isThinking: boolean;
 }
 
+export interface ContextUsage {
+    used: number;
+    limit: number;
+    warnAt: number;
+    // Older messages dropped from the prompt on the latest turn
+    trimmed: number;
+}
+
+// Tokens and speed of the latest turn
+export interface ChatMetrics {
+    turnId: number;
+    model: string;
+    promptTokens: number;
+    generatedTokens: number;
+    promptEvalMs: number;
+    generationMs: number;
+    tokensPerSecond: number;
+}
+
+// A page entry from get_history; mirrors StoredMessage in src-tauri/src/agent/memory.rs
+interface StoredMessage {
+    id: number;
+    role: string;
+    content: string;
+    kind: "chat" | "tool_call" | "tool_result";
+    pinned: boolean;
+}
+
+export interface MaintenanceReport {
+    size_before: number;
+    size_after: number;
+    pruned: number;
+}
+
 // Simple emotion detection from response content
 function detectEmotion(content: string): AvatarEmotion {
     const lower = content.toLowerCase();
@@ -55,6 +149,17 @@ function detectEmotion(content: string): AvatarEmotion {
     return "neutral";
 }
 
+function updateLastAssistant(
+    messages: ChatMessage[],
+    update: (message: ChatMessage) => ChatMessage
+): ChatMessage[] {
+    const index = messages.map((m) => m.role).lastIndexOf("assistant");
+    if (index === -1) return messages;
+    const next = [...messages];
+    next[index] = update(next[index]);
+    return next;
+}
+
 export function useChat() {
     const [messages, setMessages] = useState<ChatMessage[]>([]);
     const [status, setStatus] = useState<ChatStatus>({
@@ -63,31 +168,95 @@ export function useChat() {
     });
     const [avatarState, setAvatarState] = useState<AvatarState>("idle");
     const [emotion, setEmotion] = useState<AvatarEmotion>("neutral");
+    const [dbSize, setDbSize] = useState<number | null>(null);
+    const [contextUsage, setContextUsage] = useState<ContextUsage | null>(null);
+    const [metrics, setMetrics] = useState<ChatMetrics | null>(null);
+    // Messages older than the first one shown may be saved
+    const [hasOlder, setHasOlder] = useState(true);
+
+    const refreshDbStats = useCallback(async () => {
+        try {
+            const stats = await invoke<{ size_bytes: number }>("get_db_stats");
+            setDbSize(stats.size_bytes);
+        } catch (e) {
+            console.error("Failed to read db stats:", e);
+        }
+    }, []);
+
+    useEffect(() => {
+        refreshDbStats();
+    }, [refreshDbStats]);
+
     useEffect(() => {
         let isMounted = true;
         let unlistenMsg: UnlistenFn | undefined;
         let unlistenStatus: UnlistenFn | undefined;
+        let unlistenCleared: UnlistenFn | undefined;
+        let unlistenSpeaking: UnlistenFn | undefined;
+        let unlistenTruncated: UnlistenFn | undefined;
+        let unlistenAppend: UnlistenFn | undefined;
+        let unlistenHistory: UnlistenFn | undefined;
+        let unlistenTurnSaved: UnlistenFn | undefined;
+        let unlistenContext: UnlistenFn | undefined;
+        let unlistenMetrics: UnlistenFn | undefined;
+        let unlistenCrashed: UnlistenFn | undefined;
+        let unlistenRewrite: UnlistenFn | undefined;
+        let unlistenEmotion: UnlistenFn | undefined;
+        let unlistenRewound: UnlistenFn | undefined;
+        let neutralTimer: ReturnType<typeof setTimeout> | undefined;
+        // Text of the reply currently streaming in, for emotion detection once it completes
+        let streamed = "";
+
+        // Streamed reply: grow a partial assistant message until it is done
+        const appendToken = (
+            event: Extract<AgentEvent, { type: "assistant_delta" | "assistant_done" }>
+        ) => {
+            const content = event.type === "assistant_delta" ? event.content : "";
+            const done = event.type === "assistant_done";
+            streamed += content;
+            setMessages((prev) => {
+                const last = prev[prev.length - 1];
+                if (last?.role === "assistant" && last.partial) {
+                    return [
+                        ...prev.slice(0, -1),
+                        { ...last, content: last.content + content, partial: !done },
+                    ];
+                }
+                if (done) return prev;
+                return [
+                    ...prev,
+                    { role: "assistant", content, turnId: event.turn_id, partial: true },
+                ];
+            });
+            if (done) {
+                if (streamed) {
+                    // The reply's own emotion tag wins over guessing from its words
+                    const tagged = event.type === "assistant_done" ? event.emotion : undefined;
+                    setEmotion(tagged ?? detectEmotion(streamed));
+                    setAvatarState("idle");
+                }
+                streamed = "";
+                setStatus((prev) => ({ ...prev, isThinking: false }));
+            }
+        };
 
         const setupListeners = async () => {
-            const msgPromise = listen<{ role: string; content: string }>(
+            const msgPromise = listen<{ role: string; content: string; turn_id?: number }>(
                 "chat-message",
                 (event) => {
                     setMessages((prev) => [
                         ...prev,
-                        { role: event.payload.role, content: event.payload.content },
+                        {
+                            role: event.payload.role,
+                            content: event.payload.content,
+                            turnId: event.payload.turn_id,
+                        },
                     ]);
 
+                    // Mouth movement follows tts-speaking; this only sets the expression
                     if (event.payload.role === "assistant") {
                         setEmotion(detectEmotion(event.payload.content));
-                        setAvatarState("speaking");
-                        const speakDuration = Math.min(
-                            Math.max(event.payload.content.length * 50, 2000),
-                            15000
-                        );
-                        setTimeout(() => {
-                            setAvatarState("idle");
-                            setTimeout(() => setEmotion("neutral"), 3000);
-                        }, speakDuration);
+                        setAvatarState("idle");
                     }
 
                     if (event.payload.role === "assistant" || event.payload.role === "system") {
@@ -96,28 +265,245 @@ export function useChat() {
                 }
             );
 
-            const statusPromise = listen<{
-                status: string;
-                is_thinking: boolean;
-            }>("chat-status", (event) => {
-                setStatus({
-                    status: event.payload.status,
-                    isThinking: event.payload.is_thinking,
+            // Streamed replies, tool progress, status and errors
+            const statusPromise = listen<AgentEvent>("agent-event", (event) => {
+                const payload = event.payload;
+                switch (payload.type) {
+                    case "status":
+                        setStatus({ status: payload.status, isThinking: payload.is_thinking });
+                        if (payload.is_thinking) {
+                            setAvatarState("thinking");
+                        }
+                        break;
+                    case "assistant_delta":
+                    case "assistant_done":
+                        appendToken(payload);
+                        break;
+                    case "tool_started": {
+                        const tool: ToolActivity = { name: payload.tool, state: "running" };
+                        setMessages((prev) => [
+                            ...prev,
+                            { role: "system", content: describeTool(tool), turnId: payload.turn_id, tool },
+                        ]);
+                        break;
+                    }
+                    case "tool_finished":
+                        setMessages((prev) =>
+                            finishTool(prev, payload.tool, {
+                                state: "done",
+                                cached: payload.cached,
+                                image: payload.image,
+                            })
+                        );
+                        break;
+                    case "tool_failed":
+                        setMessages((prev) =>
+                            finishTool(prev, payload.tool, {
+                                state: "failed",
+                                error: payload.error,
+                                timedOut: payload.timed_out,
+                            })
+                        );
+                        break;
+                    case "error":
+                        setMessages((prev) => [
+                            ...prev,
+                            { role: "system", content: `❌ ${payload.message}`, turnId: payload.turn_id },
+                        ]);
+                        setStatus((prev) => ({ ...prev, isThinking: false }));
+                        break;
+                }
+            });
+
+            // `/clear` can come from the input box as well as the toolbar button
+            const clearedPromise = listen("chat-cleared", () => {
+                setMessages([]);
+                setAvatarState("idle");
+                setEmotion("neutral");
+            });
+
+            // Full transcript of a conversation opened with `/new` or `/switch`
+            const historyPromise = listen<
+                { role: string; content: string; message_id?: number; pinned: boolean }[]
+            >(
+                "chat-history",
+                (event) => {
+                    setMessages(
+                        event.payload.map((m) => ({
+                            role: m.role,
+                            content: m.content,
+                            messageId: m.message_id,
+                            pinned: m.pinned,
+                        }))
+                    );
+                    setHasOlder(true);
+                    setAvatarState("idle");
+                    setEmotion("neutral");
+                    setStatus((prev) => ({ ...prev, isThinking: false }));
+                }
+            );
+
+            // `/regenerate` and `/edit` undo the last turn, which then comes back as a new one
+            const rewoundPromise = listen("chat-rewound", () => {
+                setMessages((prev) => {
+                    const lastUser = prev.map((m) => m.role === "user").lastIndexOf(true);
+                    return lastUser === -1 ? prev : prev.slice(0, lastUser);
                 });
+            });
+
+            // A turn is persisted in one transaction at its end; attach the row ids then
+            const turnSavedPromise = listen<{ turn_id: number; first_id: number; last_id: number }>(
+                "chat-turn-saved",
+                (event) => {
+                    const { turn_id, first_id, last_id } = event.payload;
+                    setMessages((prev) => {
+                        const next = prev.map((m) =>
+                            m.turnId === turn_id && m.role === "user"
+                                ? { ...m, messageId: first_id }
+                                : m
+                        );
+                        const last = next
+                            .map((m) => m.turnId === turn_id && m.role === "assistant")
+                            .lastIndexOf(true);
+                        if (last !== -1) next[last] = { ...next[last], messageId: last_id };
+                        return next;
+                    });
+                }
+            );
+
+            // Emitted by the backend when `say` starts and when the last utterance exits
+            const speakingPromise = listen<{ speaking: boolean }>("tts-speaking", (event) => {
+                clearTimeout(neutralTimer);
+                if (event.payload.speaking) {
+                    setAvatarState("speaking");
+                } else {
+                    setAvatarState("idle");
+                    neutralTimer = setTimeout(() => setEmotion("neutral"), 3000);
+                }
+            });
+
+            // Expressions not tied to a reply, e.g. surprise when a timer rings
+            const emotionPromise = listen<{ emotion: AvatarEmotion }>("avatar-emotion", (event) => {
+                clearTimeout(neutralTimer);
+                setEmotion(event.payload.emotion);
+                // Relaxes on its own when nothing is spoken (TTS off); speech restarts the timer
+                neutralTimer = setTimeout(() => setEmotion("neutral"), 5000);
+            });
+
+            const truncatedPromise = listen("chat-truncated", () => {
+                setMessages((prev) => updateLastAssistant(prev, (m) => ({ ...m, truncated: true })));
+            });
 
-                if (event.payload.is_thinking) {
-                    setAvatarState("thinking");
+            // `/continue` output goes into the same bubble as the cut-off reply
+            const appendPromise = listen<{ content: string; truncated: boolean }>(
+                "chat-append",
+                (event) => {
+                    setMessages((prev) =>
+                        updateLastAssistant(prev, (m) => ({
+                            ...m,
+                            content: m.content + event.payload.content,
+                            truncated: event.payload.truncated,
+                        }))
+                    );
+                    setStatus((prev) => ({ ...prev, isThinking: false }));
                 }
+            );
+
+            // A tool call was stripped from the reply: keep only the prose, or drop the bubble
+            const rewritePromise = listen<{ content: string }>("chat-rewrite", (event) => {
+                const { content } = event.payload;
+                setMessages((prev) => {
+                    if (content) return updateLastAssistant(prev, (m) => ({ ...m, content }));
+                    const index = prev.map((m) => m.role).lastIndexOf("assistant");
+                    return index === -1 ? prev : prev.filter((_, i) => i !== index);
+                });
+            });
+
+            // Prompt size against the model's context window, sent before and after each call
+            const contextPromise = listen<{
+                used: number;
+                limit: number;
+                warn_at: number;
+                trimmed: number;
+            }>("context-usage", (event) => {
+                setContextUsage({
+                    used: event.payload.used,
+                    limit: event.payload.limit,
+                    warnAt: event.payload.warn_at,
+                    trimmed: event.payload.trimmed,
+                });
+            });
+
+            const metricsPromise = listen<{
+                turn_id: number;
+                model: string;
+                prompt_tokens: number;
+                generated_tokens: number;
+                prompt_eval_ms: number;
+                generation_ms: number;
+                tokens_per_second: number;
+            }>("chat-metrics", (event) => {
+                setMetrics({
+                    turnId: event.payload.turn_id,
+                    model: event.payload.model,
+                    promptTokens: event.payload.prompt_tokens,
+                    generatedTokens: event.payload.generated_tokens,
+                    promptEvalMs: event.payload.prompt_eval_ms,
+                    generationMs: event.payload.generation_ms,
+                    tokensPerSecond: event.payload.tokens_per_second,
+                });
+            });
+
+            // The backend restarts the agent loop on its own; messages sent meanwhile wait
+            const crashedPromise = listen<{
+                reason: string;
+                restarts: number;
+                retry_in_secs: number;
+            }>("agent-crashed", (event) => {
+                setMessages((prev) => [
+                    ...prev.map((m) => (m.partial ? { ...m, partial: false } : m)),
+                    {
+                        role: "system",
+                        content: `⚠️ The agent stopped (${event.payload.reason}). Restarting in ${event.payload.retry_in_secs}s…`,
+                    },
+                ]);
+                setAvatarState("idle");
+                setStatus({ status: "Restarting", isThinking: true });
             });
 
-            // Wait for both listeners to be registered
+            // Wait for all listeners to be registered
             unlistenMsg = await msgPromise;
             unlistenStatus = await statusPromise;
+            unlistenCleared = await clearedPromise;
+            unlistenSpeaking = await speakingPromise;
+            unlistenTruncated = await truncatedPromise;
+            unlistenAppend = await appendPromise;
+            unlistenHistory = await historyPromise;
+            unlistenTurnSaved = await turnSavedPromise;
+            unlistenContext = await contextPromise;
+            unlistenMetrics = await metricsPromise;
+            unlistenCrashed = await crashedPromise;
+            unlistenRewrite = await rewritePromise;
+            unlistenEmotion = await emotionPromise;
+            unlistenRewound = await rewoundPromise;
 
             // If component unmounted while we were waiting, cleanup immediately
             if (!isMounted) {
                 if (unlistenMsg) unlistenMsg();
                 if (unlistenStatus) unlistenStatus();
+                if (unlistenCleared) unlistenCleared();
+                if (unlistenSpeaking) unlistenSpeaking();
+                if (unlistenTruncated) unlistenTruncated();
+                if (unlistenAppend) unlistenAppend();
+                if (unlistenHistory) unlistenHistory();
+                if (unlistenTurnSaved) unlistenTurnSaved();
+                if (unlistenContext) unlistenContext();
+                if (unlistenMetrics) unlistenMetrics();
+                if (unlistenCrashed) unlistenCrashed();
+            if (unlistenRewrite) unlistenRewrite();
+                if (unlistenRewrite) unlistenRewrite();
+                if (unlistenEmotion) unlistenEmotion();
+                if (unlistenRewound) unlistenRewound();
             }
         };
 
@@ -127,19 +513,33 @@ export function useChat() {
             isMounted = false;
             if (unlistenMsg) unlistenMsg();
             if (unlistenStatus) unlistenStatus();
+            if (unlistenCleared) unlistenCleared();
+            if (unlistenSpeaking) unlistenSpeaking();
+            if (unlistenTruncated) unlistenTruncated();
+            if (unlistenAppend) unlistenAppend();
+            if (unlistenHistory) unlistenHistory();
+            if (unlistenTurnSaved) unlistenTurnSaved();
+            if (unlistenContext) unlistenContext();
+            if (unlistenMetrics) unlistenMetrics();
+            if (unlistenCrashed) unlistenCrashed();
+            if (unlistenRewrite) unlistenRewrite();
+            if (unlistenEmotion) unlistenEmotion();
+            if (unlistenRewound) unlistenRewound();
+            clearTimeout(neutralTimer);
         };
     }, []);
 
     const sendMessage = useCallback(
-        async (text: string) => {
-            if (!text.trim()) return;
+        // `attachments` are paths of files to send along; the backend reads them
+        async (text: string, attachments: string[] = []) => {
+            if (!text.trim() && attachments.length === 0) return;
 
             // Don't add user message locally — backend emits it via chat-message event
             // This keeps backend as single source of truth
             setStatus({ status: "Sending", isThinking: true });
 
             try {
-                await invoke("send_message", { message: text });
+                await invoke("send_message", { message: text, attachments });
             } catch (e) {
                 console.error("Failed to send message:", e);
                 setMessages((prev) => [
@@ -164,5 +564,125 @@ export function useChat() {
         }
     }, []);
 
-    return { messages, status, avatarState, emotion, sendMessage, clearChat };
+    // The partial reply stays; the backend posts a "stopped" notice
+    const stopGeneration = useCallback(async () => {
+        try {
+            await invoke("stop_generation");
+        } catch (e) {
+            console.error("Failed to stop generation:", e);
+        }
+    }, []);
+
+    // The old reply is removed on chat-rewound and the new one streams in as usual
+    const regenerate = useCallback(async () => {
+        setStatus({ status: "Sending", isThinking: true });
+        try {
+            await invoke("regenerate");
+        } catch (e) {
+            console.error("Failed to regenerate:", e);
+            setStatus({ status: "Error", isThinking: false });
+        }
+    }, []);
+
+    const editLastMessage = useCallback(async (content: string) => {
+        if (!content.trim()) return;
+        setStatus({ status: "Sending", isThinking: true });
+        try {
+            await invoke("edit_last_user_message", { content });
+        } catch (e) {
+            console.error("Failed to edit message:", e);
+            setStatus({ status: "Error", isThinking: false });
+        }
+    }, []);
+
+    // Scroll-back: put the page saved before the oldest message shown in front
+    const loadOlder = useCallback(async () => {
+        const ids = messages.flatMap((m) => (m.messageId !== undefined ? [m.messageId] : []));
+        try {
+            const page = await invoke<{ messages: StoredMessage[]; has_more: boolean }>(
+                "get_history",
+                { beforeId: ids.length > 0 ? Math.min(...ids) : null }
+            );
+            setMessages((prev) => [
+                ...page.messages.map((m) => ({
+                    // Tool output is saved with the user role but shown as a system message
+                    role: m.kind === "tool_result" ? "system" : m.role,
+                    content: m.content,
+                    messageId: m.id,
+                    pinned: m.pinned,
+                })),
+                ...prev,
+            ]);
+            setHasOlder(page.has_more);
+        } catch (e) {
+            console.error("Failed to load older messages:", e);
+        }
+    }, [messages]);
+
+    // The backend switches to the new branch and replays it via chat-history
+    const branchFrom = useCallback(async (messageId: number) => {
+        try {
+            await invoke("branch_conversation", { messageId });
+        } catch (e) {
+            console.error("Failed to branch conversation:", e);
+        }
+    }, []);
+
+    // The backend reopens the conversation and replays it without the message
+    const deleteMessage = useCallback(async (messageId: number) => {
+        try {
+            await invoke("delete_message", { messageId });
+        } catch (e) {
+            console.error("Failed to delete message:", e);
+        }
+    }, []);
+
+    const setPinned = useCallback(async (messageId: number, pinned: boolean) => {
+        try {
+            await invoke("set_message_pinned", { messageId, pinned });
+            setMessages((prev) =>
+                prev.map((m) => (m.messageId === messageId ? { ...m, pinned } : m))
+            );
+        } catch (e) {
+            console.error("Failed to pin message:", e);
+        }
+    }, []);
+
+    const runMaintenance = useCallback(async (pruneDays?: number) => {
+        setStatus({ status: "Optimizing database", isThinking: false });
+        try {
+            const report = await invoke<MaintenanceReport>("run_maintenance", {
+                pruneDays: pruneDays ?? null,
+            });
+            setDbSize(report.size_after);
+            setStatus({ status: "Online", isThinking: false });
+            return report;
+        } catch (e) {
+            console.error("Database maintenance failed:", e);
+            setStatus({ status: "Error", isThinking: false });
+            return null;
+        }
+    }, []);
+
+    return {
+        messages,
+        status,
+        avatarState,
+        emotion,
+        dbSize,
+        contextUsage,
+        metrics,
+        hasOlder,
+        sendMessage,
+        clearChat,
+        stopGeneration,
+        regenerate,
+        editLastMessage,
+        branchFrom,
+        deleteMessage,
+        setPinned,
+        loadOlder,
+        refreshDbStats,
+        runMaintenance,
+    };
 }
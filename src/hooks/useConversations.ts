@@ -0,0 +1,71 @@
+import { useState, useEffect, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+
+export interface Conversation {
+    id: number;
+    persona_name: string;
+    model: string;
+    created_at: string;
+    parent_id: number | null;
+    branch_message_id: number | null;
+    // Start of the first user message
+    preview: string | null;
+    // Start of the message a branch was taken after
+    branch_preview: string | null;
+}
+
+// Saved conversations for the picker; switching goes through the agent loop,
+// which replays the chosen conversation via chat-history
+export function useConversations() {
+    const [conversations, setConversations] = useState<Conversation[]>([]);
+    const [active, setActive] = useState<number | null>(null);
+
+    const refresh = useCallback(async () => {
+        try {
+            const list = await invoke<{ active: number; conversations: Conversation[] }>(
+                "list_conversations"
+            );
+            setConversations(list.conversations);
+            setActive(list.active);
+        } catch (e) {
+            console.error("Failed to list conversations:", e);
+        }
+    }, []);
+
+    useEffect(() => {
+        refresh();
+        const unlisten = listen("conversations-changed", () => {
+            refresh();
+        });
+        return () => {
+            unlisten.then((fn) => fn());
+        };
+    }, [refresh]);
+
+    const create = useCallback(async () => {
+        try {
+            await invoke("new_conversation");
+        } catch (e) {
+            console.error("Failed to start a conversation:", e);
+        }
+    }, []);
+
+    const open = useCallback(async (id: number) => {
+        try {
+            await invoke("switch_conversation", { id });
+        } catch (e) {
+            console.error("Failed to switch conversation:", e);
+        }
+    }, []);
+
+    const remove = useCallback(async (id: number) => {
+        try {
+            await invoke("delete_conversation", { id });
+        } catch (e) {
+            console.error("Failed to delete conversation:", e);
+        }
+    }, []);
+
+    return { conversations, active, refresh, create, open, remove };
+}
@@ -0,0 +1,43 @@
+import { useState, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+export interface EmailAccountStatus {
+    name: string;
+    address: string;
+    has_password: boolean;
+}
+
+// Accounts come from [email] in amadeus.toml; passwords go to the OS keychain, never the file
+export function useEmailAccounts() {
+    const [accounts, setAccounts] = useState<EmailAccountStatus[]>([]);
+    // Passwords being typed, by account name
+    const [drafts, setDrafts] = useState<Record<string, string>>({});
+    const [error, setError] = useState<string | null>(null);
+
+    const refresh = useCallback(async () => {
+        try {
+            setAccounts(await invoke<EmailAccountStatus[]>("get_email_accounts"));
+        } catch (e) {
+            console.error("Failed to load email accounts:", e);
+        }
+    }, []);
+
+    const setDraft = useCallback((account: string, password: string) => {
+        setDrafts((prev) => ({ ...prev, [account]: password }));
+    }, []);
+
+    // An empty password removes the saved one
+    const save = useCallback(async (account: string, password: string) => {
+        setError(null);
+        try {
+            setAccounts(
+                await invoke<EmailAccountStatus[]>("set_email_password", { account, password })
+            );
+            setDrafts((prev) => ({ ...prev, [account]: "" }));
+        } catch (e) {
+            setError(String(e));
+        }
+    }, []);
+
+    return { accounts, drafts, error, refresh, setDraft, save };
+}
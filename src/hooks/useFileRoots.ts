@@ -0,0 +1,39 @@
+import { useState, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { open } from "@tauri-apps/plugin-dialog";
+
+// Workspace folders the file_system and shell tools are confined to
+export function useFileRoots() {
+    const [roots, setRoots] = useState<string[]>([]);
+    const [error, setError] = useState<string | null>(null);
+
+    const refresh = useCallback(async () => {
+        try {
+            setRoots(await invoke<string[]>("get_file_roots"));
+        } catch (e) {
+            console.error("Failed to load workspace folders:", e);
+        }
+    }, []);
+
+    const add = useCallback(async () => {
+        const path = await open({ directory: true, title: "Add workspace folder" });
+        if (typeof path !== "string") return;
+        setError(null);
+        try {
+            setRoots(await invoke<string[]>("add_file_root", { path }));
+        } catch (e) {
+            setError(String(e));
+        }
+    }, []);
+
+    const remove = useCallback(async (path: string) => {
+        setError(null);
+        try {
+            setRoots(await invoke<string[]>("remove_file_root", { path }));
+        } catch (e) {
+            setError(String(e));
+        }
+    }, []);
+
+    return { roots, error, refresh, add, remove };
+}
@@ -0,0 +1,41 @@
+import { useState, useEffect } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+export interface SearchHit {
+    message_id: number;
+    conversation_id: number | null;
+    role: string;
+    // Matched words are wrapped in **
+    snippet: string;
+    timestamp: string;
+}
+
+// Wait for typing to pause before querying
+const SEARCH_DEBOUNCE_MS = 250;
+
+// Full-text search across every saved conversation
+export function useHistorySearch() {
+    const [query, setQuery] = useState("");
+    const [results, setResults] = useState<SearchHit[]>([]);
+
+    useEffect(() => {
+        if (!query.trim()) {
+            setResults([]);
+            return;
+        }
+        let cancelled = false;
+        const timer = setTimeout(() => {
+            invoke<SearchHit[]>("search_messages", { query })
+                .then((hits) => {
+                    if (!cancelled) setResults(hits);
+                })
+                .catch((e) => console.error("History search failed:", e));
+        }, SEARCH_DEBOUNCE_MS);
+        return () => {
+            cancelled = true;
+            clearTimeout(timer);
+        };
+    }, [query]);
+
+    return { query, setQuery, results };
+}
@@ -0,0 +1,40 @@
+import { useState, useEffect, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+export interface RunningModel {
+    name: string;
+    size: number;
+    // Bytes of the model in GPU memory; 0 on the CPU
+    size_vram: number;
+}
+
+// Where the model runs, as reported by get_llm_info
+export interface LlmInfo {
+    model: string;
+    accelerator: "metal" | "cuda" | "rocm" | "cpu";
+    cpu_only: boolean;
+    gpu_layers: number | null;
+    context_window: number;
+    batch_size: number | null;
+    threads: number | null;
+    cpu_cores: number;
+    running: RunningModel[];
+}
+
+export function useLlmInfo() {
+    const [info, setInfo] = useState<LlmInfo | null>(null);
+
+    const refresh = useCallback(async () => {
+        try {
+            setInfo(await invoke<LlmInfo>("get_llm_info"));
+        } catch (e) {
+            console.error("Failed to read LLM info:", e);
+        }
+    }, []);
+
+    useEffect(() => {
+        refresh();
+    }, [refresh]);
+
+    return { info, refresh };
+}
@@ -0,0 +1,83 @@
+import { useState, useEffect, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+
+export interface ModelEntry {
+    name: string;
+    // The .gguf file, for models found on disk
+    path: string | null;
+    size: number;
+    // False for files that get imported into Ollama on first use
+    installed: boolean;
+}
+
+export interface DownloadProgress {
+    url: string;
+    downloaded: number;
+    total: number | null;
+}
+
+// Models the chat can switch to: Ollama's own plus GGUF files in [models] dirs
+export function useModels() {
+    const [models, setModels] = useState<ModelEntry[]>([]);
+    const [active, setActive] = useState<string | null>(null);
+    const [loading, setLoading] = useState<string | null>(null);
+    const [error, setError] = useState<string | null>(null);
+    const [download, setDownload] = useState<DownloadProgress | null>(null);
+
+    const refresh = useCallback(async () => {
+        try {
+            setModels(await invoke<ModelEntry[]>("list_models"));
+        } catch (e) {
+            console.error("Failed to list models:", e);
+        }
+    }, []);
+
+    useEffect(() => {
+        refresh();
+        invoke<{ model: string }>("get_settings")
+            .then((settings) => setActive(settings.model))
+            .catch((e) => console.error("Failed to load settings:", e));
+        const unlisten = listen<DownloadProgress>("model-download-progress", (event) =>
+            setDownload(event.payload),
+        );
+        return () => {
+            unlisten.then((fn) => fn());
+        };
+    }, [refresh]);
+
+    // Importing a large GGUF file can take a while; `loading` names it meanwhile
+    const load = useCallback(async (name: string) => {
+        setLoading(name);
+        setError(null);
+        try {
+            setActive(await invoke<string>("load_model", { name }));
+            refresh();
+        } catch (e) {
+            setError(String(e));
+        } finally {
+            setLoading(null);
+        }
+    }, [refresh]);
+
+    // From a Hugging Face link; an interrupted download resumes when started again
+    const fetchModel = useCallback(async (url: string, sha256?: string) => {
+        setDownload({ url, downloaded: 0, total: null });
+        setError(null);
+        try {
+            const list = await invoke<ModelEntry[]>("download_model", {
+                url,
+                sha256: sha256 || null,
+            });
+            setModels(list);
+            return list;
+        } catch (e) {
+            setError(String(e));
+            return null;
+        } finally {
+            setDownload(null);
+        }
+    }, []);
+
+    return { models, active, loading, error, download, refresh, load, fetchModel };
+}
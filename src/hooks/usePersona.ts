@@ -0,0 +1,48 @@
+import { useState, useEffect, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+
+// Used when a persona doesn't name its own VRM model
+export const DEFAULT_AVATAR_MODEL = "/model/vrm/KurisuMakise.vrm";
+
+interface PersonaChanged {
+    name: string;
+    avatar_model: string | null;
+}
+
+// Available personas (built-in and personas/*.toml) and the active one
+export function usePersona() {
+    const [personas, setPersonas] = useState<string[]>([]);
+    const [active, setActive] = useState<string | null>(null);
+    // Null when the persona doesn't name its own model
+    const [avatarModel, setAvatarModel] = useState<string | null>(null);
+
+    const refresh = useCallback(() => {
+        invoke<string[]>("list_personas")
+            .then(setPersonas)
+            .catch((e) => console.error("Failed to list personas:", e));
+    }, []);
+
+    useEffect(() => {
+        refresh();
+        // Also sent when a persona file is edited, which may add or rename personas
+        const unlisten = listen<PersonaChanged>("persona-changed", (event) => {
+            setActive(event.payload.name);
+            setAvatarModel(event.payload.avatar_model);
+            refresh();
+        });
+        return () => {
+            unlisten.then((fn) => fn());
+        };
+    }, [refresh]);
+
+    const select = useCallback(async (name: string) => {
+        try {
+            await invoke("set_persona", { name });
+        } catch (e) {
+            console.error("Failed to switch persona:", e);
+        }
+    }, []);
+
+    return { personas, active, avatarModel, select };
+}
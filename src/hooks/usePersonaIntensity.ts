@@ -0,0 +1,27 @@
+import { useState, useEffect, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+const DEFAULT_INTENSITY = 50;
+
+// 0 = strictly professional, 100 = playfully tsundere
+export function usePersonaIntensity() {
+    const [intensity, setIntensity] = useState(DEFAULT_INTENSITY);
+
+    useEffect(() => {
+        invoke<number>("get_persona_intensity")
+            .then(setIntensity)
+            .catch((e) => console.error("Failed to load persona intensity:", e));
+    }, []);
+
+    // The backend applies it from the next turn without clearing history
+    const apply = useCallback(async (value: number) => {
+        setIntensity(value);
+        try {
+            await invoke("set_persona_intensity", { intensity: value });
+        } catch (e) {
+            console.error("Failed to set persona intensity:", e);
+        }
+    }, []);
+
+    return { intensity, apply };
+}
@@ -0,0 +1,26 @@
+import { useState, useEffect, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+// Whether Amadeus may speak up unprompted (idle check-ins, scheduled prompts, reminders)
+export function useProactiveMode() {
+    const [enabled, setEnabled] = useState(false);
+
+    useEffect(() => {
+        invoke<boolean>("get_proactive_mode")
+            .then(setEnabled)
+            .catch((e) => console.error("Failed to load proactive mode:", e));
+    }, []);
+
+    const toggle = useCallback(async () => {
+        const next = !enabled;
+        setEnabled(next);
+        try {
+            await invoke("set_proactive_mode", { enabled: next });
+        } catch (e) {
+            setEnabled(!next);
+            console.error("Failed to set proactive mode:", e);
+        }
+    }, [enabled]);
+
+    return { enabled, toggle };
+}
@@ -0,0 +1,40 @@
+import { useState, useEffect, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+// Unset (null) fields keep the model's own defaults
+export interface SamplingConfig {
+    temperature: number | null;
+    top_p: number | null;
+    top_k: number | null;
+    repeat_penalty: number | null;
+    seed: number | null;
+}
+
+const EMPTY: SamplingConfig = {
+    temperature: null,
+    top_p: null,
+    top_k: null,
+    repeat_penalty: null,
+    seed: null,
+};
+
+export function useSampling() {
+    const [sampling, setSampling] = useState<SamplingConfig>(EMPTY);
+
+    useEffect(() => {
+        invoke<Partial<SamplingConfig>>("get_sampling")
+            .then((loaded) => setSampling({ ...EMPTY, ...loaded }))
+            .catch((e) => console.error("Failed to load sampling settings:", e));
+    }, []);
+
+    const apply = useCallback(async (next: SamplingConfig) => {
+        setSampling(next);
+        try {
+            await invoke("set_sampling", { sampling: next });
+        } catch (e) {
+            console.error("Failed to apply sampling settings:", e);
+        }
+    }, []);
+
+    return { sampling, apply };
+}
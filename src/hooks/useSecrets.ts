@@ -0,0 +1,44 @@
+import { useState, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+// Email passwords are managed with their accounts, so they're left out of this list
+const isApiKey = (name: string) => !name.startsWith("email:");
+
+// API keys and other credentials in the OS keychain. Only names reach the frontend.
+export function useSecrets() {
+    const [names, setNames] = useState<string[]>([]);
+    const [newName, setNewName] = useState("");
+    const [newValue, setNewValue] = useState("");
+    const [error, setError] = useState<string | null>(null);
+
+    const refresh = useCallback(async () => {
+        try {
+            setNames((await invoke<string[]>("list_secrets")).filter(isApiKey));
+        } catch (e) {
+            console.error("Failed to load secrets:", e);
+        }
+    }, []);
+
+    const save = useCallback(async () => {
+        setError(null);
+        try {
+            const saved = await invoke<string[]>("set_secret", { key: newName, value: newValue });
+            setNames(saved.filter(isApiKey));
+            setNewName("");
+            setNewValue("");
+        } catch (e) {
+            setError(String(e));
+        }
+    }, [newName, newValue]);
+
+    const remove = useCallback(async (key: string) => {
+        setError(null);
+        try {
+            setNames((await invoke<string[]>("delete_secret", { key })).filter(isApiKey));
+        } catch (e) {
+            setError(String(e));
+        }
+    }, []);
+
+    return { names, newName, setNewName, newValue, setNewValue, error, refresh, save, remove };
+}
@@ -0,0 +1,72 @@
+import { useState, useEffect, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { SamplingConfig } from "./useSampling";
+
+// Load options for Ollama; null leaves its own choice
+export interface HardwareConfig {
+    cpu_only: boolean;
+    gpu_layers: number | null;
+    batch_size: number | null;
+    threads: number | null;
+}
+
+// The settings panel as saved in the config file; survives restarts
+export interface Settings {
+    model: string;
+    persona: string;
+    persona_intensity: number;
+    sampling: Partial<SamplingConfig>;
+    context_window: number;
+    hardware: HardwareConfig;
+    tts_enabled: boolean;
+    rate: number;
+    pitch: number;
+    volume: number;
+    output_device: string | null;
+    piper_model: string | null;
+    piper_speaker: string | null;
+    file_roots: string[];
+    proactive: boolean;
+}
+
+export function useSettings() {
+    const [settings, setSettings] = useState<Settings | null>(null);
+    const [error, setError] = useState<string | null>(null);
+
+    const refresh = useCallback(async () => {
+        try {
+            setSettings(await invoke<Settings>("get_settings"));
+        } catch (e) {
+            console.error("Failed to load settings:", e);
+        }
+    }, []);
+
+    useEffect(() => {
+        refresh();
+    }, [refresh]);
+
+    // The tray menu can mute the voice while the panel is open
+    useEffect(() => {
+        const unlisten = listen("settings-changed", () => refresh());
+        return () => {
+            unlisten.then((fn) => fn());
+        };
+    }, [refresh]);
+
+    // Save a change; values changed elsewhere (voice, folders, ...) are reloaded first
+    const update = useCallback(async (change: Partial<Settings>) => {
+        try {
+            const current = await invoke<Settings>("get_settings");
+            const next = { ...current, ...change };
+            setSettings(next);
+            setSettings(await invoke<Settings>("set_settings", { settings: next }));
+            setError(null);
+        } catch (e) {
+            setError(String(e));
+            refresh();
+        }
+    }, [refresh]);
+
+    return { settings, error, refresh, update };
+}
@@ -0,0 +1,95 @@
+import { useState, useEffect, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { open } from "@tauri-apps/plugin-dialog";
+
+export type SetupStep =
+    | "ollama"
+    | "model"
+    | "whisper"
+    | "avatar"
+    | "microphone"
+    | "accessibility"
+    | "done";
+
+export interface SetupCheck {
+    step: SetupStep;
+    ready: boolean;
+    // Chat doesn't work without it
+    required: boolean;
+    detail: string;
+}
+
+export interface SetupStatus {
+    // The first step that isn't ready
+    step: SetupStep;
+    checks: SetupCheck[];
+    // Finished or skipped once; only required steps bring the guide back
+    dismissed: boolean;
+}
+
+// First-run walkthrough state, kept current by the backend's setup-status event
+export function useSetup() {
+    const [status, setStatus] = useState<SetupStatus | null>(null);
+    const [error, setError] = useState<string | null>(null);
+
+    const refresh = useCallback(async () => {
+        try {
+            setStatus(await invoke<SetupStatus>("get_setup_status"));
+        } catch (e) {
+            console.error("Failed to check setup:", e);
+        }
+    }, []);
+
+    useEffect(() => {
+        refresh();
+        const unlisten = listen<SetupStatus>("setup-status", (event) => setStatus(event.payload));
+        return () => {
+            unlisten.then((fn) => fn());
+        };
+    }, [refresh]);
+
+    // Have the agent loop try Ollama again now instead of at its next retry
+    const reconnect = useCallback(async () => {
+        try {
+            await invoke("reload_llm");
+            setError(null);
+        } catch (e) {
+            setError(String(e));
+        }
+        refresh();
+    }, [refresh]);
+
+    const pickAvatar = useCallback(async () => {
+        const path = await open({
+            title: "Choose an avatar",
+            filters: [{ name: "VRM model", extensions: ["vrm"] }],
+        });
+        if (typeof path !== "string") return;
+        try {
+            setStatus(await invoke<SetupStatus>("pick_avatar", { path }));
+            setError(null);
+        } catch (e) {
+            setError(String(e));
+        }
+    }, []);
+
+    const requestPermission = useCallback(async (step: SetupStep) => {
+        try {
+            await invoke("request_permissions", { step });
+            setError(null);
+        } catch (e) {
+            setError(String(e));
+        }
+    }, []);
+
+    const finish = useCallback(async () => {
+        try {
+            setStatus(await invoke<SetupStatus>("finish_setup"));
+        } catch (e) {
+            setError(String(e));
+        }
+    }, []);
+
+    return { status, error, refresh, reconnect, pickAvatar, requestPermission, finish };
+}
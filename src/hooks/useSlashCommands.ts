@@ -0,0 +1,21 @@
+import { useState, useEffect } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+export interface SlashCommandInfo {
+    command: string;
+    usage: string;
+    description: string;
+}
+
+// Slash commands the agent loop understands, for the chat input's autocomplete
+export function useSlashCommands() {
+    const [commands, setCommands] = useState<SlashCommandInfo[]>([]);
+
+    useEffect(() => {
+        invoke<SlashCommandInfo[]>("list_slash_commands")
+            .then(setCommands)
+            .catch((e) => console.error("Failed to list slash commands:", e));
+    }, []);
+
+    return commands;
+}
@@ -0,0 +1,47 @@
+import { useState, useEffect, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+
+export interface ToolApprovalRequest {
+    id: number;
+    tool: string;
+    args: unknown;
+    // What the call would change, e.g. a write_file diff
+    preview: string | null;
+    turn_id: number;
+    // False for tools that ask every time, like shell
+    always_allowable: boolean;
+}
+
+export type ApprovalDecision = "allow" | "always_allow" | "deny";
+
+// Tool calls the backend is holding until the user allows or denies them
+export function useToolApproval() {
+    const [requests, setRequests] = useState<ToolApprovalRequest[]>([]);
+
+    useEffect(() => {
+        const unlistenRequest = listen<ToolApprovalRequest>("tool-approval-request", (event) => {
+            setRequests((prev) => [...prev, event.payload]);
+        });
+        // Answered here, or cancelled by Stop
+        const unlistenResolved = listen<{ id: number }>("tool-approval-resolved", (event) => {
+            setRequests((prev) => prev.filter((r) => r.id !== event.payload.id));
+        });
+
+        return () => {
+            unlistenRequest.then((fn) => fn());
+            unlistenResolved.then((fn) => fn());
+        };
+    }, []);
+
+    const respond = useCallback(async (id: number, decision: ApprovalDecision) => {
+        setRequests((prev) => prev.filter((r) => r.id !== id));
+        try {
+            await invoke("resolve_tool_approval", { id, decision });
+        } catch (e) {
+            console.error("Failed to answer tool approval:", e);
+        }
+    }, []);
+
+    return { pending: requests[0] ?? null, respond };
+}
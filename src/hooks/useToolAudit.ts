@@ -0,0 +1,49 @@
+import { useState, useEffect, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+
+export interface ToolAuditEntry {
+    id: number;
+    timestamp: string;
+    tool: string;
+    // Arguments as JSON
+    args: string;
+    status: "ok" | "cached" | "error" | "timeout" | "denied";
+    // Start of the result, or the error message
+    output: string;
+    duration_ms: number;
+}
+
+const AUDIT_LIMIT = 100;
+
+// Every tool call Amadeus made, from the tool_audit table. Refreshed as calls finish
+// while `active` (the panel is open).
+export function useToolAudit(active: boolean) {
+    const [entries, setEntries] = useState<ToolAuditEntry[]>([]);
+    const [tool, setTool] = useState<string | null>(null);
+
+    const refresh = useCallback(async () => {
+        try {
+            setEntries(
+                await invoke<ToolAuditEntry[]>("get_tool_audit", { limit: AUDIT_LIMIT, tool })
+            );
+        } catch (e) {
+            console.error("Failed to load tool activity:", e);
+        }
+    }, [tool]);
+
+    useEffect(() => {
+        if (!active) return;
+        refresh();
+        const unlisten = listen<{ type: string }>("agent-event", (event) => {
+            if (event.payload.type === "tool_finished" || event.payload.type === "tool_failed") {
+                refresh();
+            }
+        });
+        return () => {
+            unlisten.then((fn) => fn());
+        };
+    }, [active, refresh]);
+
+    return { entries, tool, setTool, refresh };
+}
@@ -0,0 +1,27 @@
+import { useState, useCallback, useRef } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+// Translations are display-only and cached per message text for the session
+export function useTranslation() {
+    const [translations, setTranslations] = useState<Record<string, string>>({});
+    const [pending, setPending] = useState<Record<string, boolean>>({});
+    const requested = useRef(new Set<string>());
+
+    const translate = useCallback(async (text: string) => {
+        if (requested.current.has(text)) return;
+        requested.current.add(text);
+        setPending((prev) => ({ ...prev, [text]: true }));
+        try {
+            const translated = await invoke<string>("translate_message", { text });
+            setTranslations((prev) => ({ ...prev, [text]: translated }));
+        } catch (e) {
+            console.error("Translation failed:", e);
+            // Allow a retry after a failure
+            requested.current.delete(text);
+        } finally {
+            setPending((prev) => ({ ...prev, [text]: false }));
+        }
+    }, []);
+
+    return { translations, pending, translate };
+}
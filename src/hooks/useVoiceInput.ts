@@ -0,0 +1,119 @@
+import { useState, useEffect, useCallback, useRef } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen as listenEvent } from "@tauri-apps/api/event";
+
+interface SttStatus {
+    available: boolean;
+    reason: string | null;
+}
+
+// What hands-free listening is doing; "awake" means the wake word was just heard
+export type ListenState = "off" | "listening" | "awake" | "hearing" | "transcribing";
+
+// Bars in the live waveform, newest on the right
+const METER_BARS = 24;
+
+// Holding the mic button longer than this makes letting go send; a quick click records
+// until the user pauses instead
+const HOLD_MS = 400;
+
+export function useVoiceInput() {
+    const [available, setAvailable] = useState(false);
+    const [reason, setReason] = useState<string | null>("Loading speech model...");
+    const [recording, setRecording] = useState(false);
+    const [listenState, setListenState] = useState<ListenState>("off");
+    // Live transcript of speech still in progress
+    const [caption, setCaption] = useState<string | null>(null);
+    // Recent microphone levels (0-1) for the waveform
+    const [levels, setLevels] = useState<number[]>(() => Array(METER_BARS).fill(0));
+    const pressedAt = useRef<number | null>(null);
+
+    useEffect(() => {
+        const apply = (status: SttStatus) => {
+            setAvailable(status.available);
+            setReason(status.reason);
+        };
+
+        invoke<SttStatus>("get_stt_status")
+            .then(apply)
+            .catch((e) => console.error("Failed to get STT status:", e));
+
+        const unlisten = listenEvent<SttStatus>("stt-status", (event) => {
+            apply(event.payload);
+        });
+
+        // Continuous listening may already be on from the config
+        invoke<boolean>("is_continuous_listening")
+            .then((on) => setListenState((prev) => (on && prev === "off" ? "listening" : prev)))
+            .catch((e) => console.error("Failed to get listening state:", e));
+        const unlistenState = listenEvent<ListenState>("voice-listening", (event) => {
+            setListenState(event.payload);
+        });
+
+        const unlistenCaption = listenEvent<{ text: string; final: boolean }>(
+            "stt-caption",
+            (event) => {
+                setCaption(event.payload.final ? null : event.payload.text || null);
+            }
+        );
+
+        const unlistenLevel = listenEvent<{ level: number }>("mic-level", (event) => {
+            setLevels((prev) => [...prev.slice(1), event.payload.level]);
+        });
+
+        return () => {
+            unlisten.then((fn) => fn());
+            unlistenState.then((fn) => fn());
+            unlistenCaption.then((fn) => fn());
+            unlistenLevel.then((fn) => fn());
+        };
+    }, []);
+
+    const listen = useCallback(async () => {
+        pressedAt.current = Date.now();
+        setLevels(Array(METER_BARS).fill(0));
+        setRecording(true);
+        try {
+            await invoke<string>("listen_once");
+        } catch (e) {
+            console.error("Voice input failed:", e);
+        } finally {
+            pressedAt.current = null;
+            setRecording(false);
+            setCaption(null);
+        }
+    }, []);
+
+    // Mic button let go: after a hold, send what was said so far
+    const release = useCallback(async () => {
+        if (pressedAt.current === null || Date.now() - pressedAt.current < HOLD_MS) return;
+        try {
+            await invoke("release_push_to_talk");
+        } catch (e) {
+            console.error("Failed to stop recording:", e);
+        }
+    }, []);
+
+    const continuous = listenState !== "off";
+
+    const toggleContinuous = useCallback(async () => {
+        try {
+            await invoke("set_continuous_listening", { enabled: !continuous });
+        } catch (e) {
+            console.error("Failed to toggle listening:", e);
+        }
+    }, [continuous]);
+
+    return {
+        available,
+        reason,
+        recording,
+        listen,
+        release,
+        levels,
+        continuous,
+        listenState,
+        toggleContinuous,
+        caption,
+    };
+}
@@ -0,0 +1,80 @@
+import { useState, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+export interface PiperVoice {
+    id: string;
+    // Empty for single-speaker models
+    speakers: string[];
+}
+
+interface VoiceSettings {
+    backend: string;
+    rate: number;
+    pitch: number;
+    voices: PiperVoice[];
+    model: string | null;
+    speaker: string | null;
+}
+
+// Speaking rate/pitch and, for the piper backend, voice model management
+export function useVoiceSettings() {
+    const [settings, setSettings] = useState<VoiceSettings | null>(null);
+    const [downloading, setDownloading] = useState<string | null>(null);
+    const [error, setError] = useState<string | null>(null);
+
+    const refresh = useCallback(async () => {
+        try {
+            setSettings(await invoke<VoiceSettings>("get_voice_settings"));
+        } catch (e) {
+            console.error("Failed to load voice settings:", e);
+            setSettings(null);
+        }
+    }, []);
+
+    const tune = useCallback(async (rate: number, pitch: number) => {
+        setSettings((prev) => (prev ? { ...prev, rate, pitch } : prev));
+        try {
+            await invoke("set_speech_tuning", { rate, pitch });
+        } catch (e) {
+            console.error("Failed to set speech rate/pitch:", e);
+        }
+    }, []);
+
+    const selectVoice = useCallback(
+        async (model: string | null, speaker: string | null) => {
+            setError(null);
+            try {
+                await invoke("set_piper_voice", { model, speaker });
+                await refresh();
+            } catch (e) {
+                setError(String(e));
+            }
+        },
+        [refresh]
+    );
+
+    const download = useCallback(async (id: string) => {
+        setDownloading(id);
+        setError(null);
+        try {
+            const voices = await invoke<PiperVoice[]>("download_piper_voice", { id });
+            setSettings((prev) => (prev ? { ...prev, voices } : prev));
+        } catch (e) {
+            setError(String(e));
+        } finally {
+            setDownloading(null);
+        }
+    }, []);
+
+    const remove = useCallback(async (id: string) => {
+        setError(null);
+        try {
+            const voices = await invoke<PiperVoice[]>("delete_piper_voice", { id });
+            setSettings((prev) => (prev ? { ...prev, voices } : prev));
+        } catch (e) {
+            setError(String(e));
+        }
+    }, []);
+
+    return { settings, downloading, error, refresh, tune, selectVoice, download, remove };
+}
@@ -1,7 +1,18 @@
 import ReactDOM from "react-dom/client";
 import App from "./App";
+import { RegionSelect } from "./components/RegionSelect";
+import { QuickChat } from "./components/QuickChat";
 import "./App.css";
 
+// Region-select overlays and the quick-chat box load the same bundle with a hash route
+const route = window.location.hash;
+
 ReactDOM.createRoot(document.getElementById("root") as HTMLElement).render(
-    <App />
+    route.startsWith("#region-select") ? (
+        <RegionSelect />
+    ) : route.startsWith("#quick-chat") ? (
+        <QuickChat />
+    ) : (
+        <App />
+    )
 );
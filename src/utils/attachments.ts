@@ -0,0 +1,24 @@
+// Markers the backend appends to a user message for each attached file (see
+// UserInput::into_message_parts in src-tauri/src/agent/input.rs)
+const ATTACHMENT_MARKER = /\n\n\[Attached (file|image): ([^\]\n]+)\]/g;
+
+export interface MessageAttachment {
+    kind: "file" | "image";
+    name: string;
+}
+
+// The typed text of a user message and the files attached to it. Inlined file contents
+// are left out; they are for the model, not for reading back in the chat.
+export function splitAttachments(content: string): {
+    text: string;
+    attachments: MessageAttachment[];
+} {
+    const matches = [...content.matchAll(ATTACHMENT_MARKER)];
+    if (matches.length === 0) {
+        return { text: content, attachments: [] };
+    }
+    return {
+        text: content.slice(0, matches[0].index),
+        attachments: matches.map((m) => ({ kind: m[1] as "file" | "image", name: m[2] })),
+    };
+}
@@ -0,0 +1,48 @@
+// Helpers for rendering markdown that is still being streamed in.
+
+const FENCE = "```";
+const BOLD = "**";
+
+// Escape the marker at `index` so react-markdown renders it literally
+function escapeAt(text: string, index: number, marker: string): string {
+    const escaped = marker.replace(/[*`]/g, (c) => `\\${c}`);
+    return text.slice(0, index) + escaped + text.slice(index + marker.length);
+}
+
+// Indices of every occurrence of `marker`, skipping already-escaped ones
+function findMarkers(text: string, marker: string): number[] {
+    const indices: number[] = [];
+    let i = text.indexOf(marker);
+    while (i !== -1) {
+        if (i === 0 || text[i - 1] !== "\\") {
+            indices.push(i);
+        }
+        i = text.indexOf(marker, i + marker.length);
+    }
+    return indices;
+}
+
+/**
+ * Make an in-progress markdown buffer render stably.
+ * An unclosed ``` fence or ** marker at the end of the buffer is shown as
+ * literal text until its closing marker arrives, so styling doesn't flip
+ * back and forth on every token.
+ */
+export function stabilizePartialMarkdown(text: string): string {
+    const fences = findMarkers(text, FENCE);
+    if (fences.length % 2 === 1) {
+        // Unterminated code block: everything after it is still code-to-be,
+        // so leave bold handling alone and just neutralize the fence.
+        return escapeAt(text, fences[fences.length - 1], FENCE);
+    }
+
+    // Only look for bold markers in the text after the last closed fence
+    const tailStart = fences.length > 0 ? fences[fences.length - 1] + FENCE.length : 0;
+    const tail = text.slice(tailStart);
+    const bolds = findMarkers(tail, BOLD);
+    if (bolds.length % 2 === 1) {
+        return text.slice(0, tailStart) + escapeAt(tail, bolds[bolds.length - 1], BOLD);
+    }
+
+    return text;
+}